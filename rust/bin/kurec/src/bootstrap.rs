@@ -0,0 +1,341 @@
+//! 起動処理の再試行と、失敗の種類に応じた終了コード
+//!
+//! NATSやmirakcはワーカーと同時に起動されることが多いため、
+//! 接続やストリーム・コンシューマーの作成は間隔を空けて再試行する。
+
+use std::error::Error;
+use std::future::Future;
+use std::process::ExitCode;
+
+use domain::service::RetryPolicy;
+use mirakc::MirakcSseConnectionError;
+use nats::{
+    error::NatsInfraError,
+    nats::{NatsClient, connect_nats_with_options},
+    stream_manager::create_or_update_streams,
+};
+use thiserror::Error;
+use tracing::{info, warn};
+use worker::CancellationToken;
+
+use crate::config::{ConfigError, KurecConfig};
+
+/// 実行中の処理に失敗した
+pub const EXIT_RUNTIME: u8 = 1;
+/// コマンドライン引数が正しくない（clapと同じ値）
+pub const EXIT_USAGE: u8 = 2;
+/// mirakcを利用できない
+pub const EXIT_MIRAKC_UNAVAILABLE: u8 = 68;
+/// NATSを利用できない
+pub const EXIT_NATS_UNAVAILABLE: u8 = 69;
+/// 設定が正しくない
+pub const EXIT_CONFIG: u8 = 78;
+
+#[derive(Debug, Error)]
+pub enum BootstrapError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error("{operation}に失敗しました（{attempts}回試行）: {source}")]
+    Nats {
+        operation: String,
+        attempts: u32,
+        source: NatsInfraError,
+    },
+    #[error("mirakcに接続できません（{attempts}回試行）: {source}")]
+    Mirakc {
+        attempts: u32,
+        source: MirakcSseConnectionError,
+    },
+    #[error("シャットダウンのため起動処理を中断しました")]
+    Cancelled,
+}
+
+impl BootstrapError {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            BootstrapError::Config(_) => ExitCode::from(EXIT_CONFIG),
+            BootstrapError::Nats { .. } => ExitCode::from(EXIT_NATS_UNAVAILABLE),
+            BootstrapError::Mirakc { .. } => ExitCode::from(EXIT_MIRAKC_UNAVAILABLE),
+            BootstrapError::Cancelled => ExitCode::SUCCESS,
+        }
+    }
+}
+
+/// mirakcのSSEストリームが再接続の上限に達して終了した
+#[derive(Debug, Error)]
+#[error("mirakcのSSEストリームが終了しました（再接続の上限に達しました）")]
+pub struct SseStreamEnded;
+
+/// タスクが返したエラーの種類に応じた終了コード
+///
+/// NATSのエラーのうち、接続やストリーム・バケットの取得に失敗したものだけをNATSを利用できないとみなす。
+pub fn exit_code_for(error: &(dyn Error + 'static)) -> ExitCode {
+    if let Some(error) = error.downcast_ref::<BootstrapError>() {
+        error.exit_code()
+    } else if error
+        .downcast_ref::<NatsInfraError>()
+        .is_some_and(is_nats_unavailable)
+    {
+        ExitCode::from(EXIT_NATS_UNAVAILABLE)
+    } else if error.is::<MirakcSseConnectionError>() || error.is::<SseStreamEnded>() {
+        ExitCode::from(EXIT_MIRAKC_UNAVAILABLE)
+    } else {
+        ExitCode::from(EXIT_RUNTIME)
+    }
+}
+
+/// `kurec run` で監視しているタスクを再起動せずに、プロセスを終了させるエラーかどうか
+///
+/// 起動処理の再試行を使い切ったエラーは、再起動しても同じ再試行を繰り返すだけなので終了させる。
+pub fn is_fatal(error: &(dyn Error + 'static)) -> bool {
+    error
+        .downcast_ref::<BootstrapError>()
+        .is_some_and(|error| !matches!(error, BootstrapError::Cancelled))
+}
+
+/// NATSに接続できないか、JetStreamのストリームやバケットを使えない状態かどうか
+fn is_nats_unavailable(error: &NatsInfraError) -> bool {
+    matches!(
+        error,
+        NatsInfraError::Connection(_)
+            | NatsInfraError::JetStreamContext(_)
+            | NatsInfraError::KvStore { .. }
+            | NatsInfraError::ObjectStore { .. }
+            | NatsInfraError::StreamCreation { .. }
+            | NatsInfraError::StreamRetrieval { .. }
+    )
+}
+
+/// NATSの操作を成功するまで再試行する
+///
+/// 上限に達するか、待機中に `shutdown` がキャンセルされると諦める。
+/// `policy.max_attempts` が0の場合は上限なく再試行する。
+pub async fn retry_nats<T, F, Fut>(
+    operation: &str,
+    policy: &RetryPolicy,
+    shutdown: &CancellationToken,
+    f: F,
+) -> Result<T, BootstrapError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, NatsInfraError>>,
+{
+    retry(operation, policy, shutdown, f, |attempts, source| {
+        BootstrapError::Nats {
+            operation: operation.to_string(),
+            attempts,
+            source,
+        }
+    })
+    .await
+}
+
+/// mirakcへの接続を成功するまで再試行する
+///
+/// 諦めた場合の扱いは [`retry_nats`] と同じ。
+pub async fn retry_mirakc<T, F, Fut>(
+    operation: &str,
+    policy: &RetryPolicy,
+    shutdown: &CancellationToken,
+    f: F,
+) -> Result<T, BootstrapError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, MirakcSseConnectionError>>,
+{
+    retry(operation, policy, shutdown, f, |attempts, source| {
+        BootstrapError::Mirakc { attempts, source }
+    })
+    .await
+}
+
+async fn retry<T, E, F, Fut>(
+    operation: &str,
+    policy: &RetryPolicy,
+    shutdown: &CancellationToken,
+    mut f: F,
+    give_up: impl FnOnce(u32, E) -> BootstrapError,
+) -> Result<T, BootstrapError>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let error = tokio::select! {
+            _ = shutdown.cancelled() => return Err(BootstrapError::Cancelled),
+            result = f() => match result {
+                Ok(value) => {
+                    if attempt > 1 {
+                        info!(attempt, "{}に成功しました", operation);
+                    }
+                    return Ok(value);
+                }
+                Err(e) => e,
+            },
+        };
+        if policy.max_attempts != 0 && attempt >= policy.max_attempts {
+            return Err(give_up(attempt, error));
+        }
+        let backoff = policy.backoff_for(attempt);
+        warn!(
+            attempt,
+            backoff = ?backoff,
+            "{}に失敗しました。再試行します: {}",
+            operation,
+            error
+        );
+        tokio::select! {
+            _ = shutdown.cancelled() => return Err(BootstrapError::Cancelled),
+            _ = tokio::time::sleep(backoff) => {}
+        }
+    }
+}
+
+/// 設定に従ってNATSに接続し、kurecが使うストリームを準備する
+pub async fn connect(
+    config: &KurecConfig,
+    shutdown: &CancellationToken,
+) -> Result<NatsClient, BootstrapError> {
+    let policy = config.startup_retry_policy();
+    let options = config.nats_connect_options();
+
+    let nats_client = retry_nats("NATSサーバーへの接続", &policy, shutdown, || {
+        connect_nats_with_options(&config.nats.url, &options)
+    })
+    .await?
+    .with_kv_bucket_config(config.kv_bucket_config());
+
+    let streams = config.stream_configs();
+    retry_nats("ストリームの作成", &policy, shutdown, || {
+        create_or_update_streams(&nats_client, &streams)
+    })
+    .await?;
+
+    Ok(nats_client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            multiplier: 2.0,
+        }
+    }
+
+    fn unavailable() -> NatsInfraError {
+        NatsInfraError::Connection("connection refused".into())
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let calls = AtomicU32::new(0);
+
+        let result = retry_nats("テスト", &policy(5), &CancellationToken::new(), || async {
+            if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(unavailable())
+            } else {
+                Ok("connected")
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), "connected");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let result: Result<(), _> =
+            retry_nats("テスト", &policy(3), &CancellationToken::new(), || async {
+                Err(unavailable())
+            })
+            .await;
+
+        let error = result.unwrap_err();
+        assert!(matches!(error, BootstrapError::Nats { attempts: 3, .. }));
+        assert!(
+            error
+                .to_string()
+                .contains("テストに失敗しました（3回試行）")
+        );
+        assert_eq!(error.exit_code(), ExitCode::from(EXIT_NATS_UNAVAILABLE));
+    }
+
+    #[tokio::test]
+    async fn test_retry_mirakc_gives_up_after_max_attempts() {
+        let result = retry_mirakc("テスト", &policy(2), &CancellationToken::new(), || {
+            mirakc::get_mirakc_event_stream("not a url", 1)
+        })
+        .await;
+
+        let Err(error) = result else {
+            panic!("接続に失敗するはずです");
+        };
+        assert!(matches!(error, BootstrapError::Mirakc { attempts: 2, .. }));
+        assert_eq!(error.exit_code(), ExitCode::from(EXIT_MIRAKC_UNAVAILABLE));
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_shutdown() {
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+
+        let result: Result<(), _> = retry_nats("テスト", &policy(0), &shutdown, || async {
+            Err(unavailable())
+        })
+        .await;
+
+        assert!(matches!(result, Err(BootstrapError::Cancelled)));
+    }
+
+    #[test]
+    fn test_exit_code_for_task_errors() {
+        let nats: Box<dyn Error + Send + Sync> = Box::new(unavailable());
+        assert_eq!(
+            exit_code_for(nats.as_ref()),
+            ExitCode::from(EXIT_NATS_UNAVAILABLE)
+        );
+
+        // 接続できていても、値の読み書きの失敗はNATSを利用できないことにはならない
+        let kv_get: Box<dyn Error + Send + Sync> = Box::new(NatsInfraError::KvGet {
+            source: "invalid value".into(),
+        });
+        assert_eq!(exit_code_for(kv_get.as_ref()), ExitCode::from(EXIT_RUNTIME));
+
+        let sse: Box<dyn Error + Send + Sync> = Box::new(SseStreamEnded);
+        assert_eq!(
+            exit_code_for(sse.as_ref()),
+            ExitCode::from(EXIT_MIRAKC_UNAVAILABLE)
+        );
+
+        let other: Box<dyn Error + Send + Sync> = "failed".into();
+        assert_eq!(exit_code_for(other.as_ref()), ExitCode::from(EXIT_RUNTIME));
+    }
+
+    #[test]
+    fn test_is_fatal() {
+        let gave_up: Box<dyn Error + Send + Sync> = Box::new(BootstrapError::Nats {
+            operation: "テスト".to_string(),
+            attempts: 3,
+            source: unavailable(),
+        });
+        assert!(is_fatal(gave_up.as_ref()));
+
+        let cancelled: Box<dyn Error + Send + Sync> = Box::new(BootstrapError::Cancelled);
+        assert!(!is_fatal(cancelled.as_ref()));
+
+        // 実行中のNATSの切断などは、タスクを再起動して立て直す
+        let nats: Box<dyn Error + Send + Sync> = Box::new(unavailable());
+        assert!(!is_fatal(nats.as_ref()));
+    }
+}
@@ -0,0 +1,1149 @@
+//! kurec の設定
+//!
+//! 既定値、設定ファイル（TOML）、環境変数、コマンドライン引数の順に上書きされる。
+
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use domain::{
+    model::{transcode::TranscodeProfile, url_extractor::DEFAULT_EXCLUDED_DOMAINS},
+    service::RetryPolicy,
+    usecase::{DEFAULT_LOGO_IMAGE_WIDTH, DEFAULT_OGP_IMAGE_WIDTH},
+};
+use meilisearch::MeilisearchConfig;
+use nats::{
+    dlq::DeadLetterQueue, kvs::KvBucketConfig, nats::NatsConnectOptions, stream::DLQ_STREAM_NAME,
+    stream_manager::StreamConfig,
+};
+use s3::S3Config;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// NATSサーバーのURLを上書きする環境変数
+pub const ENV_NATS_URL: &str = "KUREC_NATS_URL";
+/// mirakcサーバーのURLを上書きする環境変数
+pub const ENV_MIRAKC_URL: &str = "KUREC_MIRAKC_URL";
+/// mirakcへの再接続の最大回数を上書きする環境変数
+pub const ENV_MIRAKC_RETRY_MAX: &str = "KUREC_MIRAKC_RETRY_MAX";
+/// ヘルスチェック・メトリクスの待ち受けアドレスを上書きする環境変数
+pub const ENV_HTTP_LISTEN: &str = "KUREC_HTTP_LISTEN";
+/// S3互換ストレージのエンドポイントを上書きする環境変数
+pub const ENV_S3_ENDPOINT: &str = "KUREC_S3_ENDPOINT";
+/// S3互換ストレージのアクセスキーを上書きする環境変数
+pub const ENV_S3_ACCESS_KEY: &str = "KUREC_S3_ACCESS_KEY";
+/// S3互換ストレージのシークレットキーを上書きする環境変数
+pub const ENV_S3_SECRET_KEY: &str = "KUREC_S3_SECRET_KEY";
+/// MeilisearchのURLを上書きする環境変数
+pub const ENV_MEILISEARCH_URL: &str = "KUREC_MEILISEARCH_URL";
+/// MeilisearchのAPIキーを上書きする環境変数
+pub const ENV_MEILISEARCH_API_KEY: &str = "KUREC_MEILISEARCH_API_KEY";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("設定ファイルを読み込めません: {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("設定ファイルの形式が正しくありません: {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("環境変数 {name} の値が正しくありません: {value}")]
+    Env { name: &'static str, value: String },
+    #[error("設定が正しくありません: {0}")]
+    Invalid(String),
+    #[error("設定を出力できません: {0}")]
+    Serialize(#[from] toml::ser::Error),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KurecConfig {
+    pub nats: NatsConfig,
+    pub mirakc: MirakcConfig,
+    pub streams: StreamsConfig,
+    pub kv: KvConfig,
+    pub ogp: OgpConfig,
+    pub workers: WorkersConfig,
+    pub epg_sync: EpgSyncConfig,
+    pub inventory_sync: InventorySyncConfig,
+    pub logo_sync: LogoSyncConfig,
+    pub transcode: TranscodeConfig,
+    pub object_store: ObjectStoreConfig,
+    pub search: SearchConfig,
+    pub http: HttpConfig,
+    pub startup: StartupConfig,
+    pub retry: RetryConfig,
+}
+
+/// NATSへの接続設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NatsConfig {
+    pub url: String,
+    /// 接続のタイムアウト（秒）
+    pub connection_timeout_secs: u64,
+    /// 再接続の最大試行回数（省略時は無制限）
+    pub max_reconnects: Option<usize>,
+    /// サーバーに通知するクライアント名
+    pub name: Option<String>,
+}
+
+impl Default for NatsConfig {
+    fn default() -> Self {
+        Self {
+            url: "nats:4222".to_string(),
+            connection_timeout_secs: 10,
+            max_reconnects: None,
+            name: None,
+        }
+    }
+}
+
+/// mirakcへの接続設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MirakcConfig {
+    pub url: String,
+    /// SSEの再接続の最大回数（0は無限回）
+    pub retry_max: u32,
+}
+
+impl Default for MirakcConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://tuner:40772".to_string(),
+            retry_max: 5,
+        }
+    }
+}
+
+/// JetStreamのストリーム設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreamsConfig {
+    /// mirakc由来のイベント（`recording.>`）を保持するストリーム
+    pub events: StreamSettings,
+    /// OGP処理のイベント（`ogp.>`）を保持するストリーム
+    pub ogp: StreamSettings,
+}
+
+impl Default for StreamsConfig {
+    fn default() -> Self {
+        Self {
+            events: StreamSettings::new("kurec", "recording.>"),
+            ogp: StreamSettings::new("kurec-ogp", "ogp.>"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamSettings {
+    pub name: String,
+    pub subjects: Vec<String>,
+    /// メッセージの保持期間（秒、0は無期限）
+    #[serde(default)]
+    pub max_age_secs: u64,
+    #[serde(default = "default_replicas")]
+    pub replicas: usize,
+}
+
+impl StreamSettings {
+    fn new(name: &str, subject: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            subjects: vec![subject.to_string()],
+            max_age_secs: 0,
+            replicas: default_replicas(),
+        }
+    }
+
+    fn to_stream_config(&self) -> StreamConfig {
+        StreamConfig {
+            name: self.name.clone(),
+            subjects: self.subjects.clone(),
+            max_age: Duration::from_secs(self.max_age_secs),
+            num_replicas: self.replicas,
+            ..Default::default()
+        }
+    }
+}
+
+fn default_replicas() -> usize {
+    1
+}
+
+/// 新規作成するKVバケットの設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KvConfig {
+    /// キーごとに保持する履歴の数
+    pub history: i64,
+    /// 値の保持期間（秒、0は無期限）
+    pub max_age_secs: u64,
+    pub replicas: usize,
+}
+
+impl Default for KvConfig {
+    fn default() -> Self {
+        Self {
+            history: 1,
+            max_age_secs: 0,
+            replicas: default_replicas(),
+        }
+    }
+}
+
+/// OGP画像の取得設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OgpConfig {
+    /// 変換後の画像の幅（px）
+    pub image_width: u32,
+    /// URLを抽出しないドメイン（サブドメインも含む）
+    pub excluded_domains: Vec<String>,
+}
+
+impl Default for OgpConfig {
+    fn default() -> Self {
+        Self {
+            image_width: DEFAULT_OGP_IMAGE_WIDTH,
+            excluded_domains: DEFAULT_EXCLUDED_DOMAINS
+                .iter()
+                .map(|domain| domain.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// 番組表の全件同期の設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EpgSyncConfig {
+    /// 起動後に全件同期を繰り返す間隔（秒）
+    pub interval_secs: u64,
+}
+
+impl Default for EpgSyncConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3600,
+        }
+    }
+}
+
+impl EpgSyncConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+/// チューナー・チャンネル・サービスの一覧の同期の設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InventorySyncConfig {
+    /// 起動後に同期を繰り返す間隔（秒）
+    pub interval_secs: u64,
+}
+
+impl Default for InventorySyncConfig {
+    fn default() -> Self {
+        Self { interval_secs: 600 }
+    }
+}
+
+impl InventorySyncConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+/// サービスのロゴ画像の同期の設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogoSyncConfig {
+    /// 起動後に同期を繰り返す間隔（秒）
+    pub interval_secs: u64,
+    /// 変換後の画像の幅（px）
+    pub image_width: u32,
+}
+
+impl Default for LogoSyncConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 86400,
+            image_width: DEFAULT_LOGO_IMAGE_WIDTH,
+        }
+    }
+}
+
+impl LogoSyncConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+/// 保存された録画のトランスコードの設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TranscodeConfig {
+    /// mirakcの録画ディレクトリ（`records-dir`）をkurecから参照するパス
+    pub records_dir: PathBuf,
+    /// 変換後のファイルを置くディレクトリ
+    pub output_dir: PathBuf,
+    /// エンコーダーのコマンド（ffmpeg互換）
+    pub encoder: String,
+    pub profile: TranscodeProfile,
+}
+
+impl Default for TranscodeConfig {
+    fn default() -> Self {
+        Self {
+            records_dir: PathBuf::from("/recorded/records"),
+            output_dir: PathBuf::from("/recorded/transcoded"),
+            encoder: "ffmpeg".to_string(),
+            profile: TranscodeProfile::default(),
+        }
+    }
+}
+
+/// オブジェクトストアの種類
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectStoreBackendKind {
+    /// JetStream Object Store
+    #[default]
+    Nats,
+    /// MinIOなどのS3互換ストレージ
+    S3,
+}
+
+/// 録画ファイルや画像を保存するオブジェクトストアの設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObjectStoreConfig {
+    pub backend: ObjectStoreBackendKind,
+    /// バケット名
+    pub bucket: String,
+    /// OGP画像とロゴ画像をKVではなくオブジェクトストアに保存し、KVには参照だけを置く
+    pub store_images: bool,
+    pub s3: S3Settings,
+}
+
+impl Default for ObjectStoreConfig {
+    fn default() -> Self {
+        Self {
+            backend: ObjectStoreBackendKind::default(),
+            bucket: "kurec".to_string(),
+            store_images: false,
+            s3: S3Settings::default(),
+        }
+    }
+}
+
+/// S3互換ストレージへの接続設定
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct S3Settings {
+    pub endpoint: String,
+    pub region: String,
+    pub access_key: String,
+    /// 設定の出力には含めない（環境変数で渡す）
+    #[serde(skip_serializing)]
+    pub secret_key: String,
+}
+
+impl Default for S3Settings {
+    fn default() -> Self {
+        let defaults = S3Config::default();
+        Self {
+            endpoint: defaults.endpoint,
+            region: defaults.region,
+            access_key: defaults.access_key,
+            secret_key: defaults.secret_key,
+        }
+    }
+}
+
+impl std::fmt::Debug for S3Settings {
+    // シークレットキーをログに出さない
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3Settings")
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("access_key", &self.access_key)
+            .field("secret_key", &"***")
+            .finish()
+    }
+}
+
+impl ObjectStoreConfig {
+    pub fn s3_config(&self) -> S3Config {
+        S3Config {
+            endpoint: self.s3.endpoint.clone(),
+            region: self.s3.region.clone(),
+            bucket: self.bucket.clone(),
+            access_key: self.s3.access_key.clone(),
+            secret_key: self.s3.secret_key.clone(),
+        }
+    }
+
+    /// バックエンドで使えるバケット名かどうか
+    fn is_valid_bucket(&self) -> bool {
+        let bucket = &self.bucket;
+        match self.backend {
+            ObjectStoreBackendKind::Nats => {
+                !bucket.is_empty()
+                    && bucket
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            }
+            ObjectStoreBackendKind::S3 => {
+                (3..=63).contains(&bucket.len())
+                    && bucket.chars().all(|c| {
+                        c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.'
+                    })
+            }
+        }
+    }
+}
+
+/// 番組検索の検索エンジン
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchBackendKind {
+    #[default]
+    Meilisearch,
+    /// kurecに組み込んだtantivyのインデックス（Meilisearchを使わない構成向け）
+    Tantivy,
+}
+
+/// 番組検索のインデックスの設定
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    pub backend: SearchBackendKind,
+    pub meilisearch: MeilisearchSettings,
+    pub tantivy: TantivySettings,
+}
+
+/// Meilisearchへの接続設定
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MeilisearchSettings {
+    pub url: String,
+    /// 番組の文書を登録するインデックス
+    pub index: String,
+    /// 設定の出力には含めない（環境変数で渡す）
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+}
+
+impl Default for MeilisearchSettings {
+    fn default() -> Self {
+        let defaults = MeilisearchConfig::default();
+        Self {
+            url: defaults.url,
+            index: defaults.index,
+            api_key: defaults.api_key,
+        }
+    }
+}
+
+impl std::fmt::Debug for MeilisearchSettings {
+    // APIキーをログに出さない
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MeilisearchSettings")
+            .field("url", &self.url)
+            .field("index", &self.index)
+            .field("api_key", &self.api_key.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// 組み込みの検索インデックスの設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TantivySettings {
+    /// インデックスを置くディレクトリ（書き込めるのは1つのプロセスだけ）
+    pub index_dir: PathBuf,
+    /// `lindera build` でビルドした形態素解析の辞書（IPADICなど）のディレクトリ
+    pub dictionary: String,
+}
+
+impl Default for TantivySettings {
+    fn default() -> Self {
+        Self {
+            index_dir: PathBuf::from("/var/lib/kurec/search"),
+            dictionary: "/usr/share/lindera/ipadic".to_string(),
+        }
+    }
+}
+
+impl SearchConfig {
+    pub fn meilisearch_config(&self) -> MeilisearchConfig {
+        MeilisearchConfig {
+            url: self.meilisearch.url.clone(),
+            api_key: self.meilisearch.api_key.clone(),
+            index: self.meilisearch.index.clone(),
+        }
+    }
+}
+
+/// ヘルスチェック・メトリクスのHTTPサーバーの設定
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    /// 待ち受けアドレス（例: `0.0.0.0:9090`、省略時はサーバーを起動しない）
+    pub listen: Option<String>,
+}
+
+impl HttpConfig {
+    pub fn listen_addr(&self) -> Option<SocketAddr> {
+        self.listen
+            .as_deref()
+            .and_then(|listen| listen.parse().ok())
+    }
+}
+
+/// 起動時にNATSへの接続やストリーム・コンシューマーの作成を再試行する設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StartupConfig {
+    /// 試行の最大回数（0は無制限）
+    pub max_attempts: u32,
+    /// 最初の再試行までの待ち時間（ミリ秒）
+    pub initial_backoff_ms: u64,
+    /// 再試行までの待ち時間の上限（ミリ秒）
+    pub max_backoff_ms: u64,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+/// ワーカーが処理に失敗したメッセージを再試行する設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// 最初の配信を含む試行の最大回数。使い切るとデッドレターキューへ転送する
+    pub max_attempts: u32,
+    /// 1回目の再試行までの待ち時間（ミリ秒）
+    pub initial_backoff_ms: u64,
+    /// 再試行までの待ち時間の上限（ミリ秒）
+    pub max_backoff_ms: u64,
+    /// 再試行ごとに待ち時間に掛ける倍率
+    pub multiplier: f64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        let policy = RetryPolicy::default();
+        Self {
+            max_attempts: policy.max_attempts,
+            initial_backoff_ms: policy.initial_backoff.as_millis() as u64,
+            max_backoff_ms: policy.max_backoff.as_millis() as u64,
+            multiplier: policy.multiplier,
+        }
+    }
+}
+
+/// ワーカーごとの設定
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkersConfig {
+    pub epg_retriever: WorkerSettings,
+    pub ogp_url_extractor: WorkerSettings,
+    pub ogp_image_extractor: WorkerSettings,
+    pub ogp_image_processor: WorkerSettings,
+    pub auto_reserver: WorkerSettings,
+    pub onair_tracker: WorkerSettings,
+    /// 録画のイベントの種類ごとの並行数
+    pub recording_tracker: WorkerSettings,
+    pub transcoder: WorkerSettings,
+    pub recording_archiver: WorkerSettings,
+    pub program_indexer: WorkerSettings,
+}
+
+impl Default for WorkersConfig {
+    fn default() -> Self {
+        Self {
+            epg_retriever: WorkerSettings { concurrency: 1 },
+            ogp_url_extractor: WorkerSettings { concurrency: 1 },
+            ogp_image_extractor: WorkerSettings { concurrency: 16 },
+            ogp_image_processor: WorkerSettings {
+                concurrency: available_cores(),
+            },
+            auto_reserver: WorkerSettings { concurrency: 1 },
+            onair_tracker: WorkerSettings { concurrency: 1 },
+            recording_tracker: WorkerSettings { concurrency: 1 },
+            transcoder: WorkerSettings { concurrency: 1 },
+            recording_archiver: WorkerSettings { concurrency: 1 },
+            program_indexer: WorkerSettings { concurrency: 1 },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkerSettings {
+    /// 同時に処理するメッセージの最大数
+    pub concurrency: usize,
+}
+
+/// CPUを使い切る処理の既定の並行数
+fn available_cores() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+impl KurecConfig {
+    /// 設定ファイル（省略時は既定値）を読み込み、環境変数で上書きする
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.apply_env(|name| std::env::var(name).ok())?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        if let Some(url) = var(ENV_NATS_URL) {
+            self.nats.url = url;
+        }
+        if let Some(url) = var(ENV_MIRAKC_URL) {
+            self.mirakc.url = url;
+        }
+        if let Some(value) = var(ENV_MIRAKC_RETRY_MAX) {
+            self.mirakc.retry_max = value.parse().map_err(|_| ConfigError::Env {
+                name: ENV_MIRAKC_RETRY_MAX,
+                value,
+            })?;
+        }
+        if let Some(listen) = var(ENV_HTTP_LISTEN) {
+            self.http.listen = Some(listen);
+        }
+        if let Some(endpoint) = var(ENV_S3_ENDPOINT) {
+            self.object_store.s3.endpoint = endpoint;
+        }
+        if let Some(access_key) = var(ENV_S3_ACCESS_KEY) {
+            self.object_store.s3.access_key = access_key;
+        }
+        if let Some(secret_key) = var(ENV_S3_SECRET_KEY) {
+            self.object_store.s3.secret_key = secret_key;
+        }
+        if let Some(url) = var(ENV_MEILISEARCH_URL) {
+            self.search.meilisearch.url = url;
+        }
+        if let Some(api_key) = var(ENV_MEILISEARCH_API_KEY) {
+            self.search.meilisearch.api_key = Some(api_key);
+        }
+        Ok(())
+    }
+
+    /// 設定値の整合性を検証する
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.nats.url.is_empty() {
+            problems.push("nats.url が空です".to_string());
+        }
+        if !self.mirakc.url.starts_with("http://") && !self.mirakc.url.starts_with("https://") {
+            problems.push(format!(
+                "mirakc.url はhttp(s)のURLである必要があります: {}",
+                self.mirakc.url
+            ));
+        }
+
+        let mut stream_names = HashSet::from([DLQ_STREAM_NAME]);
+        for (key, stream) in [
+            ("streams.events", &self.streams.events),
+            ("streams.ogp", &self.streams.ogp),
+        ] {
+            if stream.name.is_empty() {
+                problems.push(format!("{}.name が空です", key));
+            } else if !stream_names.insert(stream.name.as_str()) {
+                problems.push(format!(
+                    "{}.name が他のストリームと重複しています: {}",
+                    key, stream.name
+                ));
+            }
+            if stream.subjects.is_empty() {
+                problems.push(format!("{}.subjects が空です", key));
+            }
+            if stream.replicas == 0 {
+                problems.push(format!("{}.replicas は1以上である必要があります", key));
+            }
+        }
+
+        if !(1..=64).contains(&self.kv.history) {
+            problems.push(format!(
+                "kv.history は1から64の範囲である必要があります: {}",
+                self.kv.history
+            ));
+        }
+        if self.kv.replicas == 0 {
+            problems.push("kv.replicas は1以上である必要があります".to_string());
+        }
+
+        if self.ogp.image_width == 0 {
+            problems.push("ogp.image_width は1以上である必要があります".to_string());
+        }
+
+        for (key, worker) in [
+            ("workers.epg_retriever", &self.workers.epg_retriever),
+            ("workers.ogp_url_extractor", &self.workers.ogp_url_extractor),
+            (
+                "workers.ogp_image_extractor",
+                &self.workers.ogp_image_extractor,
+            ),
+            (
+                "workers.ogp_image_processor",
+                &self.workers.ogp_image_processor,
+            ),
+            ("workers.auto_reserver", &self.workers.auto_reserver),
+            ("workers.onair_tracker", &self.workers.onair_tracker),
+            ("workers.recording_tracker", &self.workers.recording_tracker),
+            ("workers.transcoder", &self.workers.transcoder),
+            (
+                "workers.recording_archiver",
+                &self.workers.recording_archiver,
+            ),
+            ("workers.program_indexer", &self.workers.program_indexer),
+        ] {
+            if worker.concurrency == 0 {
+                problems.push(format!("{}.concurrency は1以上である必要があります", key));
+            }
+        }
+
+        if self.epg_sync.interval_secs == 0 {
+            problems.push("epg_sync.interval_secs は1以上である必要があります".to_string());
+        }
+        if self.inventory_sync.interval_secs == 0 {
+            problems.push("inventory_sync.interval_secs は1以上である必要があります".to_string());
+        }
+        if self.logo_sync.interval_secs == 0 {
+            problems.push("logo_sync.interval_secs は1以上である必要があります".to_string());
+        }
+        if self.logo_sync.image_width == 0 {
+            problems.push("logo_sync.image_width は1以上である必要があります".to_string());
+        }
+
+        if self.transcode.encoder.is_empty() {
+            problems.push("transcode.encoder が空です".to_string());
+        }
+        if self.transcode.records_dir == self.transcode.output_dir {
+            problems.push(
+                "transcode.output_dir は transcode.records_dir と別のディレクトリである必要があります"
+                    .to_string(),
+            );
+        }
+        if self.transcode.profile.extension.is_empty() {
+            problems.push("transcode.profile.extension が空です".to_string());
+        }
+
+        if !self.object_store.is_valid_bucket() {
+            problems.push(format!(
+                "object_store.bucket は {:?} で使えない名前です: {:?}",
+                self.object_store.backend, self.object_store.bucket
+            ));
+        }
+        if self.object_store.backend == ObjectStoreBackendKind::S3 {
+            let s3 = &self.object_store.s3;
+            if !s3.endpoint.starts_with("http://") && !s3.endpoint.starts_with("https://") {
+                problems.push(format!(
+                    "object_store.s3.endpoint はhttp(s)のURLである必要があります: {}",
+                    s3.endpoint
+                ));
+            }
+            if s3.region.is_empty() {
+                problems.push("object_store.s3.region が空です".to_string());
+            }
+            if s3.access_key.is_empty() || s3.secret_key.is_empty() {
+                problems.push(format!(
+                    "object_store.s3.access_key と object_store.s3.secret_key（{}・{}）を指定してください",
+                    ENV_S3_ACCESS_KEY, ENV_S3_SECRET_KEY
+                ));
+            }
+        }
+
+        match self.search.backend {
+            SearchBackendKind::Meilisearch => {
+                let meilisearch = &self.search.meilisearch;
+                if !meilisearch.url.starts_with("http://")
+                    && !meilisearch.url.starts_with("https://")
+                {
+                    problems.push(format!(
+                        "search.meilisearch.url はhttp(s)のURLである必要があります: {}",
+                        meilisearch.url
+                    ));
+                }
+                // インデックス名に使える文字はASCIIの英数字・ハイフン・アンダースコアだけ
+                if meilisearch.index.is_empty()
+                    || !meilisearch
+                        .index
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    problems.push(format!(
+                        "search.meilisearch.index は使えない名前です: {:?}",
+                        meilisearch.index
+                    ));
+                }
+            }
+            SearchBackendKind::Tantivy => {
+                if self.search.tantivy.index_dir.as_os_str().is_empty() {
+                    problems.push("search.tantivy.index_dir が空です".to_string());
+                }
+                if self.search.tantivy.dictionary.is_empty() {
+                    problems.push("search.tantivy.dictionary が空です".to_string());
+                }
+            }
+        }
+
+        if let Some(listen) = &self.http.listen
+            && listen.parse::<SocketAddr>().is_err()
+        {
+            problems.push(format!(
+                "http.listen は「アドレス:ポート」の形式である必要があります: {}",
+                listen
+            ));
+        }
+
+        if self.startup.initial_backoff_ms > self.startup.max_backoff_ms {
+            problems.push(format!(
+                "startup.initial_backoff_ms は startup.max_backoff_ms 以下である必要があります: {} > {}",
+                self.startup.initial_backoff_ms, self.startup.max_backoff_ms
+            ));
+        }
+
+        if self.retry.max_attempts == 0 {
+            problems.push("retry.max_attempts は1以上である必要があります".to_string());
+        }
+        if self.retry.initial_backoff_ms > self.retry.max_backoff_ms {
+            problems.push(format!(
+                "retry.initial_backoff_ms は retry.max_backoff_ms 以下である必要があります: {} > {}",
+                self.retry.initial_backoff_ms, self.retry.max_backoff_ms
+            ));
+        }
+        if !self.retry.multiplier.is_finite() || self.retry.multiplier < 1.0 {
+            problems.push(format!(
+                "retry.multiplier は1以上である必要があります: {}",
+                self.retry.multiplier
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems.join("; ")))
+        }
+    }
+
+    /// 設定をTOMLとして出力する
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn nats_connect_options(&self) -> NatsConnectOptions {
+        NatsConnectOptions {
+            connection_timeout: Duration::from_secs(self.nats.connection_timeout_secs),
+            max_reconnects: self.nats.max_reconnects,
+            name: self.nats.name.clone(),
+            // 起動時の接続は失敗を検出して再試行と終了コードで扱う
+            retry_on_initial_connect: false,
+        }
+    }
+
+    pub fn kv_bucket_config(&self) -> KvBucketConfig {
+        KvBucketConfig {
+            history: self.kv.history,
+            max_age: Duration::from_secs(self.kv.max_age_secs),
+            replicas: self.kv.replicas,
+        }
+    }
+
+    pub fn startup_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.startup.max_attempts,
+            initial_backoff: Duration::from_millis(self.startup.initial_backoff_ms),
+            max_backoff: Duration::from_millis(self.startup.max_backoff_ms),
+            ..Default::default()
+        }
+    }
+
+    /// ワーカーが処理に失敗したメッセージの再試行方針
+    pub fn worker_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.retry.max_attempts,
+            initial_backoff: Duration::from_millis(self.retry.initial_backoff_ms),
+            max_backoff: Duration::from_millis(self.retry.max_backoff_ms),
+            multiplier: self.retry.multiplier,
+        }
+    }
+
+    /// kurecが使うすべてのストリームの設定
+    pub fn stream_configs(&self) -> Vec<StreamConfig> {
+        vec![
+            self.streams.events.to_stream_config(),
+            self.streams.ogp.to_stream_config(),
+            DeadLetterQueue::stream_config(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::model::transcode::DualMonoHandling;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_default_is_valid_and_round_trips() {
+        let config = KurecConfig::default();
+        config.validate().unwrap();
+
+        let dumped = config.to_toml().unwrap();
+        let parsed: KurecConfig = toml::from_str(&dumped).unwrap();
+        assert_eq!(parsed, config);
+    }
+
+    #[test]
+    fn test_partial_file_keeps_defaults() {
+        let config: KurecConfig = toml::from_str(
+            r#"
+            [nats]
+            url = "nats://localhost:4222"
+
+            [ogp]
+            image_width = 480
+
+            [workers.ogp_image_extractor]
+            concurrency = 4
+
+            [transcode.profile]
+            height = 720
+            dual_mono = "split"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.nats.url, "nats://localhost:4222");
+        assert_eq!(config.nats.connection_timeout_secs, 10);
+        assert_eq!(config.mirakc, MirakcConfig::default());
+        assert_eq!(config.ogp.image_width, 480);
+        assert_eq!(
+            config.ogp.excluded_domains,
+            OgpConfig::default().excluded_domains
+        );
+        assert_eq!(config.workers.ogp_image_extractor.concurrency, 4);
+        assert_eq!(config.workers.epg_retriever.concurrency, 1);
+        assert_eq!(config.transcode.profile.height, Some(720));
+        assert_eq!(config.transcode.profile.dual_mono, DualMonoHandling::Split);
+        assert_eq!(config.transcode.profile.video_codec, "libx264");
+        assert_eq!(config.transcode.encoder, "ffmpeg");
+    }
+
+    #[test]
+    fn test_unknown_field_is_rejected() {
+        let result = toml::from_str::<KurecConfig>(
+            r#"
+            [nats]
+            uri = "nats://localhost:4222"
+            "#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let mut config: KurecConfig = toml::from_str(
+            r#"
+            [mirakc]
+            url = "http://mirakc:40772"
+            retry_max = 3
+            "#,
+        )
+        .unwrap();
+        let env = HashMap::from([
+            (ENV_NATS_URL, "nats://env:4222"),
+            (ENV_MIRAKC_RETRY_MAX, "0"),
+            (ENV_HTTP_LISTEN, "0.0.0.0:9090"),
+        ]);
+
+        config
+            .apply_env(|name| env.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.nats.url, "nats://env:4222");
+        assert_eq!(config.mirakc.url, "http://mirakc:40772");
+        assert_eq!(config.mirakc.retry_max, 0);
+        assert_eq!(config.http.listen_addr(), Some(([0, 0, 0, 0], 9090).into()));
+    }
+
+    #[test]
+    fn test_invalid_env_value() {
+        let mut config = KurecConfig::default();
+
+        let result =
+            config.apply_env(|name| (name == ENV_MIRAKC_RETRY_MAX).then(|| "many".to_string()));
+
+        assert!(matches!(
+            result,
+            Err(ConfigError::Env {
+                name: ENV_MIRAKC_RETRY_MAX,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_validate_reports_all_problems() {
+        let mut config = KurecConfig::default();
+        config.mirakc.url = "tuner:40772".to_string();
+        config.streams.ogp.name = config.streams.events.name.clone();
+        config.workers.ogp_image_processor.concurrency = 0;
+        config.http.listen = Some("9090".to_string());
+        config.startup.initial_backoff_ms = 60_000;
+        config.inventory_sync.interval_secs = 0;
+        config.logo_sync.image_width = 0;
+        config.transcode.output_dir = config.transcode.records_dir.clone();
+        config.object_store.backend = ObjectStoreBackendKind::S3;
+        config.object_store.bucket = "KuRec".to_string();
+        config.search.meilisearch.index = "番組".to_string();
+        config.retry.max_attempts = 0;
+
+        let Err(ConfigError::Invalid(message)) = config.validate() else {
+            panic!("検証に失敗するはずです");
+        };
+        assert!(message.contains("mirakc.url"));
+        assert!(message.contains("streams.ogp.name"));
+        assert!(message.contains("workers.ogp_image_processor.concurrency"));
+        assert!(message.contains("http.listen"));
+        assert!(message.contains("startup.initial_backoff_ms"));
+        assert!(message.contains("inventory_sync.interval_secs"));
+        assert!(message.contains("logo_sync.image_width"));
+        assert!(message.contains("transcode.output_dir"));
+        assert!(message.contains("object_store.bucket"));
+        assert!(message.contains("object_store.s3.access_key"));
+        assert!(message.contains("search.meilisearch.index"));
+        assert!(message.contains("retry.max_attempts"));
+    }
+
+    #[test]
+    fn test_s3_settings_from_file_and_env() {
+        let mut config: KurecConfig = toml::from_str(
+            r#"
+            [object_store]
+            backend = "s3"
+            store_images = true
+
+            [object_store.s3]
+            endpoint = "http://minio:9000"
+            access_key = "minio"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_err());
+        let env = HashMap::from([(ENV_S3_SECRET_KEY, "minio123")]);
+
+        config
+            .apply_env(|name| env.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        config.validate().unwrap();
+        let s3 = config.object_store.s3_config();
+        assert_eq!(s3.bucket, "kurec");
+        assert_eq!(s3.region, "us-east-1");
+        assert_eq!(s3.secret_key, "minio123");
+        // シークレットキーは設定の出力にもログにも含めない
+        assert!(!config.to_toml().unwrap().contains("minio123"));
+        assert!(!format!("{:?}", config).contains("minio123"));
+    }
+
+    #[test]
+    fn test_meilisearch_settings_from_env() {
+        let mut config = KurecConfig::default();
+        let env = HashMap::from([
+            (ENV_MEILISEARCH_URL, "http://localhost:7700"),
+            (ENV_MEILISEARCH_API_KEY, "master-key"),
+        ]);
+
+        config
+            .apply_env(|name| env.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        config.validate().unwrap();
+        let meilisearch = config.search.meilisearch_config();
+        assert_eq!(meilisearch.url, "http://localhost:7700");
+        assert_eq!(meilisearch.index, "programs");
+        assert_eq!(meilisearch.api_key.as_deref(), Some("master-key"));
+        // APIキーは設定の出力にもログにも含めない
+        assert!(!config.to_toml().unwrap().contains("master-key"));
+        assert!(!format!("{:?}", config).contains("master-key"));
+    }
+
+    #[test]
+    fn test_tantivy_backend_from_file() {
+        let mut config: KurecConfig = toml::from_str(
+            r#"
+            [search]
+            backend = "tantivy"
+
+            [search.tantivy]
+            index_dir = "/data/search"
+            "#,
+        )
+        .unwrap();
+
+        config.validate().unwrap();
+        assert_eq!(config.search.backend, SearchBackendKind::Tantivy);
+        assert_eq!(config.search.tantivy.index_dir, Path::new("/data/search"));
+        assert_eq!(
+            config.search.tantivy.dictionary,
+            TantivySettings::default().dictionary
+        );
+
+        config.search.tantivy.dictionary = String::new();
+        let Err(ConfigError::Invalid(message)) = config.validate() else {
+            panic!("検証に失敗するはずです");
+        };
+        assert!(message.contains("search.tantivy.dictionary"));
+    }
+
+    #[test]
+    fn test_stream_configs_include_dlq() {
+        let mut config = KurecConfig::default();
+        config.streams.events.max_age_secs = 3600;
+
+        let streams = config.stream_configs();
+
+        let names: Vec<&str> = streams.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["kurec", "kurec-ogp", "kurec-dlq"]);
+        assert_eq!(streams[0].max_age, Duration::from_secs(3600));
+    }
+}
@@ -1,37 +1,41 @@
-use std::vec;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
 
+use bootstrap::{BootstrapError, EXIT_CONFIG, EXIT_USAGE, exit_code_for, is_fatal};
 use clap::{Parser, Subcommand};
-use domain::model::event::recording::epg::Updated;
-use domain::model::program::ProgramsData;
-use domain::ports::ProgramsRetriever;
-use domain::repository::KvRepository;
-use futures::StreamExt as _;
-use mirakc::get_mirakc_event_stream;
+use config::KurecConfig;
+use domain::{model::recording_rule::RecordingRule, repository::RecordingRuleRepository};
 use nats::{
-    nats::connect_nats,
-    repositories::ProgramsDataRepository,
-    stream::{EventReader, EventStore},
-    stream_manager::{StreamConfig, create_or_update_streams},
+    dlq::{DeadLetter, DeadLetterQueue},
+    error::NatsInfraError,
+    nats::NatsClient,
+    repositories::RecordingRuleEntryRepository,
 };
-use tracing::{debug, error};
+use observability::{MirakcHealthCheck, Telemetry};
+use tasks::{TaskError, TaskKind, run_task, sync_epg_once};
+use tracing::{debug, error, info};
 use tracing_subscriber::{EnvFilter, fmt};
+use worker::{
+    CancellationToken, HealthServer, NatsHealthCheck, RestartPolicy, Supervisor, SupervisorHealth,
+    WorkerMetrics, shutdown_token,
+};
 
-mod ogp_image_processor_worker;
+mod bootstrap;
+mod config;
+mod observability;
 mod repositories;
-
-async fn process_ogp_image_processor(nats_url: &str) {
-    debug!("OGP画像処理ワーカーを開始します...");
-    let nats_client = connect_nats(nats_url).await.unwrap();
-
-    setup_kurec_streams(&nats_client).await.unwrap();
-
-    ogp_image_processor_worker::process_ogp_image_processor(nats_client).await;
-}
+mod tasks;
+mod workers;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
+    /// 設定ファイル（TOML）のパス
+    #[arg(long, global = true, env = "KUREC_CONFIG")]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -41,45 +45,443 @@ enum Commands {
     /// mirakcイベントを処理します
     Events {
         /// mirakcサーバーのURL
-        #[arg(short, long, default_value = "http://tuner:40772")]
-        mirakc_url: String,
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
 
         /// NATSサーバーのURL
-        #[arg(short, long, default_value = "nats:4222")]
-        nats_url: String,
+        #[arg(short, long)]
+        nats_url: Option<String>,
 
         /// 再試行の最大回数（0は無限回）
-        #[arg(short, long, default_value_t = 5)]
-        retry_max: u32,
+        #[arg(short, long)]
+        retry_max: Option<u32>,
     },
     EpgRetriever {
         /// mirakcサーバーのURL
-        #[arg(short, long, default_value = "http://tuner:40772")]
-        mirakc_url: String,
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
+
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+    },
+    /// mirakcの全サービスの番組表を同期します
+    EpgSync {
+        /// mirakcサーバーのURL
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
+
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 全件同期を繰り返す間隔（秒）
+        #[arg(short, long)]
+        interval_secs: Option<u64>,
+
+        /// 1回だけ同期して終了します
+        #[arg(long)]
+        once: bool,
+    },
+    /// mirakcのチューナー・チャンネル・サービスの一覧を同期します
+    InventorySync {
+        /// mirakcサーバーのURL
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
+
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同期を繰り返す間隔（秒）
+        #[arg(short, long)]
+        interval_secs: Option<u64>,
+    },
+    /// mirakcの全サービスのロゴ画像を取得・変換して保存します
+    LogoSync {
+        /// mirakcサーバーのURL
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
 
         /// NATSサーバーのURL
-        #[arg(short, long, default_value = "nats:4222")]
-        nats_url: String,
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同期を繰り返す間隔（秒）
+        #[arg(short, long)]
+        interval_secs: Option<u64>,
     },
     OgpUrlExtractor {
         /// NATSサーバーのURL
-        #[arg(short, long, default_value = "nats:4222")]
-        nats_url: String,
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
     },
     OgpImageExtractor {
         /// NATSサーバーのURL
-        #[arg(short, long, default_value = "nats:4222")]
-        nats_url: String,
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
     },
     OgpImageProcessor {
         /// NATSサーバーのURL
-        #[arg(short, long, default_value = "nats:4222")]
-        nats_url: String,
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+    },
+    /// 録画ルールに一致した番組を自動で予約します
+    AutoReserver {
+        /// mirakcサーバーのURL
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
+
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+    },
+    /// 各サービスで放送中の番組と次の番組を追跡します
+    OnairTracker {
+        /// mirakcサーバーのURL
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
+
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+    },
+    /// 録画のイベントから録画ごとの状態を記録します
+    RecordingTracker {
+        /// mirakcサーバーのURL
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
+
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// イベントの種類ごとに同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+    },
+    /// 保存された録画をプロファイルに従ってトランスコードします
+    Transcoder {
+        /// mirakcサーバーのURL
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
+
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+
+        /// エンコーダーのコマンド（ffmpeg互換）
+        #[arg(short, long)]
+        encoder: Option<String>,
+    },
+    /// トランスコードした録画をオブジェクトストアに保存します
+    RecordingArchiver {
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+    },
+    /// 更新された番組表を検索インデックスに登録します
+    ProgramIndexer {
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        /// 同時に処理するメッセージの最大数
+        #[arg(short, long)]
+        concurrency: Option<usize>,
+
+        /// MeilisearchのURL
+        #[arg(long)]
+        meilisearch_url: Option<String>,
+    },
+    /// 自動予約のルールを操作します
+    Rule {
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        #[command(subcommand)]
+        command: RuleCommands,
+    },
+    /// デッドレターキューを操作します
+    Dlq {
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+
+        #[command(subcommand)]
+        command: DlqCommands,
+    },
+    /// 複数のワーカーを1つのプロセスで実行します
+    Run {
+        /// 実行するワーカー（カンマ区切り、allはすべて）
+        #[arg(short, long, value_delimiter = ',', default_value = "all")]
+        workers: Vec<String>,
+
+        /// mirakcサーバーのURL
+        #[arg(short, long)]
+        mirakc_url: Option<String>,
+
+        /// NATSサーバーのURL
+        #[arg(short, long)]
+        nats_url: Option<String>,
+    },
+    /// 設定を確認します
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// 設定を読み込んで検証します
+    Check,
+    /// 環境変数と引数を反映した設定をTOMLで出力します
+    Dump,
+}
+
+#[derive(Subcommand)]
+enum RuleCommands {
+    /// ルールを一覧表示します
+    List,
+    /// JSONファイルに書いたルールを保存します（同じIDのルールは置き換えます）
+    Put {
+        /// ルールのJSONファイルのパス
+        file: PathBuf,
+    },
+    /// ルールを削除します
+    Delete {
+        /// 削除するルールのID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DlqCommands {
+    /// デッドレターを一覧表示します
+    List {
+        /// 元のサブジェクトで絞り込みます（ワイルドカード可）
+        #[arg(short, long)]
+        subject: Option<String>,
+
+        /// 表示する最大件数
+        #[arg(short, long, default_value_t = 100)]
+        limit: usize,
+    },
+    /// デッドレターを元のサブジェクトへ再投入します
+    Replay {
+        /// 再投入するデッドレターのシーケンス番号（省略時は条件に一致するすべて）
+        sequence: Option<u64>,
+
+        /// 元のサブジェクトで絞り込みます（ワイルドカード可）
+        #[arg(short, long, conflicts_with = "sequence")]
+        subject: Option<String>,
+    },
+    /// デッドレターを削除します
+    Purge {
+        /// 元のサブジェクトで絞り込みます（ワイルドカード可）
+        #[arg(short, long)]
+        subject: Option<String>,
     },
 }
 
+impl Commands {
+    /// コマンドライン引数で指定された値で設定を上書きする
+    fn apply_overrides(&self, config: &mut KurecConfig) {
+        fn set<T: Clone>(target: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *target = value.clone();
+            }
+        }
+
+        match self {
+            Commands::Events {
+                mirakc_url,
+                nats_url,
+                retry_max,
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.mirakc.retry_max, retry_max);
+            }
+            Commands::EpgRetriever {
+                mirakc_url,
+                nats_url,
+                concurrency,
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.workers.epg_retriever.concurrency, concurrency);
+            }
+            Commands::EpgSync {
+                mirakc_url,
+                nats_url,
+                interval_secs,
+                ..
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.epg_sync.interval_secs, interval_secs);
+            }
+            Commands::InventorySync {
+                mirakc_url,
+                nats_url,
+                interval_secs,
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.inventory_sync.interval_secs, interval_secs);
+            }
+            Commands::LogoSync {
+                mirakc_url,
+                nats_url,
+                interval_secs,
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.logo_sync.interval_secs, interval_secs);
+            }
+            Commands::OgpUrlExtractor {
+                nats_url,
+                concurrency,
+            } => {
+                set(&mut config.nats.url, nats_url);
+                set(
+                    &mut config.workers.ogp_url_extractor.concurrency,
+                    concurrency,
+                );
+            }
+            Commands::OgpImageExtractor {
+                nats_url,
+                concurrency,
+            } => {
+                set(&mut config.nats.url, nats_url);
+                set(
+                    &mut config.workers.ogp_image_extractor.concurrency,
+                    concurrency,
+                );
+            }
+            Commands::OgpImageProcessor {
+                nats_url,
+                concurrency,
+            } => {
+                set(&mut config.nats.url, nats_url);
+                set(
+                    &mut config.workers.ogp_image_processor.concurrency,
+                    concurrency,
+                );
+            }
+            Commands::AutoReserver {
+                mirakc_url,
+                nats_url,
+                concurrency,
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.workers.auto_reserver.concurrency, concurrency);
+            }
+            Commands::OnairTracker {
+                mirakc_url,
+                nats_url,
+                concurrency,
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.workers.onair_tracker.concurrency, concurrency);
+            }
+            Commands::RecordingTracker {
+                mirakc_url,
+                nats_url,
+                concurrency,
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(
+                    &mut config.workers.recording_tracker.concurrency,
+                    concurrency,
+                );
+            }
+            Commands::Transcoder {
+                mirakc_url,
+                nats_url,
+                concurrency,
+                encoder,
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.workers.transcoder.concurrency, concurrency);
+                set(&mut config.transcode.encoder, encoder);
+            }
+            Commands::RecordingArchiver {
+                nats_url,
+                concurrency,
+            } => {
+                set(&mut config.nats.url, nats_url);
+                set(
+                    &mut config.workers.recording_archiver.concurrency,
+                    concurrency,
+                );
+            }
+            Commands::ProgramIndexer {
+                nats_url,
+                concurrency,
+                meilisearch_url,
+            } => {
+                set(&mut config.nats.url, nats_url);
+                set(&mut config.workers.program_indexer.concurrency, concurrency);
+                set(&mut config.search.meilisearch.url, meilisearch_url);
+            }
+            Commands::Rule { nats_url, .. } | Commands::Dlq { nats_url, .. } => {
+                set(&mut config.nats.url, nats_url);
+            }
+            Commands::Run {
+                mirakc_url,
+                nats_url,
+                ..
+            } => {
+                set(&mut config.mirakc.url, mirakc_url);
+                set(&mut config.nats.url, nats_url);
+            }
+            Commands::Config { .. } => {}
+        }
+    }
+}
+
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let _ = fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .with_test_writer()
@@ -87,735 +489,423 @@ async fn main() {
 
     let cli = Cli::parse();
 
+    let mut config = match KurecConfig::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::from(EXIT_CONFIG);
+        }
+    };
+    cli.command.apply_overrides(&mut config);
+
+    // config サブコマンドは検証の結果そのものを報告する
+    if !matches!(cli.command, Commands::Config { .. })
+        && let Err(e) = config.validate()
+    {
+        error!("{}", e);
+        return ExitCode::from(EXIT_CONFIG);
+    }
+
+    let shutdown = shutdown_token();
+
     match &cli.command {
-        Commands::Events {
-            mirakc_url,
-            nats_url,
-            retry_max,
-        } => {
-            process_events(mirakc_url, nats_url, *retry_max).await;
+        Commands::Events { .. } => process_task(&config, TaskKind::Events, shutdown).await,
+        Commands::EpgRetriever { .. } => {
+            process_task(&config, TaskKind::EpgRetriever, shutdown).await
         }
-        Commands::EpgRetriever {
-            mirakc_url,
-            nats_url,
-        } => {
-            process_epg_retriever(mirakc_url, nats_url).await;
+        Commands::EpgSync { once: true, .. } => process_epg_sync_once(&config, shutdown).await,
+        Commands::EpgSync { .. } => process_task(&config, TaskKind::EpgSync, shutdown).await,
+        Commands::InventorySync { .. } => {
+            process_task(&config, TaskKind::InventorySync, shutdown).await
         }
-        Commands::OgpUrlExtractor { nats_url } => {
-            process_ogp_url_extractor(nats_url).await;
+        Commands::LogoSync { .. } => process_task(&config, TaskKind::LogoSync, shutdown).await,
+        Commands::OgpUrlExtractor { .. } => {
+            process_task(&config, TaskKind::OgpUrlExtractor, shutdown).await
         }
-        Commands::OgpImageExtractor { nats_url } => {
-            process_ogp_image_extractor(nats_url).await;
+        Commands::OgpImageExtractor { .. } => {
+            process_task(&config, TaskKind::OgpImageExtractor, shutdown).await
         }
-        Commands::OgpImageProcessor { nats_url } => {
-            process_ogp_image_processor(nats_url).await;
+        Commands::OgpImageProcessor { .. } => {
+            process_task(&config, TaskKind::OgpImageProcessor, shutdown).await
         }
-    }
-}
-
-async fn setup_kurec_streams(
-    nats_client: &nats::nats::NatsClient,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let stream_configs = vec![
-        StreamConfig {
-            name: "kurec".to_string(),
-            subjects: vec![
-                "recording.>".to_string(), // すべてのrecordingイベントをカバー
-            ],
-            ..Default::default()
-        },
-        StreamConfig {
-            name: "kurec-ogp".to_string(),
-            subjects: vec!["ogp.>".to_string()],
-            ..Default::default()
-        },
-    ];
-
-    create_or_update_streams(nats_client, &stream_configs).await?;
-
-    Ok(())
-}
-
-async fn process_events(mirakc_url: &str, nats_url: &str, retry_max: u32) {
-    let mut sse_stream = get_mirakc_event_stream(mirakc_url, retry_max)
-        .await
-        .unwrap();
-    let nats_client = connect_nats(nats_url).await.unwrap();
-    let event_store = EventStore::<Updated>::new(nats_client.clone())
-        .await
-        .unwrap();
-
-    setup_kurec_streams(&nats_client).await.unwrap();
-
-    while let Some(event) = sse_stream.next().await {
-        debug!("Received event: {:?}", event);
-        match event.event_type.as_str() {
-            "epg.programs-updated" => {
-                debug!("EPG updated: {:?}", event.data);
-                let ev = serde_json::from_str::<mirakc::sse_event::ProgramsUpdated>(&event.data);
-                match ev {
-                    Ok(ev) => {
-                        debug!("Parsed event: {:?}", ev);
-                        let domain_ev = Updated {
-                            service_id: ev.service_id,
-                            mirakc_url: mirakc_url.to_string(),
-                        };
-                        event_store.publish_event(&domain_ev).await.unwrap();
-                    }
-                    Err(e) => {
-                        debug!("Failed to parse event: {:?}", e);
-                    }
-                }
-            }
-            "recording.record-saved" => {
-                debug!("Recording saved: {:?}", event.data);
-            }
-            _ => {
-                debug!("Unknown event type: {:?}", event.event_type);
-            }
+        Commands::AutoReserver { .. } => {
+            process_task(&config, TaskKind::AutoReserver, shutdown).await
         }
-    }
-}
-
-async fn process_epg_retriever(mirakc_url: &str, nats_url: &str) {
-    use domain::model::event::recording::{epg, programs};
-    use mirakc::MirakcProgramsRetriever;
-    use nats::kvs::NatsKvRepositoryTrait;
-
-    debug!("EPGリトリーバーを開始します...");
-    let nats_client = connect_nats(nats_url).await.unwrap();
-
-    let epg_event_store = EventStore::<epg::Updated>::new(nats_client.clone())
-        .await
-        .unwrap();
-    let programs_event_store = EventStore::<programs::Updated>::new(nats_client.clone())
-        .await
-        .unwrap();
-
-    let programs_kvs_repo = ProgramsDataRepository::new(nats_client.clone())
-        .await
-        .unwrap();
-
-    setup_kurec_streams(&nats_client).await.unwrap();
-
-    let reader = epg_event_store
-        .get_reader("epg-retriever".to_string())
-        .await
-        .unwrap();
-
-    let programs_retriever = MirakcProgramsRetriever::new(mirakc_url);
-
-    debug!("EPGイベント待機中...");
-
-    loop {
-        match reader.next().await {
-            Ok((event, mut ack_handle)) => {
-                let service_id = event.service_id;
-                debug!("EPG更新イベントを受信: service_id={}", service_id);
-
-                match programs_retriever.get_programs(service_id).await {
-                    Ok(programs) => {
-                        debug!(
-                            "サービスID {} のプログラム {} 件を取得",
-                            service_id,
-                            programs.len()
-                        );
-
-                        let key = service_id.to_string();
-                        let programs_data = ProgramsData(programs);
-                        if let Err(e) = programs_kvs_repo.put(key.clone(), &programs_data).await {
-                            error!("KVSへのプログラムデータ保存に失敗: {}", e);
-                            continue;
-                        }
-
-                        let programs_updated = programs::Updated {
-                            service_id,
-                            mirakc_url: mirakc_url.to_string(),
-                        };
-
-                        match programs_event_store.publish_event(&programs_updated).await {
-                            Ok(_) => {
-                                debug!(
-                                    "プログラム更新イベントを発行しました: service_id={}",
-                                    service_id
-                                );
-                                if let Err(e) = ack_handle.ack().await {
-                                    error!("メッセージの確認（ack）に失敗: {:?}", e);
-                                }
-                            }
-                            Err(e) => error!("プログラム更新イベントの発行に失敗: {:?}", e),
-                        }
-                    }
-                    Err(e) => {
-                        error!("プログラム情報の取得に失敗: {:?}", e);
-                    }
-                }
-            }
-            Err(e) => {
-                error!("EPGイベントの受信に失敗: {:?}", e);
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-            }
+        Commands::OnairTracker { .. } => {
+            process_task(&config, TaskKind::OnairTracker, shutdown).await
+        }
+        Commands::RecordingTracker { .. } => {
+            process_task(&config, TaskKind::RecordingTracker, shutdown).await
+        }
+        Commands::Transcoder { .. } => process_task(&config, TaskKind::Transcoder, shutdown).await,
+        Commands::RecordingArchiver { .. } => {
+            process_task(&config, TaskKind::RecordingArchiver, shutdown).await
+        }
+        Commands::ProgramIndexer { .. } => {
+            process_task(&config, TaskKind::ProgramIndexer, shutdown).await
         }
+        Commands::Run { workers, .. } => process_run(config, workers, shutdown).await,
+        Commands::Rule { command, .. } => process_rule(&config, command, shutdown).await,
+        Commands::Dlq { command, .. } => process_dlq(&config, command, shutdown).await,
+        Commands::Config { command } => process_config(&config, command),
     }
 }
 
-async fn process_ogp_url_extractor(nats_url: &str) {
-    use domain::model::event::{ogp, recording::programs};
-    use domain::model::url_extractor::UrlExtractor;
-    use nats::kvs::NatsKvRepositoryTrait;
-
-    debug!("OGP URL抽出ワーカーを開始します...");
-    let nats_client = connect_nats(nats_url).await.unwrap();
-
-    let programs_event_store = EventStore::<programs::Updated>::new(nats_client.clone())
-        .await
-        .unwrap();
-    let ogp_event_store = EventStore::<ogp::url::ExtractRequest>::new(nats_client.clone())
-        .await
-        .unwrap();
-
-    let programs_kvs_repo = ProgramsDataRepository::new(nats_client.clone())
-        .await
-        .unwrap();
-
-    setup_kurec_streams(&nats_client).await.unwrap();
-
-    debug!("プログラム更新イベント待機中...");
-
-    let reader = programs_event_store
-        .get_reader("ogp_url_extractor".to_string())
-        .await
-        .unwrap();
-
-    loop {
-        match reader.next().await {
-            Ok((event, mut ack_handle)) => {
-                let service_id = event.service_id;
-                debug!("プログラム更新イベントを受信: service_id={}", service_id);
-
-                let key = service_id.to_string();
-                match programs_kvs_repo.get(key).await {
-                    Ok(Some(versioned)) => {
-                        let programs_data = versioned.value;
-                        let extractor = UrlExtractor::default();
-
-                        for program in &programs_data.0 {
-                            if let Some(extended) = &program.extended {
-                                for value in extended.values() {
-                                    let urls = extractor.extract_urls(value);
-
-                                    for url in urls {
-                                        debug!("Found URL from program {}: {}", program.id, url);
-                                        let ogp_event = ogp::url::ExtractRequest { url };
-                                        if let Err(e) =
-                                            ogp_event_store.publish_event(&ogp_event).await
-                                        {
-                                            error!("OGPリクエストイベントの発行に失敗: {:?}", e);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Ok(None) => {
-                        debug!(
-                            "プログラムデータが見つかりません: service_id={}",
-                            service_id
-                        );
-                    }
-                    Err(e) => {
-                        error!("KVSからのプログラムデータ取得に失敗: {:?}", e);
-                    }
-                }
-
-                if let Err(e) = ack_handle.ack().await {
-                    error!("イベントの確認に失敗: {:?}", e);
-                }
+fn process_config(config: &KurecConfig, command: &ConfigCommands) -> ExitCode {
+    match command {
+        ConfigCommands::Check => match config.validate() {
+            Ok(()) => {
+                println!("設定は有効です");
+                ExitCode::SUCCESS
             }
             Err(e) => {
-                error!("イベントの取得に失敗: {:?}", e);
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                eprintln!("{}", e);
+                ExitCode::from(EXIT_CONFIG)
             }
-        }
-    }
-}
-
-async fn process_ogp_image_extractor(nats_url: &str) {
-    use domain::model::event::ogp;
-    use domain::ports::HtmlFetcher;
-    use domain::service::OgpImageParser;
-    use http::ReqwestHtmlFetcher;
-
-    debug!("OGP画像抽出ワーカーを開始します...");
-    let nats_client = connect_nats(nats_url).await.unwrap();
-
-    let extract_request_store = EventStore::<ogp::url::ExtractRequest>::new(nats_client.clone())
-        .await
-        .unwrap();
-    let image_request_store = EventStore::<ogp::url::ImageRequest>::new(nats_client.clone())
-        .await
-        .unwrap();
-
-    setup_kurec_streams(&nats_client).await.unwrap();
-
-    debug!("URL抽出イベント待機中...");
-
-    let reader = extract_request_store
-        .get_reader("ogp_image_extractor".to_string())
-        .await
-        .unwrap();
-
-    let html_fetcher = ReqwestHtmlFetcher::new();
-
-    loop {
-        match reader.next().await {
-            Ok((event, mut ack_handle)) => {
-                let url = &event.url;
-                debug!("URL抽出イベントを受信: url={}", url);
-
-                match html_fetcher.fetch_html(url).await {
-                    Ok(html_content) => {
-                        match OgpImageParser::create_image_requests(&html_content) {
-                            Ok(image_requests) => {
-                                for image_request in image_requests {
-                                    debug!("Found OGP image URL: {}", image_request.url);
-                                    if let Err(e) =
-                                        image_request_store.publish_event(&image_request).await
-                                    {
-                                        error!("画像リクエストイベントの発行に失敗: {:?}", e);
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                error!("HTMLの解析に失敗: {:?}", e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("URLの取得に失敗: {:?}", e);
-                    }
-                }
-
-                if let Err(e) = ack_handle.ack().await {
-                    error!("イベントの確認に失敗: {:?}", e);
-                }
+        },
+        ConfigCommands::Dump => match config.to_toml() {
+            Ok(toml) => {
+                print!("{}", toml);
+                ExitCode::SUCCESS
             }
             Err(e) => {
-                error!("イベントの取得に失敗: {:?}", e);
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                eprintln!("{}", e);
+                ExitCode::FAILURE
             }
-        }
+        },
     }
 }
-#[cfg(test)]
-mod tests {
-    use std::collections::{BTreeMap, HashMap};
-    use std::sync::{Arc, Mutex};
-
-    use crate::ProgramsData;
-    use bytes::Bytes;
-    use domain::error::DomainError;
-    use domain::model::event::{
-        ogp,
-        recording::{epg, programs},
-    };
-    use domain::model::program::{Channel, Genre, Program, ProgramIdentifiers, ProgramTiming};
-    use domain::model::url_extractor::UrlExtractor;
-    use domain::ports::ProgramsRetriever;
-    use domain::repository::{KvRepository, Versioned};
-
-    struct MockProgramsRetriever {
-        service_id: i64,
-        programs: Vec<Program>,
-    }
 
-    #[async_trait::async_trait]
-    impl ProgramsRetriever for MockProgramsRetriever {
-        async fn get_programs(&self, service_id: i64) -> Result<Vec<Program>, DomainError> {
-            if service_id == self.service_id {
-                Ok(self.programs.clone())
-            } else {
-                Err(DomainError::ServiceNotFound(service_id))
-            }
+/// NATSに接続し、失敗した場合はエラーを報告して終了コードを返す
+async fn connect(
+    config: &KurecConfig,
+    shutdown: &CancellationToken,
+) -> Result<NatsClient, ExitCode> {
+    bootstrap::connect(config, shutdown).await.map_err(|e| {
+        match &e {
+            BootstrapError::Cancelled => info!("{}", e),
+            _ => error!("{}", e),
         }
-    }
+        e.exit_code()
+    })
+}
 
-    struct MockEventReader<T> {
-        events: Vec<T>,
-        current: usize,
-    }
+async fn process_dlq(
+    config: &KurecConfig,
+    command: &DlqCommands,
+    shutdown: CancellationToken,
+) -> ExitCode {
+    let nats_client = match connect(config, &shutdown).await {
+        Ok(nats_client) => nats_client,
+        Err(exit_code) => return exit_code,
+    };
 
-    impl<T: Clone> MockEventReader<T> {
-        fn new(events: Vec<T>) -> Self {
-            Self { events, current: 0 }
+    let dlq = DeadLetterQueue::new(nats_client.clone());
+    let exit_code = match run_dlq_command(&dlq, command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            exit_code_for(&e)
         }
+    };
+    finish(&nats_client, exit_code).await
+}
 
-        async fn next(&mut self) -> Result<T, String> {
-            if self.current < self.events.len() {
-                let event = self.events[self.current].clone();
-                self.current += 1;
-                Ok(event)
-            } else {
-                Err("イベントがありません".to_string())
+async fn run_dlq_command(
+    dlq: &DeadLetterQueue,
+    command: &DlqCommands,
+) -> Result<(), NatsInfraError> {
+    match command {
+        DlqCommands::List { subject, limit } => {
+            let dead_letters = dlq.list(subject.as_deref(), *limit).await?;
+            for dead_letter in &dead_letters {
+                print_dead_letter(dead_letter);
             }
+            println!("{} 件", dead_letters.len());
         }
-    }
-
-    struct MockEventStore<T> {
-        published_events: Arc<Mutex<Vec<T>>>,
-    }
-
-    impl<T: Clone> MockEventStore<T> {
-        fn new() -> Self {
-            Self {
-                published_events: Arc::new(Mutex::new(Vec::new())),
-            }
+        DlqCommands::Replay {
+            sequence: Some(sequence),
+            ..
+        } => {
+            let dead_letter = dlq.replay(*sequence).await?;
+            println!(
+                "#{} を {} へ再投入しました",
+                dead_letter.sequence, dead_letter.original_subject
+            );
         }
-
-        async fn publish_event(&self, event: &T) -> Result<(), String> {
-            self.published_events.lock().unwrap().push(event.clone());
-            Ok(())
+        DlqCommands::Replay {
+            sequence: None,
+            subject,
+        } => {
+            let count = dlq.replay_all(subject.as_deref()).await?;
+            println!("{} 件を再投入しました", count);
         }
-
-        fn get_published_events(&self) -> Vec<T> {
-            self.published_events.lock().unwrap().clone()
+        DlqCommands::Purge { subject } => {
+            let count = dlq.purge(subject.as_deref()).await?;
+            println!("{} 件を削除しました", count);
         }
     }
+    Ok(())
+}
 
-    struct MockKvRepository<V>
-    where
-        V: Into<Bytes> + From<Bytes> + Clone + Send + Sync,
-    {
-        data: Arc<Mutex<HashMap<String, (u64, V)>>>,
-    }
-
-    impl<V> MockKvRepository<V>
-    where
-        V: Into<Bytes> + From<Bytes> + Clone + Send + Sync,
-    {
-        fn new() -> Self {
-            Self {
-                data: Arc::new(Mutex::new(HashMap::new())),
-            }
-        }
-    }
+async fn process_rule(
+    config: &KurecConfig,
+    command: &RuleCommands,
+    shutdown: CancellationToken,
+) -> ExitCode {
+    let nats_client = match connect(config, &shutdown).await {
+        Ok(nats_client) => nats_client,
+        Err(exit_code) => return exit_code,
+    };
 
-    #[async_trait::async_trait]
-    impl<V> KvRepository<String, V> for MockKvRepository<V>
-    where
-        V: Into<Bytes> + From<Bytes> + Clone + Send + Sync,
-    {
-        async fn put(&self, key: String, value: &V) -> Result<(), DomainError> {
-            let mut data = self.data.lock().unwrap();
-            let revision = data.get::<str>(key.as_ref()).map_or(1, |(rev, _)| rev + 1);
-            data.insert(key, (revision, value.clone()));
-            Ok(())
+    let exit_code = match run_rule_command(&nats_client, command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            exit_code_for(e.as_ref())
         }
+    };
+    finish(&nats_client, exit_code).await
+}
 
-        async fn get(&self, key: String) -> Result<Option<Versioned<V>>, DomainError> {
-            let data = self.data.lock().unwrap();
-            if let Some((revision, value)) = data.get::<str>(key.as_ref()) {
-                Ok(Some(Versioned {
-                    revision: *revision,
-                    value: value.clone(),
-                }))
-            } else {
-                Ok(None)
+async fn run_rule_command(
+    nats_client: &NatsClient,
+    command: &RuleCommands,
+) -> Result<(), TaskError> {
+    let repository = RecordingRuleEntryRepository::new(nats_client.clone()).await?;
+    match command {
+        RuleCommands::List => {
+            let rules = repository.list().await?;
+            for rule in &rules {
+                println!("{}", serde_json::to_string(rule)?);
             }
+            println!("{} 件", rules.len());
         }
-
-        async fn update(&self, key: String, value: &V, revision: u64) -> Result<(), DomainError> {
-            let mut data = self.data.lock().unwrap();
-            if let Some((current_revision, _)) = data.get::<str>(key.as_ref()) {
-                if *current_revision != revision {
-                    return Err(DomainError::ProgramsStoreError(
-                        "リビジョンが一致しません".to_string(),
-                    ));
-                }
-            } else {
-                return Err(DomainError::ProgramsStoreError(
-                    "存在しないキーを更新しようとしました".to_string(),
-                ));
-            }
-
-            data.insert(key, (revision + 1, value.clone()));
-            Ok(())
+        RuleCommands::Put { file } => {
+            let text = std::fs::read_to_string(file).map_err(|e| {
+                format!(
+                    "ルールのファイルを読み込めません: {}: {}",
+                    file.display(),
+                    e
+                )
+            })?;
+            let rule: RecordingRule = serde_json::from_str(&text).map_err(|e| {
+                format!("ルールの形式が正しくありません: {}: {}", file.display(), e)
+            })?;
+            repository.put(&rule).await?;
+            println!("ルール {} を保存しました", rule.id);
         }
-
-        async fn delete(&self, key: String) -> Result<(), DomainError> {
-            let mut data = self.data.lock().unwrap();
-            data.remove::<str>(key.as_ref());
-            Ok(())
+        RuleCommands::Delete { id } => {
+            repository.delete(id).await?;
+            println!("ルール {} を削除しました", id);
         }
     }
+    Ok(())
+}
 
-    #[tokio::test]
-    async fn test_epg_retriever_logic() {
-        let service_id = 1;
-        let service_id_i32 = service_id as i32; // i64からi32への変換
-        let mirakc_url = "http://example.com";
-
-        let test_program = Program::new(
-            ProgramIdentifiers {
-                id: 123456789,
-                event_id: 1234,
-                network_id: 5678,
-                service_id: service_id_i32,
-            },
-            ProgramTiming {
-                start_at: 1619856000000,
-                duration: 1800000,
-            },
-            true,                                 // is_free
-            Some("テスト番組".to_string()),       // name
-            Some("テスト番組の説明".to_string()), // description
-            vec![Genre { lv1: 7, lv2: 0 }],       // genres
-            Channel {
-                id: service_id,
-                name: "テストチャンネル".to_string(),
-            },
-        );
-
-        let mock_retriever = MockProgramsRetriever {
-            service_id,
-            programs: vec![test_program.clone()],
-        };
-
-        let epg_updated = epg::Updated {
-            service_id,
-            mirakc_url: mirakc_url.to_string(),
-        };
-
-        let programs = mock_retriever
-            .get_programs(epg_updated.service_id)
-            .await
-            .unwrap();
-
-        let mirakc_url = "http://example.com";
-        let programs_updated = programs::Updated {
-            service_id: epg_updated.service_id,
-            mirakc_url: mirakc_url.to_string(),
-        };
-
-        assert_eq!(programs_updated.service_id, service_id);
-        assert_eq!(programs_updated.mirakc_url, mirakc_url);
-
-        assert_eq!(programs.len(), 1);
-        let program = &programs[0];
-        assert_eq!(program.id, 123456789);
-        assert_eq!(program.service_id, service_id_i32);
-        assert_eq!(program.name, Some("テスト番組".to_string()));
+/// NATS接続を終了し、終了処理の結果を反映した終了コードを返す
+async fn finish(nats_client: &NatsClient, exit_code: ExitCode) -> ExitCode {
+    match nats_client.drain().await {
+        Ok(()) => exit_code,
+        Err(e) => {
+            error!("NATS接続の終了に失敗: {:?}", e);
+            ExitCode::FAILURE
+        }
     }
+}
 
-    #[tokio::test]
-    async fn test_process_epg_retriever_with_mocks() {
-        let service_id = 1;
-        let service_id_i32 = service_id as i32;
-        let mirakc_url = "http://example.com";
-
-        let test_program = Program::new(
-            ProgramIdentifiers {
-                id: 123456789,
-                event_id: 1234,
-                network_id: 5678,
-                service_id: service_id_i32,
-            },
-            ProgramTiming {
-                start_at: 1619856000000,
-                duration: 1800000,
-            },
-            true,
-            Some("テスト番組".to_string()),
-            Some("テスト番組の説明".to_string()),
-            vec![Genre { lv1: 7, lv2: 0 }],
-            Channel {
-                id: service_id,
-                name: "テストチャンネル".to_string(),
-            },
-        );
-
-        let mock_retriever = MockProgramsRetriever {
-            service_id,
-            programs: vec![test_program.clone()],
-        };
-
-        let epg_updated = epg::Updated {
-            service_id,
-            mirakc_url: mirakc_url.to_string(),
-        };
-
-        let mut mock_reader = MockEventReader::new(vec![epg_updated.clone()]);
-
-        let mock_event_store = MockEventStore::<programs::Updated>::new();
-        let mock_kvs_repo = MockKvRepository::<ProgramsData>::new();
-
-        // process_epg_retrieverの主要なロジックを再現
-        let event = mock_reader.next().await.unwrap();
-
-        let programs = mock_retriever.get_programs(event.service_id).await.unwrap();
-
-        let key = event.service_id.to_string();
-        let programs_data = ProgramsData(programs);
-        mock_kvs_repo
-            .put(key.clone(), &programs_data)
-            .await
-            .unwrap();
-
-        let programs_updated = programs::Updated {
-            service_id: event.service_id,
-            mirakc_url: mirakc_url.to_string(),
-        };
-
-        mock_event_store
-            .publish_event(&programs_updated)
-            .await
-            .unwrap();
-
-        let published_events = mock_event_store.get_published_events();
-        assert_eq!(published_events.len(), 1);
+fn print_dead_letter(dead_letter: &DeadLetter) {
+    println!(
+        "#{} {} delivered={} consumer={}",
+        dead_letter.sequence,
+        dead_letter.original_subject,
+        dead_letter
+            .delivery_count
+            .map_or_else(|| "-".to_string(), |c| c.to_string()),
+        dead_letter.consumer.as_deref().unwrap_or("-"),
+    );
+    println!("  error: {}", dead_letter.error.as_deref().unwrap_or("-"));
+    println!(
+        "  payload: {}",
+        String::from_utf8_lossy(&dead_letter.payload)
+    );
+}
 
-        let published_event = &published_events[0];
-        assert_eq!(published_event.service_id, service_id);
-        assert_eq!(published_event.mirakc_url, mirakc_url);
+/// 1つのタスクを `shutdown` がキャンセルされるまで実行する
+async fn process_task(
+    config: &KurecConfig,
+    kind: TaskKind,
+    shutdown: CancellationToken,
+) -> ExitCode {
+    debug!("{} を開始します...", kind);
+    let nats_client = match connect(config, &shutdown).await {
+        Ok(nats_client) => nats_client,
+        Err(exit_code) => return exit_code,
+    };
 
-        let stored_programs_data = mock_kvs_repo.get(key).await.unwrap().unwrap().value;
-        let stored_programs = stored_programs_data.0;
-        assert_eq!(stored_programs.len(), 1);
-        assert_eq!(stored_programs[0].id, 123456789);
-        assert_eq!(stored_programs[0].service_id, service_id_i32);
-        assert_eq!(stored_programs[0].name, Some("テスト番組".to_string()));
+    let telemetry = Telemetry::default();
+    if let Err(e) = start_health_server(
+        config,
+        &nats_client,
+        &[kind],
+        &telemetry,
+        None,
+        shutdown.clone(),
+    ) {
+        error!("{}", e);
+        return finish(&nats_client, ExitCode::FAILURE).await;
     }
-    #[tokio::test]
-    async fn test_ogp_url_extractor() {
-        let service_id = 1;
-
-        let mut program = Program::new(
-            ProgramIdentifiers {
-                id: 1,
-                event_id: 1234,
-                network_id: 5678,
-                service_id: 1,
-            },
-            ProgramTiming {
-                start_at: 1619856000000,
-                duration: 1800000,
-            },
-            true,
-            Some("テスト番組".to_string()),
-            Some("テスト説明".to_string()),
-            vec![Genre { lv1: 7, lv2: 0 }],
-            Channel {
-                id: 1,
-                name: "テストチャンネル".to_string(),
-            },
-        );
-
-        let mut extended = BTreeMap::new();
-        extended.insert(
-            "description".to_string(),
-            "これはテスト説明です。https://example.com に詳細があります。".to_string(),
-        );
-        extended.insert(
-            "info".to_string(),
-            "詳細は http://example.com/long/path/to/url/index.html?param=value#section を参照してください。".to_string(),
-        );
-        program.extended = Some(extended);
-
-        let programs_data = ProgramsData(vec![program]);
-        let versioned = Versioned {
-            revision: 1,
-            value: programs_data,
-        };
-
-        let kvs = MockKvRepository::<ProgramsData>::new();
-        kvs.data
-            .lock()
-            .unwrap()
-            .insert(service_id.to_string(), (1, versioned.value.clone()));
 
-        let event = programs::Updated {
-            service_id,
-            mirakc_url: "http://mirakc:40772".to_string(),
-        };
-        let mut reader = MockEventReader::new(vec![event]);
-
-        let store = MockEventStore::<ogp::url::ExtractRequest>::new();
-
-        // process_ogp_url_extractorの主要なロジックを再現
-        let event = reader.next().await.unwrap();
-        let key = event.service_id.to_string();
-
-        if let Some(versioned) = kvs.get(key).await.unwrap() {
-            let programs_data = versioned.value;
-            let extractor = UrlExtractor::default();
+    let result = run_task(
+        kind,
+        config,
+        &nats_client,
+        &telemetry,
+        Arc::default(),
+        shutdown,
+    )
+    .await;
+
+    let exit_code = match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{} の実行に失敗: {}", kind, e);
+            exit_code_for(e.as_ref())
+        }
+    };
+    finish(&nats_client, exit_code).await
+}
 
-            for program in &programs_data.0 {
-                if let Some(extended) = &program.extended {
-                    for value in extended.values() {
-                        let urls = extractor.extract_urls(value);
+/// 番組表を1回だけ全件同期して終了する
+async fn process_epg_sync_once(config: &KurecConfig, shutdown: CancellationToken) -> ExitCode {
+    let nats_client = match connect(config, &shutdown).await {
+        Ok(nats_client) => nats_client,
+        Err(exit_code) => return exit_code,
+    };
 
-                        for url in urls {
-                            let ogp_event = ogp::url::ExtractRequest { url };
-                            store.publish_event(&ogp_event).await.unwrap();
-                        }
-                    }
-                }
+    let exit_code = match sync_epg_once(config, &nats_client, &Telemetry::default()).await {
+        Ok(report) => {
+            println!(
+                "更新 {} 件、変更なし {} 件、失敗 {} 件",
+                report.updated,
+                report.unchanged,
+                report.failed.len()
+            );
+            if report.failed.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                error!("番組表の同期に失敗したサービス: {:?}", report.failed);
+                ExitCode::FAILURE
             }
         }
+        Err(e) => {
+            error!("番組表の同期に失敗: {}", e);
+            exit_code_for(e.as_ref())
+        }
+    };
+    finish(&nats_client, exit_code).await
+}
 
-        let published_events = store.get_published_events();
-        assert_eq!(published_events.len(), 2);
-
-        let urls: Vec<String> = published_events.iter().map(|e| e.url.clone()).collect();
-
-        assert!(urls.contains(&"https://example.com".to_string()));
-        assert!(urls.contains(
-            &"http://example.com/long/path/to/url/index.html?param=value#section".to_string()
-        ));
-    }
+/// 選択したタスクを1つのNATS接続を共有して実行し、異常終了したタスクを再起動する
+///
+/// 起動処理の再試行を使い切ったタスクがあれば、すべてのタスクを止め、そのエラーに応じた終了コードで終了する。
+async fn process_run(
+    config: KurecConfig,
+    workers: &[String],
+    shutdown: CancellationToken,
+) -> ExitCode {
+    let kinds = match TaskKind::select(workers) {
+        Ok(kinds) => kinds,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let config = Arc::new(config);
+    let nats_client = match connect(&config, &shutdown).await {
+        Ok(nats_client) => nats_client,
+        Err(exit_code) => return exit_code,
+    };
 
-    #[tokio::test]
-    async fn test_ogp_image_extractor() {
-        use domain::service::OgpImageParser;
-
-        let html_content = r#"
-        <!DOCTYPE html>
-        <html>
-        <head>
-            <meta property="og:image" content="https://example.com/image1.jpg" />
-            <meta property="og:image" content="https://example.com/image2.png" />
-        </head>
-        <body>
-            <p>Test content</p>
-        </body>
-        </html>
-        "#;
-
-        let image_requests = OgpImageParser::create_image_requests(html_content).unwrap();
-
-        assert_eq!(image_requests.len(), 2);
-        let image_urls: Vec<String> = image_requests.iter().map(|req| req.url.clone()).collect();
-        assert!(image_urls.contains(&"https://example.com/image1.jpg".to_string()));
-        assert!(image_urls.contains(&"https://example.com/image2.png".to_string()));
-
-        let extract_request = ogp::url::ExtractRequest {
-            url: "https://example.com".to_string(),
+    let telemetry = Telemetry::default();
+    let mut supervisor = Supervisor::new(RestartPolicy::default()).with_fatal(is_fatal);
+    for &kind in &kinds {
+        let config = config.clone();
+        let nats_client = nats_client.clone();
+        let telemetry = telemetry.clone();
+        let metrics = Arc::new(WorkerMetrics::default());
+        let task_metrics = metrics.clone();
+        let factory = move |shutdown| {
+            let config = config.clone();
+            let nats_client = nats_client.clone();
+            let telemetry = telemetry.clone();
+            let metrics = task_metrics.clone();
+            async move { run_task(kind, &config, &nats_client, &telemetry, metrics, shutdown).await }
         };
-        let mut reader = MockEventReader::new(vec![extract_request]);
-        let store = MockEventStore::<ogp::url::ImageRequest>::new();
-
-        // process_ogp_image_extractorの主要なロジックを部分的に再現（HTMLフェッチ部分を除く）
-        let _event = reader.next().await.unwrap();
-
-        for image_request in &image_requests {
-            store.publish_event(image_request).await.unwrap();
+        match kind {
+            TaskKind::Events | TaskKind::EpgSync | TaskKind::InventorySync | TaskKind::LogoSync => {
+                supervisor.add(kind.to_string(), factory)
+            }
+            _ => supervisor.add_worker(kind.to_string(), metrics, factory),
+        }
+    }
+    if let Err(e) = start_health_server(
+        &config,
+        &nats_client,
+        &kinds,
+        &telemetry,
+        Some(supervisor.health()),
+        shutdown.clone(),
+    ) {
+        error!("{}", e);
+        return finish(&nats_client, ExitCode::FAILURE).await;
+    }
+    let exit_code = match supervisor.run(shutdown).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("タスクの実行に失敗したため終了します: {}", e);
+            exit_code_for(e.as_ref())
         }
+    };
+    finish(&nats_client, exit_code).await
+}
 
-        let published_events = store.get_published_events();
-        assert_eq!(published_events.len(), 2);
+/// `http.listen` が設定されていれば、ヘルスチェックとメトリクスのHTTPサーバーを起動する
+///
+/// `/readyz` はNATSと、mirakcを使うタスクがあればmirakcを検査する。
+/// `/healthz` は `supervisor` があれば、再起動待ちのタスクがないことを検査する。
+fn start_health_server(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+    kinds: &[TaskKind],
+    telemetry: &Telemetry,
+    supervisor: Option<SupervisorHealth>,
+    shutdown: CancellationToken,
+) -> Result<(), String> {
+    let Some(addr) = config.http.listen_addr() else {
+        return Ok(());
+    };
 
-        let published_urls: Vec<String> = published_events.iter().map(|e| e.url.clone()).collect();
-        assert!(published_urls.contains(&"https://example.com/image1.jpg".to_string()));
-        assert!(published_urls.contains(&"https://example.com/image2.png".to_string()));
+    let streams = config
+        .stream_configs()
+        .into_iter()
+        .map(|stream| stream.name)
+        .collect();
+    let mut server = HealthServer::new(telemetry.registry.clone())
+        .with_readiness(Arc::new(NatsHealthCheck::new(nats_client.clone(), streams)));
+    if kinds.iter().any(TaskKind::uses_mirakc) {
+        server = server.with_readiness(Arc::new(MirakcHealthCheck::new(&config.mirakc.url)));
     }
+    if let Some(supervisor) = supervisor {
+        telemetry
+            .registry
+            .register("supervisor", Arc::new(supervisor.clone()));
+        server = server.with_liveness(Arc::new(supervisor));
+    }
+
+    let (_, server) = server
+        .bind(addr, shutdown)
+        .map_err(|e| format!("HTTPサーバーを起動できません: {}: {}", addr, e))?;
+    tokio::spawn(server);
+    Ok(())
 }
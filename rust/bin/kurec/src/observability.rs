@@ -0,0 +1,161 @@
+//! ヘルスチェックとメトリクスのためのアダプター
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use domain::{
+    error::DomainError,
+    model::program::Program,
+    ports::{ImageProcessor, ImageProcessorError, ProgramsRetriever},
+};
+use mirakc::MirakcApiClient;
+use worker::{HealthCheck, Histogram, MetricsRegistry};
+
+/// プロセス全体で共有するメトリクス
+///
+/// タスクが再起動しても値が引き継がれるよう、ヒストグラムは起動時に一度だけ登録する。
+#[derive(Clone)]
+pub struct Telemetry {
+    pub registry: MetricsRegistry,
+    mirakc_get_programs: Arc<Histogram>,
+    image_processing: Arc<Histogram>,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        let registry = MetricsRegistry::default();
+        let mirakc_get_programs = Arc::new(Histogram::default());
+        registry.register_histogram(
+            "kurec_mirakc_request_duration_seconds",
+            "mirakc APIの応答時間",
+            &[("operation", "get_programs")],
+            mirakc_get_programs.clone(),
+        );
+        let image_processing = Arc::new(Histogram::default());
+        registry.register_histogram(
+            "kurec_image_processing_duration_seconds",
+            "画像の変換にかかった時間",
+            &[],
+            image_processing.clone(),
+        );
+        Self {
+            registry,
+            mirakc_get_programs,
+            image_processing,
+        }
+    }
+}
+
+impl Telemetry {
+    pub fn programs_retriever<R: ProgramsRetriever>(&self, inner: R) -> TimedProgramsRetriever<R> {
+        TimedProgramsRetriever {
+            inner,
+            histogram: self.mirakc_get_programs.clone(),
+        }
+    }
+
+    pub fn image_processor<P: ImageProcessor>(&self, inner: P) -> TimedImageProcessor<P> {
+        TimedImageProcessor {
+            inner,
+            histogram: self.image_processing.clone(),
+        }
+    }
+}
+
+/// 番組表の取得にかかった時間を記録する [`ProgramsRetriever`]
+pub struct TimedProgramsRetriever<R> {
+    inner: R,
+    histogram: Arc<Histogram>,
+}
+
+#[async_trait]
+impl<R: ProgramsRetriever + Send + Sync> ProgramsRetriever for TimedProgramsRetriever<R> {
+    async fn get_programs(&self, service_id: i64) -> Result<Vec<Program>, DomainError> {
+        let started = Instant::now();
+        let result = self.inner.get_programs(service_id).await;
+        self.histogram.observe(started.elapsed());
+        result
+    }
+}
+
+/// 画像の変換にかかった時間を記録する [`ImageProcessor`]
+pub struct TimedImageProcessor<P> {
+    inner: P,
+    histogram: Arc<Histogram>,
+}
+
+#[async_trait]
+impl<P: ImageProcessor + Send + Sync> ImageProcessor for TimedImageProcessor<P> {
+    async fn process_image(
+        &self,
+        image_data: &[u8],
+        width: u32,
+    ) -> Result<Vec<u8>, ImageProcessorError> {
+        let started = Instant::now();
+        let result = self.inner.process_image(image_data, width).await;
+        self.histogram.observe(started.elapsed());
+        result
+    }
+}
+
+/// mirakcのAPIに応答があることを確認する
+pub struct MirakcHealthCheck {
+    client: MirakcApiClient,
+}
+
+impl MirakcHealthCheck {
+    pub fn new(mirakc_url: &str) -> Self {
+        Self {
+            client: MirakcApiClient::new(mirakc_url),
+        }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for MirakcHealthCheck {
+    fn name(&self) -> &str {
+        "mirakc"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        self.client
+            .get_version()
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoImageProcessor;
+
+    #[async_trait]
+    impl ImageProcessor for EchoImageProcessor {
+        async fn process_image(
+            &self,
+            image_data: &[u8],
+            _width: u32,
+        ) -> Result<Vec<u8>, ImageProcessorError> {
+            Ok(image_data.to_vec())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timed_image_processor_records_duration() {
+        let telemetry = Telemetry::default();
+        let processor = telemetry.image_processor(EchoImageProcessor);
+
+        let output = processor.process_image(b"image", 300).await.unwrap();
+        assert_eq!(output, b"image");
+
+        let text = telemetry.registry.encode().await;
+        assert!(text.contains("kurec_image_processing_duration_seconds_count 1"));
+        assert!(text.contains(
+            r#"kurec_mirakc_request_duration_seconds_count{operation="get_programs"} 0"#
+        ));
+    }
+}
@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use domain::{
+    error::DomainError,
+    repository::{KvRepository, Versioned},
+    service::ObjectBackedRepository,
+};
+
+use super::{ObjectRefRepository, ObjectStoreBackend};
+
+/// 画像などの大きな値を、KVに直接、またはオブジェクトストアに参照を介して保存する
+///
+/// `object_store.store_images` で切り替える。
+pub enum BlobRepository<R, V> {
+    Kv(R),
+    Object(ObjectBackedRepository<ObjectStoreBackend, ObjectRefRepository, V>),
+}
+
+#[async_trait]
+impl<R, V> KvRepository<String, V> for BlobRepository<R, V>
+where
+    R: KvRepository<String, V> + Send + Sync,
+    V: Into<Bytes> + From<Bytes> + Clone + Send + Sync,
+{
+    async fn put(&self, key: String, value: &V) -> Result<(), DomainError> {
+        match self {
+            Self::Kv(repository) => repository.put(key, value).await,
+            Self::Object(repository) => repository.put(key, value).await,
+        }
+    }
+
+    async fn get(&self, key: String) -> Result<Option<Versioned<V>>, DomainError> {
+        match self {
+            Self::Kv(repository) => repository.get(key).await,
+            Self::Object(repository) => repository.get(key).await,
+        }
+    }
+
+    async fn update(&self, key: String, value: &V, revision: u64) -> Result<(), DomainError> {
+        match self {
+            Self::Kv(repository) => repository.update(key, value, revision).await,
+            Self::Object(repository) => repository.update(key, value, revision).await,
+        }
+    }
+
+    async fn delete(&self, key: String) -> Result<(), DomainError> {
+        match self {
+            Self::Kv(repository) => repository.delete(key).await,
+            Self::Object(repository) => repository.delete(key).await,
+        }
+    }
+}
@@ -1,2 +1,16 @@
+mod blob;
+mod object_ref;
+mod object_store;
+mod onair_program;
+mod recording;
+mod search_index;
+mod service_logo_data;
 mod webp_image_data;
+pub use blob::*;
+pub use object_ref::*;
+pub use object_store::*;
+pub use onair_program::*;
+pub use recording::*;
+pub use search_index::*;
+pub use service_logo_data::*;
 pub use webp_image_data::*;
@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+use domain::{
+    error::DomainError,
+    model::object::ObjectRef,
+    repository::{KvRepository, Versioned},
+};
+use nats::{error::NatsInfraError, kvs::NatsKvRepositoryImpl, nats::NatsClient};
+
+/// OGP画像の参照を保存するバケット
+pub const WEBP_IMAGE_REF_BUCKET: &str = "webp_image_ref";
+/// サービスのロゴ画像の参照を保存するバケット
+pub const SERVICE_LOGO_REF_BUCKET: &str = "service_logo_ref";
+/// トランスコードした録画の参照を保存するバケット
+pub const RECORDING_ARTIFACT_REF_BUCKET: &str = "recording_artifact_ref";
+
+/// オブジェクトストアに置いた中身への参照を用途ごとのバケットに保存する
+pub struct ObjectRefRepository {
+    inner: NatsKvRepositoryImpl<String, ObjectRef>,
+}
+
+impl ObjectRefRepository {
+    pub async fn new(nats_client: NatsClient, bucket_name: &str) -> Result<Self, NatsInfraError> {
+        let inner = NatsKvRepositoryImpl::with_bucket_name(nats_client, bucket_name).await?;
+        Ok(Self { inner })
+    }
+}
+
+#[async_trait]
+impl KvRepository<String, ObjectRef> for ObjectRefRepository {
+    async fn put(&self, key: String, value: &ObjectRef) -> Result<(), DomainError> {
+        self.inner.put(key, value).await
+    }
+
+    async fn get(&self, key: String) -> Result<Option<Versioned<ObjectRef>>, DomainError> {
+        self.inner.get(key).await
+    }
+
+    async fn update(
+        &self,
+        key: String,
+        value: &ObjectRef,
+        revision: u64,
+    ) -> Result<(), DomainError> {
+        self.inner.update(key, value, revision).await
+    }
+
+    async fn delete(&self, key: String) -> Result<(), DomainError> {
+        self.inner.delete(key).await
+    }
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use domain::{
+    error::DomainError,
+    model::object::{ObjectMeta, PutOptions, StoredObject},
+    ports::ObjectStore,
+};
+use nats::object_store::NatsObjectStore;
+use s3::S3ObjectStore;
+
+/// 設定で選んだオブジェクトストア
+#[derive(Clone)]
+pub enum ObjectStoreBackend {
+    Nats(NatsObjectStore),
+    S3(S3ObjectStore),
+}
+
+#[async_trait]
+impl ObjectStore for ObjectStoreBackend {
+    async fn put(
+        &self,
+        key: &str,
+        data: Bytes,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        match self {
+            Self::Nats(store) => store.put(key, data, options).await,
+            Self::S3(store) => store.put(key, data, options).await,
+        }
+    }
+
+    async fn put_file(
+        &self,
+        key: &str,
+        path: &Path,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        match self {
+            Self::Nats(store) => store.put_file(key, path, options).await,
+            Self::S3(store) => store.put_file(key, path, options).await,
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>, DomainError> {
+        match self {
+            Self::Nats(store) => store.get(key).await,
+            Self::S3(store) => store.get(key).await,
+        }
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<ObjectMeta>, DomainError> {
+        match self {
+            Self::Nats(store) => store.head(key).await,
+            Self::S3(store) => store.head(key).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DomainError> {
+        match self {
+            Self::Nats(store) => store.delete(key).await,
+            Self::S3(store) => store.delete(key).await,
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, DomainError> {
+        match self {
+            Self::Nats(store) => store.list(prefix).await,
+            Self::S3(store) => store.list(prefix).await,
+        }
+    }
+}
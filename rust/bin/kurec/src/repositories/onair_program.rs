@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use domain::{
+    error::DomainError,
+    model::onair::OnairProgram,
+    repository::{KvRepository, Versioned},
+};
+use nats::{error::NatsInfraError, kvs::NatsKvRepositoryImpl, nats::NatsClient};
+
+pub struct OnairProgramRepository {
+    inner: NatsKvRepositoryImpl<String, OnairProgram>,
+}
+
+impl OnairProgramRepository {
+    pub async fn new(nats_client: NatsClient) -> Result<Self, NatsInfraError> {
+        let inner = NatsKvRepositoryImpl::new(nats_client).await?;
+        Ok(Self { inner })
+    }
+}
+
+#[async_trait]
+impl KvRepository<String, OnairProgram> for OnairProgramRepository {
+    async fn put(&self, key: String, value: &OnairProgram) -> Result<(), DomainError> {
+        self.inner.put(key, value).await
+    }
+
+    async fn get(&self, key: String) -> Result<Option<Versioned<OnairProgram>>, DomainError> {
+        self.inner.get(key).await
+    }
+
+    async fn update(
+        &self,
+        key: String,
+        value: &OnairProgram,
+        revision: u64,
+    ) -> Result<(), DomainError> {
+        self.inner.update(key, value, revision).await
+    }
+
+    async fn delete(&self, key: String) -> Result<(), DomainError> {
+        self.inner.delete(key).await
+    }
+}
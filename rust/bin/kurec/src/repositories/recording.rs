@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use domain::{
+    error::DomainError,
+    model::recording::Recording,
+    repository::{KvRepository, Versioned},
+};
+use nats::{error::NatsInfraError, kvs::NatsKvRepositoryImpl, nats::NatsClient};
+
+pub struct RecordingRepository {
+    inner: NatsKvRepositoryImpl<String, Recording>,
+}
+
+impl RecordingRepository {
+    pub async fn new(nats_client: NatsClient) -> Result<Self, NatsInfraError> {
+        let inner = NatsKvRepositoryImpl::new(nats_client).await?;
+        Ok(Self { inner })
+    }
+}
+
+#[async_trait]
+impl KvRepository<String, Recording> for RecordingRepository {
+    async fn put(&self, key: String, value: &Recording) -> Result<(), DomainError> {
+        self.inner.put(key, value).await
+    }
+
+    async fn get(&self, key: String) -> Result<Option<Versioned<Recording>>, DomainError> {
+        self.inner.get(key).await
+    }
+
+    async fn update(
+        &self,
+        key: String,
+        value: &Recording,
+        revision: u64,
+    ) -> Result<(), DomainError> {
+        self.inner.update(key, value, revision).await
+    }
+
+    async fn delete(&self, key: String) -> Result<(), DomainError> {
+        self.inner.delete(key).await
+    }
+}
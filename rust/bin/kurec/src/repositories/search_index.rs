@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use domain::{
+    error::DomainError,
+    model::search::{ProgramDocument, SearchQuery, SearchResults},
+    ports::SearchIndex,
+};
+use meilisearch::MeilisearchIndex;
+use tantivy_search::TantivySearchIndex;
+
+/// 設定で選んだ番組検索のインデックス
+#[derive(Clone)]
+pub enum SearchIndexBackend {
+    Meilisearch(MeilisearchIndex),
+    Tantivy(TantivySearchIndex),
+}
+
+#[async_trait]
+impl SearchIndex for SearchIndexBackend {
+    async fn upsert(&self, documents: &[ProgramDocument]) -> Result<(), DomainError> {
+        match self {
+            Self::Meilisearch(index) => index.upsert(documents).await,
+            Self::Tantivy(index) => index.upsert(documents).await,
+        }
+    }
+
+    async fn delete_stale(&self, service_id: i64, program_ids: &[i64]) -> Result<(), DomainError> {
+        match self {
+            Self::Meilisearch(index) => index.delete_stale(service_id, program_ids).await,
+            Self::Tantivy(index) => index.delete_stale(service_id, program_ids).await,
+        }
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults, DomainError> {
+        match self {
+            Self::Meilisearch(index) => index.search(query).await,
+            Self::Tantivy(index) => index.search(query).await,
+        }
+    }
+}
@@ -0,0 +1,926 @@
+//! kurec が1つのプロセスで動かせるタスク（SSEの中継と各ワーカー）
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use domain::error::DomainError;
+use domain::ports::ProgramEventPublisher;
+use domain::types::Event;
+use domain::usecase::{
+    EpgSyncReport, EpgSyncUseCase, InventorySyncUseCase, LogoSyncUseCase, OnairTrackerUseCase,
+    RecordingConflictUseCase, RecordingTrackerUseCase,
+};
+use futures::StreamExt as _;
+use mirakc::{MirakcEventInput, get_mirakc_event_stream, sse_event::IntoDomainEvent};
+use nats::{
+    error::NatsInfraError,
+    kvs::NatsKvRepositoryTrait,
+    nats::NatsClient,
+    repositories::{
+        InventoryEntryRepository, ProgramEntryRepository, ProgramsDataRepository,
+        RecordingRuleEntryRepository,
+    },
+    stream::EventStore,
+};
+use tracing::{debug, error, info};
+use worker::{
+    CancellationToken, ConsumerLagCollector, Worker, WorkerConfig, WorkerMetrics,
+    WorkerMetricsCollector, WorkerRuntime, open_jetstream_source,
+};
+
+use crate::bootstrap::{SseStreamEnded, retry_mirakc, retry_nats};
+use crate::config::{KurecConfig, ObjectStoreBackendKind, SearchBackendKind, WorkerSettings};
+use crate::observability::Telemetry;
+use crate::repositories::{
+    BlobRepository, ObjectRefRepository, ObjectStoreBackend, OnairProgramRepository,
+    RECORDING_ARTIFACT_REF_BUCKET, RecordingRepository, SERVICE_LOGO_REF_BUCKET,
+    SearchIndexBackend, ServiceLogoDataRepository, WEBP_IMAGE_REF_BUCKET, WebpImageDataRepository,
+};
+use crate::workers::{
+    AutoReserverWorker, EpgRetrieverWorker, OgpImageExtractorWorker, OgpImageProcessorWorker,
+    OgpUrlExtractorWorker, OnairTrackerWorker, ProgramIndexerWorker, RecordingArchiverWorker,
+    RecordingConflictWorker, RecordingTrackerWorker, TranscoderWorker,
+};
+
+pub type TaskError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TaskKind {
+    Events,
+    EpgRetriever,
+    EpgSync,
+    InventorySync,
+    LogoSync,
+    OgpUrlExtractor,
+    OgpImageExtractor,
+    OgpImageProcessor,
+    AutoReserver,
+    OnairTracker,
+    RecordingTracker,
+    Transcoder,
+    RecordingArchiver,
+    ProgramIndexer,
+}
+
+impl TaskKind {
+    /// 指定されたタスク名（`all` ですべて）を重複なく解決する
+    pub fn select(names: &[String]) -> Result<Vec<TaskKind>, String> {
+        let mut kinds = Vec::new();
+        for name in names {
+            let selected = if name == "all" {
+                TaskKind::value_variants().to_vec()
+            } else {
+                vec![TaskKind::from_str(name, true).map_err(|_| {
+                    format!(
+                        "不明なワーカーです: {}（all, {} のいずれかを指定してください）",
+                        name,
+                        TaskKind::value_variants()
+                            .iter()
+                            .map(|kind| kind.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?]
+            };
+            for kind in selected {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
+            }
+        }
+        Ok(kinds)
+    }
+
+    /// mirakcのAPIを使うタスクかどうか
+    pub fn uses_mirakc(&self) -> bool {
+        matches!(
+            self,
+            TaskKind::Events
+                | TaskKind::EpgRetriever
+                | TaskKind::EpgSync
+                | TaskKind::InventorySync
+                | TaskKind::LogoSync
+                | TaskKind::AutoReserver
+                | TaskKind::OnairTracker
+                | TaskKind::RecordingTracker
+                | TaskKind::Transcoder
+        )
+    }
+}
+
+impl fmt::Display for TaskKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().expect("skipされた値はありません");
+        f.write_str(value.get_name())
+    }
+}
+
+/// `shutdown` がキャンセルされるまでタスクを実行する
+///
+/// ワーカーの初期化とコンシューマーの作成は `config.startup` に従って再試行する。
+/// ワーカーの処理件数は `metrics` に記録され、`telemetry` のレジストリから出力される。
+pub async fn run_task(
+    kind: TaskKind,
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+    telemetry: &Telemetry,
+    metrics: Arc<WorkerMetrics>,
+    shutdown: CancellationToken,
+) -> Result<(), TaskError> {
+    let context = WorkerContext {
+        config,
+        nats_client,
+        telemetry,
+        metrics,
+    };
+    let policy = config.startup_retry_policy();
+    let operation = format!("{} の初期化", kind);
+    match kind {
+        TaskKind::Events => run_events(config, nats_client, shutdown).await,
+        TaskKind::EpgRetriever => {
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_epg_retriever(config, nats_client, telemetry)
+            })
+            .await?;
+            let settings = &config.workers.epg_retriever;
+            run_worker(context, worker, settings, shutdown).await
+        }
+        TaskKind::EpgSync => {
+            let usecase = retry_nats(&operation, &policy, &shutdown, || {
+                build_epg_sync(config, nats_client, telemetry)
+            })
+            .await?;
+            run_epg_sync(&usecase, config.epg_sync.interval(), shutdown).await
+        }
+        TaskKind::InventorySync => {
+            let usecase = retry_nats(&operation, &policy, &shutdown, || {
+                build_inventory_sync(config, nats_client)
+            })
+            .await?;
+            run_inventory_sync(&usecase, config.inventory_sync.interval(), shutdown).await
+        }
+        TaskKind::LogoSync => {
+            let usecase = retry_nats(&operation, &policy, &shutdown, || {
+                build_logo_sync(config, nats_client, telemetry)
+            })
+            .await?;
+            run_logo_sync(&usecase, config.logo_sync.interval(), shutdown).await
+        }
+        TaskKind::OgpUrlExtractor => {
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_ogp_url_extractor(config, nats_client)
+            })
+            .await?;
+            let settings = &config.workers.ogp_url_extractor;
+            run_worker(context, worker, settings, shutdown).await
+        }
+        TaskKind::OgpImageExtractor => {
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_ogp_image_extractor(nats_client)
+            })
+            .await?;
+            let settings = &config.workers.ogp_image_extractor;
+            run_worker(context, worker, settings, shutdown).await
+        }
+        TaskKind::OgpImageProcessor => {
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_ogp_image_processor(config, nats_client, telemetry)
+            })
+            .await?;
+            let settings = &config.workers.ogp_image_processor;
+            run_worker(context, worker, settings, shutdown).await
+        }
+        TaskKind::AutoReserver => {
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_auto_reserver(config, nats_client)
+            })
+            .await?;
+            let settings = &config.workers.auto_reserver;
+            run_worker(context, worker, settings, shutdown).await
+        }
+        TaskKind::OnairTracker => {
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_onair_tracker(config, nats_client)
+            })
+            .await?;
+            // SSEは変化しか通知しないので、先に全サービスの放送中の番組を揃える
+            tokio::select! {
+                _ = shutdown.cancelled() => return Ok(()),
+                result = worker.0.sync_all() => {
+                    if let Err(e) = result {
+                        error!("放送中の番組の同期に失敗: {}", e);
+                    }
+                }
+            }
+            let settings = &config.workers.onair_tracker;
+            run_worker(context, worker, settings, shutdown).await
+        }
+        TaskKind::RecordingTracker => {
+            use domain::model::event::recording::{record, reservation, schedule};
+
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_recording_tracker(config, nats_client)
+            })
+            .await?;
+            let conflict_worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_recording_conflict(config, nats_client)
+            })
+            .await?;
+            let settings = &config.workers.recording_tracker;
+            // どれかが終了したら、まとめて再起動させる
+            tokio::try_join!(
+                run_worker::<reservation::Matched, _>(
+                    context.clone(),
+                    worker.clone(),
+                    settings,
+                    shutdown.clone()
+                ),
+                run_worker::<schedule::Started, _>(
+                    context.clone(),
+                    worker.clone(),
+                    settings,
+                    shutdown.clone()
+                ),
+                run_worker::<schedule::Stopped, _>(
+                    context.clone(),
+                    worker.clone(),
+                    settings,
+                    shutdown.clone()
+                ),
+                run_worker::<schedule::Failed, _>(
+                    context.clone(),
+                    worker.clone(),
+                    settings,
+                    shutdown.clone()
+                ),
+                run_worker::<schedule::Rescheduled, _>(
+                    context.clone(),
+                    worker.clone(),
+                    settings,
+                    shutdown.clone()
+                ),
+                run_worker::<record::Saved, _>(
+                    context.clone(),
+                    worker.clone(),
+                    settings,
+                    shutdown.clone()
+                ),
+                run_worker::<record::Broken, _>(
+                    context.clone(),
+                    worker,
+                    settings,
+                    shutdown.clone()
+                ),
+                run_worker::<reservation::Matched, _>(context, conflict_worker, settings, shutdown),
+            )?;
+            Ok(())
+        }
+        TaskKind::Transcoder => {
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_transcoder(config, nats_client)
+            })
+            .await?;
+            let settings = &config.workers.transcoder;
+            run_worker(context, worker, settings, shutdown).await
+        }
+        TaskKind::RecordingArchiver => {
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_recording_archiver(config, nats_client)
+            })
+            .await?;
+            let settings = &config.workers.recording_archiver;
+            run_worker(context, worker, settings, shutdown).await
+        }
+        TaskKind::ProgramIndexer => {
+            // 辞書やインデックスのディレクトリの誤りは再試行しても直らない
+            let search_index = build_search_index(config)?;
+            let worker = retry_nats(&operation, &policy, &shutdown, || {
+                build_program_indexer(nats_client, search_index.clone())
+            })
+            .await?;
+            let settings = &config.workers.program_indexer;
+            run_worker(context, worker, settings, shutdown).await
+        }
+    }
+}
+
+/// ワーカーの実行に共通して必要なもの
+#[derive(Clone)]
+struct WorkerContext<'a> {
+    config: &'a KurecConfig,
+    nats_client: &'a NatsClient,
+    telemetry: &'a Telemetry,
+    metrics: Arc<WorkerMetrics>,
+}
+
+async fn run_worker<E, W>(
+    context: WorkerContext<'_>,
+    worker: W,
+    settings: &WorkerSettings,
+    shutdown: CancellationToken,
+) -> Result<(), TaskError>
+where
+    E: Event,
+    W: Worker<E>,
+{
+    let WorkerContext {
+        config,
+        nats_client,
+        telemetry,
+        metrics,
+    } = context;
+
+    let name = worker.name().to_string();
+    let subject = EventStore::<E>::get_subject();
+    telemetry.registry.register(
+        format!("worker/{}", name),
+        Arc::new(WorkerMetricsCollector::new(
+            &name,
+            &subject,
+            metrics.clone(),
+        )),
+    );
+
+    let worker_config = WorkerConfig {
+        concurrency: settings.concurrency,
+        retry_policy: config.worker_retry_policy(),
+        ..Default::default()
+    };
+    let policy = config.startup_retry_policy();
+    let source = retry_nats(
+        &format!("コンシューマー {} の作成", name),
+        &policy,
+        &shutdown,
+        || open_jetstream_source::<E>(nats_client.clone(), &name, &worker_config),
+    )
+    .await?;
+    telemetry.registry.register(
+        format!("consumer/{}", name),
+        Arc::new(ConsumerLagCollector::new(
+            EventStore::<E>::new(nats_client.clone()).await?,
+            &name,
+        )),
+    );
+
+    WorkerRuntime::new(worker, source, worker_config)
+        .with_metrics(metrics)
+        .run(shutdown)
+        .await;
+    Ok(())
+}
+
+/// mirakcのSSEイベントをドメインイベントとしてNATSに中継する
+async fn run_events(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+    shutdown: CancellationToken,
+) -> Result<(), TaskError> {
+    use mirakc::sse_event;
+
+    let policy = config.startup_retry_policy();
+    let mut sse_stream = retry_mirakc("mirakcへの接続", &policy, &shutdown, || {
+        get_mirakc_event_stream(&config.mirakc.url, config.mirakc.retry_max)
+    })
+    .await?;
+
+    loop {
+        let event = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            event = sse_stream.next() => event,
+        };
+        let Some(event) = event else {
+            return Err(SseStreamEnded.into());
+        };
+        debug!("Received event: {:?}", event);
+        match event.event_type.as_str() {
+            "epg.programs-updated" => {
+                publish_sse_event::<sse_event::ProgramsUpdated>(nats_client, &event).await;
+            }
+            "recording.started" => {
+                publish_sse_event::<sse_event::RecordingStarted>(nats_client, &event).await;
+            }
+            "recording.stopped" => {
+                publish_sse_event::<sse_event::RecordingStopped>(nats_client, &event).await;
+            }
+            "recording.failed" => {
+                publish_sse_event::<sse_event::RecordingFailed>(nats_client, &event).await;
+            }
+            "recording.rescheduled" => {
+                publish_sse_event::<sse_event::RecordingRescheduled>(nats_client, &event).await;
+            }
+            "recording.record-saved" => {
+                publish_sse_event::<sse_event::RecordSaved>(nats_client, &event).await;
+            }
+            "recording.record-removed" => {
+                publish_sse_event::<sse_event::RecordRemoved>(nats_client, &event).await;
+            }
+            "recording.record-broken" => {
+                publish_sse_event::<sse_event::RecordBroken>(nats_client, &event).await;
+            }
+            "onair.program-changed" => {
+                publish_sse_event::<sse_event::OnairProgramChanged>(nats_client, &event).await;
+            }
+            "timeshift.timeline" => {
+                publish_sse_event::<sse_event::TimeshiftTimeline>(nats_client, &event).await;
+            }
+            "timeshift.started" => {
+                publish_sse_event::<sse_event::TimeshiftStarted>(nats_client, &event).await;
+            }
+            "timeshift.stopped" => {
+                publish_sse_event::<sse_event::TimeshiftStopped>(nats_client, &event).await;
+            }
+            "timeshift.record-started" => {
+                publish_sse_event::<sse_event::TimeshiftRecordStarted>(nats_client, &event).await;
+            }
+            "timeshift.record-updated" => {
+                publish_sse_event::<sse_event::TimeshiftRecordUpdated>(nats_client, &event).await;
+            }
+            "timeshift.record-ended" => {
+                publish_sse_event::<sse_event::TimeshiftRecordEnded>(nats_client, &event).await;
+            }
+            _ => {
+                debug!("Unknown event type: {:?}", event.event_type);
+            }
+        }
+    }
+
+    // ストリームを破棄してmirakcとのSSE接続を閉じる
+    drop(sse_stream);
+    info!("SSEストリームを閉じました");
+    Ok(())
+}
+
+/// 起動時と `interval` ごとに番組表を全件同期する
+///
+/// 取りこぼしたSSEイベントを補うためのもので、同期に失敗しても次の同期で再試行する。
+async fn run_epg_sync(
+    usecase: &(impl EpgSyncUseCase + Sync),
+    interval: Duration,
+    shutdown: CancellationToken,
+) -> Result<(), TaskError> {
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            result = usecase.sync_all() => {
+                if let Err(e) = result {
+                    error!("番組表の同期に失敗: {}", e);
+                }
+            }
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+    Ok(())
+}
+
+/// 起動時と `interval` ごとにチューナー・チャンネル・サービスの一覧を同期する
+async fn run_inventory_sync(
+    usecase: &(impl InventorySyncUseCase + Sync),
+    interval: Duration,
+    shutdown: CancellationToken,
+) -> Result<(), TaskError> {
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            result = usecase.sync() => {
+                if let Err(e) = result {
+                    error!("チューナー・チャンネル情報の同期に失敗: {}", e);
+                }
+            }
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+    Ok(())
+}
+
+/// 起動時と `interval` ごとに全サービスのロゴ画像を同期する
+async fn run_logo_sync(
+    usecase: &(impl LogoSyncUseCase + Sync),
+    interval: Duration,
+    shutdown: CancellationToken,
+) -> Result<(), TaskError> {
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            result = usecase.sync_all() => {
+                if let Err(e) = result {
+                    error!("ロゴ画像の同期に失敗: {}", e);
+                }
+            }
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+    Ok(())
+}
+
+/// 番組表を1回だけ全件同期する
+pub async fn sync_epg_once(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+    telemetry: &Telemetry,
+) -> Result<EpgSyncReport, TaskError> {
+    let usecase = build_epg_sync(config, nats_client, telemetry).await?;
+    Ok(usecase.sync_all().await?)
+}
+
+/// SSEイベントのペイロードをパースし、対応するドメインイベントとして発行する
+async fn publish_sse_event<S>(nats_client: &NatsClient, event: &MirakcEventInput)
+where
+    S: serde::de::DeserializeOwned + IntoDomainEvent + std::fmt::Debug,
+{
+    let ev = match serde_json::from_str::<S>(&event.data) {
+        Ok(ev) => ev,
+        Err(e) => {
+            debug!(
+                "Failed to parse event: type={}, error={:?}",
+                event.event_type, e
+            );
+            return;
+        }
+    };
+    debug!("Parsed event: {:?}", ev);
+
+    let domain_ev = ev.into_domain_event(&event.mirakc_url);
+    let event_store = match EventStore::<S::Output>::new(nats_client.clone()).await {
+        Ok(store) => store,
+        Err(e) => {
+            error!("イベントストアの作成に失敗: {:?}", e);
+            return;
+        }
+    };
+    if let Err(e) = event_store.publish_event(&domain_ev).await {
+        error!(
+            "イベントの発行に失敗: type={}, error={:?}",
+            event.event_type, e
+        );
+    }
+}
+
+async fn build_epg_retriever(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+    telemetry: &Telemetry,
+) -> Result<impl Worker<domain::model::event::recording::epg::Updated>, NatsInfraError> {
+    use domain::usecase::EpgRetrieverUseCaseImpl;
+    use mirakc::MirakcProgramsRetriever;
+
+    let programs_kvs_repo = ProgramsDataRepository::new(nats_client.clone()).await?;
+    let program_entry_repo = ProgramEntryRepository::new(nats_client.clone()).await?;
+    let programs_event_store = EventStore::new(nats_client.clone()).await?;
+
+    Ok(EpgRetrieverWorker(EpgRetrieverUseCaseImpl::new(
+        telemetry.programs_retriever(MirakcProgramsRetriever::new(&config.mirakc.url)),
+        programs_kvs_repo,
+        program_entry_repo,
+        programs_event_store,
+        build_program_event_publisher(nats_client).await?,
+    )))
+}
+
+async fn build_epg_sync(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+    telemetry: &Telemetry,
+) -> Result<impl EpgSyncUseCase + Send + Sync, NatsInfraError> {
+    use domain::usecase::EpgSyncUseCaseImpl;
+    use mirakc::MirakcProgramsRetriever;
+
+    let retriever = MirakcProgramsRetriever::new(&config.mirakc.url);
+    let programs_kvs_repo = ProgramsDataRepository::new(nats_client.clone()).await?;
+    let program_entry_repo = ProgramEntryRepository::new(nats_client.clone()).await?;
+    let programs_event_store = EventStore::new(nats_client.clone()).await?;
+
+    Ok(EpgSyncUseCaseImpl::new(
+        retriever.clone(),
+        telemetry.programs_retriever(retriever),
+        programs_kvs_repo,
+        program_entry_repo,
+        programs_event_store,
+        build_program_event_publisher(nats_client).await?,
+        &config.mirakc.url,
+    ))
+}
+
+async fn build_inventory_sync(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<impl InventorySyncUseCase + Send + Sync, NatsInfraError> {
+    use domain::usecase::InventorySyncUseCaseImpl;
+    use mirakc::MirakcInventoryRetriever;
+
+    Ok(InventorySyncUseCaseImpl::new(
+        MirakcInventoryRetriever::new(&config.mirakc.url),
+        InventoryEntryRepository::new(nats_client.clone()).await?,
+    ))
+}
+
+async fn build_logo_sync(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+    telemetry: &Telemetry,
+) -> Result<impl LogoSyncUseCase + Send + Sync, NatsInfraError> {
+    use domain::service::{ObjectBackedRepository, WebpImageProcessor};
+    use domain::usecase::{LogoSyncUseCaseImpl, ServiceLogoData};
+    use http::ReqwestImageFetcher;
+    use mirakc::MirakcInventoryRetriever;
+
+    let logo_repository: BlobRepository<_, ServiceLogoData> = if config.object_store.store_images {
+        BlobRepository::Object(ObjectBackedRepository::new(
+            build_object_store(config, nats_client).await?,
+            ObjectRefRepository::new(nats_client.clone(), SERVICE_LOGO_REF_BUCKET).await?,
+            "logos",
+            "image/webp",
+        ))
+    } else {
+        BlobRepository::Kv(ServiceLogoDataRepository::new(nats_client.clone()).await?)
+    };
+    let logo_event_store = EventStore::new(nats_client.clone()).await?;
+
+    Ok(LogoSyncUseCaseImpl::new(
+        MirakcInventoryRetriever::new(&config.mirakc.url),
+        ReqwestImageFetcher::default(),
+        telemetry.image_processor(WebpImageProcessor),
+        logo_repository,
+        logo_event_store,
+        &config.mirakc.url,
+    )
+    .with_image_width(config.logo_sync.image_width))
+}
+
+/// 番組ごとの追加・変更・削除イベントをそれぞれのストリームに発行する
+async fn build_program_event_publisher(
+    nats_client: &NatsClient,
+) -> Result<impl ProgramEventPublisher + Send + Sync, NatsInfraError> {
+    use domain::service::ProgramEventPublisherImpl;
+
+    Ok(ProgramEventPublisherImpl::new(
+        EventStore::new(nats_client.clone()).await?,
+        EventStore::new(nats_client.clone()).await?,
+        EventStore::new(nats_client.clone()).await?,
+    ))
+}
+
+async fn build_ogp_url_extractor(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<impl Worker<domain::model::event::recording::programs::Updated>, NatsInfraError> {
+    use domain::model::url_extractor::UrlExtractor;
+    use domain::usecase::OgpUrlExtractorUseCaseImpl;
+
+    let programs_kvs_repo = ProgramsDataRepository::new(nats_client.clone()).await?;
+    let ogp_event_store = EventStore::new(nats_client.clone()).await?;
+
+    Ok(OgpUrlExtractorWorker(
+        OgpUrlExtractorUseCaseImpl::new(programs_kvs_repo, ogp_event_store)
+            .with_extractor(UrlExtractor::new(config.ogp.excluded_domains.clone())),
+    ))
+}
+
+async fn build_ogp_image_extractor(
+    nats_client: &NatsClient,
+) -> Result<impl Worker<domain::model::event::ogp::url::ExtractRequest>, NatsInfraError> {
+    use domain::usecase::OgpImageExtractorUseCaseImpl;
+    use http::ReqwestHtmlFetcher;
+
+    let image_request_store = EventStore::new(nats_client.clone()).await?;
+
+    Ok(OgpImageExtractorWorker(OgpImageExtractorUseCaseImpl::new(
+        ReqwestHtmlFetcher::new(),
+        image_request_store,
+    )))
+}
+
+async fn build_ogp_image_processor(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+    telemetry: &Telemetry,
+) -> Result<impl Worker<domain::model::event::ogp::url::ImageRequest>, NatsInfraError> {
+    use domain::service::{ObjectBackedRepository, WebpImageProcessor};
+    use domain::usecase::{OgpImageProcessorUseCaseImpl, WebpImageData};
+    use http::ReqwestImageFetcher;
+
+    let webp_image_repository: BlobRepository<_, WebpImageData> =
+        if config.object_store.store_images {
+            BlobRepository::Object(ObjectBackedRepository::new(
+                build_object_store(config, nats_client).await?,
+                ObjectRefRepository::new(nats_client.clone(), WEBP_IMAGE_REF_BUCKET).await?,
+                "ogp",
+                "image/webp",
+            ))
+        } else {
+            BlobRepository::Kv(WebpImageDataRepository::new(nats_client.clone()).await?)
+        };
+
+    Ok(OgpImageProcessorWorker(
+        OgpImageProcessorUseCaseImpl::new(
+            ReqwestImageFetcher::default(),
+            telemetry.image_processor(WebpImageProcessor),
+            webp_image_repository,
+        )
+        .with_image_width(config.ogp.image_width),
+    ))
+}
+
+async fn build_auto_reserver(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<impl Worker<domain::model::event::recording::programs::Updated>, NatsInfraError> {
+    use domain::usecase::AutoReservationUseCaseImpl;
+    use mirakc::MirakcRecordingScheduler;
+
+    let programs_kvs_repo = ProgramsDataRepository::new(nats_client.clone()).await?;
+    let rule_repo = RecordingRuleEntryRepository::new(nats_client.clone()).await?;
+
+    Ok(AutoReserverWorker(AutoReservationUseCaseImpl::new(
+        programs_kvs_repo,
+        rule_repo,
+        MirakcRecordingScheduler::new(&config.mirakc.url),
+        EventStore::new(nats_client.clone()).await?,
+        EventStore::new(nats_client.clone()).await?,
+        EventStore::new(nats_client.clone()).await?,
+    )))
+}
+
+async fn build_onair_tracker(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<OnairTrackerWorker<impl OnairTrackerUseCase + Send + Sync + 'static>, NatsInfraError> {
+    use domain::usecase::OnairTrackerUseCaseImpl;
+    use mirakc::MirakcProgramsRetriever;
+
+    let onair_repository = OnairProgramRepository::new(nats_client.clone()).await?;
+
+    Ok(OnairTrackerWorker(OnairTrackerUseCaseImpl::new(
+        MirakcProgramsRetriever::new(&config.mirakc.url),
+        onair_repository,
+    )))
+}
+
+async fn build_recording_tracker(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<
+    RecordingTrackerWorker<impl RecordingTrackerUseCase + Send + Sync + 'static>,
+    NatsInfraError,
+> {
+    use domain::usecase::RecordingTrackerUseCaseImpl;
+    use mirakc::MirakcRecordingScheduler;
+
+    let recording_repository = RecordingRepository::new(nats_client.clone()).await?;
+
+    Ok(RecordingTrackerWorker(Arc::new(
+        RecordingTrackerUseCaseImpl::new(
+            MirakcRecordingScheduler::new(&config.mirakc.url),
+            recording_repository,
+        ),
+    )))
+}
+
+async fn build_recording_conflict(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<
+    RecordingConflictWorker<impl RecordingConflictUseCase + Send + Sync + 'static>,
+    NatsInfraError,
+> {
+    use domain::usecase::RecordingConflictUseCaseImpl;
+    use mirakc::MirakcRecordingScheduler;
+
+    Ok(RecordingConflictWorker(RecordingConflictUseCaseImpl::new(
+        MirakcRecordingScheduler::new(&config.mirakc.url),
+        InventoryEntryRepository::new(nats_client.clone()).await?,
+        EventStore::new(nats_client.clone()).await?,
+    )))
+}
+
+async fn build_transcoder(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<impl Worker<domain::model::event::recording::record::Saved>, NatsInfraError> {
+    use domain::usecase::TranscodeUseCaseImpl;
+    use mirakc::MirakcRecordingScheduler;
+    use transcoder::CommandTranscoder;
+
+    let transcode = &config.transcode;
+
+    Ok(TranscoderWorker(
+        TranscodeUseCaseImpl::new(
+            MirakcRecordingScheduler::new(&config.mirakc.url),
+            CommandTranscoder::new(&transcode.encoder),
+            EventStore::new(nats_client.clone()).await?,
+            EventStore::new(nats_client.clone()).await?,
+            EventStore::new(nats_client.clone()).await?,
+            EventStore::new(nats_client.clone()).await?,
+            &transcode.records_dir,
+            &transcode.output_dir,
+        )
+        .with_profile(transcode.profile.clone()),
+    ))
+}
+
+async fn build_recording_archiver(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<impl Worker<domain::model::event::recording::transcode::Completed>, NatsInfraError> {
+    use domain::usecase::RecordingArchiveUseCaseImpl;
+
+    Ok(RecordingArchiverWorker(RecordingArchiveUseCaseImpl::new(
+        build_object_store(config, nats_client).await?,
+        ObjectRefRepository::new(nats_client.clone(), RECORDING_ARTIFACT_REF_BUCKET).await?,
+    )))
+}
+
+async fn build_program_indexer(
+    nats_client: &NatsClient,
+    search_index: SearchIndexBackend,
+) -> Result<impl Worker<domain::model::event::recording::programs::Updated>, NatsInfraError> {
+    use domain::usecase::ProgramIndexerUseCaseImpl;
+
+    let programs_kvs_repo = ProgramsDataRepository::new(nats_client.clone()).await?;
+
+    Ok(ProgramIndexerWorker(ProgramIndexerUseCaseImpl::new(
+        programs_kvs_repo,
+        search_index,
+    )))
+}
+
+/// 設定で選んだ番組検索のインデックス（tantivyなら辞書を読み込んでインデックスを開く）
+fn build_search_index(config: &KurecConfig) -> Result<SearchIndexBackend, DomainError> {
+    use meilisearch::MeilisearchIndex;
+    use tantivy_search::{JapaneseTokenizer, TantivySearchIndex};
+
+    let search = &config.search;
+    Ok(match search.backend {
+        SearchBackendKind::Meilisearch => {
+            SearchIndexBackend::Meilisearch(MeilisearchIndex::new(&search.meilisearch_config()))
+        }
+        SearchBackendKind::Tantivy => {
+            let tokenizer = JapaneseTokenizer::from_dictionary(&search.tantivy.dictionary)?;
+            SearchIndexBackend::Tantivy(TantivySearchIndex::open(
+                &search.tantivy.index_dir,
+                tokenizer,
+            )?)
+        }
+    })
+}
+
+/// 設定で選んだオブジェクトストア（S3互換ストレージのバケットは最初に使うときに作成する）
+async fn build_object_store(
+    config: &KurecConfig,
+    nats_client: &NatsClient,
+) -> Result<ObjectStoreBackend, NatsInfraError> {
+    use nats::object_store::NatsObjectStore;
+    use s3::S3ObjectStore;
+
+    let object_store = &config.object_store;
+    Ok(match object_store.backend {
+        ObjectStoreBackendKind::Nats => ObjectStoreBackend::Nats(
+            NatsObjectStore::new(nats_client.clone(), &object_store.bucket).await?,
+        ),
+        ObjectStoreBackendKind::S3 => {
+            ObjectStoreBackend::S3(S3ObjectStore::new(&object_store.s3_config()))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_select_all() {
+        let kinds = TaskKind::select(&names(&["all"])).unwrap();
+        assert_eq!(kinds, TaskKind::value_variants());
+    }
+
+    #[test]
+    fn test_select_list_without_duplicates() {
+        let kinds = TaskKind::select(&names(&["events", "ogp-image-processor", "events"])).unwrap();
+        assert_eq!(kinds, [TaskKind::Events, TaskKind::OgpImageProcessor]);
+    }
+
+    #[test]
+    fn test_select_unknown() {
+        let error = TaskKind::select(&names(&["events", "recorder"])).unwrap_err();
+        assert!(error.contains("recorder"));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(TaskKind::EpgRetriever.to_string(), "epg-retriever");
+    }
+}
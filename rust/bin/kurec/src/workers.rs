@@ -0,0 +1,219 @@
+//! パイプラインの各ステージをワーカーとしてユースケースに結びつける
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use domain::{
+    error::DomainError,
+    model::event::{
+        ogp,
+        recording::{epg, onair, programs, record, reservation, schedule, transcode},
+    },
+    usecase::{
+        AutoReservationUseCase, EpgRetrieverUseCase, OgpImageExtractorUseCase,
+        OgpImageProcessorUseCase, OgpUrlExtractorUseCase, OnairTrackerUseCase,
+        ProgramIndexerUseCase, RecordingArchiveUseCase, RecordingConflictUseCase,
+        RecordingTrackerUseCase, TranscodeUseCase,
+    },
+};
+use worker::Worker;
+
+pub struct EpgRetrieverWorker<U>(pub U);
+
+#[async_trait]
+impl<U: EpgRetrieverUseCase + Send + Sync + 'static> Worker<epg::Updated>
+    for EpgRetrieverWorker<U>
+{
+    fn name(&self) -> &str {
+        "epg-retriever"
+    }
+
+    async fn handle(&self, event: &epg::Updated) -> Result<(), DomainError> {
+        self.0.retrieve_programs(event).await
+    }
+}
+
+pub struct OgpUrlExtractorWorker<U>(pub U);
+
+#[async_trait]
+impl<U: OgpUrlExtractorUseCase + Send + Sync + 'static> Worker<programs::Updated>
+    for OgpUrlExtractorWorker<U>
+{
+    fn name(&self) -> &str {
+        "ogp_url_extractor"
+    }
+
+    async fn handle(&self, event: &programs::Updated) -> Result<(), DomainError> {
+        self.0.extract_urls(event).await
+    }
+}
+
+pub struct AutoReserverWorker<U>(pub U);
+
+#[async_trait]
+impl<U: AutoReservationUseCase + Send + Sync + 'static> Worker<programs::Updated>
+    for AutoReserverWorker<U>
+{
+    fn name(&self) -> &str {
+        "auto_reserver"
+    }
+
+    async fn handle(&self, event: &programs::Updated) -> Result<(), DomainError> {
+        self.0.reserve_programs(event).await
+    }
+}
+
+pub struct OnairTrackerWorker<U>(pub U);
+
+#[async_trait]
+impl<U: OnairTrackerUseCase + Send + Sync + 'static> Worker<onair::ProgramChanged>
+    for OnairTrackerWorker<U>
+{
+    fn name(&self) -> &str {
+        "onair_tracker"
+    }
+
+    async fn handle(&self, event: &onair::ProgramChanged) -> Result<(), DomainError> {
+        self.0.track(event).await
+    }
+}
+
+/// 録画のイベントの種類ごとにコンシューマーを分けて、同じユースケースで処理する
+pub struct RecordingTrackerWorker<U>(pub Arc<U>);
+
+impl<U> Clone for RecordingTrackerWorker<U> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+macro_rules! recording_tracker_worker {
+    ($event:ty, $name:literal, $method:ident) => {
+        #[async_trait]
+        impl<U: RecordingTrackerUseCase + Send + Sync + 'static> Worker<$event>
+            for RecordingTrackerWorker<U>
+        {
+            fn name(&self) -> &str {
+                $name
+            }
+
+            async fn handle(&self, event: &$event) -> Result<(), DomainError> {
+                self.0.$method(event).await
+            }
+        }
+    };
+}
+
+recording_tracker_worker!(
+    reservation::Matched,
+    "recording_tracker_reserved",
+    on_reserved
+);
+recording_tracker_worker!(schedule::Started, "recording_tracker_started", on_started);
+recording_tracker_worker!(schedule::Stopped, "recording_tracker_stopped", on_stopped);
+recording_tracker_worker!(schedule::Failed, "recording_tracker_failed", on_failed);
+recording_tracker_worker!(
+    schedule::Rescheduled,
+    "recording_tracker_rescheduled",
+    on_rescheduled
+);
+recording_tracker_worker!(
+    record::Saved,
+    "recording_tracker_record_saved",
+    on_record_saved
+);
+recording_tracker_worker!(
+    record::Broken,
+    "recording_tracker_record_broken",
+    on_record_broken
+);
+
+/// 予約が増えるたびに録画予約の競合を調べる
+pub struct RecordingConflictWorker<U>(pub U);
+
+#[async_trait]
+impl<U: RecordingConflictUseCase + Send + Sync + 'static> Worker<reservation::Matched>
+    for RecordingConflictWorker<U>
+{
+    fn name(&self) -> &str {
+        "recording_conflict_checker"
+    }
+
+    async fn handle(&self, event: &reservation::Matched) -> Result<(), DomainError> {
+        self.0.check_conflicts(&event.mirakc_url).await
+    }
+}
+
+/// 変換が終わるまでメッセージの処理中通知を送り続けるので、長い録画でも再配信されない
+pub struct TranscoderWorker<U>(pub U);
+
+#[async_trait]
+impl<U: TranscodeUseCase + Send + Sync + 'static> Worker<record::Saved> for TranscoderWorker<U> {
+    fn name(&self) -> &str {
+        "transcoder"
+    }
+
+    async fn handle(&self, event: &record::Saved) -> Result<(), DomainError> {
+        self.0.on_record_saved(event).await
+    }
+}
+
+pub struct RecordingArchiverWorker<U>(pub U);
+
+#[async_trait]
+impl<U: RecordingArchiveUseCase + Send + Sync + 'static> Worker<transcode::Completed>
+    for RecordingArchiverWorker<U>
+{
+    fn name(&self) -> &str {
+        "recording_archiver"
+    }
+
+    async fn handle(&self, event: &transcode::Completed) -> Result<(), DomainError> {
+        self.0.on_transcoded(event).await
+    }
+}
+
+pub struct ProgramIndexerWorker<U>(pub U);
+
+#[async_trait]
+impl<U: ProgramIndexerUseCase + Send + Sync + 'static> Worker<programs::Updated>
+    for ProgramIndexerWorker<U>
+{
+    fn name(&self) -> &str {
+        "program_indexer"
+    }
+
+    async fn handle(&self, event: &programs::Updated) -> Result<(), DomainError> {
+        self.0.index_programs(event).await
+    }
+}
+
+pub struct OgpImageExtractorWorker<U>(pub U);
+
+#[async_trait]
+impl<U: OgpImageExtractorUseCase + Send + Sync + 'static> Worker<ogp::url::ExtractRequest>
+    for OgpImageExtractorWorker<U>
+{
+    fn name(&self) -> &str {
+        "ogp_image_extractor"
+    }
+
+    async fn handle(&self, event: &ogp::url::ExtractRequest) -> Result<(), DomainError> {
+        self.0.extract_images(event).await
+    }
+}
+
+pub struct OgpImageProcessorWorker<U>(pub U);
+
+#[async_trait]
+impl<U: OgpImageProcessorUseCase + Send + Sync + 'static> Worker<ogp::url::ImageRequest>
+    for OgpImageProcessorWorker<U>
+{
+    fn name(&self) -> &str {
+        "ogp_image_processor"
+    }
+
+    async fn handle(&self, event: &ogp::url::ImageRequest) -> Result<(), DomainError> {
+        self.0.process_image_request(event).await
+    }
+}
@@ -8,12 +8,61 @@ pub enum DomainError {
     #[error("プログラム取得エラー: {0}")]
     ProgramsRetrievalError(String),
 
+    /// 指定したリビジョンが最新でないため更新できなかった（読み直してやり直せる）
+    #[error("リビジョンが一致しません: {0}")]
+    RevisionConflict(String),
+
+    #[error("KVSエラー: {0}")]
+    KvStoreError(String),
+
     #[error("サービス(ID={0})が見つかりません")]
     ServiceNotFound(i64),
 
+    #[error("チューナー・チャンネル情報の取得エラー: {0}")]
+    InventoryRetrievalError(String),
+
     #[error("画像処理エラー: {0}")]
     ImageProcessingError(String),
 
+    #[error("HTML取得エラー: {0}")]
+    HtmlFetchError(String),
+
+    #[error("HTML解析エラー: {0}")]
+    HtmlParseError(String),
+
+    #[error("録画ルールが正しくありません: {0}")]
+    InvalidRecordingRule(String),
+
+    #[error("録画予約エラー: {0}")]
+    RecordingScheduleError(String),
+
+    #[error("トランスコードエラー: {0}")]
+    TranscodeError(String),
+
+    #[error("オブジェクトストアエラー: {0}")]
+    ObjectStoreError(String),
+
+    #[error("検索インデックスエラー: {0}")]
+    SearchIndexError(String),
+
+    #[error("イベント発行エラー: {0}")]
+    EventPublishError(String),
+
     #[error("不明なエラー: {0}")]
     UnknownError(String),
 }
+
+impl DomainError {
+    /// 再試行すれば成功する可能性があるエラーかどうか
+    ///
+    /// 存在しないリソースの参照など、何度試行しても結果が変わらないエラーは `false` を返す。
+    pub fn is_retryable(&self) -> bool {
+        !matches!(
+            self,
+            DomainError::ServiceNotFound(_)
+                | DomainError::HtmlParseError(_)
+                | DomainError::InvalidRecordingRule(_)
+                | DomainError::TranscodeError(_)
+        )
+    }
+}
@@ -23,6 +23,279 @@ pub mod recording {
         }
         impl Event for Updated {}
     }
+    pub mod program {
+        use serde::{Deserialize, Serialize};
+
+        use crate::model::program::{Program, ProgramField};
+        use crate::types::Event;
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Added {
+            pub service_id: i64,
+            pub program: Program,
+            pub mirakc_url: String,
+        }
+        impl Event for Added {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Changed {
+            pub service_id: i64,
+            pub program: Program,
+            pub changed_fields: Vec<ProgramField>,
+            pub mirakc_url: String,
+        }
+        impl Event for Changed {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Removed {
+            pub service_id: i64,
+            pub program_id: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for Removed {}
+    }
+    pub mod schedule {
+        use serde::{Deserialize, Serialize};
+
+        use crate::model::recording::RecordingFailedReason;
+        use crate::types::Event;
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Started {
+            pub program_id: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for Started {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Stopped {
+            pub program_id: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for Stopped {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Failed {
+            pub program_id: i64,
+            pub reason: RecordingFailedReason,
+            pub mirakc_url: String,
+        }
+        impl Event for Failed {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Rescheduled {
+            pub program_id: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for Rescheduled {}
+
+        /// 録画予約がチューナーの数を超えて重なっている
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Conflict {
+            pub start_at: i64,
+            pub end_at: i64,
+            /// チューナーを割り当てられる予約の番組ID
+            pub winner_program_ids: Vec<i64>,
+            /// チューナーが足りず録画できない予約の番組ID
+            pub loser_program_ids: Vec<i64>,
+            pub mirakc_url: String,
+        }
+        impl Event for Conflict {}
+    }
+    pub mod reservation {
+        use serde::{Deserialize, Serialize};
+
+        use crate::model::recording::ReservationSkipReason;
+        use crate::types::Event;
+
+        /// ルールに一致した番組を予約した
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Matched {
+            pub rule_id: String,
+            pub service_id: i64,
+            pub program_id: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for Matched {}
+
+        /// ルールに一致した番組を予約しなかった
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Skipped {
+            pub rule_id: String,
+            pub service_id: i64,
+            pub program_id: i64,
+            pub reason: ReservationSkipReason,
+            pub mirakc_url: String,
+        }
+        impl Event for Skipped {}
+
+        /// 同じ番組に一致した、より優先度の高いルールがあった
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Conflicted {
+            pub rule_id: String,
+            pub winner_rule_id: String,
+            pub service_id: i64,
+            pub program_id: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for Conflicted {}
+    }
+    pub mod record {
+        use serde::{Deserialize, Serialize};
+
+        use crate::model::recording::RecordingStatus;
+        use crate::types::Event;
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Saved {
+            pub record_id: String,
+            pub recording_status: RecordingStatus,
+            pub mirakc_url: String,
+        }
+        impl Event for Saved {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Removed {
+            pub record_id: String,
+            pub mirakc_url: String,
+        }
+        impl Event for Removed {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Broken {
+            pub record_id: String,
+            pub reason: String,
+            pub mirakc_url: String,
+        }
+        impl Event for Broken {}
+    }
+    pub mod service {
+        use serde::{Deserialize, Serialize};
+
+        use crate::types::Event;
+
+        /// サービスのロゴ画像が追加・変更された
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct LogoChanged {
+            pub service_id: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for LogoChanged {}
+    }
+    pub mod onair {
+        use serde::{Deserialize, Serialize};
+
+        use crate::types::Event;
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct ProgramChanged {
+            pub service_id: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for ProgramChanged {}
+    }
+    pub mod transcode {
+        use serde::{Deserialize, Serialize};
+
+        use crate::types::Event;
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Started {
+            pub record_id: String,
+            pub program_id: i64,
+            pub profile: String,
+            pub output_path: String,
+            pub mirakc_url: String,
+        }
+        impl Event for Started {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Progress {
+            pub record_id: String,
+            pub program_id: i64,
+            /// 出力済みの長さ（ミリ秒）
+            pub out_time: i64,
+            /// 録画の長さが分からなければ `None`
+            pub percent: Option<u8>,
+            pub speed: Option<f64>,
+            pub mirakc_url: String,
+        }
+        impl Event for Progress {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Completed {
+            pub record_id: String,
+            pub program_id: i64,
+            pub profile: String,
+            pub output_path: String,
+            pub output_length: u64,
+            pub mirakc_url: String,
+        }
+        impl Event for Completed {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Failed {
+            pub record_id: String,
+            pub program_id: i64,
+            pub profile: String,
+            pub reason: String,
+            pub mirakc_url: String,
+        }
+        impl Event for Failed {}
+    }
+    pub mod timeshift {
+        use serde::{Deserialize, Serialize};
+
+        use crate::types::Event;
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Timeline {
+            pub recorder: String,
+            pub start_time: Option<i64>,
+            pub end_time: Option<i64>,
+            pub duration: i64,
+            pub mirakc_url: String,
+        }
+        impl Event for Timeline {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Started {
+            pub recorder: String,
+            pub mirakc_url: String,
+        }
+        impl Event for Started {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct Stopped {
+            pub recorder: String,
+            pub mirakc_url: String,
+        }
+        impl Event for Stopped {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct RecordStarted {
+            pub recorder: String,
+            pub record_id: u32,
+            pub mirakc_url: String,
+        }
+        impl Event for RecordStarted {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct RecordUpdated {
+            pub recorder: String,
+            pub record_id: u32,
+            pub mirakc_url: String,
+        }
+        impl Event for RecordUpdated {}
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        pub struct RecordEnded {
+            pub recorder: String,
+            pub record_id: u32,
+            pub mirakc_url: String,
+        }
+        impl Event for RecordEnded {}
+    }
 }
 
 pub mod ogp {
@@ -0,0 +1,152 @@
+//! mirakcのチューナー・チャンネル・サービスの一覧
+
+use serde::{Deserialize, Serialize};
+
+/// 放送の種別
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ChannelType {
+    Gr,
+    Bs,
+    Cs,
+    Sky,
+    #[serde(rename = "BS4K")]
+    Bs4k,
+}
+
+impl ChannelType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelType::Gr => "GR",
+            ChannelType::Bs => "BS",
+            ChannelType::Cs => "CS",
+            ChannelType::Sky => "SKY",
+            ChannelType::Bs4k => "BS4K",
+        }
+    }
+}
+
+impl std::fmt::Display for ChannelType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// mirakcのチューナー
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tuner {
+    /// mirakcの設定での順番
+    pub index: u32,
+    pub name: String,
+    /// 受信できる放送の種別
+    pub types: Vec<ChannelType>,
+    pub is_available: bool,
+    pub is_fault: bool,
+}
+
+impl Tuner {
+    /// `channel_type` の放送を受信できるかどうか
+    pub fn supports(&self, channel_type: ChannelType) -> bool {
+        self.is_available && !self.is_fault && self.types.contains(&channel_type)
+    }
+}
+
+/// 物理チャンネル
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelInfo {
+    pub channel_type: ChannelType,
+    /// チャンネル番号（例: 地上波の `27`、BSの `BS01_0`）
+    pub channel: String,
+    pub name: String,
+    /// このチャンネルで放送しているサービスのID
+    pub service_ids: Vec<i64>,
+}
+
+impl ChannelInfo {
+    /// 種別とチャンネル番号をつないだキー（例: `GR.27`）
+    pub fn key(&self) -> String {
+        format!("{}.{}", self.channel_type, self.channel)
+    }
+}
+
+/// サービス（番組を放送する編成チャンネル）
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Service {
+    /// Mirakurun形式のサービスID（ネットワークID * 100000 + サービスID）
+    pub id: i64,
+    pub service_id: i32,
+    pub network_id: i32,
+    /// ARIBのサービス形式種別（1はデジタルTV）
+    pub service_type: i32,
+    pub name: String,
+    pub channel_type: ChannelType,
+    pub channel: String,
+    pub has_logo_data: bool,
+    pub logo_id: Option<i32>,
+    /// リモコンのボタン番号
+    pub remote_control_key_id: Option<i32>,
+}
+
+/// チューナー・チャンネル・サービスの一覧の一式
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Inventory {
+    pub tuners: Vec<Tuner>,
+    pub channels: Vec<ChannelInfo>,
+    pub services: Vec<Service>,
+}
+
+impl Inventory {
+    /// `channel_type` の放送を受信できるチューナーの数
+    pub fn tuner_count(&self, channel_type: ChannelType) -> usize {
+        self.tuners
+            .iter()
+            .filter(|tuner| tuner.supports(channel_type))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::test_util::test_tuner;
+
+    #[test]
+    fn test_channel_type_serde() {
+        let types = vec![ChannelType::Gr, ChannelType::Sky, ChannelType::Bs4k];
+        let json = serde_json::to_string(&types).unwrap();
+        assert_eq!(json, r#"["GR","SKY","BS4K"]"#);
+        assert_eq!(
+            serde_json::from_str::<Vec<ChannelType>>(&json).unwrap(),
+            types
+        );
+    }
+
+    #[test]
+    fn test_tuner_count() {
+        let mut fault = test_tuner(2, &[ChannelType::Gr]);
+        fault.is_fault = true;
+        let inventory = Inventory {
+            tuners: vec![
+                test_tuner(0, &[ChannelType::Gr]),
+                test_tuner(1, &[ChannelType::Bs, ChannelType::Cs]),
+                fault,
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(inventory.tuner_count(ChannelType::Gr), 1);
+        assert_eq!(inventory.tuner_count(ChannelType::Cs), 1);
+        assert_eq!(inventory.tuner_count(ChannelType::Sky), 0);
+    }
+
+    #[test]
+    fn test_channel_key() {
+        let channel = ChannelInfo {
+            channel_type: ChannelType::Bs,
+            channel: "BS01_0".to_string(),
+            name: "BS1".to_string(),
+            service_ids: vec![400101],
+        };
+        assert_eq!(channel.key(), "BS.BS01_0");
+    }
+}
@@ -1,3 +1,10 @@
 pub mod event;
+pub mod inventory;
+pub mod object;
+pub mod onair;
 pub mod program;
+pub mod recording;
+pub mod recording_rule;
+pub mod search;
+pub mod transcode;
 pub mod url_extractor;
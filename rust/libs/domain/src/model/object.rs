@@ -0,0 +1,106 @@
+//! オブジェクトストアに保存するオブジェクト
+
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+/// 種類が分からないオブジェクトのContent-Type
+pub const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// オブジェクトを保存するときに付ける情報
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PutOptions {
+    pub content_type: String,
+    /// 任意のメタデータ（S3互換ストレージでは `x-amz-meta-*` になる）
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl Default for PutOptions {
+    fn default() -> Self {
+        Self::new(DEFAULT_CONTENT_TYPE)
+    }
+}
+
+impl PutOptions {
+    pub fn new(content_type: &str) -> Self {
+        Self {
+            content_type: content_type.to_string(),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// メタデータを追加する
+    pub fn with_metadata(mut self, key: &str, value: impl ToString) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+/// 保存されているオブジェクトの情報
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObjectMeta {
+    pub key: String,
+    /// サイズ（バイト）
+    pub size: u64,
+    pub content_type: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+    /// 最終更新日時（UNIX時間のミリ秒、分からなければ `None`）
+    pub last_modified: Option<i64>,
+}
+
+/// 中身を読み込んだオブジェクト
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredObject {
+    pub meta: ObjectMeta,
+    pub data: Bytes,
+}
+
+/// KVに置く、オブジェクトストアに保存した中身への参照
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectRef {
+    /// オブジェクトストアでのキー
+    pub key: String,
+    pub content_type: String,
+    /// サイズ（バイト）
+    pub size: u64,
+}
+
+impl ObjectRef {
+    pub fn new(meta: &ObjectMeta, options: &PutOptions) -> Self {
+        Self {
+            key: meta.key.clone(),
+            content_type: meta
+                .content_type
+                .clone()
+                .unwrap_or_else(|| options.content_type.clone()),
+            size: meta.size,
+        }
+    }
+}
+
+impl From<Bytes> for ObjectRef {
+    fn from(bytes: Bytes) -> Self {
+        serde_json::from_slice(&bytes).unwrap_or_default()
+    }
+}
+
+impl From<ObjectRef> for Bytes {
+    fn from(object_ref: ObjectRef) -> Self {
+        Bytes::from(serde_json::to_vec(&object_ref).unwrap_or_default())
+    }
+}
+
+/// ファイルの拡張子から動画・画像のContent-Typeを推測する
+pub fn content_type_for_extension(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "mp4" | "m4v" => "video/mp4",
+        "mkv" => "video/x-matroska",
+        "webm" => "video/webm",
+        "ts" | "m2ts" => "video/MP2T",
+        "webp" => "image/webp",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        _ => DEFAULT_CONTENT_TYPE,
+    }
+}
@@ -0,0 +1,29 @@
+//! サービスで放送中の番組
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use super::program::Program;
+
+/// サービスで放送中の番組と次の番組（EIT[p/f]から得たもの）
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OnairProgram {
+    /// Mirakurun形式のサービスID
+    pub service_id: i64,
+    /// 放送中の番組（放送していないときは `None`）
+    pub current: Option<Program>,
+    /// 次の番組
+    pub next: Option<Program>,
+}
+
+impl From<Bytes> for OnairProgram {
+    fn from(bytes: Bytes) -> Self {
+        serde_json::from_slice(&bytes).unwrap_or_default()
+    }
+}
+
+impl From<OnairProgram> for Bytes {
+    fn from(onair: OnairProgram) -> Self {
+        Bytes::from(serde_json::to_vec(&onair).unwrap_or_default())
+    }
+}
@@ -1,9 +1,9 @@
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Program {
     pub id: i64,
     pub event_id: i32,
@@ -58,6 +58,72 @@ impl Program {
             related_items: None,
         }
     }
+
+    /// Mirakurun形式のサービスID（番組IDはサービスID * 100000 + イベントID）
+    pub fn mirakurun_service_id(&self) -> i64 {
+        self.id / 100000
+    }
+
+    /// `start_at` から `end_at` まで（UNIX時間のミリ秒）の間に少しでも放送されるかどうか
+    pub fn overlaps(&self, start_at: i64, end_at: i64) -> bool {
+        self.start_at < end_at && start_at < self.end_at
+    }
+
+    /// 大分類 `lv1`（`lv2` を指定した場合は中分類も）が一致するジャンルを持つかどうか
+    pub fn has_genre(&self, lv1: u8, lv2: Option<u8>) -> bool {
+        self.genres
+            .iter()
+            .any(|genre| genre.lv1 == lv1 && lv2.is_none_or(|lv2| genre.lv2 == lv2))
+    }
+
+    /// `other` と比べて値が異なる項目を返す
+    ///
+    /// 他の項目から導出される `end_at` などは含めない。
+    pub fn changed_fields(&self, other: &Program) -> Vec<ProgramField> {
+        let mut fields = Vec::new();
+        let mut check = |changed: bool, field: ProgramField| {
+            if changed {
+                fields.push(field);
+            }
+        };
+        check(self.event_id != other.event_id, ProgramField::EventId);
+        check(self.start_at != other.start_at, ProgramField::StartAt);
+        check(self.duration != other.duration, ProgramField::Duration);
+        check(self.is_free != other.is_free, ProgramField::IsFree);
+        check(self.name != other.name, ProgramField::Name);
+        check(
+            self.description != other.description,
+            ProgramField::Description,
+        );
+        check(self.extended != other.extended, ProgramField::Extended);
+        check(self.genres != other.genres, ProgramField::Genres);
+        check(self.channel != other.channel, ProgramField::Channel);
+        check(self.video != other.video, ProgramField::Video);
+        check(self.audio != other.audio, ProgramField::Audio);
+        check(
+            self.related_items != other.related_items,
+            ProgramField::RelatedItems,
+        );
+        fields
+    }
+}
+
+/// 番組の変更を検出する項目
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgramField {
+    EventId,
+    StartAt,
+    Duration,
+    IsFree,
+    Name,
+    Description,
+    Extended,
+    Genres,
+    Channel,
+    Video,
+    Audio,
+    RelatedItems,
 }
 
 #[derive(Debug, Clone)]
@@ -74,13 +140,13 @@ pub struct ProgramTiming {
     pub duration: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Channel {
     pub id: i64,
     pub name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Genre {
     pub lv1: u8,
     pub lv2: u8,
@@ -239,7 +305,7 @@ impl fmt::Display for Genre {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Video {
     pub r#type: Option<String>,
     pub resolution: Option<String>,
@@ -266,7 +332,7 @@ impl Video {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Audio {
     pub component_type: Option<u8>,
     pub component_type_name: Option<String>,
@@ -277,6 +343,14 @@ pub struct Audio {
 }
 
 impl Audio {
+    /// 1/0+1/0モード（デュアルモノ）
+    pub const DUAL_MONO_COMPONENT_TYPE: u8 = 0b00010;
+
+    /// 左右のチャンネルに別々の音声（主・副音声）が入ったデュアルモノかどうか
+    pub fn is_dual_mono(component_type: u8) -> bool {
+        component_type == Self::DUAL_MONO_COMPONENT_TYPE
+    }
+
     pub fn get_component_type_name(component_type: u8) -> String {
         match component_type {
             0b00001 => "1/0モード（シングルモノ）".to_string(),
@@ -305,7 +379,7 @@ impl Audio {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RelatedItem {
     pub r#type: String,
     pub network_id: Option<i32>,
@@ -313,9 +387,48 @@ pub struct RelatedItem {
     pub event_id: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProgramsData(pub Vec<Program>);
 
+/// 同じサービスの新旧の番組表を `Program::id` で突き合わせた差分
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProgramsDiff {
+    pub added: Vec<Program>,
+    /// 変更後の番組と、変更された項目
+    pub changed: Vec<(Program, Vec<ProgramField>)>,
+    pub removed: Vec<Program>,
+}
+
+impl ProgramsDiff {
+    pub fn between(old: &[Program], new: &[Program]) -> Self {
+        let old_by_id: HashMap<i64, &Program> = old.iter().map(|p| (p.id, p)).collect();
+        let new_ids: HashSet<i64> = new.iter().map(|p| p.id).collect();
+
+        let mut diff = Self::default();
+        for program in new {
+            match old_by_id.get(&program.id) {
+                None => diff.added.push(program.clone()),
+                Some(old_program) => {
+                    let fields = old_program.changed_fields(program);
+                    if !fields.is_empty() {
+                        diff.changed.push((program.clone(), fields));
+                    }
+                }
+            }
+        }
+        diff.removed = old
+            .iter()
+            .filter(|p| !new_ids.contains(&p.id))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
 impl From<Bytes> for ProgramsData {
     fn from(bytes: Bytes) -> Self {
         serde_json::from_slice(&bytes).unwrap_or_else(|_| ProgramsData(Vec::new()))
@@ -332,6 +445,30 @@ impl From<ProgramsData> for Bytes {
 mod tests {
     use super::*;
 
+    /// サービス1の30分のアニメ番組
+    fn program(id: i64, name: &str) -> Program {
+        Program::new(
+            ProgramIdentifiers {
+                id,
+                event_id: id as i32,
+                service_id: 1,
+                network_id: 0,
+            },
+            ProgramTiming {
+                start_at: 1619856000000,
+                duration: 1800000,
+            },
+            true,
+            Some(name.to_string()),
+            None,
+            vec![Genre { lv1: 7, lv2: 0 }],
+            Channel {
+                id: 1,
+                name: "テストチャンネル".to_string(),
+            },
+        )
+    }
+
     #[test]
     fn test_program_serialization() {
         let identifiers = ProgramIdentifiers {
@@ -379,6 +516,54 @@ mod tests {
         assert_eq!(deserialized.channel.name, program.channel.name);
     }
 
+    #[test]
+    fn test_programs_diff() {
+        let kept = program(1, "変わらない番組");
+        let mut changed = program(2, "変わる番組");
+        let removed = program(3, "なくなる番組");
+        let added = program(4, "新しい番組");
+        let old = vec![kept.clone(), changed.clone(), removed.clone()];
+
+        changed.name = Some("変わった番組".to_string());
+        changed.start_at += 60000;
+        let new = vec![kept, changed.clone(), added.clone()];
+
+        let diff = ProgramsDiff::between(&old, &new);
+
+        assert_eq!(diff.added, [added]);
+        assert_eq!(
+            diff.changed,
+            [(changed, vec![ProgramField::StartAt, ProgramField::Name])]
+        );
+        assert_eq!(diff.removed, [removed]);
+        assert!(ProgramsDiff::between(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_program_overlaps_and_genre() {
+        let program = program(1, "番組");
+        let start_at = program.start_at;
+        let end_at = program.end_at;
+
+        assert!(program.overlaps(start_at - 1, start_at + 1));
+        assert!(program.overlaps(end_at - 1, end_at + 1));
+        assert!(!program.overlaps(end_at, end_at + 1));
+        assert!(!program.overlaps(start_at - 1, start_at));
+
+        assert!(program.has_genre(7, None));
+        assert!(program.has_genre(7, Some(0)));
+        assert!(!program.has_genre(7, Some(1)));
+        assert!(!program.has_genre(0, None));
+    }
+
+    #[test]
+    fn test_program_field_serialization() {
+        assert_eq!(
+            serde_json::to_string(&ProgramField::RelatedItems).unwrap(),
+            r#""related_items""#
+        );
+    }
+
     #[test]
     fn test_genre_to_string() {
         assert_eq!(
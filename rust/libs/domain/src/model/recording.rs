@@ -0,0 +1,422 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::model::inventory::{ChannelType, Service};
+use crate::model::program::{Audio, Program};
+
+/// 録画失敗の理由
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordingFailedReason {
+    StartRecordingFailed {
+        message: String,
+    },
+    IoError {
+        message: String,
+        os_error: Option<i32>,
+    },
+    PipelineError {
+        exit_code: i32,
+    },
+    NeedRescheduling,
+    ScheduleExpired,
+    RemovedFromEpg,
+}
+
+/// 録画済みレコードの状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordingStatus {
+    Recording,
+    Finished,
+    Canceled,
+    Failed,
+}
+
+/// mirakcに作成を依頼する録画予約
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScheduleRequest {
+    pub program_id: i64,
+    /// チューナーを使う優先度
+    pub priority: i32,
+    pub tags: Vec<String>,
+}
+
+/// 録画予約の作成結果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScheduleOutcome {
+    Created,
+    /// 同じ番組の予約がすでにある
+    AlreadyScheduled,
+    /// mirakcが番組を知らない（番組表から消えたなど）
+    ProgramNotFound,
+}
+
+/// ルールに一致した番組を予約しなかった理由
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReservationSkipReason {
+    AlreadyScheduled,
+    ProgramNotFound,
+}
+
+/// mirakcの録画予約の状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleState {
+    Scheduled,
+    /// 番組の開始を追跡中
+    Tracking,
+    Recording,
+    /// 番組の変更により再予約待ち
+    Rescheduling,
+    Finished,
+    Failed,
+}
+
+/// mirakcに登録されている録画予約
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingSchedule {
+    pub program_id: i64,
+    pub program_name: Option<String>,
+    pub start_at: i64,
+    pub end_at: i64,
+    pub state: ScheduleState,
+    pub priority: i32,
+    pub tags: Vec<String>,
+    pub failed_reason: Option<RecordingFailedReason>,
+}
+
+/// 録画中のレコーダー
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveRecorder {
+    pub program_id: i64,
+    pub started_at: i64,
+}
+
+/// mirakcが保存している録画
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub id: String,
+    pub program_id: i64,
+    /// Mirakurun形式のサービスID
+    pub service_id: i64,
+    pub program_name: Option<String>,
+    pub tags: Vec<String>,
+    pub status: RecordingStatus,
+    pub start_time: i64,
+    /// 録画中は `None`
+    pub end_time: Option<i64>,
+    /// mirakcの録画ディレクトリからの相対パス
+    pub content_path: String,
+    pub content_type: String,
+    /// コンテンツのファイルがなければ `None`
+    pub content_length: Option<u64>,
+    pub failed_reason: Option<RecordingFailedReason>,
+    /// 番組の音声のコンポーネントタイプ（主音声から順に）
+    #[serde(default)]
+    pub audio_component_types: Vec<u8>,
+}
+
+impl Record {
+    /// 録画の長さ（ミリ秒、録画中は `None`）
+    pub fn duration(&self) -> Option<i64> {
+        self.end_time.map(|end_time| end_time - self.start_time)
+    }
+
+    /// 主音声がデュアルモノ（二か国語放送など）かどうか
+    pub fn is_dual_mono(&self) -> bool {
+        self.audio_component_types
+            .first()
+            .is_some_and(|&component_type| Audio::is_dual_mono(component_type))
+    }
+}
+
+/// チューナーの競合を調べる録画予約
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledRecording {
+    pub program_id: i64,
+    pub start_at: i64,
+    pub end_at: i64,
+    /// 同じ物理チャンネルの予約は1つのチューナーを共有する
+    pub channel_type: ChannelType,
+    pub channel: String,
+    /// チューナーを使う優先度（大きいほど優先）
+    pub priority: i32,
+}
+
+impl ScheduledRecording {
+    /// 番組と、それを放送するサービスから作る
+    pub fn new(program: &Program, service: &Service, priority: i32) -> Self {
+        Self {
+            program_id: program.id,
+            start_at: program.start_at,
+            end_at: program.end_at,
+            channel_type: service.channel_type,
+            channel: service.channel.clone(),
+            priority,
+        }
+    }
+
+    /// `start_at` から `end_at` までの間に少しでも録画するかどうか
+    pub fn overlaps(&self, start_at: i64, end_at: i64) -> bool {
+        self.start_at < end_at && start_at < self.end_at
+    }
+}
+
+/// チューナーが足りず、一部の予約を録画できない期間
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingConflict {
+    pub start_at: i64,
+    pub end_at: i64,
+    /// チューナーを割り当てられる予約の番組ID（昇順）
+    pub winners: Vec<i64>,
+    /// チューナーを割り当てられない予約の番組ID（昇順）
+    pub losers: Vec<i64>,
+}
+
+/// kurecが追跡している録画の状態
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingState {
+    #[default]
+    Scheduled,
+    /// レコーダーが起動し、録画の保存を待っている
+    Tuning,
+    Recording,
+    Saved,
+    /// 録画の途中で取り消された
+    Canceled,
+    Failed,
+    /// 保存した録画が壊れていた
+    Broken,
+}
+
+impl RecordingState {
+    /// これ以上録画が進まない状態かどうか
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            RecordingState::Saved
+                | RecordingState::Canceled
+                | RecordingState::Failed
+                | RecordingState::Broken
+        )
+    }
+}
+
+/// 1つの番組の録画の経過
+///
+/// 録画のイベントは種類ごとに別々に処理されるので、順番が入れ替わって届いても
+/// 状態が後戻りしないように遷移を制限する。各メソッドは変化があった場合に `true` を返す。
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recording {
+    pub program_id: i64,
+    pub state: RecordingState,
+    /// mirakcの録画ID（保存が始まるまでは `None`）
+    pub record_id: Option<String>,
+    /// mirakcの録画ディレクトリからの相対パス
+    pub content_path: Option<String>,
+    pub content_length: Option<u64>,
+    pub failed_reason: Option<RecordingFailedReason>,
+    pub broken_reason: Option<String>,
+    /// 追跡を始めた時刻（UNIX時間のミリ秒、以下同じ）
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub started_at: Option<i64>,
+    pub ended_at: Option<i64>,
+}
+
+impl Recording {
+    pub fn new(program_id: i64, at: i64) -> Self {
+        Self {
+            program_id,
+            created_at: at,
+            updated_at: at,
+            ..Default::default()
+        }
+    }
+
+    /// レコーダーが起動した
+    pub fn start(&mut self, at: i64) -> bool {
+        if self.state != RecordingState::Scheduled {
+            return false;
+        }
+        self.state = RecordingState::Tuning;
+        self.started_at = Some(at);
+        self.touch(at)
+    }
+
+    /// レコーダーが停止した（結果は録画の保存で決まる）
+    pub fn stop(&mut self, at: i64) -> bool {
+        if !matches!(
+            self.state,
+            RecordingState::Tuning | RecordingState::Recording
+        ) || self.ended_at.is_some()
+        {
+            return false;
+        }
+        self.ended_at = Some(at);
+        self.touch(at)
+    }
+
+    /// mirakcが録画を保存した（録画中も繰り返し保存される）
+    pub fn save(&mut self, record: &Record, at: i64) -> bool {
+        let state = match record.status {
+            RecordingStatus::Recording => RecordingState::Recording,
+            RecordingStatus::Finished => RecordingState::Saved,
+            RecordingStatus::Canceled => RecordingState::Canceled,
+            RecordingStatus::Failed => RecordingState::Failed,
+        };
+        if self.state == RecordingState::Broken
+            || (self.state.is_finished() && !state.is_finished())
+        {
+            return false;
+        }
+
+        let before = self.clone();
+        self.state = state;
+        self.record_id = Some(record.id.clone());
+        self.content_path = Some(record.content_path.clone());
+        self.content_length = record.content_length;
+        self.started_at = Some(record.start_time);
+        self.ended_at = record.end_time.or(self.ended_at);
+        if record.failed_reason.is_some() {
+            self.failed_reason = record.failed_reason.clone();
+        }
+        *self != before && self.touch(at)
+    }
+
+    /// 録画に失敗した
+    pub fn fail(&mut self, reason: RecordingFailedReason, at: i64) -> bool {
+        if (self.state.is_finished() && self.state != RecordingState::Failed)
+            || self.failed_reason.as_ref() == Some(&reason)
+        {
+            return false;
+        }
+        self.state = RecordingState::Failed;
+        self.failed_reason = Some(reason);
+        self.ended_at = self.ended_at.or(Some(at));
+        self.touch(at)
+    }
+
+    /// 番組の変更により予約し直された
+    pub fn reschedule(&mut self, at: i64) -> bool {
+        if matches!(
+            self.state,
+            RecordingState::Scheduled
+                | RecordingState::Saved
+                | RecordingState::Canceled
+                | RecordingState::Broken
+        ) {
+            return false;
+        }
+        self.state = RecordingState::Scheduled;
+        self.started_at = None;
+        self.ended_at = None;
+        self.failed_reason = None;
+        self.touch(at)
+    }
+
+    /// 保存した録画が壊れていた
+    pub fn mark_broken(&mut self, reason: &str, at: i64) -> bool {
+        if self.state == RecordingState::Broken && self.broken_reason.as_deref() == Some(reason) {
+            return false;
+        }
+        self.state = RecordingState::Broken;
+        self.broken_reason = Some(reason.to_string());
+        self.touch(at)
+    }
+
+    fn touch(&mut self, at: i64) -> bool {
+        self.updated_at = at;
+        true
+    }
+}
+
+impl From<Bytes> for Recording {
+    fn from(bytes: Bytes) -> Self {
+        serde_json::from_slice(&bytes).unwrap_or_default()
+    }
+}
+
+impl From<Recording> for Bytes {
+    fn from(recording: Recording) -> Self {
+        Bytes::from(serde_json::to_vec(&recording).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(status: RecordingStatus, end_time: Option<i64>) -> Record {
+        Record {
+            id: "0000000000000001".to_string(),
+            program_id: 1,
+            service_id: 3273601024,
+            program_name: Some("ニュース".to_string()),
+            tags: vec![],
+            status,
+            start_time: 1000,
+            end_time,
+            content_path: "0000000000000001.m2ts".to_string(),
+            content_type: "video/MP2T".to_string(),
+            content_length: Some(1024),
+            failed_reason: None,
+            audio_component_types: vec![0b00011],
+        }
+    }
+
+    #[test]
+    fn test_recording_lifecycle() {
+        let mut recording = Recording::new(1, 0);
+
+        assert!(recording.start(1000));
+        assert_eq!(recording.state, RecordingState::Tuning);
+        assert!(recording.save(&record(RecordingStatus::Recording, None), 2000));
+        assert_eq!(recording.state, RecordingState::Recording);
+        assert!(!recording.save(&record(RecordingStatus::Recording, None), 3000));
+        assert!(recording.stop(4000));
+        assert!(recording.save(&record(RecordingStatus::Finished, Some(3900)), 5000));
+
+        assert_eq!(recording.state, RecordingState::Saved);
+        assert_eq!(recording.record_id.as_deref(), Some("0000000000000001"));
+        assert_eq!(recording.content_length, Some(1024));
+        assert_eq!(recording.started_at, Some(1000));
+        assert_eq!(recording.ended_at, Some(3900));
+        assert_eq!(recording.updated_at, 5000);
+    }
+
+    #[test]
+    fn test_recording_ignores_late_events() {
+        let mut recording = Recording::new(1, 0);
+        recording.save(&record(RecordingStatus::Finished, Some(3900)), 5000);
+
+        // 種類の違うイベントが遅れて届いても後戻りしない
+        assert!(!recording.start(6000));
+        assert!(!recording.save(&record(RecordingStatus::Recording, None), 6000));
+        assert!(!recording.fail(RecordingFailedReason::ScheduleExpired, 6000));
+        assert!(!recording.reschedule(6000));
+        assert_eq!(recording.state, RecordingState::Saved);
+        assert_eq!(recording.updated_at, 5000);
+
+        assert!(recording.mark_broken("壊れています", 7000));
+        assert!(!recording.mark_broken("壊れています", 8000));
+        assert!(!recording.save(&record(RecordingStatus::Finished, Some(3900)), 8000));
+        assert_eq!(recording.state, RecordingState::Broken);
+    }
+
+    #[test]
+    fn test_recording_rescheduled_after_failure() {
+        let mut recording = Recording::new(1, 0);
+        recording.start(1000);
+
+        assert!(recording.fail(RecordingFailedReason::NeedRescheduling, 2000));
+        assert_eq!(recording.ended_at, Some(2000));
+        assert!(recording.reschedule(3000));
+
+        assert_eq!(recording.state, RecordingState::Scheduled);
+        assert_eq!(recording.failed_reason, None);
+        assert_eq!(recording.started_at, None);
+        assert!(recording.start(4000));
+    }
+}
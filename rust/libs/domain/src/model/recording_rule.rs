@@ -0,0 +1,408 @@
+//! 番組を自動で録画予約するためのルール
+
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::error::DomainError;
+use crate::model::program::Program;
+
+/// 番組表の時刻（UTCのUNIX時間）を日本時間に直すためのオフセット（ミリ秒）
+const JST_OFFSET_MILLIS: i64 = 9 * 60 * 60 * 1000;
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// 自動予約のルール
+///
+/// 省略した条件はすべての番組に一致する。曜日と時刻は日本時間で判定する。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordingRule {
+    /// ルールのID（KVのキーに使うので英数字と `-`、`_` のみ）
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub keywords: KeywordFilter,
+    /// いずれかに一致するジャンル
+    #[serde(default)]
+    pub genres: Vec<GenreFilter>,
+    /// 対象のサービスID（Mirakurun形式）
+    #[serde(default)]
+    pub service_ids: Vec<i64>,
+    /// 対象のチャンネル名（サービス名）
+    #[serde(default)]
+    pub channel_names: Vec<String>,
+    /// 放送開始の曜日
+    #[serde(default)]
+    pub weekdays: Vec<Weekday>,
+    /// 放送開始時刻の範囲
+    #[serde(default)]
+    pub time_window: Option<TimeWindow>,
+    /// 無料放送かどうか
+    #[serde(default)]
+    pub is_free: Option<bool>,
+    /// 優先度（大きいほど優先し、mirakcのチューナーの優先度にも使う）
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl RecordingRule {
+    /// ID・正規表現・時間帯が正しいかを検証する
+    pub fn validate(&self) -> Result<(), DomainError> {
+        let valid_id = !self.id.is_empty()
+            && self
+                .id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_id {
+            return Err(DomainError::InvalidRecordingRule(format!(
+                "IDには英数字と - _ のみ使えます: {:?}",
+                self.id
+            )));
+        }
+        RuleMatcher::new(self.clone()).map(|_| ())
+    }
+}
+
+/// 番組名と番組説明に対するキーワードの条件（正規表現）
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeywordFilter {
+    /// いずれかに一致すること
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// どれにも一致しないこと
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// ジャンルの条件（`lv2` を省略すると大分類だけで判定する）
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenreFilter {
+    pub lv1: u8,
+    #[serde(default)]
+    pub lv2: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Sun,
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+}
+
+impl Weekday {
+    const ALL: [Weekday; 7] = [
+        Weekday::Sun,
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+    ];
+
+    /// UNIX時間（ミリ秒）の日本時間での曜日
+    pub fn of(timestamp: i64) -> Weekday {
+        let days = (timestamp + JST_OFFSET_MILLIS).div_euclid(DAY_MILLIS);
+        // 1970-01-01は木曜日
+        Self::ALL[(days + 4).rem_euclid(7) as usize]
+    }
+}
+
+/// 1日のうちの時刻（0時からの分）
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(u16);
+
+impl TimeOfDay {
+    pub fn new(hour: u16, minute: u16) -> Option<Self> {
+        (hour < 24 && minute < 60).then_some(Self(hour * 60 + minute))
+    }
+
+    /// UNIX時間（ミリ秒）の日本時間での時刻
+    pub fn of(timestamp: i64) -> TimeOfDay {
+        let millis = (timestamp + JST_OFFSET_MILLIS).rem_euclid(DAY_MILLIS);
+        Self((millis / 60_000) as u16)
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    /// `HH:MM` 形式の文字列を読む
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .split_once(':')
+            .and_then(|(hour, minute)| TimeOfDay::new(hour.parse().ok()?, minute.parse().ok()?))
+            .ok_or_else(|| format!("時刻は HH:MM の形式である必要があります: {}", value))
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+/// 放送開始時刻の範囲（`end` を含まない）
+///
+/// `start` が `end` より遅い場合は日付をまたぐ範囲（例: 23:00〜02:00）として扱う。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: TimeOfDay) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+/// 正規表現をコンパイルしたルール
+pub struct RuleMatcher {
+    rule: RecordingRule,
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl RuleMatcher {
+    pub fn new(rule: RecordingRule) -> Result<Self, DomainError> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    Regex::new(pattern).map_err(|e| {
+                        DomainError::InvalidRecordingRule(format!(
+                            "ルール {} の正規表現が正しくありません: {}",
+                            rule.id, e
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        if let Some(window) = &rule.time_window
+            && window.start == window.end
+        {
+            return Err(DomainError::InvalidRecordingRule(format!(
+                "ルール {} の時間帯の開始と終了が同じです: {}",
+                rule.id, window.start
+            )));
+        }
+        let include = compile(&rule.keywords.include)?;
+        let exclude = compile(&rule.keywords.exclude)?;
+
+        Ok(Self {
+            rule,
+            include,
+            exclude,
+        })
+    }
+
+    pub fn rule(&self) -> &RecordingRule {
+        &self.rule
+    }
+
+    /// 番組がルールのすべての条件に一致するかどうか
+    pub fn matches(&self, program: &Program) -> bool {
+        let rule = &self.rule;
+        if !rule.service_ids.is_empty()
+            && !rule.service_ids.contains(&program.mirakurun_service_id())
+        {
+            return false;
+        }
+        if !rule.channel_names.is_empty() && !rule.channel_names.contains(&program.channel.name) {
+            return false;
+        }
+        if rule
+            .is_free
+            .is_some_and(|is_free| is_free != program.is_free)
+        {
+            return false;
+        }
+        if !rule.weekdays.is_empty() && !rule.weekdays.contains(&Weekday::of(program.start_at)) {
+            return false;
+        }
+        if let Some(window) = &rule.time_window
+            && !window.contains(TimeOfDay::of(program.start_at))
+        {
+            return false;
+        }
+        if !rule.genres.is_empty()
+            && !rule
+                .genres
+                .iter()
+                .any(|genre| program.has_genre(genre.lv1, genre.lv2))
+        {
+            return false;
+        }
+        self.matches_keywords(program)
+    }
+
+    fn matches_keywords(&self, program: &Program) -> bool {
+        let texts: Vec<&str> = [&program.name, &program.description]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        let found = |regex: &Regex| texts.iter().any(|text| regex.is_match(text));
+
+        (self.include.is_empty() || self.include.iter().any(found))
+            && !self.exclude.iter().any(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::test_util::test_program;
+
+    /// 2021-05-01（土）21:00 JST に始まるアニメ
+    fn dragon() -> Program {
+        let mut program = test_program(
+            327360102408478,
+            3273601024,
+            1619870400000,
+            "小林さんちのメイドラゴン　＃３[再]",
+        );
+        program.description = Some("新生活、はじまる！".to_string());
+        program
+    }
+
+    fn rule() -> RecordingRule {
+        serde_json::from_str(r#"{"id": "dragon", "name": "メイドラゴン"}"#).unwrap()
+    }
+
+    fn matches(rule: RecordingRule) -> bool {
+        RuleMatcher::new(rule).unwrap().matches(&dragon())
+    }
+
+    #[test]
+    fn test_empty_rule_matches_everything() {
+        let rule = rule();
+        assert!(rule.enabled);
+        assert!(matches(rule));
+    }
+
+    #[test]
+    fn test_keywords() {
+        let mut rule = rule();
+        rule.keywords.include = vec!["メイド(ラゴン|喫茶)".to_string()];
+        assert!(matches(rule.clone()));
+
+        rule.keywords.exclude = vec![r"\[再\]".to_string()];
+        assert!(!matches(rule.clone()));
+
+        rule.keywords.include = vec!["新生活".to_string()];
+        rule.keywords.exclude = vec![];
+        assert!(matches(rule));
+    }
+
+    #[test]
+    fn test_genre_service_and_free_filters() {
+        let mut rule = rule();
+        rule.genres = vec![GenreFilter { lv1: 7, lv2: None }];
+        rule.service_ids = vec![3273601024];
+        rule.channel_names = vec!["テストチャンネル".to_string()];
+        rule.is_free = Some(true);
+        assert!(matches(rule.clone()));
+
+        let mut other_genre = rule.clone();
+        other_genre.genres = vec![GenreFilter {
+            lv1: 7,
+            lv2: Some(1),
+        }];
+        assert!(!matches(other_genre));
+
+        let mut other_service = rule.clone();
+        other_service.service_ids = vec![3273601025];
+        assert!(!matches(other_service));
+
+        rule.is_free = Some(false);
+        assert!(!matches(rule));
+    }
+
+    #[test]
+    fn test_weekday_and_time_window() {
+        let program = dragon();
+        assert_eq!(Weekday::of(program.start_at), Weekday::Sat);
+        assert_eq!(TimeOfDay::of(program.start_at).to_string(), "21:00");
+
+        let mut rule = rule();
+        rule.weekdays = vec![Weekday::Fri, Weekday::Sat];
+        rule.time_window = Some(TimeWindow {
+            start: TimeOfDay::new(20, 0).unwrap(),
+            end: TimeOfDay::new(2, 0).unwrap(),
+        });
+        assert!(matches(rule.clone()));
+
+        rule.time_window = Some(TimeWindow {
+            start: TimeOfDay::new(21, 30).unwrap(),
+            end: TimeOfDay::new(23, 0).unwrap(),
+        });
+        assert!(!matches(rule.clone()));
+
+        rule.time_window = None;
+        rule.weekdays = vec![Weekday::Sun];
+        assert!(!matches(rule));
+    }
+
+    #[test]
+    fn test_deserialize_and_validate() {
+        let rule: RecordingRule = serde_json::from_str(
+            r#"{
+                "id": "anime-late",
+                "name": "深夜アニメ",
+                "keywords": {"include": ["^アニメ"]},
+                "genres": [{"lv1": 7}],
+                "weekdays": ["sat", "sun"],
+                "time_window": {"start": "23:00", "end": "03:00"},
+                "priority": 5
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(rule.weekdays, [Weekday::Sat, Weekday::Sun]);
+        assert_eq!(rule.time_window.as_ref().unwrap().end.to_string(), "03:00");
+        rule.validate().unwrap();
+
+        let mut invalid_id = rule.clone();
+        invalid_id.id = "anime.late".to_string();
+        assert!(invalid_id.validate().is_err());
+
+        let mut invalid_regex = rule.clone();
+        invalid_regex.keywords.exclude = vec!["(".to_string()];
+        assert!(invalid_regex.validate().is_err());
+
+        // 開始と終了が同じ時間帯は、どの時刻も含まない
+        let mut empty_window = rule;
+        empty_window.time_window = Some(TimeWindow {
+            start: TimeOfDay::new(23, 0).unwrap(),
+            end: TimeOfDay::new(23, 0).unwrap(),
+        });
+        assert!(empty_window.validate().is_err());
+
+        assert!(
+            serde_json::from_str::<TimeWindow>(r#"{"start": "24:00", "end": "01:00"}"#).is_err()
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! 番組の全文検索
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::program::Program;
+
+/// 検索インデックスに登録する番組の文書
+///
+/// 検索に使う項目だけを平らにしたもので、番組の詳細は番組IDから引き直す。
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProgramDocument {
+    /// 番組ID
+    pub id: i64,
+    /// Mirakurun形式のサービスID
+    pub service_id: i64,
+    pub name: String,
+    pub description: String,
+    /// 番組詳細の見出しと本文を改行でつないだもの
+    pub extended: String,
+    /// ジャンル名（`大分類/中分類`）
+    pub genres: Vec<String>,
+    /// ジャンルの大分類（重複なし）
+    pub genre_lv1: Vec<u8>,
+    pub channel_name: String,
+    /// 放送開始時刻（UNIX時間のミリ秒）
+    pub start_at: i64,
+    /// 放送終了時刻（UNIX時間のミリ秒）
+    pub end_at: i64,
+}
+
+impl From<&Program> for ProgramDocument {
+    fn from(program: &Program) -> Self {
+        let extended = program
+            .extended
+            .iter()
+            .flatten()
+            .map(|(heading, text)| format!("{}\n{}", heading, text))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut genre_lv1: Vec<u8> = program.genres.iter().map(|genre| genre.lv1).collect();
+        genre_lv1.sort_unstable();
+        genre_lv1.dedup();
+        Self {
+            id: program.id,
+            service_id: program.mirakurun_service_id(),
+            name: program.name.clone().unwrap_or_default(),
+            description: program.description.clone().unwrap_or_default(),
+            extended,
+            genres: program.genre_names.clone(),
+            genre_lv1,
+            channel_name: program.channel.name.clone(),
+            start_at: program.start_at,
+            end_at: program.end_at,
+        }
+    }
+}
+
+/// 番組の検索条件
+///
+/// 条件はすべて満たすものを返し、指定しなかった条件では絞り込まない。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// 検索語（空白区切りの語はすべて含むもの、`"` で囲んだ部分はその語順のまま含むもの）
+    pub text: String,
+    /// いずれかを大分類に持つ番組
+    pub genre_lv1: Vec<u8>,
+    /// いずれかのサービスの番組
+    pub service_ids: Vec<i64>,
+    /// この時刻（UNIX時間のミリ秒）より後まで放送される番組
+    pub from: Option<i64>,
+    /// この時刻（UNIX時間のミリ秒）より前に始まる番組
+    pub to: Option<i64>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// 一度に返す検索結果の既定の件数
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            genre_lv1: Vec::new(),
+            service_ids: Vec::new(),
+            from: None,
+            to: None,
+            limit: DEFAULT_SEARCH_LIMIT,
+            offset: 0,
+        }
+    }
+}
+
+impl SearchQuery {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 大分類 `lv1` のジャンルの番組に絞り込む
+    pub fn with_genre(mut self, lv1: u8) -> Self {
+        self.genre_lv1.push(lv1);
+        self
+    }
+
+    pub fn with_service(mut self, service_id: i64) -> Self {
+        self.service_ids.push(service_id);
+        self
+    }
+
+    /// `from` から `to` まで（UNIX時間のミリ秒）の間に少しでも放送される番組に絞り込む
+    pub fn with_time_range(mut self, from: i64, to: i64) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+/// 検索結果
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchResults {
+    /// 一致した番組（関連度の高い順）
+    pub hits: Vec<ProgramDocument>,
+    /// 一致した番組のおおよその総数
+    pub total: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::program::{Channel, Genre, ProgramIdentifiers, ProgramTiming};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_document_from_program() {
+        let mut program = Program::new(
+            ProgramIdentifiers {
+                id: 327360102412345,
+                event_id: 12345,
+                service_id: 1024,
+                network_id: 32736,
+            },
+            ProgramTiming {
+                start_at: 1619856000000,
+                duration: 1800000,
+            },
+            true,
+            Some("ニュース7".to_string()),
+            None,
+            vec![
+                Genre { lv1: 0, lv2: 0 },
+                Genre { lv1: 0, lv2: 1 },
+                Genre { lv1: 2, lv2: 0 },
+            ],
+            Channel {
+                id: 3273601024,
+                name: "ＮＨＫ総合１・東京".to_string(),
+            },
+        );
+        program.extended = Some(BTreeMap::from([
+            ("出演者".to_string(), "山田太郎".to_string()),
+            ("番組内容".to_string(), "今日のニュース".to_string()),
+        ]));
+
+        let document = ProgramDocument::from(&program);
+
+        assert_eq!(document.service_id, 3273601024);
+        assert_eq!(document.name, "ニュース7");
+        assert_eq!(document.description, "");
+        assert_eq!(
+            document.extended,
+            "出演者\n山田太郎\n番組内容\n今日のニュース"
+        );
+        assert_eq!(document.genres, program.genre_names);
+        assert_eq!(document.genre_lv1, [0, 2]);
+        assert_eq!(document.channel_name, "ＮＨＫ総合１・東京");
+        assert_eq!(document.end_at, 1619857800000);
+    }
+}
@@ -0,0 +1,376 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::recording::Record;
+
+/// デュアルモノ（二か国語放送など）の音声の扱い
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DualMonoHandling {
+    /// 左右に主・副音声が入ったまま変換する
+    Keep,
+    /// 左チャンネルの主音声だけをモノラルで残す
+    #[default]
+    Main,
+    /// 主音声と副音声を別々のモノラルの音声トラックに分ける
+    Split,
+}
+
+/// 録画をトランスコードするときの設定
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TranscodeProfile {
+    /// イベントに載せるプロファイル名
+    pub name: String,
+    pub video_codec: String,
+    /// 映像の品質（小さいほど高画質）
+    pub crf: u8,
+    /// 出力する映像の高さ（px、`None` なら元のまま）
+    pub height: Option<u32>,
+    /// インターレースを解除するかどうか
+    pub deinterlace: bool,
+    pub audio_codec: String,
+    pub audio_bitrate: String,
+    pub dual_mono: DualMonoHandling,
+    /// 出力ファイルの拡張子
+    pub extension: String,
+}
+
+impl Default for TranscodeProfile {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            video_codec: "libx264".to_string(),
+            crf: 23,
+            height: None,
+            deinterlace: true,
+            audio_codec: "aac".to_string(),
+            audio_bitrate: "192k".to_string(),
+            dual_mono: DualMonoHandling::default(),
+            extension: "mp4".to_string(),
+        }
+    }
+}
+
+/// 1つの録画のトランスコード
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscodeJob {
+    pub record_id: String,
+    pub program_id: i64,
+    pub input_path: PathBuf,
+    pub output_path: PathBuf,
+    /// 録画の長さ（ミリ秒、分からなければ `None`）
+    pub duration: Option<i64>,
+    /// 主音声がデュアルモノかどうか
+    pub dual_mono: bool,
+    pub profile: TranscodeProfile,
+}
+
+impl TranscodeJob {
+    /// `records_dir` にある録画を、同じ名前で拡張子を変えて `output_dir` に出力するジョブを作る
+    pub fn new(
+        record: &Record,
+        profile: &TranscodeProfile,
+        records_dir: &Path,
+        output_dir: &Path,
+    ) -> Self {
+        let input_path = records_dir.join(&record.content_path);
+        let mut file_name = Path::new(&record.content_path)
+            .file_stem()
+            .map(|stem| stem.to_os_string())
+            .unwrap_or_else(|| record.id.clone().into());
+        file_name.push(".");
+        file_name.push(&profile.extension);
+        let output_path = output_dir.join(file_name);
+        Self {
+            record_id: record.id.clone(),
+            program_id: record.program_id,
+            input_path,
+            output_path,
+            duration: record.duration(),
+            dual_mono: record.is_dual_mono(),
+            profile: profile.clone(),
+        }
+    }
+
+    /// ffmpeg互換のエンコーダーに渡す引数
+    ///
+    /// 進捗は `key=value` 形式で標準出力に書き出させる。
+    pub fn encoder_args(&self) -> Vec<String> {
+        let profile = &self.profile;
+        let mut args: Vec<String> = [
+            "-hide_banner",
+            "-nostdin",
+            "-nostats",
+            "-loglevel",
+            "error",
+            "-y",
+            "-progress",
+            "pipe:1",
+            "-i",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        args.push(self.input_path.to_string_lossy().into_owned());
+
+        args.extend(["-map", "0:v:0", "-c:v"].map(String::from));
+        args.push(profile.video_codec.clone());
+        args.push("-crf".to_string());
+        args.push(profile.crf.to_string());
+        let mut video_filters = Vec::new();
+        if profile.deinterlace {
+            video_filters.push("yadif".to_string());
+        }
+        if let Some(height) = profile.height {
+            video_filters.push(format!("scale=-2:{}", height));
+        }
+        if !video_filters.is_empty() {
+            args.push("-vf".to_string());
+            args.push(video_filters.join(","));
+        }
+
+        let dual_mono = if self.dual_mono {
+            profile.dual_mono
+        } else {
+            DualMonoHandling::Keep
+        };
+        match dual_mono {
+            DualMonoHandling::Keep => {
+                args.extend(["-map", "0:a?"].map(String::from));
+            }
+            DualMonoHandling::Main => {
+                args.extend(["-map", "0:a:0", "-af", "pan=mono|c0=c0"].map(String::from));
+            }
+            DualMonoHandling::Split => {
+                args.extend(
+                    [
+                        "-filter_complex",
+                        "[0:a:0]channelsplit=channel_layout=stereo[main][sub]",
+                        "-map",
+                        "[main]",
+                        "-map",
+                        "[sub]",
+                    ]
+                    .map(String::from),
+                );
+            }
+        }
+        args.push("-c:a".to_string());
+        args.push(profile.audio_codec.clone());
+        args.push("-b:a".to_string());
+        args.push(profile.audio_bitrate.clone());
+
+        args.push(self.output_path.to_string_lossy().into_owned());
+        args
+    }
+}
+
+/// トランスコードの進捗
+#[derive(Clone, Debug, PartialEq)]
+pub struct TranscodeProgress {
+    /// 出力済みの長さ（ミリ秒）
+    pub out_time: i64,
+    /// 進捗率（%、録画の長さが分からなければ `None`）
+    pub percent: Option<u8>,
+    /// 再生速度に対する変換速度の倍率
+    pub speed: Option<f64>,
+    /// エンコーダーが最後の進捗を書き出したかどうか
+    pub finished: bool,
+}
+
+/// ffmpegの `-progress` の出力を1行ずつ読んで進捗を求める
+///
+/// 進捗は `progress=continue` または `progress=end` の行で区切られたブロックごとに返す。
+#[derive(Clone, Debug, Default)]
+pub struct TranscodeProgressParser {
+    duration: Option<i64>,
+    out_time: i64,
+    speed: Option<f64>,
+}
+
+impl TranscodeProgressParser {
+    pub fn new(duration: Option<i64>) -> Self {
+        Self {
+            duration,
+            ..Default::default()
+        }
+    }
+
+    /// 1行を読み、ブロックの終わりならその時点の進捗を返す
+    pub fn parse_line(&mut self, line: &str) -> Option<TranscodeProgress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+        match key.trim() {
+            // `out_time_ms` も実際にはマイクロ秒
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<i64>() {
+                    self.out_time = micros.max(0) / 1000;
+                }
+            }
+            "speed" => {
+                self.speed = value.trim_end_matches('x').trim().parse().ok();
+            }
+            "progress" => {
+                let finished = value == "end";
+                return Some(TranscodeProgress {
+                    out_time: self.out_time,
+                    percent: self.percent(finished),
+                    speed: self.speed,
+                    finished,
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn percent(&self, finished: bool) -> Option<u8> {
+        if finished {
+            return Some(100);
+        }
+        let duration = self.duration.filter(|&duration| duration > 0)?;
+        Some((self.out_time.saturating_mul(100) / duration).clamp(0, 100) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::recording::RecordingStatus;
+
+    fn record(audio_component_types: Vec<u8>) -> Record {
+        Record {
+            id: "0000000000000001".to_string(),
+            program_id: 327360102412345,
+            service_id: 3273601024,
+            program_name: Some("映画".to_string()),
+            tags: vec![],
+            status: RecordingStatus::Finished,
+            start_time: 1619856000000,
+            end_time: Some(1619863200000),
+            content_path: "2021/movie.m2ts".to_string(),
+            content_type: "video/MP2T".to_string(),
+            content_length: Some(1024),
+            failed_reason: None,
+            audio_component_types,
+        }
+    }
+
+    fn job(audio_component_types: Vec<u8>, profile: TranscodeProfile) -> TranscodeJob {
+        TranscodeJob::new(
+            &record(audio_component_types),
+            &profile,
+            Path::new("/records"),
+            Path::new("/videos"),
+        )
+    }
+
+    /// `flag` の直後の引数
+    fn arg_after<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+        args.iter()
+            .position(|arg| arg == flag)
+            .map(|index| args[index + 1].as_str())
+    }
+
+    #[test]
+    fn test_job_paths_and_video_args() {
+        let job = job(
+            vec![0b00011],
+            TranscodeProfile {
+                height: Some(720),
+                crf: 20,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(job.input_path, Path::new("/records/2021/movie.m2ts"));
+        assert_eq!(job.output_path, Path::new("/videos/movie.mp4"));
+        assert_eq!(job.duration, Some(7200000));
+        assert!(!job.dual_mono);
+
+        let args = job.encoder_args();
+        assert_eq!(arg_after(&args, "-progress"), Some("pipe:1"));
+        assert_eq!(arg_after(&args, "-i"), Some("/records/2021/movie.m2ts"));
+        assert_eq!(arg_after(&args, "-c:v"), Some("libx264"));
+        assert_eq!(arg_after(&args, "-crf"), Some("20"));
+        assert_eq!(arg_after(&args, "-vf"), Some("yadif,scale=-2:720"));
+        assert_eq!(arg_after(&args, "-map"), Some("0:v:0"));
+        assert!(args.iter().any(|arg| arg == "0:a?"));
+        assert_eq!(args.last().map(String::as_str), Some("/videos/movie.mp4"));
+    }
+
+    #[test]
+    fn test_dual_mono_handling() {
+        let main = job(vec![0b00010, 0b00011], TranscodeProfile::default()).encoder_args();
+        assert_eq!(arg_after(&main, "-af"), Some("pan=mono|c0=c0"));
+
+        let split = job(
+            vec![0b00010],
+            TranscodeProfile {
+                dual_mono: DualMonoHandling::Split,
+                ..Default::default()
+            },
+        )
+        .encoder_args();
+        assert!(
+            arg_after(&split, "-filter_complex")
+                .is_some_and(|filter| filter.contains("channelsplit"))
+        );
+        assert!(split.iter().any(|arg| arg == "[main]"));
+        assert!(split.iter().any(|arg| arg == "[sub]"));
+
+        // デュアルモノでなければ指定に関わらずそのまま変換する
+        let stereo = job(
+            vec![0b00011],
+            TranscodeProfile {
+                dual_mono: DualMonoHandling::Split,
+                ..Default::default()
+            },
+        )
+        .encoder_args();
+        assert!(
+            !stereo
+                .iter()
+                .any(|arg| arg == "-filter_complex" || arg == "-af")
+        );
+    }
+
+    #[test]
+    fn test_progress_parser() {
+        let mut parser = TranscodeProgressParser::new(Some(60000));
+
+        assert_eq!(parser.parse_line("frame=450"), None);
+        assert_eq!(parser.parse_line("out_time_us=15000000"), None);
+        assert_eq!(parser.parse_line("speed=2.5x"), None);
+        assert_eq!(
+            parser.parse_line("progress=continue"),
+            Some(TranscodeProgress {
+                out_time: 15000,
+                percent: Some(25),
+                speed: Some(2.5),
+                finished: false,
+            })
+        );
+
+        // 値が分からないときは直前の値のまま
+        parser.parse_line("out_time_us=N/A");
+        parser.parse_line("speed=N/A");
+        let progress = parser.parse_line("progress=continue").unwrap();
+        assert_eq!(progress.out_time, 15000);
+        assert_eq!(progress.speed, None);
+
+        parser.parse_line("out_time_ms=59000000");
+        let progress = parser.parse_line("progress=end").unwrap();
+        assert_eq!(progress.percent, Some(100));
+        assert!(progress.finished);
+
+        let mut parser = TranscodeProgressParser::new(None);
+        parser.parse_line("out_time_us=1000000");
+        assert_eq!(
+            parser.parse_line("progress=continue").unwrap().percent,
+            None
+        );
+    }
+}
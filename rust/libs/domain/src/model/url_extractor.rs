@@ -2,6 +2,15 @@ use linkify::{LinkFinder, LinkKind};
 use std::collections::HashSet;
 use url::Url;
 
+/// OGPを取得しても意味のない（ログインが必要など）既定の除外ドメイン
+pub const DEFAULT_EXCLUDED_DOMAINS: &[&str] = &[
+    "x.com",
+    "twitter.com",
+    "facebook.com",
+    "tiktok.com",
+    "instagram.com",
+];
+
 pub struct UrlExtractor {
     excluded_domains: HashSet<String>,
 }
@@ -13,13 +22,12 @@ impl UrlExtractor {
     }
 
     pub fn with_default_exclusions() -> Self {
-        Self::new(vec![
-            "x.com".to_string(),
-            "twitter.com".to_string(),
-            "facebook.com".to_string(),
-            "tiktok.com".to_string(),
-            "instagram.com".to_string(),
-        ])
+        Self::new(
+            DEFAULT_EXCLUDED_DOMAINS
+                .iter()
+                .map(|domain| domain.to_string())
+                .collect(),
+        )
     }
 
     pub fn extract_urls(&self, text: &str) -> Vec<String> {
@@ -30,12 +38,11 @@ impl UrlExtractor {
             .links(text)
             .filter_map(|link| {
                 let url = link.as_str().to_string();
-                if let Ok(url_parsed) = Url::parse(&url) {
-                    if let Some(host) = url_parsed.host_str() {
-                        if !self.is_excluded_domain(host) {
-                            return Some(url);
-                        }
-                    }
+                if let Ok(url_parsed) = Url::parse(&url)
+                    && let Some(host) = url_parsed.host_str()
+                    && !self.is_excluded_domain(host)
+                {
+                    return Some(url);
                 }
                 None
             })
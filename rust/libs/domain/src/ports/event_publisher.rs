@@ -0,0 +1,9 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::types::Event;
+
+#[async_trait]
+pub trait EventPublisher<E: Event> {
+    async fn publish(&self, event: &E) -> Result<(), DomainError>;
+}
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::inventory::{ChannelInfo, Service, Tuner};
+
+/// mirakcのチューナー・チャンネル・サービスの一覧の取得
+#[async_trait]
+pub trait InventoryRetriever {
+    async fn get_tuners(&self) -> Result<Vec<Tuner>, DomainError>;
+
+    async fn get_channels(&self) -> Result<Vec<ChannelInfo>, DomainError>;
+
+    async fn get_services(&self) -> Result<Vec<Service>, DomainError>;
+}
@@ -1,9 +1,33 @@
+mod event_publisher;
 mod html_fetcher;
 mod image_fetcher;
 mod image_processor;
+mod inventory_retriever;
+mod object_store;
+mod onair_program_retriever;
+mod program_event_publisher;
+mod program_query;
 mod programs_retriever;
+mod recorder_controller;
+mod recording_scheduler;
+mod records_manager;
+mod search_index;
+mod services_retriever;
+mod transcoder;
 
+pub use event_publisher::*;
 pub use html_fetcher::*;
 pub use image_fetcher::*;
 pub use image_processor::*;
+pub use inventory_retriever::*;
+pub use object_store::*;
+pub use onair_program_retriever::*;
+pub use program_event_publisher::*;
+pub use program_query::*;
 pub use programs_retriever::*;
+pub use recorder_controller::*;
+pub use recording_scheduler::*;
+pub use records_manager::*;
+pub use search_index::*;
+pub use services_retriever::*;
+pub use transcoder::*;
@@ -0,0 +1,38 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::DomainError;
+use crate::model::object::{ObjectMeta, PutOptions, StoredObject};
+
+/// 録画ファイルや画像など、KVに入りきらない大きなデータを保存する
+#[async_trait]
+pub trait ObjectStore {
+    /// データを保存する（同じキーのオブジェクトがあれば置き換える）
+    async fn put(
+        &self,
+        key: &str,
+        data: Bytes,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError>;
+
+    /// ファイルの中身をメモリに読み込まずに保存する
+    async fn put_file(
+        &self,
+        key: &str,
+        path: &Path,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError>;
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>, DomainError>;
+
+    /// 中身を読み込まずにオブジェクトの情報だけを取得する
+    async fn head(&self, key: &str) -> Result<Option<ObjectMeta>, DomainError>;
+
+    /// オブジェクトを削除する（なければ何もしない）
+    async fn delete(&self, key: &str) -> Result<(), DomainError>;
+
+    /// キーが `prefix` で始まるオブジェクトの一覧
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, DomainError>;
+}
@@ -0,0 +1,11 @@
+use crate::error::DomainError;
+use crate::model::onair::OnairProgram;
+
+#[async_trait::async_trait]
+pub trait OnairProgramRetriever {
+    /// サービスで放送中の番組と次の番組を返す
+    async fn get_onair_program(&self, service_id: i64) -> Result<OnairProgram, DomainError>;
+
+    /// 全サービスの放送中の番組と次の番組を返す
+    async fn get_onair_programs(&self) -> Result<Vec<OnairProgram>, DomainError>;
+}
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::program::ProgramsDiff;
+
+#[async_trait]
+pub trait ProgramEventPublisher {
+    /// 番組表の差分を番組ごとのイベントとして発行する
+    async fn publish_diff(
+        &self,
+        service_id: i64,
+        mirakc_url: &str,
+        diff: &ProgramsDiff,
+    ) -> Result<(), DomainError>;
+}
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::program::Program;
+
+/// 保存済みの番組の検索
+///
+/// 複数の番組を返すものは放送開始時刻の順に並べる。
+#[async_trait]
+pub trait ProgramQuery {
+    async fn find_by_id(&self, program_id: i64) -> Result<Option<Program>, DomainError>;
+
+    async fn find_by_service(&self, service_id: i64) -> Result<Vec<Program>, DomainError>;
+
+    /// `start_at` から `end_at` まで（UNIX時間のミリ秒）の間に少しでも放送される番組
+    async fn find_by_time_range(
+        &self,
+        start_at: i64,
+        end_at: i64,
+    ) -> Result<Vec<Program>, DomainError>;
+
+    /// 大分類 `lv1`（`lv2` を指定した場合は中分類も）が一致するジャンルを持つ番組
+    async fn find_by_genre(&self, lv1: u8, lv2: Option<u8>) -> Result<Vec<Program>, DomainError>;
+}
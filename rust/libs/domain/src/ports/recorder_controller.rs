@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::recording::{ActiveRecorder, ScheduleOutcome, ScheduleRequest};
+
+/// 予約を介さずに録画を直接操作する
+#[async_trait]
+pub trait RecorderController {
+    async fn list_recorders(&self) -> Result<Vec<ActiveRecorder>, DomainError>;
+
+    /// 番組の録画をすぐに開始する
+    async fn start_recording(
+        &self,
+        request: &ScheduleRequest,
+    ) -> Result<ScheduleOutcome, DomainError>;
+
+    /// 録画を停止する（録画中でなければ `false`）
+    async fn stop_recording(&self, program_id: i64) -> Result<bool, DomainError>;
+}
@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::recording::{RecordingSchedule, ScheduleOutcome, ScheduleRequest};
+
+#[async_trait]
+pub trait RecordingScheduler {
+    /// 番組の録画予約を作成する
+    async fn create_schedule(
+        &self,
+        request: &ScheduleRequest,
+    ) -> Result<ScheduleOutcome, DomainError>;
+
+    /// 録画予約を開始時刻順に取得する
+    async fn list_schedules(&self) -> Result<Vec<RecordingSchedule>, DomainError>;
+
+    async fn get_schedule(&self, program_id: i64)
+    -> Result<Option<RecordingSchedule>, DomainError>;
+
+    /// 録画予約を削除する（予約がなければ `false`）
+    async fn delete_schedule(&self, program_id: i64) -> Result<bool, DomainError>;
+
+    /// タグを持つ録画予約をまとめて削除する（録画中のものは残る）
+    async fn delete_schedules_by_tag(&self, tag: &str) -> Result<(), DomainError>;
+}
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::recording::Record;
+
+#[async_trait]
+pub trait RecordsManager {
+    async fn list_records(&self) -> Result<Vec<Record>, DomainError>;
+
+    async fn get_record(&self, record_id: &str) -> Result<Option<Record>, DomainError>;
+
+    /// 録画を削除する（`purge` ならファイルも削除する。録画がなければ `false`）
+    async fn remove_record(&self, record_id: &str, purge: bool) -> Result<bool, DomainError>;
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::search::{ProgramDocument, SearchQuery, SearchResults};
+
+/// 番組の全文検索インデックス
+#[async_trait]
+pub trait SearchIndex {
+    /// 文書を追加する（同じ番組IDの文書は置き換える）
+    async fn upsert(&self, documents: &[ProgramDocument]) -> Result<(), DomainError>;
+
+    /// サービスの文書のうち、番組IDが `program_ids` に含まれないものを削除する
+    async fn delete_stale(&self, service_id: i64, program_ids: &[i64]) -> Result<(), DomainError>;
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults, DomainError>;
+}
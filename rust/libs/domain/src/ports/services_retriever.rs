@@ -0,0 +1,7 @@
+use crate::error::DomainError;
+
+#[async_trait::async_trait]
+pub trait ServicesRetriever {
+    /// 番組表を取得できるすべてのサービスのIDを返す
+    async fn get_service_ids(&self) -> Result<Vec<i64>, DomainError>;
+}
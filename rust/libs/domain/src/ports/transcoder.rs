@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::model::transcode::{TranscodeJob, TranscodeProgress};
+
+#[derive(Clone, Debug, Error)]
+pub enum TranscoderError {
+    #[error("エンコーダーを起動できません: {0}")]
+    SpawnError(String),
+
+    #[error("エンコーダーが異常終了しました（終了コード: {exit_code:?}）: {message}")]
+    EncoderFailed {
+        exit_code: Option<i32>,
+        message: String,
+    },
+
+    #[error("出力ファイルを確認できません: {0}")]
+    OutputError(String),
+}
+
+/// トランスコードの進捗を受け取る
+#[async_trait]
+pub trait TranscodeProgressListener: Send + Sync {
+    async fn on_progress(&self, progress: &TranscodeProgress);
+}
+
+#[async_trait]
+pub trait Transcoder {
+    /// ジョブを実行し、出力したファイルのサイズ（バイト）を返す
+    async fn transcode(
+        &self,
+        job: &TranscodeJob,
+        listener: &dyn TranscodeProgressListener,
+    ) -> Result<u64, TranscoderError>;
+}
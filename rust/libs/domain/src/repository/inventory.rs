@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::inventory::{ChannelInfo, Inventory, Service, Tuner};
+
+/// チューナー・チャンネル・サービスの一覧の保存先
+///
+/// 一覧はそれぞれ、チューナーは番号、チャンネルは種別とチャンネル番号、サービスはIDの順に返す。
+#[async_trait]
+pub trait InventoryRepository {
+    async fn load(&self) -> Result<Inventory, DomainError>;
+
+    async fn find_service(&self, service_id: i64) -> Result<Option<Service>, DomainError>;
+
+    /// チューナーの一覧を置き換える（`tuners` にないチューナーは消す）
+    async fn replace_tuners(&self, tuners: &[Tuner]) -> Result<(), DomainError>;
+
+    /// チャンネルの一覧を置き換える（`channels` にないチャンネルは消す）
+    async fn replace_channels(&self, channels: &[ChannelInfo]) -> Result<(), DomainError>;
+
+    /// サービスの一覧を置き換える（`services` にないサービスは消す）
+    async fn replace_services(&self, services: &[Service]) -> Result<(), DomainError>;
+}
@@ -18,6 +18,9 @@ where
 {
     async fn put(&self, key: K, value: &V) -> Result<(), DomainError>;
     async fn get(&self, key: K) -> Result<Option<Versioned<V>>, DomainError>;
+    /// `revision` が最新のときだけ更新する（0はキーがまだないときだけ作成する）
+    ///
+    /// 最新でなければ `DomainError::RevisionConflict` を返す。
     async fn update(&self, key: K, value: &V, revision: u64) -> Result<(), DomainError>;
     async fn delete(&self, key: K) -> Result<(), DomainError>;
 }
@@ -1,2 +1,8 @@
+mod inventory;
 mod kvs;
+mod program;
+mod recording_rule;
+pub use inventory::*;
 pub use kvs::*;
+pub use program::*;
+pub use recording_rule::*;
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::program::ProgramsDiff;
+
+/// 番組を1件ずつ保存する保存先
+#[async_trait]
+pub trait ProgramRepository {
+    /// 番組表の差分を反映する（追加・変更された番組を保存し、削除された番組を消す）
+    async fn apply_diff(&self, diff: &ProgramsDiff) -> Result<(), DomainError>;
+
+    /// サービスの番組が1件でも保存されているかどうか
+    async fn has_programs(&self, service_id: i64) -> Result<bool, DomainError>;
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use crate::error::DomainError;
+use crate::model::recording_rule::RecordingRule;
+
+/// 自動予約のルールの保存先
+#[async_trait]
+pub trait RecordingRuleRepository {
+    /// すべてのルールをIDの順に返す
+    async fn list(&self) -> Result<Vec<RecordingRule>, DomainError>;
+
+    /// ルールを検証して保存する（同じIDのルールは置き換える）
+    async fn put(&self, rule: &RecordingRule) -> Result<(), DomainError>;
+
+    async fn delete(&self, rule_id: &str) -> Result<(), DomainError>;
+}
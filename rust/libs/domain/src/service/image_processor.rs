@@ -1,6 +1,6 @@
 use crate::ports::{ImageProcessor, ImageProcessorError};
 use async_trait::async_trait;
-use image::GenericImageView;
+use image::{DynamicImage, GenericImageView};
 use webp::Encoder;
 
 #[derive(Default)]
@@ -27,6 +27,12 @@ impl ImageProcessor for WebpImageProcessor {
         };
 
         let resized = img.resize(width, height, image::imageops::FilterType::Lanczos3);
+        // エンコーダーはRGBとRGBAだけを扱えるので、透過のある画像はRGBAに揃えて透過を保つ
+        let resized = if resized.color().has_alpha() {
+            DynamicImage::ImageRgba8(resized.to_rgba8())
+        } else {
+            DynamicImage::ImageRgb8(resized.to_rgb8())
+        };
 
         let encoder = Encoder::from_image(&resized)
             .map_err(|e| ImageProcessorError::ConversionError(e.to_string()))?;
@@ -42,7 +48,7 @@ mod tests {
     use std::io::Cursor;
 
     use super::*;
-    use image::{ImageBuffer, Rgba};
+    use image::{ImageBuffer, LumaA, Rgba};
 
     #[tokio::test]
     async fn test_process_image() {
@@ -59,7 +65,7 @@ mod tests {
         img.write_to(&mut cursor, image::ImageFormat::Png)
             .expect("Failed to write test image");
 
-        let processor = WebpImageProcessor::default();
+        let processor = WebpImageProcessor;
 
         let target_width = 300;
         let result = processor.process_image(&png_data, target_width).await;
@@ -84,4 +90,30 @@ mod tests {
             "リサイズ後の高さが一致しません"
         );
     }
+
+    #[tokio::test]
+    async fn test_process_image_keeps_transparency() {
+        // 左半分が透明なグレースケール＋アルファの画像（ロゴ画像を想定）
+        let img = ImageBuffer::from_fn(64, 36, |x, _| {
+            if x < 32 {
+                LumaA([0u8, 0])
+            } else {
+                LumaA([200u8, 255])
+            }
+        });
+        let mut png_data = Vec::new();
+        img.write_to(&mut Cursor::new(&mut png_data), image::ImageFormat::Png)
+            .expect("Failed to write test image");
+
+        let webp_data = WebpImageProcessor
+            .process_image(&png_data, 64)
+            .await
+            .unwrap();
+
+        let webp_img = image::load_from_memory(&webp_data)
+            .expect("Failed to load WebP image")
+            .to_rgba8();
+        assert_eq!(webp_img.get_pixel(4, 18)[3], 0, "透明な部分が失われました");
+        assert_eq!(webp_img.get_pixel(60, 18)[3], 255);
+    }
 }
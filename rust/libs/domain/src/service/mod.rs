@@ -1,5 +1,13 @@
 mod html_parser;
 mod image_processor;
+mod object_backed_repository;
+mod program_event_publisher;
+mod recording_conflict;
+mod retry_policy;
 
 pub use html_parser::*;
 pub use image_processor::*;
+pub use object_backed_repository::*;
+pub use program_event_publisher::*;
+pub use recording_conflict::*;
+pub use retry_policy::*;
@@ -0,0 +1,296 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::{
+    error::DomainError,
+    model::object::{ObjectRef, PutOptions},
+    ports::ObjectStore,
+    repository::{KvRepository, Versioned},
+};
+
+/// 値の中身をオブジェクトストアに置き、KVには参照だけを保存するリポジトリ
+///
+/// KVの1エントリに収まらない画像などを、`KvRepository` のまま扱うために使う。
+/// リビジョンは参照のリビジョン。中身は内容ごとに別のオブジェクトへ書き込んでから参照を切り替え、
+/// 古いオブジェクトはその後で消すため、リビジョンが合わずに更新できなかった場合も中身は変わらない。
+pub struct ObjectBackedRepository<O, R, V>
+where
+    O: ObjectStore + Send + Sync,
+    R: KvRepository<String, ObjectRef> + Send + Sync,
+{
+    object_store: O,
+    ref_repository: R,
+    prefix: String,
+    content_type: String,
+    _value: PhantomData<fn() -> V>,
+}
+
+impl<O, R, V> ObjectBackedRepository<O, R, V>
+where
+    O: ObjectStore + Send + Sync,
+    R: KvRepository<String, ObjectRef> + Send + Sync,
+{
+    /// `prefix` の下に、Content-Typeを `content_type` として中身を保存する
+    pub fn new(object_store: O, ref_repository: R, prefix: &str, content_type: &str) -> Self {
+        Self {
+            object_store,
+            ref_repository,
+            prefix: prefix.trim_end_matches('/').to_string(),
+            content_type: content_type.to_string(),
+            _value: PhantomData,
+        }
+    }
+
+    /// KVのキーと中身に対応するオブジェクトのキー
+    ///
+    /// KVのキーにはURLなども使われるため、ハッシュにしてオブジェクトストアで使えない文字を避ける。
+    /// 中身のハッシュも含めて、更新のたびに別のオブジェクトに書き込む。
+    pub fn object_key(&self, key: &str, data: &[u8]) -> String {
+        format!(
+            "{}/{:x}/{:x}",
+            self.prefix,
+            Sha256::digest(key.as_bytes()),
+            Sha256::digest(data)
+        )
+    }
+
+    async fn put_object(&self, key: &str, value: &V) -> Result<ObjectRef, DomainError>
+    where
+        V: Into<Bytes> + Clone + Send + Sync,
+    {
+        let data: Bytes = value.clone().into();
+        let options = PutOptions::new(&self.content_type).with_metadata("kv_key", key);
+        let meta = self
+            .object_store
+            .put(&self.object_key(key, &data), data, &options)
+            .await?;
+        Ok(ObjectRef::new(&meta, &options))
+    }
+
+    /// 参照されなくなったオブジェクトを消す
+    ///
+    /// 消せなくても値は正しく読めるので、警告だけして続ける。
+    async fn delete_unreferenced(&self, key: &str, object_key: &str) {
+        if let Err(e) = self.object_store.delete(object_key).await {
+            warn!(
+                "キー {} の参照されなくなったオブジェクト {} を削除できません: {}",
+                key, object_key, e
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl<O, R, V> KvRepository<String, V> for ObjectBackedRepository<O, R, V>
+where
+    O: ObjectStore + Send + Sync,
+    R: KvRepository<String, ObjectRef> + Send + Sync,
+    V: Into<Bytes> + From<Bytes> + Clone + Send + Sync,
+{
+    async fn put(&self, key: String, value: &V) -> Result<(), DomainError> {
+        let previous = self
+            .ref_repository
+            .get(key.clone())
+            .await?
+            .map(|versioned| versioned.value.key);
+        let object_ref = self.put_object(&key, value).await?;
+        self.ref_repository.put(key.clone(), &object_ref).await?;
+        if let Some(previous) = previous.filter(|previous| *previous != object_ref.key) {
+            self.delete_unreferenced(&key, &previous).await;
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: String) -> Result<Option<Versioned<V>>, DomainError> {
+        let Some(versioned) = self.ref_repository.get(key.clone()).await? else {
+            return Ok(None);
+        };
+        match self.object_store.get(&versioned.value.key).await? {
+            Some(object) => Ok(Some(Versioned {
+                revision: versioned.revision,
+                value: V::from(object.data),
+            })),
+            None => {
+                warn!(
+                    "キー {} の参照先のオブジェクト {} がありません",
+                    key, versioned.value.key
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    async fn update(&self, key: String, value: &V, revision: u64) -> Result<(), DomainError> {
+        let previous = self
+            .ref_repository
+            .get(key.clone())
+            .await?
+            .filter(|versioned| versioned.revision == revision)
+            .map(|versioned| versioned.value.key);
+        let object_ref = self.put_object(&key, value).await?;
+        if let Err(e) = self
+            .ref_repository
+            .update(key.clone(), &object_ref, revision)
+            .await
+        {
+            // 書き込んだオブジェクトを今の参照が指していなければ片付ける
+            if let Ok(current) = self.ref_repository.get(key.clone()).await
+                && current.is_none_or(|current| current.value.key != object_ref.key)
+            {
+                self.delete_unreferenced(&key, &object_ref.key).await;
+            }
+            return Err(e);
+        }
+        if let Some(previous) = previous.filter(|previous| *previous != object_ref.key) {
+            self.delete_unreferenced(&key, &previous).await;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, key: String) -> Result<(), DomainError> {
+        let Some(versioned) = self.ref_repository.get(key.clone()).await? else {
+            return Ok(());
+        };
+        self.ref_repository.delete(key).await?;
+        self.object_store.delete(&versioned.value.key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::test_util::{MockKvRepository, MockObjectStore};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Blob(Bytes);
+
+    impl From<Bytes> for Blob {
+        fn from(bytes: Bytes) -> Self {
+            Self(bytes)
+        }
+    }
+
+    impl From<Blob> for Bytes {
+        fn from(blob: Blob) -> Self {
+            blob.0
+        }
+    }
+
+    fn repository() -> (
+        ObjectBackedRepository<MockObjectStore, MockKvRepository<ObjectRef>, Blob>,
+        MockObjectStore,
+        MockKvRepository<ObjectRef>,
+    ) {
+        let object_store = MockObjectStore::new();
+        let refs = MockKvRepository::new();
+        let repository = ObjectBackedRepository::new(
+            object_store.clone(),
+            refs.clone(),
+            "images/",
+            "image/webp",
+        );
+        (repository, object_store, refs)
+    }
+
+    #[tokio::test]
+    async fn test_put_stores_object_and_ref() {
+        let (repository, object_store, refs) = repository();
+        let key = "https://example.com/image.png".to_string();
+
+        repository
+            .put(key.clone(), &Blob(Bytes::from_static(b"webp")))
+            .await
+            .unwrap();
+
+        let object_ref = refs.get(key.clone()).await.unwrap().unwrap().value;
+        assert!(object_ref.key.starts_with("images/"));
+        assert!(!object_ref.key.contains("https"));
+        assert_eq!(object_ref.content_type, "image/webp");
+        assert_eq!(object_ref.size, 4);
+        let object = object_store.get(&object_ref.key).await.unwrap().unwrap();
+        assert_eq!(object.meta.metadata.get("kv_key"), Some(&key));
+
+        let versioned = repository.get(key).await.unwrap().unwrap();
+        assert_eq!(versioned.revision, 1);
+        assert_eq!(versioned.value, Blob(Bytes::from_static(b"webp")));
+    }
+
+    #[tokio::test]
+    async fn test_update_uses_ref_revision() {
+        let (repository, object_store, _) = repository();
+        let key = "3273601024".to_string();
+        repository
+            .put(key.clone(), &Blob(Bytes::from_static(b"old")))
+            .await
+            .unwrap();
+
+        repository
+            .update(key.clone(), &Blob(Bytes::from_static(b"new")), 1)
+            .await
+            .unwrap();
+
+        let versioned = repository.get(key.clone()).await.unwrap().unwrap();
+        assert_eq!(versioned.revision, 2);
+        assert_eq!(versioned.value, Blob(Bytes::from_static(b"new")));
+        // 前の中身のオブジェクトは消す
+        assert_eq!(
+            object_store.keys(),
+            vec![repository.object_key(&key, b"new")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_with_stale_revision_keeps_content() {
+        let (repository, object_store, _) = repository();
+        let key = "3273601024".to_string();
+        repository
+            .put(key.clone(), &Blob(Bytes::from_static(b"old")))
+            .await
+            .unwrap();
+        repository
+            .update(key.clone(), &Blob(Bytes::from_static(b"current")), 1)
+            .await
+            .unwrap();
+
+        let result = repository
+            .update(key.clone(), &Blob(Bytes::from_static(b"stale")), 1)
+            .await;
+
+        assert!(matches!(result, Err(DomainError::RevisionConflict(_))));
+        let versioned = repository.get(key.clone()).await.unwrap().unwrap();
+        assert_eq!(versioned.revision, 2);
+        assert_eq!(versioned.value, Blob(Bytes::from_static(b"current")));
+        assert_eq!(
+            object_store.keys(),
+            vec![repository.object_key(&key, b"current")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_missing_object() {
+        let (repository, object_store, refs) = repository();
+        repository
+            .put("a".to_string(), &Blob(Bytes::from_static(b"a")))
+            .await
+            .unwrap();
+        repository
+            .put("b".to_string(), &Blob(Bytes::from_static(b"b")))
+            .await
+            .unwrap();
+
+        repository.delete("a".to_string()).await.unwrap();
+        assert!(refs.get("a".to_string()).await.unwrap().is_none());
+        assert_eq!(object_store.keys(), vec![repository.object_key("b", b"b")]);
+
+        // 参照だけが残っている場合は値がないものとして扱う
+        object_store
+            .delete(&repository.object_key("b", b"b"))
+            .await
+            .unwrap();
+        assert!(repository.get("b".to_string()).await.unwrap().is_none());
+    }
+}
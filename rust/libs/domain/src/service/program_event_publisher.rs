@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+
+use crate::{
+    error::DomainError,
+    model::{event::recording::program, program::ProgramsDiff},
+    ports::{EventPublisher, ProgramEventPublisher},
+};
+
+/// 追加・変更・削除のイベントをそれぞれの発行先に振り分ける [`ProgramEventPublisher`]
+pub struct ProgramEventPublisherImpl<A, C, R>
+where
+    A: EventPublisher<program::Added> + Send + Sync,
+    C: EventPublisher<program::Changed> + Send + Sync,
+    R: EventPublisher<program::Removed> + Send + Sync,
+{
+    added: A,
+    changed: C,
+    removed: R,
+}
+
+impl<A, C, R> ProgramEventPublisherImpl<A, C, R>
+where
+    A: EventPublisher<program::Added> + Send + Sync,
+    C: EventPublisher<program::Changed> + Send + Sync,
+    R: EventPublisher<program::Removed> + Send + Sync,
+{
+    pub fn new(added: A, changed: C, removed: R) -> Self {
+        Self {
+            added,
+            changed,
+            removed,
+        }
+    }
+}
+
+#[async_trait]
+impl<A, C, R> ProgramEventPublisher for ProgramEventPublisherImpl<A, C, R>
+where
+    A: EventPublisher<program::Added> + Send + Sync,
+    C: EventPublisher<program::Changed> + Send + Sync,
+    R: EventPublisher<program::Removed> + Send + Sync,
+{
+    async fn publish_diff(
+        &self,
+        service_id: i64,
+        mirakc_url: &str,
+        diff: &ProgramsDiff,
+    ) -> Result<(), DomainError> {
+        for program in &diff.added {
+            self.added
+                .publish(&program::Added {
+                    service_id,
+                    program: program.clone(),
+                    mirakc_url: mirakc_url.to_string(),
+                })
+                .await?;
+        }
+        for (program, changed_fields) in &diff.changed {
+            self.changed
+                .publish(&program::Changed {
+                    service_id,
+                    program: program.clone(),
+                    changed_fields: changed_fields.clone(),
+                    mirakc_url: mirakc_url.to_string(),
+                })
+                .await?;
+        }
+        for program in &diff.removed {
+            self.removed
+                .publish(&program::Removed {
+                    service_id,
+                    program_id: program.id,
+                    mirakc_url: mirakc_url.to_string(),
+                })
+                .await?;
+        }
+        Ok(())
+    }
+}
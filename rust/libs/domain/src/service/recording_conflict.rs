@@ -0,0 +1,363 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use crate::{
+    error::DomainError,
+    model::{
+        event::recording::schedule,
+        inventory::{ChannelType, Tuner},
+        recording::{RecordingConflict, ScheduledRecording},
+    },
+    ports::EventPublisher,
+};
+
+/// 録画予約の重なりのうち、チューナーが足りなくなる期間を求める
+///
+/// 予約の開始・終了の時刻で区切った区間ごとに、優先度の高い予約（同じ優先度なら開始の早い予約）から
+/// 受信できるチューナーを割り当てる。同じ物理チャンネルの予約は1つのチューナーを共有する。
+/// チューナーを割り当てられなかった予約は録画に失敗するとみなし、それ以降の区間では数えない。
+pub fn detect_conflicts(
+    tuners: &[Tuner],
+    recordings: &[ScheduledRecording],
+) -> Vec<RecordingConflict> {
+    let tuners: Vec<&Tuner> = tuners
+        .iter()
+        .filter(|tuner| tuner.is_available && !tuner.is_fault)
+        .collect();
+    let mut ordered: Vec<&ScheduledRecording> = recordings
+        .iter()
+        .filter(|recording| recording.start_at < recording.end_at)
+        .collect();
+    ordered.sort_by_key(|recording| {
+        (
+            Reverse(recording.priority),
+            recording.start_at,
+            recording.program_id,
+        )
+    });
+
+    let mut boundaries: Vec<i64> = ordered
+        .iter()
+        .flat_map(|recording| [recording.start_at, recording.end_at])
+        .collect();
+    boundaries.sort();
+    boundaries.dedup();
+
+    let mut lost = HashSet::new();
+    let mut conflicts = Vec::new();
+    for window in boundaries.windows(2) {
+        let (start_at, end_at) = (window[0], window[1]);
+        let mut allocation = TunerAllocation::new(&tuners);
+        let mut winners = Vec::new();
+        let mut losers = Vec::new();
+        for recording in ordered.iter().filter(|recording| {
+            !lost.contains(&recording.program_id) && recording.overlaps(start_at, end_at)
+        }) {
+            if allocation.assign(recording.channel_type, &recording.channel) {
+                winners.push(recording.program_id);
+            } else {
+                losers.push(recording.program_id);
+            }
+        }
+        if losers.is_empty() {
+            continue;
+        }
+
+        lost.extend(losers.iter().copied());
+        winners.sort();
+        losers.sort();
+        conflicts.push(RecordingConflict {
+            start_at,
+            end_at,
+            winners,
+            losers,
+        });
+    }
+    conflicts
+}
+
+/// 1つの区間での物理チャンネルへのチューナーの割り当て
+///
+/// 割り当てられないときは、割り当て済みのチャンネルを別のチューナーに移せないか探す（二部マッチングの増加路）。
+struct TunerAllocation<'a> {
+    tuners: &'a [&'a Tuner],
+    channels: Vec<(ChannelType, &'a str)>,
+    /// チューナーごとに割り当てたチャンネル（`channels` の添字）
+    owners: Vec<Option<usize>>,
+}
+
+impl<'a> TunerAllocation<'a> {
+    fn new(tuners: &'a [&'a Tuner]) -> Self {
+        Self {
+            tuners,
+            channels: Vec::new(),
+            owners: vec![None; tuners.len()],
+        }
+    }
+
+    /// チャンネルにチューナーを割り当てる（割り当て済みのチャンネルなら共有する）
+    fn assign(&mut self, channel_type: ChannelType, channel: &'a str) -> bool {
+        if self.channels.contains(&(channel_type, channel)) {
+            return true;
+        }
+        self.channels.push((channel_type, channel));
+        let index = self.channels.len() - 1;
+        let mut visited = vec![false; self.tuners.len()];
+        if self.augment(index, &mut visited) {
+            true
+        } else {
+            self.channels.pop();
+            false
+        }
+    }
+
+    fn augment(&mut self, index: usize, visited: &mut [bool]) -> bool {
+        let channel_type = self.channels[index].0;
+        for tuner in 0..self.tuners.len() {
+            if visited[tuner] || !self.tuners[tuner].types.contains(&channel_type) {
+                continue;
+            }
+            visited[tuner] = true;
+            let owner = self.owners[tuner];
+            if owner.is_none_or(|owner| self.augment(owner, visited)) {
+                self.owners[tuner] = Some(index);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// 録画予約の競合を調べ、見つかった競合を `recording::schedule::Conflict` として発行する
+pub struct RecordingConflictDetector<E>
+where
+    E: EventPublisher<schedule::Conflict> + Send + Sync,
+{
+    event_publisher: E,
+}
+
+impl<E> RecordingConflictDetector<E>
+where
+    E: EventPublisher<schedule::Conflict> + Send + Sync,
+{
+    pub fn new(event_publisher: E) -> Self {
+        Self { event_publisher }
+    }
+
+    pub async fn detect(
+        &self,
+        tuners: &[Tuner],
+        recordings: &[ScheduledRecording],
+        mirakc_url: &str,
+    ) -> Result<Vec<RecordingConflict>, DomainError> {
+        let conflicts = detect_conflicts(tuners, recordings);
+        for conflict in &conflicts {
+            self.event_publisher
+                .publish(&schedule::Conflict {
+                    start_at: conflict.start_at,
+                    end_at: conflict.end_at,
+                    winner_program_ids: conflict.winners.clone(),
+                    loser_program_ids: conflict.losers.clone(),
+                    mirakc_url: mirakc_url.to_string(),
+                })
+                .await?;
+        }
+        Ok(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::test_util::{MockEventPublisher, test_tuner};
+
+    /// 20:00（UNIX時間のミリ秒）からの分
+    fn at(minutes: i64) -> i64 {
+        1619866800000 + minutes * 60 * 1000
+    }
+
+    fn recording(
+        program_id: i64,
+        (start, end): (i64, i64),
+        (channel_type, channel): (ChannelType, &str),
+        priority: i32,
+    ) -> ScheduledRecording {
+        ScheduledRecording {
+            program_id,
+            start_at: at(start),
+            end_at: at(end),
+            channel_type,
+            channel: channel.to_string(),
+            priority,
+        }
+    }
+
+    const GR27: (ChannelType, &str) = (ChannelType::Gr, "27");
+    const GR26: (ChannelType, &str) = (ChannelType::Gr, "26");
+    const BS15: (ChannelType, &str) = (ChannelType::Bs, "BS15_0");
+
+    #[test]
+    fn test_no_conflict_within_capacity() {
+        let tuners = [
+            test_tuner(0, &[ChannelType::Gr]),
+            test_tuner(1, &[ChannelType::Gr]),
+        ];
+        let recordings = [
+            recording(1, (0, 60), GR27, 0),
+            recording(2, (30, 90), GR26, 0),
+            // 前の番組の終了と同時に始まる番組は重ならない
+            recording(3, (60, 120), GR27, 0),
+        ];
+
+        assert!(detect_conflicts(&tuners, &recordings).is_empty());
+    }
+
+    #[test]
+    fn test_higher_priority_wins_overlap() {
+        let tuners = [test_tuner(0, &[ChannelType::Gr])];
+        let recordings = [
+            recording(1, (0, 60), GR27, 0),
+            recording(2, (30, 90), GR26, 5),
+        ];
+
+        let conflicts = detect_conflicts(&tuners, &recordings);
+
+        assert_eq!(
+            conflicts,
+            [RecordingConflict {
+                start_at: at(30),
+                end_at: at(60),
+                winners: vec![2],
+                losers: vec![1],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_earlier_start_wins_same_priority() {
+        let tuners = [
+            test_tuner(0, &[ChannelType::Gr]),
+            test_tuner(1, &[ChannelType::Gr]),
+        ];
+        let recordings = [
+            recording(3, (20, 40), (ChannelType::Gr, "25"), 0),
+            recording(1, (0, 60), GR27, 0),
+            recording(2, (10, 60), GR26, 0),
+        ];
+
+        let conflicts = detect_conflicts(&tuners, &recordings);
+
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            (conflicts[0].start_at, conflicts[0].end_at),
+            (at(20), at(40))
+        );
+        assert_eq!(conflicts[0].winners, [1, 2]);
+        assert_eq!(conflicts[0].losers, [3]);
+    }
+
+    #[test]
+    fn test_same_channel_shares_tuner() {
+        let tuners = [test_tuner(0, &[ChannelType::Gr])];
+        let recordings = [
+            recording(1, (0, 60), GR27, 0),
+            recording(2, (0, 60), GR27, 0),
+        ];
+
+        assert!(detect_conflicts(&tuners, &recordings).is_empty());
+    }
+
+    #[test]
+    fn test_reassigns_tuner_supporting_more_types() {
+        // 優先度の高い地上波の予約がBSも受信できるチューナーを使っていても、BSの予約は録画できる
+        let tuners = [
+            test_tuner(0, &[ChannelType::Gr, ChannelType::Bs]),
+            test_tuner(1, &[ChannelType::Gr]),
+        ];
+        let recordings = [
+            recording(1, (0, 60), GR27, 10),
+            recording(2, (0, 60), BS15, 0),
+        ];
+
+        assert!(detect_conflicts(&tuners, &recordings).is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_type_and_faulty_tuner() {
+        let mut faulty = test_tuner(1, &[ChannelType::Bs]);
+        faulty.is_fault = true;
+        let tuners = [test_tuner(0, &[ChannelType::Gr]), faulty];
+        let recordings = [
+            recording(1, (0, 60), GR27, 0),
+            recording(2, (30, 60), BS15, 0),
+        ];
+
+        let conflicts = detect_conflicts(&tuners, &recordings);
+
+        assert_eq!(
+            conflicts,
+            [RecordingConflict {
+                start_at: at(30),
+                end_at: at(60),
+                winners: vec![1],
+                losers: vec![2],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_loser_is_not_counted_afterwards() {
+        let tuners = [test_tuner(0, &[ChannelType::Gr])];
+        let recordings = [
+            recording(1, (0, 30), GR27, 5),
+            recording(2, (0, 120), GR26, 0),
+            recording(3, (60, 90), (ChannelType::Gr, "25"), 0),
+        ];
+
+        let conflicts = detect_conflicts(&tuners, &recordings);
+
+        // 2は最初の区間で録画できなくなるので、3とは競合しない
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].losers, [2]);
+    }
+
+    #[tokio::test]
+    async fn test_detect_publishes_conflicts() {
+        let publisher = MockEventPublisher::<schedule::Conflict>::new();
+        let detector = RecordingConflictDetector::new(publisher.clone());
+        let tuners = [test_tuner(0, &[ChannelType::Gr])];
+        let recordings = [
+            recording(1, (0, 60), GR27, 0),
+            recording(2, (30, 90), GR26, 5),
+        ];
+
+        let conflicts = detector
+            .detect(&tuners, &recordings, "http://example.com")
+            .await
+            .unwrap();
+
+        assert_eq!(conflicts.len(), 1);
+        let published = publisher.published_events();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].start_at, at(30));
+        assert_eq!(published[0].winner_program_ids, [2]);
+        assert_eq!(published[0].loser_program_ids, [1]);
+        assert_eq!(published[0].mirakc_url, "http://example.com");
+    }
+
+    #[tokio::test]
+    async fn test_detect_fails_when_publish_fails() {
+        let detector = RecordingConflictDetector::new(MockEventPublisher::failing());
+        let tuners = [test_tuner(0, &[ChannelType::Gr])];
+        let recordings = [
+            recording(1, (0, 60), GR27, 0),
+            recording(2, (0, 60), GR26, 0),
+        ];
+
+        let result = detector
+            .detect(&tuners, &recordings, "http://example.com")
+            .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use crate::error::DomainError;
+
+/// 処理に失敗したときの再試行方針
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// 初回を含む最大試行回数
+    pub max_attempts: u32,
+    /// 1回目の再試行までの待ち時間
+    pub initial_backoff: Duration,
+    /// 待ち時間の上限
+    pub max_backoff: Duration,
+    /// 再試行ごとに待ち時間に掛ける倍率
+    pub multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            multiplier: 2.0,
+        }
+    }
+}
+
+/// 失敗した処理をどう扱うか
+#[derive(Clone, Debug, PartialEq)]
+pub enum RetryDecision {
+    /// 指定時間待ってから再試行する
+    Retry(Duration),
+    /// 再試行しても成功しないため諦める
+    GiveUp,
+}
+
+impl RetryPolicy {
+    /// `attempt` 回目（1始まり）の試行に失敗した後の待ち時間
+    pub fn backoff_for(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        if !backoff.is_finite() || backoff >= self.max_backoff.as_secs_f64() {
+            self.max_backoff
+        } else {
+            Duration::from_secs_f64(backoff)
+        }
+    }
+
+    /// `attempt` 回目の試行が再試行可能なエラーで失敗したときの扱いを決める
+    pub fn decide(&self, attempt: u32) -> RetryDecision {
+        if attempt >= self.max_attempts {
+            RetryDecision::GiveUp
+        } else {
+            RetryDecision::Retry(self.backoff_for(attempt))
+        }
+    }
+
+    /// `attempt` 回目の試行がエラー `error` で失敗したときの扱いを決める
+    pub fn decide_for(&self, attempt: u32, error: &DomainError) -> RetryDecision {
+        if error.is_retryable() {
+            self.decide(attempt)
+        } else {
+            RetryDecision::GiveUp
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_for() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+        };
+        assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_for(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff_for(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff_for(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff_for(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_decide() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.decide(1),
+            RetryDecision::Retry(Duration::from_secs(1))
+        );
+        assert_eq!(
+            policy.decide(2),
+            RetryDecision::Retry(Duration::from_secs(2))
+        );
+        assert_eq!(policy.decide(3), RetryDecision::GiveUp);
+    }
+
+    #[test]
+    fn test_decide_for_permanent_error() {
+        let policy = RetryPolicy::default();
+        assert_eq!(
+            policy.decide_for(1, &DomainError::ServiceNotFound(1)),
+            RetryDecision::GiveUp
+        );
+        assert_eq!(
+            policy.decide_for(
+                1,
+                &DomainError::ProgramsRetrievalError("timeout".to_string())
+            ),
+            RetryDecision::Retry(Duration::from_secs(1))
+        );
+    }
+}
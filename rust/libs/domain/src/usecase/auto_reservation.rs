@@ -0,0 +1,496 @@
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    error::DomainError,
+    model::{
+        event::recording::{programs, reservation},
+        program::{Program, ProgramsData},
+        recording::{ReservationSkipReason, ScheduleOutcome, ScheduleRequest},
+        recording_rule::RuleMatcher,
+    },
+    ports::{EventPublisher, RecordingScheduler},
+    repository::{KvRepository, RecordingRuleRepository},
+};
+use async_trait::async_trait;
+use tracing::{debug, info, warn};
+
+#[async_trait]
+pub trait AutoReservationUseCase {
+    /// 更新されたサービスの番組表を録画ルールで評価し、一致した番組をmirakcに予約する
+    ///
+    /// 放送が始まっている番組と、すでに予約されている番組は評価しない。
+    async fn reserve_programs(&self, event: &programs::Updated) -> Result<(), DomainError>;
+}
+
+pub struct AutoReservationUseCaseImpl<R, L, S, M, K, C>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    L: RecordingRuleRepository + Send + Sync,
+    S: RecordingScheduler + Send + Sync,
+    M: EventPublisher<reservation::Matched> + Send + Sync,
+    K: EventPublisher<reservation::Skipped> + Send + Sync,
+    C: EventPublisher<reservation::Conflicted> + Send + Sync,
+{
+    programs_repository: R,
+    rule_repository: L,
+    scheduler: S,
+    matched_publisher: M,
+    skipped_publisher: K,
+    conflicted_publisher: C,
+    clock: fn() -> i64,
+}
+
+impl<R, L, S, M, K, C> AutoReservationUseCaseImpl<R, L, S, M, K, C>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    L: RecordingRuleRepository + Send + Sync,
+    S: RecordingScheduler + Send + Sync,
+    M: EventPublisher<reservation::Matched> + Send + Sync,
+    K: EventPublisher<reservation::Skipped> + Send + Sync,
+    C: EventPublisher<reservation::Conflicted> + Send + Sync,
+{
+    pub fn new(
+        programs_repository: R,
+        rule_repository: L,
+        scheduler: S,
+        matched_publisher: M,
+        skipped_publisher: K,
+        conflicted_publisher: C,
+    ) -> Self {
+        Self {
+            programs_repository,
+            rule_repository,
+            scheduler,
+            matched_publisher,
+            skipped_publisher,
+            conflicted_publisher,
+            clock: current_time_millis,
+        }
+    }
+
+    /// 現在時刻（UNIX時間のミリ秒）の取得方法を差し替える
+    pub fn with_clock(mut self, clock: fn() -> i64) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 有効なルールを読み込む（正しくないルールは警告して無視する）
+    async fn load_matchers(&self) -> Result<Vec<RuleMatcher>, DomainError> {
+        let rules = self.rule_repository.list().await?;
+        Ok(rules
+            .into_iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                RuleMatcher::new(rule)
+                    .inspect_err(|e| warn!("録画ルールを無視します: {}", e))
+                    .ok()
+            })
+            .collect())
+    }
+
+    /// 番組に一致したルールのうち最も優先度の高いもので予約する
+    async fn reserve_program(
+        &self,
+        matchers: &[RuleMatcher],
+        program: &Program,
+        event: &programs::Updated,
+    ) -> Result<(), DomainError> {
+        let mut rules: Vec<_> = matchers
+            .iter()
+            .filter(|matcher| matcher.matches(program))
+            .map(RuleMatcher::rule)
+            .collect();
+        // 優先度が同じ場合はIDの順に選ぶ
+        rules.sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.id.cmp(&b.id)));
+        let Some((winner, losers)) = rules.split_first() else {
+            return Ok(());
+        };
+
+        for loser in losers {
+            self.conflicted_publisher
+                .publish(&reservation::Conflicted {
+                    rule_id: loser.id.clone(),
+                    winner_rule_id: winner.id.clone(),
+                    service_id: event.service_id,
+                    program_id: program.id,
+                    mirakc_url: event.mirakc_url.clone(),
+                })
+                .await?;
+        }
+
+        let request = ScheduleRequest {
+            program_id: program.id,
+            priority: winner.priority,
+            tags: vec![format!("rule:{}", winner.id)],
+        };
+        let skip_reason = match self.scheduler.create_schedule(&request).await? {
+            ScheduleOutcome::Created => {
+                info!(
+                    rule_id = %winner.id,
+                    program_id = program.id,
+                    "ルールに一致した番組を予約しました: {}",
+                    program.name.as_deref().unwrap_or("-")
+                );
+                return self
+                    .matched_publisher
+                    .publish(&reservation::Matched {
+                        rule_id: winner.id.clone(),
+                        service_id: event.service_id,
+                        program_id: program.id,
+                        mirakc_url: event.mirakc_url.clone(),
+                    })
+                    .await;
+            }
+            ScheduleOutcome::AlreadyScheduled => ReservationSkipReason::AlreadyScheduled,
+            ScheduleOutcome::ProgramNotFound => ReservationSkipReason::ProgramNotFound,
+        };
+        debug!(
+            rule_id = %winner.id,
+            program_id = program.id,
+            reason = ?skip_reason,
+            "ルールに一致した番組を予約しませんでした"
+        );
+        self.skipped_publisher
+            .publish(&reservation::Skipped {
+                rule_id: winner.id.clone(),
+                service_id: event.service_id,
+                program_id: program.id,
+                reason: skip_reason,
+                mirakc_url: event.mirakc_url.clone(),
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R, L, S, M, K, C> AutoReservationUseCase for AutoReservationUseCaseImpl<R, L, S, M, K, C>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    L: RecordingRuleRepository + Send + Sync,
+    S: RecordingScheduler + Send + Sync,
+    M: EventPublisher<reservation::Matched> + Send + Sync,
+    K: EventPublisher<reservation::Skipped> + Send + Sync,
+    C: EventPublisher<reservation::Conflicted> + Send + Sync,
+{
+    async fn reserve_programs(&self, event: &programs::Updated) -> Result<(), DomainError> {
+        let matchers = self.load_matchers().await?;
+        if matchers.is_empty() {
+            debug!("有効な録画ルールがありません");
+            return Ok(());
+        }
+
+        let Some(versioned) = self
+            .programs_repository
+            .get(event.service_id.to_string())
+            .await?
+        else {
+            debug!(
+                "プログラムデータが見つかりません: service_id={}",
+                event.service_id
+            );
+            return Ok(());
+        };
+
+        let now = (self.clock)();
+        let programs: Vec<&Program> = versioned
+            .value
+            .0
+            .iter()
+            .filter(|p| p.start_at > now)
+            .collect();
+        if programs.is_empty() {
+            return Ok(());
+        }
+
+        // 番組表が更新されるたびに、予約済みの番組を予約し直したりイベントを発行したりしない
+        let scheduled: HashSet<i64> = self
+            .scheduler
+            .list_schedules()
+            .await?
+            .into_iter()
+            .map(|schedule| schedule.program_id)
+            .collect();
+        for program in programs {
+            if scheduled.contains(&program.id) {
+                continue;
+            }
+            self.reserve_program(&matchers, program, event).await?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn current_time_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::recording::{RecordingSchedule, ScheduleState};
+    use crate::model::recording_rule::RecordingRule;
+    use crate::usecase::test_util::{MockEventPublisher, MockKvRepository, test_program};
+    use std::sync::{Arc, Mutex};
+
+    const NOW: i64 = 1619856000000;
+    const SERVICE_ID: i64 = 3273601024;
+
+    #[derive(Clone)]
+    struct MockRuleRepository(Vec<RecordingRule>);
+
+    #[async_trait]
+    impl RecordingRuleRepository for MockRuleRepository {
+        async fn list(&self) -> Result<Vec<RecordingRule>, DomainError> {
+            Ok(self.0.clone())
+        }
+
+        async fn put(&self, _rule: &RecordingRule) -> Result<(), DomainError> {
+            Ok(())
+        }
+
+        async fn delete(&self, _rule_id: &str) -> Result<(), DomainError> {
+            Ok(())
+        }
+    }
+
+    /// 予約を記録し、`existing` の番組には（一覧の取得後に他から予約されたものとして）すでに予約があると答えるモック
+    #[derive(Clone, Default)]
+    struct MockScheduler {
+        existing: Vec<i64>,
+        requests: Arc<Mutex<Vec<ScheduleRequest>>>,
+        schedules: Arc<Mutex<Vec<RecordingSchedule>>>,
+    }
+
+    #[async_trait]
+    impl RecordingScheduler for MockScheduler {
+        async fn create_schedule(
+            &self,
+            request: &ScheduleRequest,
+        ) -> Result<ScheduleOutcome, DomainError> {
+            self.requests.lock().unwrap().push(request.clone());
+            if self.existing.contains(&request.program_id) {
+                return Ok(ScheduleOutcome::AlreadyScheduled);
+            }
+            self.schedules.lock().unwrap().push(RecordingSchedule {
+                program_id: request.program_id,
+                program_name: None,
+                start_at: NOW + 60000,
+                end_at: NOW + 60000 + 1800000,
+                state: ScheduleState::Scheduled,
+                priority: request.priority,
+                tags: request.tags.clone(),
+                failed_reason: None,
+            });
+            Ok(ScheduleOutcome::Created)
+        }
+
+        async fn list_schedules(&self) -> Result<Vec<RecordingSchedule>, DomainError> {
+            Ok(self.schedules.lock().unwrap().clone())
+        }
+
+        async fn get_schedule(
+            &self,
+            program_id: i64,
+        ) -> Result<Option<RecordingSchedule>, DomainError> {
+            Ok(self
+                .schedules
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|schedule| schedule.program_id == program_id)
+                .cloned())
+        }
+
+        async fn delete_schedule(&self, program_id: i64) -> Result<bool, DomainError> {
+            let mut schedules = self.schedules.lock().unwrap();
+            let count = schedules.len();
+            schedules.retain(|schedule| schedule.program_id != program_id);
+            Ok(schedules.len() != count)
+        }
+
+        async fn delete_schedules_by_tag(&self, tag: &str) -> Result<(), DomainError> {
+            self.schedules
+                .lock()
+                .unwrap()
+                .retain(|schedule| !schedule.tags.iter().any(|t| t == tag));
+            Ok(())
+        }
+    }
+
+    struct ReservationEvents {
+        matched: MockEventPublisher<reservation::Matched>,
+        skipped: MockEventPublisher<reservation::Skipped>,
+        conflicted: MockEventPublisher<reservation::Conflicted>,
+    }
+
+    impl ReservationEvents {
+        fn new() -> Self {
+            Self {
+                matched: MockEventPublisher::new(),
+                skipped: MockEventPublisher::new(),
+                conflicted: MockEventPublisher::new(),
+            }
+        }
+    }
+
+    fn rule(id: &str, keyword: &str, priority: i32) -> RecordingRule {
+        let mut rule: RecordingRule =
+            serde_json::from_value(serde_json::json!({"id": id, "name": id})).unwrap();
+        rule.keywords.include = vec![keyword.to_string()];
+        rule.priority = priority;
+        rule
+    }
+
+    async fn reserve(
+        rules: Vec<RecordingRule>,
+        programs: Vec<Program>,
+        scheduler: MockScheduler,
+        events: &ReservationEvents,
+    ) {
+        let service_id = 3273601024;
+        let repository = MockKvRepository::<ProgramsData>::new();
+        repository
+            .put(service_id.to_string(), &ProgramsData(programs))
+            .await
+            .unwrap();
+        let usecase = AutoReservationUseCaseImpl::new(
+            repository,
+            MockRuleRepository(rules),
+            scheduler,
+            events.matched.clone(),
+            events.skipped.clone(),
+            events.conflicted.clone(),
+        )
+        .with_clock(|| NOW);
+
+        usecase
+            .reserve_programs(&programs::Updated {
+                service_id,
+                mirakc_url: "http://example.com".to_string(),
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reserve_matching_future_programs() {
+        let scheduler = MockScheduler::default();
+        let events = ReservationEvents::new();
+        let mut disabled = rule("disabled", "ニュース", 0);
+        disabled.enabled = false;
+
+        reserve(
+            vec![rule("anime", "アニメ", 3), disabled],
+            vec![
+                test_program(327360102400001, SERVICE_ID, NOW - 60000, "放送中のアニメ"),
+                test_program(327360102400002, SERVICE_ID, NOW + 60000, "これからのアニメ"),
+                test_program(327360102400003, SERVICE_ID, NOW + 60000, "ニュース"),
+            ],
+            scheduler.clone(),
+            &events,
+        )
+        .await;
+
+        assert_eq!(
+            *scheduler.requests.lock().unwrap(),
+            [ScheduleRequest {
+                program_id: 327360102400002,
+                priority: 3,
+                tags: vec!["rule:anime".to_string()],
+            }]
+        );
+        let matched = events.matched.published_events();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].rule_id, "anime");
+        assert_eq!(matched[0].service_id, 3273601024);
+        assert!(events.skipped.published_events().is_empty());
+        assert!(events.conflicted.published_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_rule_wins_and_existing_schedule_is_skipped() {
+        let scheduler = MockScheduler {
+            existing: vec![327360102400002],
+            ..Default::default()
+        };
+        let events = ReservationEvents::new();
+
+        reserve(
+            vec![
+                rule("all", ".*", 0),
+                rule("dragon", "ドラゴン", 10),
+                rule("maid", "メイド", 10),
+            ],
+            vec![
+                test_program(327360102400001, SERVICE_ID, NOW + 60000, "メイドラゴン"),
+                test_program(327360102400002, SERVICE_ID, NOW + 60000, "予約済みの番組"),
+            ],
+            scheduler.clone(),
+            &events,
+        )
+        .await;
+
+        let requests = scheduler.requests.lock().unwrap().clone();
+        assert_eq!(requests[0].tags, ["rule:dragon"]);
+        assert_eq!(requests[0].priority, 10);
+
+        let conflicted = events.conflicted.published_events();
+        let losers: Vec<&str> = conflicted.iter().map(|e| e.rule_id.as_str()).collect();
+        assert_eq!(losers, ["maid", "all"]);
+        assert!(conflicted.iter().all(|e| e.winner_rule_id == "dragon"));
+
+        let skipped = events.skipped.published_events();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].rule_id, "all");
+        assert_eq!(skipped[0].program_id, 327360102400002);
+        assert_eq!(skipped[0].reason, ReservationSkipReason::AlreadyScheduled);
+        assert_eq!(events.matched.published_events().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_rule_is_ignored() {
+        let scheduler = MockScheduler::default();
+        let events = ReservationEvents::new();
+
+        reserve(
+            vec![rule("broken", "(", 0)],
+            vec![test_program(
+                327360102400001,
+                SERVICE_ID,
+                NOW + 60000,
+                "番組",
+            )],
+            scheduler.clone(),
+            &events,
+        )
+        .await;
+
+        assert!(scheduler.requests.lock().unwrap().is_empty());
+        assert!(events.matched.published_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_programs_are_not_reserved_again() {
+        let scheduler = MockScheduler::default();
+        let events = ReservationEvents::new();
+        let rules = vec![rule("dragon", "ドラゴン", 10), rule("maid", "メイド", 0)];
+        let programs = vec![test_program(
+            327360102400001,
+            SERVICE_ID,
+            NOW + 60000,
+            "メイドラゴン",
+        )];
+
+        // 番組表が変わらないまま2回更新された
+        reserve(rules.clone(), programs.clone(), scheduler.clone(), &events).await;
+        reserve(rules, programs, scheduler.clone(), &events).await;
+
+        assert_eq!(scheduler.requests.lock().unwrap().len(), 1);
+        assert_eq!(events.matched.published_events().len(), 1);
+        assert_eq!(events.conflicted.published_events().len(), 1);
+        assert!(events.skipped.published_events().is_empty());
+    }
+}
@@ -0,0 +1,435 @@
+use crate::{
+    error::DomainError,
+    model::{
+        event::recording::{epg, programs},
+        program::{Program, ProgramsData, ProgramsDiff},
+    },
+    ports::{EventPublisher, ProgramEventPublisher, ProgramsRetriever},
+    repository::{KvRepository, ProgramRepository},
+};
+use async_trait::async_trait;
+use tracing::debug;
+
+#[async_trait]
+pub trait EpgRetrieverUseCase {
+    async fn retrieve_programs(&self, event: &epg::Updated) -> Result<(), DomainError>;
+}
+
+pub struct EpgRetrieverUseCaseImpl<P, R, Q, E, D>
+where
+    P: ProgramsRetriever + Send + Sync,
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    Q: ProgramRepository + Send + Sync,
+    E: EventPublisher<programs::Updated> + Send + Sync,
+    D: ProgramEventPublisher + Send + Sync,
+{
+    programs_retriever: P,
+    programs_repository: R,
+    program_repository: Q,
+    event_publisher: E,
+    program_event_publisher: D,
+}
+
+impl<P, R, Q, E, D> EpgRetrieverUseCaseImpl<P, R, Q, E, D>
+where
+    P: ProgramsRetriever + Send + Sync,
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    Q: ProgramRepository + Send + Sync,
+    E: EventPublisher<programs::Updated> + Send + Sync,
+    D: ProgramEventPublisher + Send + Sync,
+{
+    pub fn new(
+        programs_retriever: P,
+        programs_repository: R,
+        program_repository: Q,
+        event_publisher: E,
+        program_event_publisher: D,
+    ) -> Self {
+        Self {
+            programs_retriever,
+            programs_repository,
+            program_repository,
+            event_publisher,
+            program_event_publisher,
+        }
+    }
+}
+
+#[async_trait]
+impl<P, R, Q, E, D> EpgRetrieverUseCase for EpgRetrieverUseCaseImpl<P, R, Q, E, D>
+where
+    P: ProgramsRetriever + Send + Sync,
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    Q: ProgramRepository + Send + Sync,
+    E: EventPublisher<programs::Updated> + Send + Sync,
+    D: ProgramEventPublisher + Send + Sync,
+{
+    async fn retrieve_programs(&self, event: &epg::Updated) -> Result<(), DomainError> {
+        let service_id = event.service_id;
+        debug!("EPG更新イベントを受信: service_id={}", service_id);
+
+        let programs = self.programs_retriever.get_programs(service_id).await?;
+        debug!(
+            "サービスID {} のプログラム {} 件を取得",
+            service_id,
+            programs.len()
+        );
+
+        let diff = store_programs(
+            &self.programs_repository,
+            &self.program_repository,
+            &self.program_event_publisher,
+            service_id,
+            &event.mirakc_url,
+            programs,
+        )
+        .await?;
+        debug!(
+            "サービスID {} の番組表の差分: 追加 {} 件、変更 {} 件、削除 {} 件",
+            service_id,
+            diff.added.len(),
+            diff.changed.len(),
+            diff.removed.len()
+        );
+
+        // 番組表が変わっていなくても、EPGの更新は通知する。
+        // 保存後の通知に失敗して再配信された場合も、ここで通知し直される
+        self.event_publisher
+            .publish(&programs::Updated {
+                service_id,
+                mirakc_url: event.mirakc_url.clone(),
+            })
+            .await?;
+        debug!(
+            "プログラム更新イベントを発行しました: service_id={}",
+            service_id
+        );
+        Ok(())
+    }
+}
+
+/// 保存済みの番組表との差分を番組ごとのイベントとして発行し、新しい番組表を保存する
+///
+/// 番組単位の保存先にも差分を反映する。差分がなければ、番組単位の保存先にサービスの番組がないときだけ保存する。
+/// 発行してから保存するため、発行に失敗した差分は次の取得で再び検出される。
+/// 保存に失敗して再試行された場合は、同じイベントが再び発行されうる。
+/// `programs::Updated` は保存した番組表を読めるようになってから、呼び出し側が発行する。
+pub(crate) async fn store_programs<R, Q, D>(
+    programs_repository: &R,
+    program_repository: &Q,
+    program_event_publisher: &D,
+    service_id: i64,
+    mirakc_url: &str,
+    programs: Vec<Program>,
+) -> Result<ProgramsDiff, DomainError>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    Q: ProgramRepository + Send + Sync,
+    D: ProgramEventPublisher + Send + Sync,
+{
+    let current = programs_repository
+        .get(service_id.to_string())
+        .await?
+        .map_or_else(Vec::new, |current| current.value.0);
+
+    let diff = ProgramsDiff::between(&current, &programs);
+    if diff.is_empty() {
+        // 番組単位の保存先が後から加わった場合などは、変わっていない番組表からも保存する
+        if !programs.is_empty() && !program_repository.has_programs(service_id).await? {
+            debug!(
+                "サービスID {} の番組が番組単位の保存先にないので保存します",
+                service_id
+            );
+            program_repository
+                .apply_diff(&ProgramsDiff {
+                    added: programs,
+                    ..Default::default()
+                })
+                .await?;
+        }
+        return Ok(diff);
+    }
+
+    program_event_publisher
+        .publish_diff(service_id, mirakc_url, &diff)
+        .await?;
+    program_repository.apply_diff(&diff).await?;
+    programs_repository
+        .put(service_id.to_string(), &ProgramsData(programs))
+        .await?;
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::recording::program;
+    use crate::model::program::ProgramField;
+    use crate::service::ProgramEventPublisherImpl;
+    use crate::usecase::test_util::{
+        MockEventPublisher, MockKvRepository, MockProgramRepository, test_program,
+    };
+
+    const START_AT: i64 = 1619856000000;
+
+    struct MockProgramsRetriever {
+        service_id: i64,
+        programs: Vec<Program>,
+    }
+
+    #[async_trait]
+    impl ProgramsRetriever for MockProgramsRetriever {
+        async fn get_programs(&self, service_id: i64) -> Result<Vec<Program>, DomainError> {
+            if service_id == self.service_id {
+                Ok(self.programs.clone())
+            } else {
+                Err(DomainError::ServiceNotFound(service_id))
+            }
+        }
+    }
+
+    /// 番組ごとのイベントを種類別に記録するモック
+    struct ProgramEvents {
+        added: MockEventPublisher<program::Added>,
+        changed: MockEventPublisher<program::Changed>,
+        removed: MockEventPublisher<program::Removed>,
+    }
+
+    impl ProgramEvents {
+        fn new() -> Self {
+            Self {
+                added: MockEventPublisher::new(),
+                changed: MockEventPublisher::new(),
+                removed: MockEventPublisher::new(),
+            }
+        }
+
+        fn publisher(
+            &self,
+        ) -> ProgramEventPublisherImpl<
+            MockEventPublisher<program::Added>,
+            MockEventPublisher<program::Changed>,
+            MockEventPublisher<program::Removed>,
+        > {
+            ProgramEventPublisherImpl::new(
+                self.added.clone(),
+                self.changed.clone(),
+                self.removed.clone(),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_programs() {
+        let service_id = 1;
+        let mirakc_url = "http://example.com";
+
+        let repository = MockKvRepository::<ProgramsData>::new();
+        let publisher = MockEventPublisher::<programs::Updated>::new();
+        let program_events = ProgramEvents::new();
+        let usecase = EpgRetrieverUseCaseImpl::new(
+            MockProgramsRetriever {
+                service_id,
+                programs: vec![test_program(123456789, service_id, START_AT, "テスト番組")],
+            },
+            repository.clone(),
+            MockProgramRepository::new(),
+            publisher.clone(),
+            program_events.publisher(),
+        );
+
+        usecase
+            .retrieve_programs(&epg::Updated {
+                service_id,
+                mirakc_url: mirakc_url.to_string(),
+            })
+            .await
+            .unwrap();
+
+        let added = program_events.added.published_events();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].service_id, service_id);
+        assert_eq!(added[0].program.id, 123456789);
+        assert!(program_events.changed.published_events().is_empty());
+        assert!(program_events.removed.published_events().is_empty());
+
+        let published_events = publisher.published_events();
+        assert_eq!(published_events.len(), 1);
+        assert_eq!(published_events[0].service_id, service_id);
+        assert_eq!(published_events[0].mirakc_url, mirakc_url);
+
+        let stored = repository
+            .get(service_id.to_string())
+            .await
+            .unwrap()
+            .unwrap()
+            .value;
+        assert_eq!(stored.0.len(), 1);
+        assert_eq!(stored.0[0].id, 123456789);
+        assert_eq!(stored.0[0].service_id, service_id as i32);
+        assert_eq!(stored.0[0].name, Some("テスト番組".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_programs_service_not_found() {
+        let repository = MockKvRepository::<ProgramsData>::new();
+        let publisher = MockEventPublisher::<programs::Updated>::new();
+        let usecase = EpgRetrieverUseCaseImpl::new(
+            MockProgramsRetriever {
+                service_id: 1,
+                programs: vec![],
+            },
+            repository.clone(),
+            MockProgramRepository::new(),
+            publisher.clone(),
+            ProgramEvents::new().publisher(),
+        );
+
+        let result = usecase
+            .retrieve_programs(&epg::Updated {
+                service_id: 2,
+                mirakc_url: "http://example.com".to_string(),
+            })
+            .await;
+
+        assert!(matches!(result, Err(DomainError::ServiceNotFound(2))));
+        assert!(publisher.published_events().is_empty());
+        assert!(repository.get("2".to_string()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_programs_publish_error() {
+        let service_id = 1;
+        let repository = MockKvRepository::<ProgramsData>::new();
+        let publisher = MockEventPublisher::<programs::Updated>::failing_times(1);
+        let program_events = ProgramEvents::new();
+        let usecase = EpgRetrieverUseCaseImpl::new(
+            MockProgramsRetriever {
+                service_id,
+                programs: vec![test_program(123456789, service_id, START_AT, "テスト番組")],
+            },
+            repository.clone(),
+            MockProgramRepository::new(),
+            publisher.clone(),
+            program_events.publisher(),
+        );
+        let event = epg::Updated {
+            service_id,
+            mirakc_url: "http://example.com".to_string(),
+        };
+
+        let result = usecase.retrieve_programs(&event).await;
+
+        assert!(matches!(result, Err(DomainError::EventPublishError(_))));
+        // 通知より先に保存しているので、購読側は通知を受けた時点で新しい番組表を読める
+        assert!(
+            repository
+                .get(service_id.to_string())
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        // 再配信では差分がなくても通知し直す
+        usecase.retrieve_programs(&event).await.unwrap();
+        assert_eq!(publisher.published_events().len(), 1);
+        assert_eq!(program_events.added.published_events().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_programs_publishes_diff() {
+        let service_id = 1;
+        let unchanged = test_program(1, service_id, START_AT, "変わらない番組");
+        let mut changed = test_program(2, service_id, START_AT, "変わる番組");
+        let removed = test_program(3, service_id, START_AT, "なくなる番組");
+
+        let repository = MockKvRepository::<ProgramsData>::new();
+        repository
+            .put(
+                service_id.to_string(),
+                &ProgramsData(vec![unchanged.clone(), changed.clone(), removed]),
+            )
+            .await
+            .unwrap();
+
+        changed.name = Some("変更後の番組".to_string());
+        let program_repository = MockProgramRepository::new();
+        let program_events = ProgramEvents::new();
+        let usecase = EpgRetrieverUseCaseImpl::new(
+            MockProgramsRetriever {
+                service_id,
+                programs: vec![unchanged, changed],
+            },
+            repository.clone(),
+            program_repository.clone(),
+            MockEventPublisher::<programs::Updated>::new(),
+            program_events.publisher(),
+        );
+
+        usecase
+            .retrieve_programs(&epg::Updated {
+                service_id,
+                mirakc_url: "http://example.com".to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert!(program_events.added.published_events().is_empty());
+        let changed = program_events.changed.published_events();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].program.id, 2);
+        assert_eq!(changed[0].changed_fields, [ProgramField::Name]);
+        let removed = program_events.removed.published_events();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].program_id, 3);
+
+        let stored = repository
+            .get(service_id.to_string())
+            .await
+            .unwrap()
+            .unwrap()
+            .value;
+        assert_eq!(stored.0.len(), 2);
+        assert_eq!(program_repository.program_ids(), [2]);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_programs_backfills_program_repository() {
+        let service_id = 1;
+        let programs = vec![
+            test_program(1, service_id, START_AT, "番組1"),
+            test_program(2, service_id, START_AT, "番組2"),
+        ];
+        let repository = MockKvRepository::<ProgramsData>::new();
+        repository
+            .put(service_id.to_string(), &ProgramsData(programs.clone()))
+            .await
+            .unwrap();
+        let program_repository = MockProgramRepository::new();
+        let publisher = MockEventPublisher::<programs::Updated>::new();
+        let program_events = ProgramEvents::new();
+        let usecase = EpgRetrieverUseCaseImpl::new(
+            MockProgramsRetriever {
+                service_id,
+                programs,
+            },
+            repository,
+            program_repository.clone(),
+            publisher.clone(),
+            program_events.publisher(),
+        );
+
+        usecase
+            .retrieve_programs(&epg::Updated {
+                service_id,
+                mirakc_url: "http://example.com".to_string(),
+            })
+            .await
+            .unwrap();
+
+        // 番組表は変わっていないので、番組ごとのイベントは発行しない
+        assert_eq!(program_repository.program_ids(), [1, 2]);
+        assert!(program_events.added.published_events().is_empty());
+        assert_eq!(publisher.published_events().len(), 1);
+    }
+}
@@ -0,0 +1,328 @@
+use crate::{
+    error::DomainError,
+    model::{event::recording::programs, program::ProgramsData},
+    ports::{EventPublisher, ProgramEventPublisher, ProgramsRetriever, ServicesRetriever},
+    repository::{KvRepository, ProgramRepository},
+};
+use async_trait::async_trait;
+use std::{collections::BTreeSet, sync::Mutex};
+use tracing::{debug, info, warn};
+
+use super::epg_retriever::store_programs;
+
+/// 全サービスの番組表を同期した結果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EpgSyncReport {
+    /// 番組表が変わっていたか、前回通知できなかった分を通知し直したサービスの数
+    pub updated: usize,
+    /// 番組表が変わっていなかったサービスの数
+    pub unchanged: usize,
+    /// 番組表の取得・保存に失敗したサービスのID
+    pub failed: Vec<i64>,
+}
+
+#[async_trait]
+pub trait EpgSyncUseCase {
+    /// mirakcの全サービスの番組表を取得し、変わっていたものだけを保存して通知する
+    ///
+    /// 番組ごとの差分は [`ProgramEventPublisher`] で、サービスごとの更新は `programs::Updated` で通知する。
+    async fn sync_all(&self) -> Result<EpgSyncReport, DomainError>;
+}
+
+pub struct EpgSyncUseCaseImpl<S, P, R, Q, E, D>
+where
+    S: ServicesRetriever + Send + Sync,
+    P: ProgramsRetriever + Send + Sync,
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    Q: ProgramRepository + Send + Sync,
+    E: EventPublisher<programs::Updated> + Send + Sync,
+    D: ProgramEventPublisher + Send + Sync,
+{
+    services_retriever: S,
+    programs_retriever: P,
+    programs_repository: R,
+    program_repository: Q,
+    event_publisher: E,
+    program_event_publisher: D,
+    mirakc_url: String,
+    /// 番組表を保存したが `programs::Updated` を発行できなかったサービスのID
+    unpublished: Mutex<BTreeSet<i64>>,
+}
+
+impl<S, P, R, Q, E, D> EpgSyncUseCaseImpl<S, P, R, Q, E, D>
+where
+    S: ServicesRetriever + Send + Sync,
+    P: ProgramsRetriever + Send + Sync,
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    Q: ProgramRepository + Send + Sync,
+    E: EventPublisher<programs::Updated> + Send + Sync,
+    D: ProgramEventPublisher + Send + Sync,
+{
+    pub fn new(
+        services_retriever: S,
+        programs_retriever: P,
+        programs_repository: R,
+        program_repository: Q,
+        event_publisher: E,
+        program_event_publisher: D,
+        mirakc_url: &str,
+    ) -> Self {
+        Self {
+            services_retriever,
+            programs_retriever,
+            programs_repository,
+            program_repository,
+            event_publisher,
+            program_event_publisher,
+            mirakc_url: mirakc_url.to_string(),
+            unpublished: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// 1つのサービスの番組表を同期し、`programs::Updated` を発行したかどうかを返す
+    ///
+    /// 保存後の発行に失敗したサービスは覚えておき、次の同期で番組表が変わっていなくても発行し直す。
+    async fn sync_service(&self, service_id: i64) -> Result<bool, DomainError> {
+        let programs = self.programs_retriever.get_programs(service_id).await?;
+
+        let diff = store_programs(
+            &self.programs_repository,
+            &self.program_repository,
+            &self.program_event_publisher,
+            service_id,
+            &self.mirakc_url,
+            programs,
+        )
+        .await?;
+        let unpublished = self.unpublished.lock().unwrap().contains(&service_id);
+        if diff.is_empty() && !unpublished {
+            debug!("サービスID {} の番組表は変わっていません", service_id);
+            return Ok(false);
+        }
+
+        if let Err(e) = self
+            .event_publisher
+            .publish(&programs::Updated {
+                service_id,
+                mirakc_url: self.mirakc_url.clone(),
+            })
+            .await
+        {
+            self.unpublished.lock().unwrap().insert(service_id);
+            return Err(e);
+        }
+        self.unpublished.lock().unwrap().remove(&service_id);
+        debug!(
+            "サービスID {} の番組表を更新しました: 追加 {} 件、変更 {} 件、削除 {} 件",
+            service_id,
+            diff.added.len(),
+            diff.changed.len(),
+            diff.removed.len()
+        );
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl<S, P, R, Q, E, D> EpgSyncUseCase for EpgSyncUseCaseImpl<S, P, R, Q, E, D>
+where
+    S: ServicesRetriever + Send + Sync,
+    P: ProgramsRetriever + Send + Sync,
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    Q: ProgramRepository + Send + Sync,
+    E: EventPublisher<programs::Updated> + Send + Sync,
+    D: ProgramEventPublisher + Send + Sync,
+{
+    async fn sync_all(&self) -> Result<EpgSyncReport, DomainError> {
+        let service_ids = self.services_retriever.get_service_ids().await?;
+        debug!("{} 件のサービスの番組表を同期します", service_ids.len());
+
+        let mut report = EpgSyncReport::default();
+        for service_id in service_ids {
+            match self.sync_service(service_id).await {
+                Ok(true) => report.updated += 1,
+                Ok(false) => report.unchanged += 1,
+                Err(e) => {
+                    warn!("サービスID {} の番組表の同期に失敗: {}", service_id, e);
+                    report.failed.push(service_id);
+                }
+            }
+        }
+
+        info!(
+            updated = report.updated,
+            unchanged = report.unchanged,
+            failed = report.failed.len(),
+            "番組表の同期が完了しました"
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::recording::program;
+    use crate::model::program::Program;
+    use crate::service::ProgramEventPublisherImpl;
+    use crate::usecase::test_util::{
+        MockEventPublisher, MockKvRepository, MockProgramRepository, test_program,
+    };
+    use std::collections::BTreeMap;
+
+    const START_AT: i64 = 1619856000000;
+
+    /// サービス一覧と番組表を返すmirakcのモック（一覧には番組表のないサービス404も含む）
+    #[derive(Clone)]
+    struct MockMirakc {
+        programs: BTreeMap<i64, Vec<Program>>,
+    }
+
+    #[async_trait]
+    impl ServicesRetriever for MockMirakc {
+        async fn get_service_ids(&self) -> Result<Vec<i64>, DomainError> {
+            Ok(self.programs.keys().copied().chain([404]).collect())
+        }
+    }
+
+    #[async_trait]
+    impl ProgramsRetriever for MockMirakc {
+        async fn get_programs(&self, service_id: i64) -> Result<Vec<Program>, DomainError> {
+            self.programs
+                .get(&service_id)
+                .cloned()
+                .ok_or(DomainError::ServiceNotFound(service_id))
+        }
+    }
+
+    fn program_event_publisher(
+        added: MockEventPublisher<program::Added>,
+    ) -> impl ProgramEventPublisher + Send + Sync {
+        ProgramEventPublisherImpl::new(
+            added,
+            MockEventPublisher::<program::Changed>::new(),
+            MockEventPublisher::<program::Removed>::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_publishes_only_changed_services() {
+        let mirakc = MockMirakc {
+            programs: BTreeMap::from([
+                (1, vec![test_program(11, 1, START_AT, "変わらない番組")]),
+                (2, vec![test_program(21, 2, START_AT, "新しい番組")]),
+                (3, vec![test_program(31, 3, START_AT, "初めての番組")]),
+            ]),
+        };
+        let repository = MockKvRepository::<ProgramsData>::new();
+        repository
+            .put(
+                "1".to_string(),
+                &ProgramsData(vec![test_program(11, 1, START_AT, "変わらない番組")]),
+            )
+            .await
+            .unwrap();
+        repository
+            .put(
+                "2".to_string(),
+                &ProgramsData(vec![test_program(21, 2, START_AT, "古い番組")]),
+            )
+            .await
+            .unwrap();
+        let publisher = MockEventPublisher::<programs::Updated>::new();
+        let added = MockEventPublisher::<program::Added>::new();
+        let usecase = EpgSyncUseCaseImpl::new(
+            mirakc.clone(),
+            mirakc,
+            repository.clone(),
+            MockProgramRepository::new(),
+            publisher.clone(),
+            program_event_publisher(added.clone()),
+            "http://example.com",
+        );
+
+        let report = usecase.sync_all().await.unwrap();
+
+        assert_eq!(
+            report,
+            EpgSyncReport {
+                updated: 2,
+                unchanged: 1,
+                failed: vec![404],
+            }
+        );
+        let published = publisher.published_events();
+        let service_ids: Vec<i64> = published.iter().map(|event| event.service_id).collect();
+        assert_eq!(service_ids, [2, 3]);
+        assert_eq!(published[0].mirakc_url, "http://example.com");
+        let added_ids: Vec<i64> = added
+            .published_events()
+            .iter()
+            .map(|event| event.program.id)
+            .collect();
+        assert_eq!(added_ids, [31]);
+
+        let stored = repository.get("2".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.revision, 2);
+        assert_eq!(stored.value.0[0].name, Some("新しい番組".to_string()));
+        assert!(repository.get("3".to_string()).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_records_publish_failure() {
+        let mirakc = MockMirakc {
+            programs: BTreeMap::from([(1, vec![test_program(11, 1, START_AT, "番組")])]),
+        };
+        let usecase = EpgSyncUseCaseImpl::new(
+            mirakc.clone(),
+            mirakc,
+            MockKvRepository::<ProgramsData>::new(),
+            MockProgramRepository::new(),
+            MockEventPublisher::<programs::Updated>::failing(),
+            program_event_publisher(MockEventPublisher::new()),
+            "http://example.com",
+        );
+
+        let report = usecase.sync_all().await.unwrap();
+
+        assert_eq!(report.updated, 0);
+        assert_eq!(report.failed, [1, 404]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_republishes_after_publish_failure() {
+        let mirakc = MockMirakc {
+            programs: BTreeMap::from([(1, vec![test_program(11, 1, START_AT, "番組")])]),
+        };
+        let repository = MockKvRepository::<ProgramsData>::new();
+        let publisher = MockEventPublisher::<programs::Updated>::failing_times(1);
+        let usecase = EpgSyncUseCaseImpl::new(
+            mirakc.clone(),
+            mirakc,
+            repository.clone(),
+            MockProgramRepository::new(),
+            publisher.clone(),
+            program_event_publisher(MockEventPublisher::new()),
+            "http://example.com",
+        );
+
+        let report = usecase.sync_all().await.unwrap();
+        assert_eq!(report.failed, [1, 404]);
+        // 通知より先に保存する
+        assert!(repository.get("1".to_string()).await.unwrap().is_some());
+
+        // 番組表は保存済みで変わっていないが、通知できていないので通知し直す
+        let report = usecase.sync_all().await.unwrap();
+        assert_eq!(report.updated, 1);
+        let service_ids: Vec<i64> = publisher
+            .published_events()
+            .iter()
+            .map(|event| event.service_id)
+            .collect();
+        assert_eq!(service_ids, [1]);
+
+        let report = usecase.sync_all().await.unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(publisher.published_events().len(), 1);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    error::DomainError, model::inventory::Inventory, ports::InventoryRetriever,
+    repository::InventoryRepository,
+};
+use async_trait::async_trait;
+use tracing::{debug, info};
+
+/// チューナー・チャンネル・サービスの一覧を同期した結果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InventorySyncReport {
+    pub tuners: usize,
+    pub channels: usize,
+    pub services: usize,
+    /// 保存済みの一覧から変わっていたかどうか
+    pub updated: bool,
+}
+
+#[async_trait]
+pub trait InventorySyncUseCase {
+    /// mirakcのチューナー・チャンネル・サービスの一覧を取得し、変わっていたものだけを保存する
+    ///
+    /// どれか1つでも取得に失敗した場合は何も保存しない。
+    async fn sync(&self) -> Result<InventorySyncReport, DomainError>;
+}
+
+pub struct InventorySyncUseCaseImpl<R, S>
+where
+    R: InventoryRetriever + Send + Sync,
+    S: InventoryRepository + Send + Sync,
+{
+    retriever: R,
+    repository: S,
+}
+
+impl<R, S> InventorySyncUseCaseImpl<R, S>
+where
+    R: InventoryRetriever + Send + Sync,
+    S: InventoryRepository + Send + Sync,
+{
+    pub fn new(retriever: R, repository: S) -> Self {
+        Self {
+            retriever,
+            repository,
+        }
+    }
+
+    async fn retrieve(&self) -> Result<Inventory, DomainError> {
+        let mut inventory = Inventory {
+            tuners: self.retriever.get_tuners().await?,
+            channels: self.retriever.get_channels().await?,
+            services: self.retriever.get_services().await?,
+        };
+        // 保存先が返す順に揃えて比べる
+        inventory.tuners.sort_by_key(|tuner| tuner.index);
+        inventory
+            .channels
+            .sort_by(|a, b| (a.channel_type, &a.channel).cmp(&(b.channel_type, &b.channel)));
+        inventory.services.sort_by_key(|service| service.id);
+        Ok(inventory)
+    }
+}
+
+#[async_trait]
+impl<R, S> InventorySyncUseCase for InventorySyncUseCaseImpl<R, S>
+where
+    R: InventoryRetriever + Send + Sync,
+    S: InventoryRepository + Send + Sync,
+{
+    async fn sync(&self) -> Result<InventorySyncReport, DomainError> {
+        let inventory = self.retrieve().await?;
+        let stored = self.repository.load().await?;
+
+        let mut updated = false;
+        if inventory.tuners != stored.tuners {
+            self.repository.replace_tuners(&inventory.tuners).await?;
+            debug!("チューナーの一覧を更新しました");
+            updated = true;
+        }
+        if inventory.channels != stored.channels {
+            self.repository
+                .replace_channels(&inventory.channels)
+                .await?;
+            debug!("チャンネルの一覧を更新しました");
+            updated = true;
+        }
+        if inventory.services != stored.services {
+            self.repository
+                .replace_services(&inventory.services)
+                .await?;
+            debug!("サービスの一覧を更新しました");
+            updated = true;
+        }
+
+        let report = InventorySyncReport {
+            tuners: inventory.tuners.len(),
+            channels: inventory.channels.len(),
+            services: inventory.services.len(),
+            updated,
+        };
+        info!(
+            tuners = report.tuners,
+            channels = report.channels,
+            services = report.services,
+            updated = report.updated,
+            "チューナー・チャンネル情報の同期が完了しました"
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::inventory::{ChannelInfo, ChannelType, Service, Tuner};
+    use crate::usecase::test_util::{MockInventoryRepository, test_service, test_tuner};
+
+    #[derive(Clone, Default)]
+    struct MockMirakc {
+        inventory: Inventory,
+        fail_services: bool,
+    }
+
+    #[async_trait]
+    impl InventoryRetriever for MockMirakc {
+        async fn get_tuners(&self) -> Result<Vec<Tuner>, DomainError> {
+            Ok(self.inventory.tuners.clone())
+        }
+
+        async fn get_channels(&self) -> Result<Vec<ChannelInfo>, DomainError> {
+            Ok(self.inventory.channels.clone())
+        }
+
+        async fn get_services(&self) -> Result<Vec<Service>, DomainError> {
+            if self.fail_services {
+                return Err(DomainError::InventoryRetrievalError(
+                    "モックの取得エラー".to_string(),
+                ));
+            }
+            Ok(self.inventory.services.clone())
+        }
+    }
+
+    fn test_channel(channel: &str, service_ids: Vec<i64>) -> ChannelInfo {
+        ChannelInfo {
+            channel_type: ChannelType::Gr,
+            channel: channel.to_string(),
+            name: format!("チャンネル{}", channel),
+            service_ids,
+        }
+    }
+
+    fn test_inventory() -> Inventory {
+        Inventory {
+            tuners: vec![
+                test_tuner(1, &[ChannelType::Gr]),
+                test_tuner(0, &[ChannelType::Gr]),
+            ],
+            channels: vec![
+                test_channel("27", vec![3273601024]),
+                test_channel("26", vec![3273701032]),
+            ],
+            services: vec![test_service(3273701032), test_service(3273601024)],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_stores_sorted_inventory() {
+        let repository = MockInventoryRepository::new();
+        let usecase = InventorySyncUseCaseImpl::new(
+            MockMirakc {
+                inventory: test_inventory(),
+                ..Default::default()
+            },
+            repository.clone(),
+        );
+
+        let report = usecase.sync().await.unwrap();
+
+        assert_eq!(
+            report,
+            InventorySyncReport {
+                tuners: 2,
+                channels: 2,
+                services: 2,
+                updated: true,
+            }
+        );
+        let stored = repository.load().await.unwrap();
+        let indexes: Vec<u32> = stored.tuners.iter().map(|tuner| tuner.index).collect();
+        assert_eq!(indexes, [0, 1]);
+        let channels: Vec<&str> = stored.channels.iter().map(|c| c.channel.as_str()).collect();
+        assert_eq!(channels, ["26", "27"]);
+        let services: Vec<i64> = stored.services.iter().map(|s| s.id).collect();
+        assert_eq!(services, [3273601024, 3273701032]);
+    }
+
+    #[tokio::test]
+    async fn test_sync_replaces_only_changed_lists() {
+        let repository = MockInventoryRepository::new();
+        let mut inventory = test_inventory();
+        let usecase = InventorySyncUseCaseImpl::new(
+            MockMirakc {
+                inventory: inventory.clone(),
+                ..Default::default()
+            },
+            repository.clone(),
+        );
+        usecase.sync().await.unwrap();
+        assert!(!usecase.sync().await.unwrap().updated);
+
+        inventory.services.pop();
+        let usecase = InventorySyncUseCaseImpl::new(
+            MockMirakc {
+                inventory,
+                ..Default::default()
+            },
+            repository.clone(),
+        );
+        let report = usecase.sync().await.unwrap();
+
+        assert!(report.updated);
+        assert_eq!(report.services, 1);
+        assert_eq!(
+            repository.replaced(),
+            ["tuners", "channels", "services", "services"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_stores_nothing_on_failure() {
+        let repository = MockInventoryRepository::new();
+        let usecase = InventorySyncUseCaseImpl::new(
+            MockMirakc {
+                inventory: test_inventory(),
+                fail_services: true,
+            },
+            repository.clone(),
+        );
+
+        assert!(usecase.sync().await.is_err());
+        assert!(repository.replaced().is_empty());
+    }
+}
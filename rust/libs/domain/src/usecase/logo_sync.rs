@@ -0,0 +1,387 @@
+use crate::{
+    error::DomainError,
+    model::event::recording::service,
+    ports::{EventPublisher, ImageFetcher, ImageProcessor, InventoryRetriever},
+    repository::KvRepository,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, sync::Mutex};
+use tracing::{debug, info, warn};
+
+/// WebPに変換したサービスのロゴ画像
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceLogoData(pub Bytes);
+
+impl From<Bytes> for ServiceLogoData {
+    fn from(bytes: Bytes) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<ServiceLogoData> for Bytes {
+    fn from(val: ServiceLogoData) -> Self {
+        val.0
+    }
+}
+
+/// 変換後のロゴ画像の既定の幅（px）
+pub const DEFAULT_LOGO_IMAGE_WIDTH: u32 = 64;
+
+/// 全サービスのロゴ画像を同期した結果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogoSyncReport {
+    /// ロゴ画像が追加・変更されたか、前回通知できなかった分を通知し直したサービスの数
+    pub updated: usize,
+    /// ロゴ画像が変わっていなかったサービスの数
+    pub unchanged: usize,
+    /// ロゴ画像のないサービスの数
+    pub skipped: usize,
+    /// ロゴ画像の取得・変換・保存に失敗したサービスのID
+    pub failed: Vec<i64>,
+}
+
+#[async_trait]
+pub trait LogoSyncUseCase {
+    /// ロゴ画像のある全サービスのロゴを取得・変換し、変わっていたものだけを保存して通知する
+    async fn sync_all(&self) -> Result<LogoSyncReport, DomainError>;
+}
+
+pub struct LogoSyncUseCaseImpl<S, F, P, R, E>
+where
+    S: InventoryRetriever + Send + Sync,
+    F: ImageFetcher + Send + Sync,
+    P: ImageProcessor + Send + Sync,
+    R: KvRepository<String, ServiceLogoData> + Send + Sync,
+    E: EventPublisher<service::LogoChanged> + Send + Sync,
+{
+    services_retriever: S,
+    image_fetcher: F,
+    image_processor: P,
+    logo_repository: R,
+    event_publisher: E,
+    mirakc_url: String,
+    image_width: u32,
+    /// ロゴ画像を保存したが `service::LogoChanged` を発行できなかったサービスのID
+    unpublished: Mutex<BTreeSet<i64>>,
+}
+
+impl<S, F, P, R, E> LogoSyncUseCaseImpl<S, F, P, R, E>
+where
+    S: InventoryRetriever + Send + Sync,
+    F: ImageFetcher + Send + Sync,
+    P: ImageProcessor + Send + Sync,
+    R: KvRepository<String, ServiceLogoData> + Send + Sync,
+    E: EventPublisher<service::LogoChanged> + Send + Sync,
+{
+    pub fn new(
+        services_retriever: S,
+        image_fetcher: F,
+        image_processor: P,
+        logo_repository: R,
+        event_publisher: E,
+        mirakc_url: &str,
+    ) -> Self {
+        Self {
+            services_retriever,
+            image_fetcher,
+            image_processor,
+            logo_repository,
+            event_publisher,
+            mirakc_url: mirakc_url.to_string(),
+            image_width: DEFAULT_LOGO_IMAGE_WIDTH,
+            unpublished: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// 変換後の画像の幅（px）を指定する
+    pub fn with_image_width(mut self, image_width: u32) -> Self {
+        self.image_width = image_width;
+        self
+    }
+
+    fn logo_url(&self, service_id: i64) -> String {
+        format!(
+            "{}/api/services/{}/logo",
+            self.mirakc_url.trim_end_matches('/'),
+            service_id
+        )
+    }
+
+    /// 1つのサービスのロゴ画像を同期し、`service::LogoChanged` を発行したかどうかを返す
+    ///
+    /// 購読側が通知を受けて保存済みのロゴ画像を読めるように、保存してから通知する。
+    /// 保存後の通知に失敗したサービスは覚えておき、次の同期でロゴ画像が変わっていなくても通知し直す。
+    async fn sync_logo(&self, service_id: i64) -> Result<bool, DomainError> {
+        let image_data = self
+            .image_fetcher
+            .fetch_image(&self.logo_url(service_id))
+            .await
+            .map_err(|e| {
+                DomainError::ImageProcessingError(format!("ロゴ画像の取得に失敗: {}", e))
+            })?;
+        // 透過を保ったままWebPに変換する
+        let webp_data = self
+            .image_processor
+            .process_image(&image_data, self.image_width)
+            .await
+            .map_err(|e| {
+                DomainError::ImageProcessingError(format!("ロゴ画像の処理に失敗: {}", e))
+            })?;
+
+        let key = service_id.to_string();
+        let stored = self.logo_repository.get(key.clone()).await?;
+        if stored.is_some_and(|stored| stored.value.0 == webp_data) {
+            if !self.unpublished.lock().unwrap().contains(&service_id) {
+                debug!("サービスID {} のロゴ画像は変わっていません", service_id);
+                return Ok(false);
+            }
+        } else {
+            self.logo_repository
+                .put(key, &ServiceLogoData(Bytes::from(webp_data)))
+                .await?;
+            debug!("サービスID {} のロゴ画像を保存しました", service_id);
+        }
+
+        if let Err(e) = self
+            .event_publisher
+            .publish(&service::LogoChanged {
+                service_id,
+                mirakc_url: self.mirakc_url.clone(),
+            })
+            .await
+        {
+            self.unpublished.lock().unwrap().insert(service_id);
+            return Err(e);
+        }
+        self.unpublished.lock().unwrap().remove(&service_id);
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl<S, F, P, R, E> LogoSyncUseCase for LogoSyncUseCaseImpl<S, F, P, R, E>
+where
+    S: InventoryRetriever + Send + Sync,
+    F: ImageFetcher + Send + Sync,
+    P: ImageProcessor + Send + Sync,
+    R: KvRepository<String, ServiceLogoData> + Send + Sync,
+    E: EventPublisher<service::LogoChanged> + Send + Sync,
+{
+    async fn sync_all(&self) -> Result<LogoSyncReport, DomainError> {
+        let services = self.services_retriever.get_services().await?;
+
+        let mut report = LogoSyncReport::default();
+        for service in services {
+            if !service.has_logo_data {
+                report.skipped += 1;
+                continue;
+            }
+            match self.sync_logo(service.id).await {
+                Ok(true) => report.updated += 1,
+                Ok(false) => report.unchanged += 1,
+                Err(e) => {
+                    warn!("サービスID {} のロゴ画像の同期に失敗: {}", service.id, e);
+                    report.failed.push(service.id);
+                }
+            }
+        }
+
+        info!(
+            updated = report.updated,
+            unchanged = report.unchanged,
+            skipped = report.skipped,
+            failed = report.failed.len(),
+            "ロゴ画像の同期が完了しました"
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::inventory::{ChannelInfo, Service, Tuner};
+    use crate::ports::{ImageFetcherError, ImageProcessorError};
+    use crate::usecase::test_util::{MockEventPublisher, MockKvRepository, test_service};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    struct MockMirakc {
+        services: Vec<Service>,
+        /// URLごとのロゴ画像（ないURLは取得に失敗する）
+        logos: HashMap<String, Vec<u8>>,
+        requested: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockMirakc {
+        fn new(services: Vec<Service>, logos: &[(i64, &[u8])]) -> Self {
+            Self {
+                services,
+                logos: logos
+                    .iter()
+                    .map(|(id, data)| {
+                        (
+                            format!("http://mirakc:40772/api/services/{}/logo", id),
+                            data.to_vec(),
+                        )
+                    })
+                    .collect(),
+                requested: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl InventoryRetriever for MockMirakc {
+        async fn get_tuners(&self) -> Result<Vec<Tuner>, DomainError> {
+            Ok(vec![])
+        }
+
+        async fn get_channels(&self) -> Result<Vec<ChannelInfo>, DomainError> {
+            Ok(vec![])
+        }
+
+        async fn get_services(&self) -> Result<Vec<Service>, DomainError> {
+            Ok(self.services.clone())
+        }
+    }
+
+    #[async_trait]
+    impl ImageFetcher for MockMirakc {
+        async fn fetch_image(&self, url: &str) -> Result<Vec<u8>, ImageFetcherError> {
+            self.requested.lock().unwrap().push(url.to_string());
+            self.logos
+                .get(url)
+                .cloned()
+                .ok_or_else(|| ImageFetcherError::FetchError("404 Not Found".to_string()))
+        }
+    }
+
+    /// 画像の前に幅を付けるだけの変換
+    struct MockImageProcessor;
+
+    #[async_trait]
+    impl ImageProcessor for MockImageProcessor {
+        async fn process_image(
+            &self,
+            image_data: &[u8],
+            width: u32,
+        ) -> Result<Vec<u8>, ImageProcessorError> {
+            Ok([&[width as u8], image_data].concat())
+        }
+    }
+
+    fn service(id: i64, has_logo_data: bool) -> Service {
+        Service {
+            has_logo_data,
+            ..test_service(id)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_stores_changed_logos() {
+        let mirakc = MockMirakc::new(
+            vec![
+                service(1, true),
+                service(2, true),
+                service(3, false),
+                service(4, true),
+            ],
+            &[(1, b"same"), (2, b"new")],
+        );
+        let repository = MockKvRepository::<ServiceLogoData>::new();
+        repository
+            .put("1".to_string(), &ServiceLogoData(Bytes::from("\x20same")))
+            .await
+            .unwrap();
+        repository
+            .put("2".to_string(), &ServiceLogoData(Bytes::from("\x20old")))
+            .await
+            .unwrap();
+        let publisher = MockEventPublisher::<service::LogoChanged>::new();
+        let usecase = LogoSyncUseCaseImpl::new(
+            mirakc.clone(),
+            mirakc.clone(),
+            MockImageProcessor,
+            repository.clone(),
+            publisher.clone(),
+            "http://mirakc:40772/",
+        )
+        .with_image_width(32);
+
+        let report = usecase.sync_all().await.unwrap();
+
+        assert_eq!(
+            report,
+            LogoSyncReport {
+                updated: 1,
+                unchanged: 1,
+                skipped: 1,
+                failed: vec![4],
+            }
+        );
+        assert_eq!(
+            mirakc.requested.lock().unwrap().clone(),
+            [
+                "http://mirakc:40772/api/services/1/logo",
+                "http://mirakc:40772/api/services/2/logo",
+                "http://mirakc:40772/api/services/4/logo",
+            ]
+        );
+        let stored = repository.get("2".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.value.0, Bytes::from("\x20new"));
+        assert_eq!(stored.revision, 2);
+        assert_eq!(
+            repository
+                .get("1".to_string())
+                .await
+                .unwrap()
+                .unwrap()
+                .revision,
+            1
+        );
+        let published = publisher.published_events();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].service_id, 2);
+        assert_eq!(published[0].mirakc_url, "http://mirakc:40772/");
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_republishes_after_publish_failure() {
+        let mirakc = MockMirakc::new(vec![service(1, true)], &[(1, b"logo")]);
+        let repository = MockKvRepository::<ServiceLogoData>::new();
+        let publisher = MockEventPublisher::<service::LogoChanged>::failing_times(1);
+        let usecase = LogoSyncUseCaseImpl::new(
+            mirakc.clone(),
+            mirakc,
+            MockImageProcessor,
+            repository.clone(),
+            publisher.clone(),
+            "http://mirakc:40772",
+        );
+
+        let report = usecase.sync_all().await.unwrap();
+
+        assert_eq!(report.updated, 0);
+        assert_eq!(report.failed, [1]);
+        // 通知より先に保存する
+        let stored = repository.get("1".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.revision, 1);
+
+        // ロゴ画像は保存済みで変わっていないが、通知できていないので通知し直す
+        let report = usecase.sync_all().await.unwrap();
+
+        assert_eq!(report.updated, 1);
+        assert_eq!(publisher.published_events().len(), 1);
+        let stored = repository.get("1".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.revision, 1);
+
+        let report = usecase.sync_all().await.unwrap();
+
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(publisher.published_events().len(), 1);
+    }
+}
@@ -1,3 +1,32 @@
+mod auto_reservation;
+mod epg_retriever;
+mod epg_sync;
+mod inventory_sync;
+mod logo_sync;
+mod ogp_image_extractor;
 mod ogp_image_processor;
+mod ogp_url_extractor;
+mod onair_tracker;
+mod program_indexer;
+mod recording_archive;
+mod recording_conflict;
+mod recording_tracker;
+mod transcode;
 
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
+pub use auto_reservation::*;
+pub use epg_retriever::*;
+pub use epg_sync::*;
+pub use inventory_sync::*;
+pub use logo_sync::*;
+pub use ogp_image_extractor::*;
 pub use ogp_image_processor::*;
+pub use ogp_url_extractor::*;
+pub use onair_tracker::*;
+pub use program_indexer::*;
+pub use recording_archive::*;
+pub use recording_conflict::*;
+pub use recording_tracker::*;
+pub use transcode::*;
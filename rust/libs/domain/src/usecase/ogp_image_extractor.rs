@@ -0,0 +1,141 @@
+use crate::{
+    error::DomainError,
+    model::event::ogp,
+    ports::{EventPublisher, HtmlFetcher},
+    service::OgpImageParser,
+};
+use async_trait::async_trait;
+use tracing::debug;
+
+#[async_trait]
+pub trait OgpImageExtractorUseCase {
+    async fn extract_images(&self, request: &ogp::url::ExtractRequest) -> Result<(), DomainError>;
+}
+
+pub struct OgpImageExtractorUseCaseImpl<F, E>
+where
+    F: HtmlFetcher + Send + Sync,
+    E: EventPublisher<ogp::url::ImageRequest> + Send + Sync,
+{
+    html_fetcher: F,
+    event_publisher: E,
+}
+
+impl<F, E> OgpImageExtractorUseCaseImpl<F, E>
+where
+    F: HtmlFetcher + Send + Sync,
+    E: EventPublisher<ogp::url::ImageRequest> + Send + Sync,
+{
+    pub fn new(html_fetcher: F, event_publisher: E) -> Self {
+        Self {
+            html_fetcher,
+            event_publisher,
+        }
+    }
+}
+
+#[async_trait]
+impl<F, E> OgpImageExtractorUseCase for OgpImageExtractorUseCaseImpl<F, E>
+where
+    F: HtmlFetcher + Send + Sync,
+    E: EventPublisher<ogp::url::ImageRequest> + Send + Sync,
+{
+    async fn extract_images(&self, request: &ogp::url::ExtractRequest) -> Result<(), DomainError> {
+        let url = &request.url;
+        debug!("URL抽出イベントを受信: url={}", url);
+
+        let html_content = self
+            .html_fetcher
+            .fetch_html(url)
+            .await
+            .map_err(|e| DomainError::HtmlFetchError(e.to_string()))?;
+
+        let image_requests = OgpImageParser::create_image_requests(&html_content)
+            .map_err(|e| DomainError::HtmlParseError(e.to_string()))?;
+
+        for image_request in image_requests {
+            debug!("Found OGP image URL: {}", image_request.url);
+            self.event_publisher.publish(&image_request).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ports::HtmlFetcherError;
+    use crate::usecase::test_util::MockEventPublisher;
+
+    struct MockHtmlFetcher {
+        html: Option<String>,
+    }
+
+    #[async_trait]
+    impl HtmlFetcher for MockHtmlFetcher {
+        async fn fetch_html(&self, _url: &str) -> Result<String, HtmlFetcherError> {
+            self.html
+                .clone()
+                .ok_or_else(|| HtmlFetcherError::FetchError("接続に失敗".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extract_images() {
+        let html_content = r#"
+        <!DOCTYPE html>
+        <html>
+        <head>
+            <meta property="og:image" content="https://example.com/image1.jpg" />
+            <meta property="og:image" content="https://example.com/image2.png" />
+        </head>
+        <body>
+            <p>Test content</p>
+        </body>
+        </html>
+        "#;
+
+        let publisher = MockEventPublisher::<ogp::url::ImageRequest>::new();
+        let usecase = OgpImageExtractorUseCaseImpl::new(
+            MockHtmlFetcher {
+                html: Some(html_content.to_string()),
+            },
+            publisher.clone(),
+        );
+
+        usecase
+            .extract_images(&ogp::url::ExtractRequest {
+                url: "https://example.com".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let image_urls: Vec<String> = publisher
+            .published_events()
+            .into_iter()
+            .map(|req| req.url)
+            .collect();
+        assert_eq!(image_urls.len(), 2);
+        assert!(image_urls.contains(&"https://example.com/image1.jpg".to_string()));
+        assert!(image_urls.contains(&"https://example.com/image2.png".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_extract_images_fetch_error() {
+        let publisher = MockEventPublisher::<ogp::url::ImageRequest>::new();
+        let usecase =
+            OgpImageExtractorUseCaseImpl::new(MockHtmlFetcher { html: None }, publisher.clone());
+
+        let result = usecase
+            .extract_images(&ogp::url::ExtractRequest {
+                url: "https://example.com".to_string(),
+            })
+            .await;
+
+        match result {
+            Err(e @ DomainError::HtmlFetchError(_)) => assert!(e.is_retryable()),
+            other => panic!("予期しない結果: {:?}", other),
+        }
+        assert!(publisher.published_events().is_empty());
+    }
+}
@@ -24,6 +24,9 @@ impl From<WebpImageData> for Bytes {
     }
 }
 
+/// 変換後のOGP画像の既定の幅（px）
+pub const DEFAULT_OGP_IMAGE_WIDTH: u32 = 300;
+
 #[async_trait]
 pub trait OgpImageProcessorUseCase {
     async fn process_image_request(&self, request: &ImageRequest) -> Result<(), DomainError>;
@@ -38,6 +41,7 @@ where
     image_fetcher: F,
     image_processor: P,
     image_repository: R,
+    image_width: u32,
 }
 
 impl<F, P, R> OgpImageProcessorUseCaseImpl<F, P, R>
@@ -51,8 +55,15 @@ where
             image_fetcher,
             image_processor,
             image_repository,
+            image_width: DEFAULT_OGP_IMAGE_WIDTH,
         }
     }
+
+    /// 変換後の画像の幅（px）を指定する
+    pub fn with_image_width(mut self, image_width: u32) -> Self {
+        self.image_width = image_width;
+        self
+    }
 }
 
 #[async_trait]
@@ -79,7 +90,11 @@ where
             }
         };
 
-        let webp_data = match self.image_processor.process_image(&image_data, 300).await {
+        let webp_data = match self
+            .image_processor
+            .process_image(&image_data, self.image_width)
+            .await
+        {
             Ok(data) => data,
             Err(e) => {
                 error!("画像の処理に失敗しました: {}", e);
@@ -119,7 +134,6 @@ mod tests {
         repository::Versioned,
     };
     use async_trait::async_trait;
-    use bytes::Bytes;
     use std::{
         collections::HashMap,
         sync::{Arc, Mutex},
@@ -227,9 +241,7 @@ mod tests {
             let mut data = self.data.lock().unwrap();
             if let Some((current_revision, _)) = data.get(&key) {
                 if *current_revision != revision {
-                    return Err(DomainError::ProgramsStoreError(
-                        "リビジョンが一致しません".to_string(),
-                    ));
+                    return Err(DomainError::RevisionConflict(key));
                 }
             } else {
                 return Err(DomainError::ProgramsStoreError(
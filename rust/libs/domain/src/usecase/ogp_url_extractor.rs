@@ -0,0 +1,154 @@
+use crate::{
+    error::DomainError,
+    model::{
+        event::{ogp, recording::programs},
+        program::ProgramsData,
+        url_extractor::UrlExtractor,
+    },
+    ports::EventPublisher,
+    repository::KvRepository,
+};
+use async_trait::async_trait;
+use tracing::debug;
+
+#[async_trait]
+pub trait OgpUrlExtractorUseCase {
+    async fn extract_urls(&self, event: &programs::Updated) -> Result<(), DomainError>;
+}
+
+pub struct OgpUrlExtractorUseCaseImpl<R, E>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    E: EventPublisher<ogp::url::ExtractRequest> + Send + Sync,
+{
+    programs_repository: R,
+    event_publisher: E,
+    extractor: UrlExtractor,
+}
+
+impl<R, E> OgpUrlExtractorUseCaseImpl<R, E>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    E: EventPublisher<ogp::url::ExtractRequest> + Send + Sync,
+{
+    pub fn new(programs_repository: R, event_publisher: E) -> Self {
+        Self {
+            programs_repository,
+            event_publisher,
+            extractor: UrlExtractor::default(),
+        }
+    }
+
+    /// URLの抽出に使う [`UrlExtractor`] を差し替える
+    pub fn with_extractor(mut self, extractor: UrlExtractor) -> Self {
+        self.extractor = extractor;
+        self
+    }
+}
+
+#[async_trait]
+impl<R, E> OgpUrlExtractorUseCase for OgpUrlExtractorUseCaseImpl<R, E>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    E: EventPublisher<ogp::url::ExtractRequest> + Send + Sync,
+{
+    async fn extract_urls(&self, event: &programs::Updated) -> Result<(), DomainError> {
+        let service_id = event.service_id;
+        debug!("プログラム更新イベントを受信: service_id={}", service_id);
+
+        let Some(versioned) = self.programs_repository.get(service_id.to_string()).await? else {
+            debug!(
+                "プログラムデータが見つかりません: service_id={}",
+                service_id
+            );
+            return Ok(());
+        };
+
+        for program in &versioned.value.0 {
+            let Some(extended) = &program.extended else {
+                continue;
+            };
+            for value in extended.values() {
+                for url in self.extractor.extract_urls(value) {
+                    debug!("Found URL from program {}: {}", program.id, url);
+                    self.event_publisher
+                        .publish(&ogp::url::ExtractRequest { url })
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::test_util::{MockEventPublisher, MockKvRepository, test_program};
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_extract_urls() {
+        let service_id = 1;
+
+        let mut program = test_program(1, service_id, 1619856000000, "テスト番組");
+        program.description = Some("テスト説明".to_string());
+
+        let mut extended = BTreeMap::new();
+        extended.insert(
+            "description".to_string(),
+            "これはテスト説明です。https://example.com に詳細があります。".to_string(),
+        );
+        extended.insert(
+            "info".to_string(),
+            "詳細は http://example.com/long/path/to/url/index.html?param=value#section を参照してください。".to_string(),
+        );
+        program.extended = Some(extended);
+
+        let repository = MockKvRepository::<ProgramsData>::new();
+        repository
+            .put(service_id.to_string(), &ProgramsData(vec![program]))
+            .await
+            .unwrap();
+        let publisher = MockEventPublisher::<ogp::url::ExtractRequest>::new();
+        let usecase = OgpUrlExtractorUseCaseImpl::new(repository, publisher.clone());
+
+        usecase
+            .extract_urls(&programs::Updated {
+                service_id,
+                mirakc_url: "http://mirakc:40772".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let urls: Vec<String> = publisher
+            .published_events()
+            .into_iter()
+            .map(|e| e.url)
+            .collect();
+        assert_eq!(urls.len(), 2);
+        assert!(urls.contains(&"https://example.com".to_string()));
+        assert!(urls.contains(
+            &"http://example.com/long/path/to/url/index.html?param=value#section".to_string()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_extract_urls_without_programs() {
+        let publisher = MockEventPublisher::<ogp::url::ExtractRequest>::new();
+        let usecase = OgpUrlExtractorUseCaseImpl::new(
+            MockKvRepository::<ProgramsData>::new(),
+            publisher.clone(),
+        );
+
+        usecase
+            .extract_urls(&programs::Updated {
+                service_id: 1,
+                mirakc_url: "http://mirakc:40772".to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert!(publisher.published_events().is_empty());
+    }
+}
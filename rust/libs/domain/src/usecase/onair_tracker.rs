@@ -0,0 +1,188 @@
+use crate::{
+    error::DomainError,
+    model::{event::recording::onair, onair::OnairProgram},
+    ports::OnairProgramRetriever,
+    repository::KvRepository,
+};
+use async_trait::async_trait;
+use tracing::{debug, info};
+
+#[async_trait]
+pub trait OnairTrackerUseCase {
+    /// 放送中の番組が変わったサービスの、放送中の番組と次の番組を保存する
+    async fn track(&self, event: &onair::ProgramChanged) -> Result<(), DomainError>;
+
+    /// 全サービスの放送中の番組を取得し、変わっていたサービスの数を返す
+    ///
+    /// SSEは変化しか通知しないので、起動時にこれで揃えておく。
+    async fn sync_all(&self) -> Result<usize, DomainError>;
+}
+
+pub struct OnairTrackerUseCaseImpl<P, R>
+where
+    P: OnairProgramRetriever + Send + Sync,
+    R: KvRepository<String, OnairProgram> + Send + Sync,
+{
+    onair_retriever: P,
+    onair_repository: R,
+}
+
+impl<P, R> OnairTrackerUseCaseImpl<P, R>
+where
+    P: OnairProgramRetriever + Send + Sync,
+    R: KvRepository<String, OnairProgram> + Send + Sync,
+{
+    pub fn new(onair_retriever: P, onair_repository: R) -> Self {
+        Self {
+            onair_retriever,
+            onair_repository,
+        }
+    }
+
+    /// 保存済みの内容と異なる場合だけ保存し、保存したかどうかを返す
+    async fn store(&self, onair: &OnairProgram) -> Result<bool, DomainError> {
+        let key = onair.service_id.to_string();
+        let stored = self.onair_repository.get(key.clone()).await?;
+        if stored.is_some_and(|stored| &stored.value == onair) {
+            return Ok(false);
+        }
+        self.onair_repository.put(key, onair).await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl<P, R> OnairTrackerUseCase for OnairTrackerUseCaseImpl<P, R>
+where
+    P: OnairProgramRetriever + Send + Sync,
+    R: KvRepository<String, OnairProgram> + Send + Sync,
+{
+    async fn track(&self, event: &onair::ProgramChanged) -> Result<(), DomainError> {
+        let onair = self
+            .onair_retriever
+            .get_onair_program(event.service_id)
+            .await?;
+        if self.store(&onair).await? {
+            debug!(
+                "サービスID {} の放送中の番組を更新しました: current={:?}, next={:?}",
+                event.service_id,
+                onair.current.as_ref().map(|program| program.id),
+                onair.next.as_ref().map(|program| program.id)
+            );
+        }
+        Ok(())
+    }
+
+    async fn sync_all(&self) -> Result<usize, DomainError> {
+        let programs = self.onair_retriever.get_onair_programs().await?;
+        let mut updated = 0;
+        for onair in &programs {
+            if self.store(onair).await? {
+                updated += 1;
+            }
+        }
+        info!(
+            services = programs.len(),
+            updated, "放送中の番組の同期が完了しました"
+        );
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::test_util::{MockKvRepository, test_program};
+    use std::collections::BTreeMap;
+
+    #[derive(Clone, Default)]
+    struct MockMirakc {
+        onair: BTreeMap<i64, OnairProgram>,
+    }
+
+    #[async_trait]
+    impl OnairProgramRetriever for MockMirakc {
+        async fn get_onair_program(&self, service_id: i64) -> Result<OnairProgram, DomainError> {
+            self.onair
+                .get(&service_id)
+                .cloned()
+                .ok_or(DomainError::ServiceNotFound(service_id))
+        }
+
+        async fn get_onair_programs(&self) -> Result<Vec<OnairProgram>, DomainError> {
+            Ok(self.onair.values().cloned().collect())
+        }
+    }
+
+    fn onair(service_id: i64, current: Option<&str>, next: Option<&str>) -> OnairProgram {
+        OnairProgram {
+            service_id,
+            current: current.map(|name| test_program(service_id * 100000 + 1, service_id, 0, name)),
+            next: next.map(|name| test_program(service_id * 100000 + 2, service_id, 1800000, name)),
+        }
+    }
+
+    fn changed(service_id: i64) -> onair::ProgramChanged {
+        onair::ProgramChanged {
+            service_id,
+            mirakc_url: "http://mirakc:40772".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_track_stores_current_and_next() {
+        let mut mirakc = MockMirakc::default();
+        mirakc
+            .onair
+            .insert(1, onair(1, Some("ニュース"), Some("天気予報")));
+        let repository = MockKvRepository::<OnairProgram>::new();
+        let usecase = OnairTrackerUseCaseImpl::new(mirakc.clone(), repository.clone());
+
+        usecase.track(&changed(1)).await.unwrap();
+        usecase.track(&changed(1)).await.unwrap();
+
+        let stored = repository.get("1".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.value, mirakc.onair[&1]);
+        // 変わっていなければ保存し直さない
+        assert_eq!(stored.revision, 1);
+
+        // 延長で放送中の番組だけが残った
+        mirakc.onair.insert(1, onair(1, Some("ニュース"), None));
+        let usecase = OnairTrackerUseCaseImpl::new(mirakc, repository.clone());
+        usecase.track(&changed(1)).await.unwrap();
+
+        let stored = repository.get("1".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.revision, 2);
+        assert!(stored.value.next.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_track_unknown_service() {
+        let repository = MockKvRepository::<OnairProgram>::new();
+        let usecase = OnairTrackerUseCaseImpl::new(MockMirakc::default(), repository.clone());
+
+        let result = usecase.track(&changed(1)).await;
+
+        assert!(matches!(result, Err(DomainError::ServiceNotFound(1))));
+        assert!(repository.get("1".to_string()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sync_all_counts_changed_services() {
+        let mut mirakc = MockMirakc::default();
+        mirakc.onair.insert(1, onair(1, Some("ニュース"), None));
+        mirakc.onair.insert(2, onair(2, None, Some("映画")));
+        let repository = MockKvRepository::<OnairProgram>::new();
+        repository
+            .put("1".to_string(), &onair(1, Some("ニュース"), None))
+            .await
+            .unwrap();
+        let usecase = OnairTrackerUseCaseImpl::new(mirakc, repository.clone());
+
+        assert_eq!(usecase.sync_all().await.unwrap(), 1);
+
+        let stored = repository.get("2".to_string()).await.unwrap().unwrap();
+        assert!(stored.value.current.is_none());
+        assert_eq!(stored.value.next.unwrap().name.as_deref(), Some("映画"));
+    }
+}
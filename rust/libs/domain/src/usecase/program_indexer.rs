@@ -0,0 +1,211 @@
+use crate::{
+    error::DomainError,
+    model::{event::recording::programs, program::ProgramsData, search::ProgramDocument},
+    ports::SearchIndex,
+    repository::KvRepository,
+};
+use async_trait::async_trait;
+use tracing::debug;
+
+#[async_trait]
+pub trait ProgramIndexerUseCase {
+    /// サービスの番組表を検索インデックスに反映する
+    ///
+    /// 番組表に残っている番組の文書を追加・更新し、なくなった番組の文書を削除する。
+    async fn index_programs(&self, event: &programs::Updated) -> Result<(), DomainError>;
+}
+
+pub struct ProgramIndexerUseCaseImpl<R, S>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    S: SearchIndex + Send + Sync,
+{
+    programs_repository: R,
+    search_index: S,
+}
+
+impl<R, S> ProgramIndexerUseCaseImpl<R, S>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    S: SearchIndex + Send + Sync,
+{
+    pub fn new(programs_repository: R, search_index: S) -> Self {
+        Self {
+            programs_repository,
+            search_index,
+        }
+    }
+}
+
+#[async_trait]
+impl<R, S> ProgramIndexerUseCase for ProgramIndexerUseCaseImpl<R, S>
+where
+    R: KvRepository<String, ProgramsData> + Send + Sync,
+    S: SearchIndex + Send + Sync,
+{
+    async fn index_programs(&self, event: &programs::Updated) -> Result<(), DomainError> {
+        let service_id = event.service_id;
+        // 番組表がなければサービスの文書をすべて削除する
+        let programs = self
+            .programs_repository
+            .get(service_id.to_string())
+            .await?
+            .map(|versioned| versioned.value.0)
+            .unwrap_or_default();
+
+        let documents: Vec<ProgramDocument> = programs.iter().map(ProgramDocument::from).collect();
+        if !documents.is_empty() {
+            self.search_index.upsert(&documents).await?;
+        }
+        let program_ids: Vec<i64> = programs.iter().map(|program| program.id).collect();
+        self.search_index
+            .delete_stale(service_id, &program_ids)
+            .await?;
+        debug!(
+            "サービスID {} の番組 {} 件を検索インデックスに反映しました",
+            service_id,
+            documents.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::program::Program;
+    use crate::model::search::{SearchQuery, SearchResults};
+    use crate::usecase::test_util::{MockKvRepository, test_program};
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    /// 番組IDをキーに文書を保持する `SearchIndex` のモック
+    #[derive(Clone, Default)]
+    struct MockSearchIndex {
+        documents: Arc<Mutex<BTreeMap<i64, ProgramDocument>>>,
+    }
+
+    #[async_trait]
+    impl SearchIndex for MockSearchIndex {
+        async fn upsert(&self, documents: &[ProgramDocument]) -> Result<(), DomainError> {
+            let mut stored = self.documents.lock().unwrap();
+            for document in documents {
+                stored.insert(document.id, document.clone());
+            }
+            Ok(())
+        }
+
+        async fn delete_stale(
+            &self,
+            service_id: i64,
+            program_ids: &[i64],
+        ) -> Result<(), DomainError> {
+            self.documents.lock().unwrap().retain(|id, document| {
+                document.service_id != service_id || program_ids.contains(id)
+            });
+            Ok(())
+        }
+
+        /// サービスで絞り込み、番組名に検索語を含む文書を番組IDの順に返す
+        async fn search(&self, query: &SearchQuery) -> Result<SearchResults, DomainError> {
+            let hits: Vec<ProgramDocument> = self
+                .documents
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|document| {
+                    query.service_ids.is_empty() || query.service_ids.contains(&document.service_id)
+                })
+                .filter(|document| document.name.contains(&query.text))
+                .cloned()
+                .collect();
+            Ok(SearchResults {
+                total: hits.len(),
+                hits: hits
+                    .into_iter()
+                    .skip(query.offset)
+                    .take(query.limit)
+                    .collect(),
+            })
+        }
+    }
+
+    const NHK: i64 = 3273601024;
+
+    fn program(event_id: i64, name: &str) -> Program {
+        test_program(NHK * 100000 + event_id, NHK, 1619856000000, name)
+    }
+
+    fn updated(service_id: i64) -> programs::Updated {
+        programs::Updated {
+            service_id,
+            mirakc_url: "http://tuner:40772".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_programs_upserts_and_deletes() {
+        let repository = MockKvRepository::<ProgramsData>::new();
+        let search_index = MockSearchIndex::default();
+        // 他のサービスの文書は残す
+        search_index
+            .upsert(&[ProgramDocument {
+                id: 3273701032 * 100000 + 1,
+                service_id: 3273701032,
+                ..ProgramDocument::from(&program(1, "他局の番組"))
+            }])
+            .await
+            .unwrap();
+        let usecase = ProgramIndexerUseCaseImpl::new(repository.clone(), search_index.clone());
+
+        repository
+            .put(
+                "3273601024".to_string(),
+                &ProgramsData(vec![program(1, "アニメ"), program(2, "映画")]),
+            )
+            .await
+            .unwrap();
+        usecase.index_programs(&updated(3273601024)).await.unwrap();
+        repository
+            .put(
+                "3273601024".to_string(),
+                &ProgramsData(vec![program(2, "映画（字幕版）")]),
+            )
+            .await
+            .unwrap();
+        usecase.index_programs(&updated(3273601024)).await.unwrap();
+
+        let results = search_index.search(&SearchQuery::new("")).await.unwrap();
+        let names: Vec<(i64, &str)> = results
+            .hits
+            .iter()
+            .map(|document| (document.service_id, document.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [(3273601024, "映画（字幕版）"), (3273701032, "他局の番組")]
+        );
+        let results = search_index
+            .search(&SearchQuery::new("映画").with_service(3273601024))
+            .await
+            .unwrap();
+        assert_eq!(results.total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_index_programs_without_programs_data() {
+        let search_index = MockSearchIndex::default();
+        search_index
+            .upsert(&[ProgramDocument::from(&program(1, "アニメ"))])
+            .await
+            .unwrap();
+        let usecase = ProgramIndexerUseCaseImpl::new(
+            MockKvRepository::<ProgramsData>::new(),
+            search_index.clone(),
+        );
+
+        usecase.index_programs(&updated(3273601024)).await.unwrap();
+
+        assert!(search_index.documents.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use crate::{
+    error::DomainError,
+    model::{
+        event::recording::transcode,
+        object::{ObjectRef, PutOptions, content_type_for_extension},
+    },
+    ports::ObjectStore,
+    repository::KvRepository,
+};
+use async_trait::async_trait;
+use tracing::{debug, info};
+
+#[async_trait]
+pub trait RecordingArchiveUseCase {
+    /// トランスコードした録画をオブジェクトストアに保存し、参照を録画IDをキーとしてKVに保存する
+    async fn on_transcoded(&self, event: &transcode::Completed) -> Result<(), DomainError>;
+}
+
+pub struct RecordingArchiveUseCaseImpl<O, R>
+where
+    O: ObjectStore + Send + Sync,
+    R: KvRepository<String, ObjectRef> + Send + Sync,
+{
+    object_store: O,
+    artifact_repository: R,
+}
+
+impl<O, R> RecordingArchiveUseCaseImpl<O, R>
+where
+    O: ObjectStore + Send + Sync,
+    R: KvRepository<String, ObjectRef> + Send + Sync,
+{
+    pub fn new(object_store: O, artifact_repository: R) -> Self {
+        Self {
+            object_store,
+            artifact_repository,
+        }
+    }
+}
+
+/// 録画ファイルを保存するオブジェクトのキー
+pub fn recording_artifact_key(record_id: &str, file_name: &str) -> String {
+    format!("recordings/{}/{}", record_id, file_name)
+}
+
+#[async_trait]
+impl<O, R> RecordingArchiveUseCase for RecordingArchiveUseCaseImpl<O, R>
+where
+    O: ObjectStore + Send + Sync,
+    R: KvRepository<String, ObjectRef> + Send + Sync,
+{
+    async fn on_transcoded(&self, event: &transcode::Completed) -> Result<(), DomainError> {
+        let path = Path::new(&event.output_path);
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| {
+                DomainError::ObjectStoreError(format!(
+                    "出力ファイルのパスが正しくありません: {}",
+                    event.output_path
+                ))
+            })?;
+        let key = recording_artifact_key(&event.record_id, &file_name);
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let options = PutOptions::new(content_type_for_extension(&extension))
+            .with_metadata("record_id", &event.record_id)
+            .with_metadata("program_id", event.program_id)
+            .with_metadata("profile", &event.profile);
+
+        // 再配信されたイベントでは同じファイルを送り直さない
+        let meta = match self.object_store.head(&key).await? {
+            Some(meta) if meta.size == event.output_length => {
+                debug!("録画 {} は保存済みです: {}", event.record_id, key);
+                meta
+            }
+            _ => self.object_store.put_file(&key, path, &options).await?,
+        };
+
+        self.artifact_repository
+            .put(event.record_id.clone(), &ObjectRef::new(&meta, &options))
+            .await?;
+        info!(
+            "録画 {} をオブジェクトストアに保存しました: {}（{}バイト）",
+            event.record_id, key, meta.size
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::test_util::{MockKvRepository, MockObjectStore};
+    use bytes::Bytes;
+
+    fn completed(output_path: &Path, output_length: u64) -> transcode::Completed {
+        transcode::Completed {
+            record_id: "0000000000000001".to_string(),
+            program_id: 327360102412345,
+            profile: "default".to_string(),
+            output_path: output_path.to_string_lossy().into_owned(),
+            output_length,
+            mirakc_url: "http://tuner:40772".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_on_transcoded_stores_file_and_ref() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("movie.mp4");
+        std::fs::write(&path, b"mp4 data").unwrap();
+        let object_store = MockObjectStore::new();
+        let artifacts = MockKvRepository::new();
+        let usecase = RecordingArchiveUseCaseImpl::new(object_store.clone(), artifacts.clone());
+
+        usecase.on_transcoded(&completed(&path, 8)).await.unwrap();
+
+        let key = "recordings/0000000000000001/movie.mp4";
+        let object = object_store.get(key).await.unwrap().unwrap();
+        assert_eq!(object.data, Bytes::from_static(b"mp4 data"));
+        assert_eq!(object.meta.content_type.as_deref(), Some("video/mp4"));
+        assert_eq!(
+            object.meta.metadata.get("program_id").map(String::as_str),
+            Some("327360102412345")
+        );
+        let object_ref = artifacts
+            .get("0000000000000001".to_string())
+            .await
+            .unwrap()
+            .unwrap()
+            .value;
+        assert_eq!(
+            object_ref,
+            ObjectRef {
+                key: key.to_string(),
+                content_type: "video/mp4".to_string(),
+                size: 8,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_on_transcoded_skips_uploaded_file() {
+        let object_store = MockObjectStore::new();
+        let artifacts = MockKvRepository::new();
+        let key = "recordings/0000000000000001/movie.mkv";
+        object_store
+            .put(
+                key,
+                Bytes::from_static(b"mkv"),
+                &PutOptions::new("video/x-matroska"),
+            )
+            .await
+            .unwrap();
+        let usecase = RecordingArchiveUseCaseImpl::new(object_store.clone(), artifacts.clone());
+
+        // ファイルはもう消えているが、保存済みなので読み込まない
+        usecase
+            .on_transcoded(&completed(Path::new("/nonexistent/movie.mkv"), 3))
+            .await
+            .unwrap();
+
+        let object_ref = artifacts
+            .get("0000000000000001".to_string())
+            .await
+            .unwrap()
+            .unwrap()
+            .value;
+        assert_eq!(object_ref.key, key);
+        assert_eq!(object_ref.content_type, "video/x-matroska");
+    }
+
+    #[tokio::test]
+    async fn test_on_transcoded_missing_file() {
+        let usecase =
+            RecordingArchiveUseCaseImpl::new(MockObjectStore::new(), MockKvRepository::new());
+
+        let result = usecase
+            .on_transcoded(&completed(Path::new("/nonexistent/movie.mp4"), 8))
+            .await;
+
+        assert!(matches!(result, Err(DomainError::ObjectStoreError(_))));
+    }
+}
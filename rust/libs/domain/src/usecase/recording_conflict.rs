@@ -0,0 +1,246 @@
+use crate::{
+    error::DomainError,
+    model::{
+        event::recording::schedule,
+        recording::{RecordingSchedule, ScheduleState, ScheduledRecording},
+    },
+    ports::{EventPublisher, RecordingScheduler},
+    repository::InventoryRepository,
+    service::RecordingConflictDetector,
+};
+use async_trait::async_trait;
+use tracing::{debug, info, warn};
+
+#[async_trait]
+pub trait RecordingConflictUseCase {
+    /// mirakcの録画予約とチューナーの一覧から競合を調べ、見つかった競合を `recording::schedule::Conflict` として発行する
+    ///
+    /// 予約が増えるたびに調べ直すので、同じ競合が再び発行されうる。
+    async fn check_conflicts(&self, mirakc_url: &str) -> Result<(), DomainError>;
+}
+
+pub struct RecordingConflictUseCaseImpl<S, I, E>
+where
+    S: RecordingScheduler + Send + Sync,
+    I: InventoryRepository + Send + Sync,
+    E: EventPublisher<schedule::Conflict> + Send + Sync,
+{
+    scheduler: S,
+    inventory_repository: I,
+    detector: RecordingConflictDetector<E>,
+}
+
+impl<S, I, E> RecordingConflictUseCaseImpl<S, I, E>
+where
+    S: RecordingScheduler + Send + Sync,
+    I: InventoryRepository + Send + Sync,
+    E: EventPublisher<schedule::Conflict> + Send + Sync,
+{
+    pub fn new(scheduler: S, inventory_repository: I, event_publisher: E) -> Self {
+        Self {
+            scheduler,
+            inventory_repository,
+            detector: RecordingConflictDetector::new(event_publisher),
+        }
+    }
+}
+
+/// これからチューナーを使う予約かどうか（終わった予約と失敗した予約は数えない）
+fn is_pending(schedule: &RecordingSchedule) -> bool {
+    !matches!(
+        schedule.state,
+        ScheduleState::Finished | ScheduleState::Failed
+    )
+}
+
+#[async_trait]
+impl<S, I, E> RecordingConflictUseCase for RecordingConflictUseCaseImpl<S, I, E>
+where
+    S: RecordingScheduler + Send + Sync,
+    I: InventoryRepository + Send + Sync,
+    E: EventPublisher<schedule::Conflict> + Send + Sync,
+{
+    async fn check_conflicts(&self, mirakc_url: &str) -> Result<(), DomainError> {
+        let schedules = self.scheduler.list_schedules().await?;
+        let inventory = self.inventory_repository.load().await?;
+
+        // Mirakurunの番組IDはサービスID * 100000 + イベントIDなので、サービスは番組IDから求める
+        let recordings: Vec<ScheduledRecording> = schedules
+            .iter()
+            .filter(|schedule| is_pending(schedule))
+            .filter_map(|schedule| {
+                let service_id = schedule.program_id / 100000;
+                let Some(service) = inventory
+                    .services
+                    .iter()
+                    .find(|service| service.id == service_id)
+                else {
+                    warn!(
+                        "録画予約のサービスが見つからないので競合を調べません: program_id={}, service_id={}",
+                        schedule.program_id, service_id
+                    );
+                    return None;
+                };
+                Some(ScheduledRecording {
+                    program_id: schedule.program_id,
+                    start_at: schedule.start_at,
+                    end_at: schedule.end_at,
+                    channel_type: service.channel_type,
+                    channel: service.channel.clone(),
+                    priority: schedule.priority,
+                })
+            })
+            .collect();
+
+        let conflicts = self
+            .detector
+            .detect(&inventory.tuners, &recordings, mirakc_url)
+            .await?;
+        if conflicts.is_empty() {
+            debug!("{} 件の録画予約に競合はありません", recordings.len());
+        } else {
+            info!(
+                "{} 件の録画予約に {} 件の競合が見つかりました",
+                recordings.len(),
+                conflicts.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::inventory::{ChannelType, Service};
+    use crate::model::recording::{ScheduleOutcome, ScheduleRequest};
+    use crate::usecase::test_util::{
+        MockEventPublisher, MockInventoryRepository, test_service, test_tuner,
+    };
+
+    const START_AT: i64 = 1619866800000;
+    const MINUTE: i64 = 60 * 1000;
+
+    /// 決まった録画予約の一覧を返すモック
+    struct MockScheduler(Vec<RecordingSchedule>);
+
+    #[async_trait]
+    impl RecordingScheduler for MockScheduler {
+        async fn create_schedule(
+            &self,
+            _request: &ScheduleRequest,
+        ) -> Result<ScheduleOutcome, DomainError> {
+            Ok(ScheduleOutcome::Created)
+        }
+
+        async fn list_schedules(&self) -> Result<Vec<RecordingSchedule>, DomainError> {
+            Ok(self.0.clone())
+        }
+
+        async fn get_schedule(
+            &self,
+            program_id: i64,
+        ) -> Result<Option<RecordingSchedule>, DomainError> {
+            Ok(self
+                .0
+                .iter()
+                .find(|schedule| schedule.program_id == program_id)
+                .cloned())
+        }
+
+        async fn delete_schedule(&self, program_id: i64) -> Result<bool, DomainError> {
+            Ok(self
+                .0
+                .iter()
+                .any(|schedule| schedule.program_id == program_id))
+        }
+
+        async fn delete_schedules_by_tag(&self, _tag: &str) -> Result<(), DomainError> {
+            Ok(())
+        }
+    }
+
+    fn schedule(
+        program_id: i64,
+        (start, end): (i64, i64),
+        state: ScheduleState,
+        priority: i32,
+    ) -> RecordingSchedule {
+        RecordingSchedule {
+            program_id,
+            program_name: None,
+            start_at: START_AT + start * MINUTE,
+            end_at: START_AT + end * MINUTE,
+            state,
+            priority,
+            tags: vec![],
+            failed_reason: None,
+        }
+    }
+
+    fn service(id: i64, channel: &str) -> Service {
+        Service {
+            channel: channel.to_string(),
+            ..test_service(id)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_conflicts_publishes_conflict() {
+        let inventory_repository = MockInventoryRepository::new();
+        {
+            let mut inventory = inventory_repository.inventory.lock().unwrap();
+            inventory.tuners = vec![test_tuner(0, &[ChannelType::Gr])];
+            inventory.services = vec![service(3273601024, "27"), service(3273701032, "26")];
+        }
+        let publisher = MockEventPublisher::<schedule::Conflict>::new();
+        let usecase = RecordingConflictUseCaseImpl::new(
+            MockScheduler(vec![
+                schedule(327360102400001, (0, 60), ScheduleState::Scheduled, 0),
+                schedule(327370103200001, (30, 90), ScheduleState::Scheduled, 5),
+                // 終わった予約はチューナーを使わない
+                schedule(327370103200002, (0, 30), ScheduleState::Finished, 10),
+                // サービスの分からない予約は数えない
+                schedule(999999999900001, (0, 90), ScheduleState::Scheduled, 10),
+            ]),
+            inventory_repository,
+            publisher.clone(),
+        );
+
+        usecase.check_conflicts("http://example.com").await.unwrap();
+
+        let published = publisher.published_events();
+        assert_eq!(published.len(), 1);
+        assert_eq!(published[0].start_at, START_AT + 30 * MINUTE);
+        assert_eq!(published[0].end_at, START_AT + 60 * MINUTE);
+        assert_eq!(published[0].winner_program_ids, [327370103200001]);
+        assert_eq!(published[0].loser_program_ids, [327360102400001]);
+        assert_eq!(published[0].mirakc_url, "http://example.com");
+    }
+
+    #[tokio::test]
+    async fn test_check_conflicts_without_conflict() {
+        let inventory_repository = MockInventoryRepository::new();
+        {
+            let mut inventory = inventory_repository.inventory.lock().unwrap();
+            inventory.tuners = vec![
+                test_tuner(0, &[ChannelType::Gr]),
+                test_tuner(1, &[ChannelType::Gr]),
+            ];
+            inventory.services = vec![service(3273601024, "27"), service(3273701032, "26")];
+        }
+        let publisher = MockEventPublisher::<schedule::Conflict>::new();
+        let usecase = RecordingConflictUseCaseImpl::new(
+            MockScheduler(vec![
+                schedule(327360102400001, (0, 60), ScheduleState::Recording, 0),
+                schedule(327370103200001, (30, 90), ScheduleState::Scheduled, 0),
+            ]),
+            inventory_repository,
+            publisher.clone(),
+        );
+
+        usecase.check_conflicts("http://example.com").await.unwrap();
+
+        assert!(publisher.published_events().is_empty());
+    }
+}
@@ -0,0 +1,489 @@
+use crate::{
+    error::DomainError,
+    model::{
+        event::recording::{record, reservation, schedule},
+        recording::Recording,
+    },
+    ports::RecordsManager,
+    repository::KvRepository,
+};
+use async_trait::async_trait;
+use tracing::{debug, warn};
+
+use super::auto_reservation::current_time_millis;
+
+/// 他のイベントの処理と更新がぶつかったときに読み直す回数
+const MAX_UPDATE_ATTEMPTS: usize = 5;
+
+#[async_trait]
+pub trait RecordingTrackerUseCase {
+    /// ルールによる予約から録画の追跡を始める
+    async fn on_reserved(&self, event: &reservation::Matched) -> Result<(), DomainError>;
+
+    async fn on_started(&self, event: &schedule::Started) -> Result<(), DomainError>;
+
+    async fn on_stopped(&self, event: &schedule::Stopped) -> Result<(), DomainError>;
+
+    async fn on_failed(&self, event: &schedule::Failed) -> Result<(), DomainError>;
+
+    async fn on_rescheduled(&self, event: &schedule::Rescheduled) -> Result<(), DomainError>;
+
+    /// 保存された録画の番組・ファイル・状態をmirakcから取得して反映する
+    async fn on_record_saved(&self, event: &record::Saved) -> Result<(), DomainError>;
+
+    async fn on_record_broken(&self, event: &record::Broken) -> Result<(), DomainError>;
+}
+
+pub struct RecordingTrackerUseCaseImpl<M, R>
+where
+    M: RecordsManager + Send + Sync,
+    R: KvRepository<String, Recording> + Send + Sync,
+{
+    records_manager: M,
+    recording_repository: R,
+    clock: fn() -> i64,
+}
+
+impl<M, R> RecordingTrackerUseCaseImpl<M, R>
+where
+    M: RecordsManager + Send + Sync,
+    R: KvRepository<String, Recording> + Send + Sync,
+{
+    pub fn new(records_manager: M, recording_repository: R) -> Self {
+        Self {
+            records_manager,
+            recording_repository,
+            clock: current_time_millis,
+        }
+    }
+
+    /// 現在時刻（UNIX時間のミリ秒）の取得方法を差し替える
+    pub fn with_clock(mut self, clock: fn() -> i64) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 番組の録画を読み込んで `apply` で変更し、変化があればリビジョンを確かめて保存する
+    ///
+    /// 保存までの間に他のイベントで更新されていた場合は、読み直してやり直す。
+    /// 追跡していない録画（mirakcで直接予約されたものなど）は新しく作る。
+    async fn modify<F>(&self, program_id: i64, apply: F) -> Result<(), DomainError>
+    where
+        F: Fn(&mut Recording, i64) -> bool + Send + Sync,
+    {
+        let key = program_id.to_string();
+        let mut attempt = 1;
+        loop {
+            let now = (self.clock)();
+            // 追跡していない録画はリビジョン0で作成し、先に作られていたら読み直す
+            let (mut recording, revision, created) =
+                match self.recording_repository.get(key.clone()).await? {
+                    Some(versioned) => (versioned.value, versioned.revision, false),
+                    None => (Recording::new(program_id, now), 0, true),
+                };
+            if !apply(&mut recording, now) && !created {
+                debug!("番組ID {} の録画は変わっていません", program_id);
+                return Ok(());
+            }
+            match self
+                .recording_repository
+                .update(key.clone(), &recording, revision)
+                .await
+            {
+                Ok(()) => {
+                    if created {
+                        debug!(
+                            "番組ID {} の録画の追跡を始めます: {:?}",
+                            program_id, recording.state
+                        );
+                    } else {
+                        debug!(
+                            "番組ID {} の録画を更新しました: {:?}",
+                            program_id, recording.state
+                        );
+                    }
+                    return Ok(());
+                }
+                Err(DomainError::RevisionConflict(e)) if attempt < MAX_UPDATE_ATTEMPTS => {
+                    debug!(
+                        "番組ID {} の録画の更新がぶつかったので読み直します: {}",
+                        program_id, e
+                    );
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<M, R> RecordingTrackerUseCase for RecordingTrackerUseCaseImpl<M, R>
+where
+    M: RecordsManager + Send + Sync,
+    R: KvRepository<String, Recording> + Send + Sync,
+{
+    async fn on_reserved(&self, event: &reservation::Matched) -> Result<(), DomainError> {
+        // 作るだけで、追跡中の録画は変えない
+        self.modify(event.program_id, |_, _| false).await
+    }
+
+    async fn on_started(&self, event: &schedule::Started) -> Result<(), DomainError> {
+        self.modify(event.program_id, |recording, now| recording.start(now))
+            .await
+    }
+
+    async fn on_stopped(&self, event: &schedule::Stopped) -> Result<(), DomainError> {
+        self.modify(event.program_id, |recording, now| recording.stop(now))
+            .await
+    }
+
+    async fn on_failed(&self, event: &schedule::Failed) -> Result<(), DomainError> {
+        self.modify(event.program_id, |recording, now| {
+            recording.fail(event.reason.clone(), now)
+        })
+        .await
+    }
+
+    async fn on_rescheduled(&self, event: &schedule::Rescheduled) -> Result<(), DomainError> {
+        self.modify(event.program_id, |recording, now| recording.reschedule(now))
+            .await
+    }
+
+    async fn on_record_saved(&self, event: &record::Saved) -> Result<(), DomainError> {
+        let Some(record) = self.records_manager.get_record(&event.record_id).await? else {
+            warn!("録画 {} はすでに削除されています", event.record_id);
+            return Ok(());
+        };
+        self.modify(record.program_id, |recording, now| {
+            recording.save(&record, now)
+        })
+        .await
+    }
+
+    async fn on_record_broken(&self, event: &record::Broken) -> Result<(), DomainError> {
+        let Some(record) = self.records_manager.get_record(&event.record_id).await? else {
+            warn!("録画 {} はすでに削除されています", event.record_id);
+            return Ok(());
+        };
+        self.modify(record.program_id, |recording, now| {
+            recording.mark_broken(&event.reason, now)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::recording::{Record, RecordingFailedReason, RecordingState, RecordingStatus};
+    use crate::repository::Versioned;
+    use crate::usecase::test_util::MockKvRepository;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    const MIRAKC_URL: &str = "http://mirakc:40772";
+
+    #[derive(Clone, Default)]
+    struct MockRecordsManager {
+        records: Arc<Mutex<Vec<Record>>>,
+    }
+
+    #[async_trait]
+    impl RecordsManager for MockRecordsManager {
+        async fn list_records(&self) -> Result<Vec<Record>, DomainError> {
+            Ok(self.records.lock().unwrap().clone())
+        }
+
+        async fn get_record(&self, record_id: &str) -> Result<Option<Record>, DomainError> {
+            Ok(self
+                .records
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|record| record.id == record_id)
+                .cloned())
+        }
+
+        async fn remove_record(&self, record_id: &str, _purge: bool) -> Result<bool, DomainError> {
+            let mut records = self.records.lock().unwrap();
+            let count = records.len();
+            records.retain(|record| record.id != record_id);
+            Ok(records.len() != count)
+        }
+    }
+
+    /// 最初の `conflicts` 回の更新を、他から更新されたものとして失敗させる
+    #[derive(Clone)]
+    struct ConflictingRepository {
+        inner: MockKvRepository<Recording>,
+        conflicts: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl KvRepository<String, Recording> for ConflictingRepository {
+        async fn put(&self, key: String, value: &Recording) -> Result<(), DomainError> {
+            self.inner.put(key, value).await
+        }
+
+        async fn get(&self, key: String) -> Result<Option<Versioned<Recording>>, DomainError> {
+            self.inner.get(key).await
+        }
+
+        async fn update(
+            &self,
+            key: String,
+            value: &Recording,
+            revision: u64,
+        ) -> Result<(), DomainError> {
+            if self.conflicts.load(Ordering::SeqCst) > 0 {
+                self.conflicts.fetch_sub(1, Ordering::SeqCst);
+                // 他のワーカーが先に録画を保存（または作成）した
+                let mut other = match self.inner.get(key.clone()).await? {
+                    Some(versioned) => versioned.value,
+                    None => value.clone(),
+                };
+                other.content_length = Some(1);
+                self.inner.put(key.clone(), &other).await?;
+            }
+            self.inner.update(key, value, revision).await
+        }
+
+        async fn delete(&self, key: String) -> Result<(), DomainError> {
+            self.inner.delete(key).await
+        }
+    }
+
+    /// 更新のたびにリビジョン以外のエラーで失敗する
+    #[derive(Clone, Default)]
+    struct BrokenRepository {
+        inner: MockKvRepository<Recording>,
+        updates: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl KvRepository<String, Recording> for BrokenRepository {
+        async fn put(&self, key: String, value: &Recording) -> Result<(), DomainError> {
+            self.inner.put(key, value).await
+        }
+
+        async fn get(&self, key: String) -> Result<Option<Versioned<Recording>>, DomainError> {
+            self.inner.get(key).await
+        }
+
+        async fn update(
+            &self,
+            _key: String,
+            _value: &Recording,
+            _revision: u64,
+        ) -> Result<(), DomainError> {
+            self.updates.fetch_add(1, Ordering::SeqCst);
+            Err(DomainError::ProgramsStoreError(
+                "KVSに接続できません".to_string(),
+            ))
+        }
+
+        async fn delete(&self, key: String) -> Result<(), DomainError> {
+            self.inner.delete(key).await
+        }
+    }
+
+    fn now() -> i64 {
+        1619856000000
+    }
+
+    fn test_record(status: RecordingStatus) -> Record {
+        Record {
+            id: "0000000000000001".to_string(),
+            program_id: 327360102412345,
+            service_id: 3273601024,
+            program_name: Some("ニュース".to_string()),
+            tags: vec![],
+            status,
+            start_time: 1619855990000,
+            end_time: None,
+            content_path: "0000000000000001.m2ts".to_string(),
+            content_type: "video/MP2T".to_string(),
+            content_length: Some(1024),
+            failed_reason: None,
+            audio_component_types: vec![0b00011],
+        }
+    }
+
+    fn started(program_id: i64) -> schedule::Started {
+        schedule::Started {
+            program_id,
+            mirakc_url: MIRAKC_URL.to_string(),
+        }
+    }
+
+    fn saved(record_id: &str) -> record::Saved {
+        record::Saved {
+            record_id: record_id.to_string(),
+            recording_status: RecordingStatus::Recording,
+            mirakc_url: MIRAKC_URL.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tracks_recording_lifecycle() {
+        let records = MockRecordsManager::default();
+        records
+            .records
+            .lock()
+            .unwrap()
+            .push(test_record(RecordingStatus::Recording));
+        let repository = MockKvRepository::<Recording>::new();
+        let usecase =
+            RecordingTrackerUseCaseImpl::new(records.clone(), repository.clone()).with_clock(now);
+        let key = "327360102412345".to_string();
+
+        usecase
+            .on_reserved(&reservation::Matched {
+                rule_id: "news".to_string(),
+                service_id: 3273601024,
+                program_id: 327360102412345,
+                mirakc_url: MIRAKC_URL.to_string(),
+            })
+            .await
+            .unwrap();
+        let stored = repository.get(key.clone()).await.unwrap().unwrap();
+        assert_eq!(stored.value.state, RecordingState::Scheduled);
+        assert_eq!(stored.value.created_at, now());
+
+        usecase.on_started(&started(327360102412345)).await.unwrap();
+        usecase
+            .on_record_saved(&saved("0000000000000001"))
+            .await
+            .unwrap();
+        let stored = repository.get(key.clone()).await.unwrap().unwrap();
+        assert_eq!(stored.value.state, RecordingState::Recording);
+        assert_eq!(stored.revision, 3);
+
+        records.records.lock().unwrap()[0] = Record {
+            end_time: Some(1619857800000),
+            content_length: Some(4096),
+            ..test_record(RecordingStatus::Finished)
+        };
+        usecase
+            .on_record_saved(&saved("0000000000000001"))
+            .await
+            .unwrap();
+        // 同じ保存が再び届いても更新しない
+        usecase
+            .on_record_saved(&saved("0000000000000001"))
+            .await
+            .unwrap();
+
+        let stored = repository.get(key).await.unwrap().unwrap();
+        assert_eq!(stored.revision, 4);
+        assert_eq!(stored.value.state, RecordingState::Saved);
+        assert_eq!(
+            stored.value.content_path.as_deref(),
+            Some("0000000000000001.m2ts")
+        );
+        assert_eq!(stored.value.content_length, Some(4096));
+        assert_eq!(stored.value.ended_at, Some(1619857800000));
+    }
+
+    #[tokio::test]
+    async fn test_tracks_unreserved_recording() {
+        let repository = MockKvRepository::<Recording>::new();
+        let usecase =
+            RecordingTrackerUseCaseImpl::new(MockRecordsManager::default(), repository.clone())
+                .with_clock(now);
+
+        usecase
+            .on_failed(&schedule::Failed {
+                program_id: 1,
+                reason: RecordingFailedReason::ScheduleExpired,
+                mirakc_url: MIRAKC_URL.to_string(),
+            })
+            .await
+            .unwrap();
+
+        let stored = repository.get("1".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.value.state, RecordingState::Failed);
+        assert_eq!(
+            stored.value.failed_reason,
+            Some(RecordingFailedReason::ScheduleExpired)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retries_conflicting_update() {
+        let records = MockRecordsManager::default();
+        let repository = ConflictingRepository {
+            inner: MockKvRepository::new(),
+            conflicts: Arc::new(AtomicUsize::new(2)),
+        };
+        let usecase =
+            RecordingTrackerUseCaseImpl::new(records.clone(), repository.clone()).with_clock(now);
+        // 作成がぶつかっても、先に作られた録画を上書きしない
+        usecase.on_started(&started(1)).await.unwrap();
+        assert_eq!(repository.conflicts.load(Ordering::SeqCst), 1);
+
+        usecase
+            .on_stopped(&schedule::Stopped {
+                program_id: 1,
+                mirakc_url: MIRAKC_URL.to_string(),
+            })
+            .await
+            .unwrap();
+
+        let stored = repository.get("1".to_string()).await.unwrap().unwrap();
+        assert_eq!(stored.value.ended_at, Some(now()));
+        // 他のワーカーの更新を失っていない
+        assert_eq!(stored.value.content_length, Some(1));
+        assert_eq!(repository.conflicts.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_repeated_conflicts() {
+        let repository = ConflictingRepository {
+            inner: MockKvRepository::new(),
+            conflicts: Arc::new(AtomicUsize::new(MAX_UPDATE_ATTEMPTS)),
+        };
+        let usecase =
+            RecordingTrackerUseCaseImpl::new(MockRecordsManager::default(), repository.clone())
+                .with_clock(now);
+        repository
+            .inner
+            .put("1".to_string(), &Recording::new(1, 0))
+            .await
+            .unwrap();
+
+        assert!(usecase.on_started(&started(1)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_other_errors() {
+        let repository = BrokenRepository::default();
+        let usecase =
+            RecordingTrackerUseCaseImpl::new(MockRecordsManager::default(), repository.clone())
+                .with_clock(now);
+
+        let result = usecase.on_started(&started(1)).await;
+
+        assert!(matches!(result, Err(DomainError::ProgramsStoreError(_))));
+        assert_eq!(repository.updates.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_ignores_removed_record() {
+        let repository = MockKvRepository::<Recording>::new();
+        let usecase =
+            RecordingTrackerUseCaseImpl::new(MockRecordsManager::default(), repository.clone());
+
+        usecase
+            .on_record_broken(&record::Broken {
+                record_id: "0000000000000001".to_string(),
+                reason: "壊れています".to_string(),
+                mirakc_url: MIRAKC_URL.to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert!(repository.data.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,372 @@
+//! ユースケースのテストで共通して使うモックと番組
+//!
+//! 他のクレートのテストからは `test-util` フィーチャーを有効にして使う。
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::{
+    error::DomainError,
+    model::inventory::{ChannelInfo, ChannelType, Inventory, Service, Tuner},
+    model::object::{ObjectMeta, PutOptions, StoredObject},
+    model::program::{Channel, Genre, Program, ProgramIdentifiers, ProgramTiming, ProgramsDiff},
+    ports::{EventPublisher, ObjectStore},
+    repository::{InventoryRepository, KvRepository, ProgramRepository, Versioned},
+    types::Event,
+};
+
+#[derive(Clone)]
+pub struct MockEventPublisher<E> {
+    published_events: Arc<Mutex<Vec<E>>>,
+    /// 残りの発行に失敗する回数
+    failures: Arc<Mutex<usize>>,
+}
+
+impl<E: Clone> MockEventPublisher<E> {
+    pub fn new() -> Self {
+        Self::failing_times(0)
+    }
+
+    /// 常に発行に失敗するモック
+    pub fn failing() -> Self {
+        Self::failing_times(usize::MAX)
+    }
+
+    /// 最初の `times` 回だけ発行に失敗するモック
+    pub fn failing_times(times: usize) -> Self {
+        Self {
+            published_events: Arc::new(Mutex::new(Vec::new())),
+            failures: Arc::new(Mutex::new(times)),
+        }
+    }
+
+    pub fn published_events(&self) -> Vec<E> {
+        self.published_events.lock().unwrap().clone()
+    }
+}
+
+impl<E: Clone> Default for MockEventPublisher<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<E: Event> EventPublisher<E> for MockEventPublisher<E> {
+    async fn publish(&self, event: &E) -> Result<(), DomainError> {
+        {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(DomainError::EventPublishError(
+                    "モックの発行エラー".to_string(),
+                ));
+            }
+        }
+        self.published_events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct MockKvRepository<V> {
+    pub data: Arc<Mutex<HashMap<String, (u64, V)>>>,
+}
+
+impl<V> MockKvRepository<V> {
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<V> Default for MockKvRepository<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<V> KvRepository<String, V> for MockKvRepository<V>
+where
+    V: Into<Bytes> + Clone + Send + Sync,
+{
+    async fn put(&self, key: String, value: &V) -> Result<(), DomainError> {
+        let mut data = self.data.lock().unwrap();
+        let revision = data.get(&key).map_or(1, |(rev, _)| rev + 1);
+        data.insert(key, (revision, value.clone()));
+        Ok(())
+    }
+
+    async fn get(&self, key: String) -> Result<Option<Versioned<V>>, DomainError> {
+        let data = self.data.lock().unwrap();
+        Ok(data.get(&key).map(|(revision, value)| Versioned {
+            revision: *revision,
+            value: value.clone(),
+        }))
+    }
+
+    async fn update(&self, key: String, value: &V, revision: u64) -> Result<(), DomainError> {
+        let mut data = self.data.lock().unwrap();
+        // リビジョン0はキーがまだないときだけ作成する
+        let current_revision = data.get(&key).map_or(0, |(rev, _)| *rev);
+        if current_revision != revision {
+            return Err(DomainError::RevisionConflict(format!(
+                "{} (現在: {}, 指定: {})",
+                key, current_revision, revision
+            )));
+        }
+        data.insert(key, (revision + 1, value.clone()));
+        Ok(())
+    }
+
+    async fn delete(&self, key: String) -> Result<(), DomainError> {
+        self.data.lock().unwrap().remove(&key);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct MockProgramRepository {
+    pub programs: Arc<Mutex<HashMap<i64, Program>>>,
+}
+
+impl MockProgramRepository {
+    pub fn new() -> Self {
+        Self {
+            programs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 保存されている番組のID（昇順）
+    pub fn program_ids(&self) -> Vec<i64> {
+        let mut ids: Vec<i64> = self.programs.lock().unwrap().keys().copied().collect();
+        ids.sort();
+        ids
+    }
+}
+
+#[async_trait]
+impl ProgramRepository for MockProgramRepository {
+    async fn apply_diff(&self, diff: &ProgramsDiff) -> Result<(), DomainError> {
+        let mut programs = self.programs.lock().unwrap();
+        for program in diff
+            .added
+            .iter()
+            .chain(diff.changed.iter().map(|(program, _)| program))
+        {
+            programs.insert(program.id, program.clone());
+        }
+        for program in &diff.removed {
+            programs.remove(&program.id);
+        }
+        Ok(())
+    }
+
+    async fn has_programs(&self, service_id: i64) -> Result<bool, DomainError> {
+        Ok(self
+            .programs
+            .lock()
+            .unwrap()
+            .values()
+            .any(|program| program.mirakurun_service_id() == service_id))
+    }
+}
+
+/// 置き換えた一覧の種類を記録する `InventoryRepository` のモック
+#[derive(Clone, Default)]
+pub struct MockInventoryRepository {
+    pub inventory: Arc<Mutex<Inventory>>,
+    replaced: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl MockInventoryRepository {
+    pub fn new() -> Self {
+        Self {
+            inventory: Arc::new(Mutex::new(Inventory::default())),
+            replaced: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// 置き換えた一覧の種類（`tuners`・`channels`・`services`）を呼ばれた順に返す
+    pub fn replaced(&self) -> Vec<&'static str> {
+        self.replaced.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl InventoryRepository for MockInventoryRepository {
+    async fn load(&self) -> Result<Inventory, DomainError> {
+        Ok(self.inventory.lock().unwrap().clone())
+    }
+
+    async fn find_service(&self, service_id: i64) -> Result<Option<Service>, DomainError> {
+        let inventory = self.inventory.lock().unwrap();
+        Ok(inventory
+            .services
+            .iter()
+            .find(|service| service.id == service_id)
+            .cloned())
+    }
+
+    async fn replace_tuners(&self, tuners: &[Tuner]) -> Result<(), DomainError> {
+        self.inventory.lock().unwrap().tuners = tuners.to_vec();
+        self.replaced.lock().unwrap().push("tuners");
+        Ok(())
+    }
+
+    async fn replace_channels(&self, channels: &[ChannelInfo]) -> Result<(), DomainError> {
+        self.inventory.lock().unwrap().channels = channels.to_vec();
+        self.replaced.lock().unwrap().push("channels");
+        Ok(())
+    }
+
+    async fn replace_services(&self, services: &[Service]) -> Result<(), DomainError> {
+        self.inventory.lock().unwrap().services = services.to_vec();
+        self.replaced.lock().unwrap().push("services");
+        Ok(())
+    }
+}
+
+/// メモリ上にオブジェクトを保存する `ObjectStore` のモック
+#[derive(Clone, Default)]
+pub struct MockObjectStore {
+    pub objects: Arc<Mutex<BTreeMap<String, StoredObject>>>,
+}
+
+impl MockObjectStore {
+    pub fn new() -> Self {
+        Self {
+            objects: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// 保存されているオブジェクトのキー（昇順）
+    pub fn keys(&self) -> Vec<String> {
+        self.objects.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for MockObjectStore {
+    async fn put(
+        &self,
+        key: &str,
+        data: Bytes,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        let meta = ObjectMeta {
+            key: key.to_string(),
+            size: data.len() as u64,
+            content_type: Some(options.content_type.clone()),
+            metadata: options.metadata.clone(),
+            last_modified: None,
+        };
+        self.objects.lock().unwrap().insert(
+            key.to_string(),
+            StoredObject {
+                meta: meta.clone(),
+                data,
+            },
+        );
+        Ok(meta)
+    }
+
+    async fn put_file(
+        &self,
+        key: &str,
+        path: &Path,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        let data = std::fs::read(path)
+            .map_err(|e| DomainError::ObjectStoreError(format!("{}: {}", path.display(), e)))?;
+        self.put(key, Bytes::from(data), options).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>, DomainError> {
+        Ok(self.objects.lock().unwrap().get(key).cloned())
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<ObjectMeta>, DomainError> {
+        Ok(self
+            .objects
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|object| object.meta.clone()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DomainError> {
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, DomainError> {
+        Ok(self
+            .objects
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|object| object.meta.key.starts_with(prefix))
+            .map(|object| object.meta.clone())
+            .collect())
+    }
+}
+
+/// `types` の放送波を受信できる、使用可能なテスト用チューナー
+pub fn test_tuner(index: u32, types: &[ChannelType]) -> Tuner {
+    Tuner {
+        index,
+        name: format!("tuner{}", index),
+        types: types.to_vec(),
+        is_available: true,
+        is_fault: false,
+    }
+}
+
+/// 地上波のテスト用サービス
+pub fn test_service(id: i64) -> Service {
+    Service {
+        id,
+        service_id: (id % 100000) as i32,
+        network_id: (id / 100000) as i32,
+        service_type: 1,
+        name: format!("サービス{}", id),
+        channel_type: ChannelType::Gr,
+        channel: "27".to_string(),
+        has_logo_data: false,
+        logo_id: None,
+        remote_control_key_id: None,
+    }
+}
+
+/// テスト用の30分のアニメ番組
+///
+/// `service_id` はmirakcのサービスID（`network_id * 100000 + service_id`）。
+pub fn test_program(id: i64, service_id: i64, start_at: i64, name: &str) -> Program {
+    Program::new(
+        ProgramIdentifiers {
+            id,
+            event_id: (id % 100000) as i32,
+            network_id: (service_id / 100000) as i32,
+            service_id: (service_id % 100000) as i32,
+        },
+        ProgramTiming {
+            start_at,
+            duration: 1800000,
+        },
+        true,
+        Some(name.to_string()),
+        None,
+        vec![Genre { lv1: 7, lv2: 0 }],
+        Channel {
+            id: service_id,
+            name: "テストチャンネル".to_string(),
+        },
+    )
+}
@@ -0,0 +1,461 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::{
+    error::DomainError,
+    model::{
+        event::recording::{record, transcode},
+        recording::RecordingStatus,
+        transcode::{TranscodeJob, TranscodeProfile, TranscodeProgress},
+    },
+    ports::{EventPublisher, RecordsManager, TranscodeProgressListener, Transcoder},
+};
+use async_trait::async_trait;
+use tracing::{debug, info, warn};
+
+#[async_trait]
+pub trait TranscodeUseCase {
+    /// 正常に保存された録画をプロファイルに従ってトランスコードする
+    ///
+    /// 失敗した場合は `transcode::Failed` を発行してからエラーを返す。
+    async fn on_record_saved(&self, event: &record::Saved) -> Result<(), DomainError>;
+}
+
+pub struct TranscodeUseCaseImpl<M, T, S, P, C, F>
+where
+    M: RecordsManager + Send + Sync,
+    T: Transcoder + Send + Sync,
+    S: EventPublisher<transcode::Started> + Send + Sync,
+    P: EventPublisher<transcode::Progress> + Send + Sync,
+    C: EventPublisher<transcode::Completed> + Send + Sync,
+    F: EventPublisher<transcode::Failed> + Send + Sync,
+{
+    records_manager: M,
+    transcoder: T,
+    started_publisher: S,
+    progress_publisher: P,
+    completed_publisher: C,
+    failed_publisher: F,
+    profile: TranscodeProfile,
+    records_dir: PathBuf,
+    output_dir: PathBuf,
+}
+
+impl<M, T, S, P, C, F> TranscodeUseCaseImpl<M, T, S, P, C, F>
+where
+    M: RecordsManager + Send + Sync,
+    T: Transcoder + Send + Sync,
+    S: EventPublisher<transcode::Started> + Send + Sync,
+    P: EventPublisher<transcode::Progress> + Send + Sync,
+    C: EventPublisher<transcode::Completed> + Send + Sync,
+    F: EventPublisher<transcode::Failed> + Send + Sync,
+{
+    /// `records_dir` はmirakcの録画ディレクトリ、`output_dir` は変換後のファイルを置くディレクトリ
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        records_manager: M,
+        transcoder: T,
+        started_publisher: S,
+        progress_publisher: P,
+        completed_publisher: C,
+        failed_publisher: F,
+        records_dir: impl Into<PathBuf>,
+        output_dir: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            records_manager,
+            transcoder,
+            started_publisher,
+            progress_publisher,
+            completed_publisher,
+            failed_publisher,
+            profile: TranscodeProfile::default(),
+            records_dir: records_dir.into(),
+            output_dir: output_dir.into(),
+        }
+    }
+
+    /// 変換に使うプロファイルを指定する
+    pub fn with_profile(mut self, profile: TranscodeProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    async fn publish_failed(
+        &self,
+        job: &TranscodeJob,
+        reason: String,
+        mirakc_url: &str,
+    ) -> Result<(), DomainError> {
+        self.failed_publisher
+            .publish(&transcode::Failed {
+                record_id: job.record_id.clone(),
+                program_id: job.program_id,
+                profile: job.profile.name.clone(),
+                reason,
+                mirakc_url: mirakc_url.to_string(),
+            })
+            .await
+    }
+}
+
+/// 進捗を `transcode::Progress` として発行する（進捗率が変わったときだけ）
+struct ProgressReporter<'a, P>
+where
+    P: EventPublisher<transcode::Progress> + Send + Sync,
+{
+    publisher: &'a P,
+    job: &'a TranscodeJob,
+    mirakc_url: &'a str,
+    last_percent: Mutex<Option<u8>>,
+}
+
+#[async_trait]
+impl<P> TranscodeProgressListener for ProgressReporter<'_, P>
+where
+    P: EventPublisher<transcode::Progress> + Send + Sync,
+{
+    async fn on_progress(&self, progress: &TranscodeProgress) {
+        // 録画の長さが分からなければ、届いた進捗をすべて発行する
+        if progress.percent.is_some() {
+            let mut last_percent = self.last_percent.lock().unwrap();
+            if *last_percent == progress.percent {
+                return;
+            }
+            *last_percent = progress.percent;
+        }
+        let event = transcode::Progress {
+            record_id: self.job.record_id.clone(),
+            program_id: self.job.program_id,
+            out_time: progress.out_time,
+            percent: progress.percent,
+            speed: progress.speed,
+            mirakc_url: self.mirakc_url.to_string(),
+        };
+        // 進捗の通知に失敗しても変換は続ける
+        if let Err(e) = self.publisher.publish(&event).await {
+            warn!("録画ID {} の進捗の発行に失敗: {}", self.job.record_id, e);
+        }
+    }
+}
+
+#[async_trait]
+impl<M, T, S, P, C, F> TranscodeUseCase for TranscodeUseCaseImpl<M, T, S, P, C, F>
+where
+    M: RecordsManager + Send + Sync,
+    T: Transcoder + Send + Sync,
+    S: EventPublisher<transcode::Started> + Send + Sync,
+    P: EventPublisher<transcode::Progress> + Send + Sync,
+    C: EventPublisher<transcode::Completed> + Send + Sync,
+    F: EventPublisher<transcode::Failed> + Send + Sync,
+{
+    async fn on_record_saved(&self, event: &record::Saved) -> Result<(), DomainError> {
+        if event.recording_status != RecordingStatus::Finished {
+            debug!(
+                "録画ID {} は正常に終了していないので変換しません: {:?}",
+                event.record_id, event.recording_status
+            );
+            return Ok(());
+        }
+        let Some(record) = self.records_manager.get_record(&event.record_id).await? else {
+            warn!("録画ID {} がmirakcにありません", event.record_id);
+            return Ok(());
+        };
+        if record.status != RecordingStatus::Finished || record.content_length.is_none() {
+            debug!(
+                "録画ID {} は変換できる状態ではありません: {:?}",
+                record.id, record.status
+            );
+            return Ok(());
+        }
+
+        let job = TranscodeJob::new(&record, &self.profile, &self.records_dir, &self.output_dir);
+        let output_path = job.output_path.to_string_lossy().into_owned();
+        self.started_publisher
+            .publish(&transcode::Started {
+                record_id: job.record_id.clone(),
+                program_id: job.program_id,
+                profile: job.profile.name.clone(),
+                output_path: output_path.clone(),
+                mirakc_url: event.mirakc_url.clone(),
+            })
+            .await?;
+        info!(
+            record_id = %job.record_id,
+            profile = %job.profile.name,
+            dual_mono = job.dual_mono,
+            "録画の変換を開始します: {}",
+            output_path
+        );
+
+        let reporter = ProgressReporter {
+            publisher: &self.progress_publisher,
+            job: &job,
+            mirakc_url: &event.mirakc_url,
+            last_percent: Mutex::new(None),
+        };
+        let output_length = match self.transcoder.transcode(&job, &reporter).await {
+            Ok(output_length) => output_length,
+            Err(e) => {
+                warn!("録画ID {} の変換に失敗: {}", job.record_id, e);
+                self.publish_failed(&job, e.to_string(), &event.mirakc_url)
+                    .await?;
+                return Err(DomainError::TranscodeError(e.to_string()));
+            }
+        };
+
+        self.completed_publisher
+            .publish(&transcode::Completed {
+                record_id: job.record_id.clone(),
+                program_id: job.program_id,
+                profile: job.profile.name.clone(),
+                output_path,
+                output_length,
+                mirakc_url: event.mirakc_url.clone(),
+            })
+            .await?;
+        info!(
+            record_id = %job.record_id,
+            output_length, "録画の変換が完了しました"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::recording::Record;
+    use crate::model::transcode::DualMonoHandling;
+    use crate::ports::TranscoderError;
+    use crate::usecase::test_util::MockEventPublisher;
+    use std::sync::Arc;
+
+    const MIRAKC_URL: &str = "http://mirakc:40772";
+
+    #[derive(Clone, Default)]
+    struct MockRecordsManager {
+        records: Vec<Record>,
+    }
+
+    #[async_trait]
+    impl RecordsManager for MockRecordsManager {
+        async fn list_records(&self) -> Result<Vec<Record>, DomainError> {
+            Ok(self.records.clone())
+        }
+
+        async fn get_record(&self, record_id: &str) -> Result<Option<Record>, DomainError> {
+            Ok(self.records.iter().find(|r| r.id == record_id).cloned())
+        }
+
+        async fn remove_record(&self, record_id: &str, _purge: bool) -> Result<bool, DomainError> {
+            Ok(self.records.iter().any(|r| r.id == record_id))
+        }
+    }
+
+    /// 決まった進捗を通知して終わるエンコーダー
+    #[derive(Clone, Default)]
+    struct MockTranscoder {
+        progress: Vec<TranscodeProgress>,
+        fail: bool,
+        jobs: Arc<Mutex<Vec<TranscodeJob>>>,
+    }
+
+    #[async_trait]
+    impl Transcoder for MockTranscoder {
+        async fn transcode(
+            &self,
+            job: &TranscodeJob,
+            listener: &dyn TranscodeProgressListener,
+        ) -> Result<u64, TranscoderError> {
+            self.jobs.lock().unwrap().push(job.clone());
+            for progress in &self.progress {
+                listener.on_progress(progress).await;
+            }
+            if self.fail {
+                return Err(TranscoderError::EncoderFailed {
+                    exit_code: Some(1),
+                    message: "Invalid data found when processing input".to_string(),
+                });
+            }
+            Ok(4096)
+        }
+    }
+
+    fn test_record(status: RecordingStatus) -> Record {
+        Record {
+            id: "0000000000000001".to_string(),
+            program_id: 327360102412345,
+            service_id: 3273601024,
+            program_name: Some("映画".to_string()),
+            tags: vec![],
+            status,
+            start_time: 1619856000000,
+            end_time: Some(1619856060000),
+            content_path: "0000000000000001.m2ts".to_string(),
+            content_type: "video/MP2T".to_string(),
+            content_length: Some(1024),
+            failed_reason: None,
+            audio_component_types: vec![0b00010],
+        }
+    }
+
+    fn saved(status: RecordingStatus) -> record::Saved {
+        record::Saved {
+            record_id: "0000000000000001".to_string(),
+            recording_status: status,
+            mirakc_url: MIRAKC_URL.to_string(),
+        }
+    }
+
+    fn progress(out_time: i64, percent: Option<u8>) -> TranscodeProgress {
+        TranscodeProgress {
+            out_time,
+            percent,
+            speed: Some(2.0),
+            finished: false,
+        }
+    }
+
+    struct Publishers {
+        started: MockEventPublisher<transcode::Started>,
+        progress: MockEventPublisher<transcode::Progress>,
+        completed: MockEventPublisher<transcode::Completed>,
+        failed: MockEventPublisher<transcode::Failed>,
+    }
+
+    type TestUseCase = TranscodeUseCaseImpl<
+        MockRecordsManager,
+        MockTranscoder,
+        MockEventPublisher<transcode::Started>,
+        MockEventPublisher<transcode::Progress>,
+        MockEventPublisher<transcode::Completed>,
+        MockEventPublisher<transcode::Failed>,
+    >;
+
+    fn usecase(
+        records: MockRecordsManager,
+        transcoder: MockTranscoder,
+    ) -> (TestUseCase, Publishers) {
+        let publishers = Publishers {
+            started: MockEventPublisher::new(),
+            progress: MockEventPublisher::new(),
+            completed: MockEventPublisher::new(),
+            failed: MockEventPublisher::new(),
+        };
+        let usecase = TranscodeUseCaseImpl::new(
+            records,
+            transcoder,
+            publishers.started.clone(),
+            publishers.progress.clone(),
+            publishers.completed.clone(),
+            publishers.failed.clone(),
+            "/records",
+            "/videos",
+        )
+        .with_profile(TranscodeProfile {
+            name: "720p".to_string(),
+            height: Some(720),
+            dual_mono: DualMonoHandling::Split,
+            ..Default::default()
+        });
+        (usecase, publishers)
+    }
+
+    #[tokio::test]
+    async fn test_transcode_saved_record() {
+        let records = MockRecordsManager {
+            records: vec![test_record(RecordingStatus::Finished)],
+        };
+        let transcoder = MockTranscoder {
+            progress: vec![
+                progress(0, Some(0)),
+                progress(300, Some(0)),
+                progress(30000, Some(50)),
+                progress(60000, Some(100)),
+            ],
+            ..Default::default()
+        };
+        let (usecase, publishers) = usecase(records, transcoder.clone());
+
+        usecase
+            .on_record_saved(&saved(RecordingStatus::Finished))
+            .await
+            .unwrap();
+
+        let jobs = transcoder.jobs.lock().unwrap().clone();
+        assert_eq!(jobs.len(), 1);
+        assert!(jobs[0].dual_mono);
+        assert_eq!(jobs[0].duration, Some(60000));
+        assert_eq!(
+            jobs[0].output_path,
+            PathBuf::from("/videos/0000000000000001.mp4")
+        );
+
+        let started = publishers.started.published_events();
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].profile, "720p");
+        assert_eq!(started[0].output_path, "/videos/0000000000000001.mp4");
+        // 進捗率が変わらない通知は発行しない
+        let percents: Vec<_> = publishers
+            .progress
+            .published_events()
+            .iter()
+            .map(|progress| progress.percent)
+            .collect();
+        assert_eq!(percents, [Some(0), Some(50), Some(100)]);
+        let completed = publishers.completed.published_events();
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].output_length, 4096);
+        assert_eq!(completed[0].mirakc_url, MIRAKC_URL);
+        assert!(publishers.failed.published_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_skip_unfinished_record() {
+        let records = MockRecordsManager {
+            records: vec![test_record(RecordingStatus::Failed)],
+        };
+        let transcoder = MockTranscoder::default();
+        let (usecase, publishers) = usecase(records, transcoder.clone());
+
+        usecase
+            .on_record_saved(&saved(RecordingStatus::Failed))
+            .await
+            .unwrap();
+        // イベントの状態が古くても、mirakcの録画の状態を確かめる
+        usecase
+            .on_record_saved(&saved(RecordingStatus::Finished))
+            .await
+            .unwrap();
+
+        assert!(transcoder.jobs.lock().unwrap().is_empty());
+        assert!(publishers.started.published_events().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_encoder_failure_publishes_failed() {
+        let records = MockRecordsManager {
+            records: vec![test_record(RecordingStatus::Finished)],
+        };
+        let transcoder = MockTranscoder {
+            progress: vec![progress(1000, Some(1))],
+            fail: true,
+            ..Default::default()
+        };
+        let (usecase, publishers) = usecase(records, transcoder);
+
+        let result = usecase
+            .on_record_saved(&saved(RecordingStatus::Finished))
+            .await;
+
+        assert!(matches!(result, Err(DomainError::TranscodeError(_))));
+        assert!(!result.unwrap_err().is_retryable());
+        assert_eq!(publishers.started.published_events().len(), 1);
+        assert!(publishers.completed.published_events().is_empty());
+        let failed = publishers.failed.published_events();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].record_id, "0000000000000001");
+        assert!(failed[0].reason.contains("終了コード"));
+    }
+}
@@ -32,6 +32,9 @@ impl ImageFetcher for ReqwestImageFetcher {
             .send()
             .await
             .map_err(|e| ImageFetcherError::FetchError(e.to_string()))?
+            // 404などのエラーページを画像として扱わない
+            .error_for_status()
+            .map_err(|e| ImageFetcherError::FetchError(e.to_string()))?
             .bytes()
             .await
             .map(|b| b.to_vec())
@@ -71,6 +74,22 @@ mod tests {
         server_handle.abort();
     }
 
+    #[tokio::test]
+    async fn test_fetch_image_error_status() {
+        let image_route = warp::path!("missing.png")
+            .map(|| warp::reply::with_status("Not Found", warp::http::StatusCode::NOT_FOUND));
+
+        let (addr, server) = warp::serve(image_route).bind_ephemeral(([127, 0, 0, 1], 0));
+        let server_handle = tokio::spawn(server);
+
+        let url = format!("http://127.0.0.1:{}/missing.png", addr.port());
+        let result = ReqwestImageFetcher::default().fetch_image(&url).await;
+
+        assert!(result.is_err());
+
+        server_handle.abort();
+    }
+
     #[tokio::test]
     async fn test_fetch_image_error() {
         let url = "http://non-existent-domain-12345.example";
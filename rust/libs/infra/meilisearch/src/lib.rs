@@ -0,0 +1,3 @@
+mod meilisearch_index;
+
+pub use meilisearch_index::*;
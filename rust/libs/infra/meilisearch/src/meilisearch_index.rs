@@ -0,0 +1,424 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use domain::{
+    error::DomainError,
+    model::search::{ProgramDocument, SearchQuery, SearchResults},
+    ports::SearchIndex,
+};
+use reqwest::{Client, Method, RequestBuilder, Response};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::sync::OnceCell;
+use tracing::{debug, info};
+
+/// 絞り込みに使う属性
+pub const FILTERABLE_ATTRIBUTES: [&str; 5] =
+    ["id", "service_id", "genre_lv1", "start_at", "end_at"];
+/// 検索語と照合する属性（重要な順）
+pub const SEARCHABLE_ATTRIBUTES: [&str; 5] =
+    ["name", "description", "extended", "genres", "channel_name"];
+
+/// Meilisearchへの接続設定
+#[derive(Clone)]
+pub struct MeilisearchConfig {
+    pub url: String,
+    /// マスターキーまたはAPIキー（認証を無効にしたサーバーなら `None`）
+    pub api_key: Option<String>,
+    /// 番組の文書を登録するインデックス
+    pub index: String,
+}
+
+impl Default for MeilisearchConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://meilisearch:7700".to_string(),
+            api_key: None,
+            index: "programs".to_string(),
+        }
+    }
+}
+
+/// 書き込みを受け付けたときに返されるタスク
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnqueuedTask {
+    task_uid: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponse {
+    hits: Vec<ProgramDocument>,
+    #[serde(default)]
+    estimated_total_hits: Option<usize>,
+    #[serde(default)]
+    total_hits: Option<usize>,
+}
+
+/// Meilisearchのインデックスを使う `SearchIndex`
+///
+/// 書き込みはMeilisearchのタスクとして順に処理されるため、完了を待たずに返る。
+/// インデックスと設定は最初の操作のときに作成・更新する。
+#[derive(Clone)]
+pub struct MeilisearchIndex {
+    client: Client,
+    config: MeilisearchConfig,
+    settings_applied: Arc<OnceCell<()>>,
+}
+
+impl MeilisearchIndex {
+    pub fn new(config: &MeilisearchConfig) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to create HTTP client"),
+            config: config.clone(),
+            settings_applied: Arc::new(OnceCell::new()),
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}{}", self.config.url.trim_end_matches('/'), path);
+        let request = self.client.request(method, url);
+        match &self.config.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    fn index_path(&self, path: &str) -> String {
+        format!("/indexes/{}{}", self.config.index, path)
+    }
+
+    async fn send(
+        &self,
+        operation: &str,
+        request: RequestBuilder,
+    ) -> Result<Response, DomainError> {
+        let response = request.send().await.map_err(|e| {
+            DomainError::SearchIndexError(format!("Meilisearchの{}に失敗: {}", operation, e))
+        })?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        // エラーの本文は `{"message": ..., "code": ...}` の形式
+        let message = match response.json::<Value>().await {
+            Ok(body) => body["message"].as_str().unwrap_or_default().to_string(),
+            Err(_) => String::new(),
+        };
+        Err(DomainError::SearchIndexError(format!(
+            "Meilisearchの{}に失敗 ({}): {}",
+            operation, status, message
+        )))
+    }
+
+    /// 書き込みを依頼し、受け付けられたタスクのIDを返す
+    async fn enqueue(&self, operation: &str, request: RequestBuilder) -> Result<u64, DomainError> {
+        let task: EnqueuedTask =
+            self.send(operation, request)
+                .await?
+                .json()
+                .await
+                .map_err(|e| {
+                    DomainError::SearchIndexError(format!(
+                        "Meilisearchの{}の応答を解釈できません: {}",
+                        operation, e
+                    ))
+                })?;
+        debug!(index = %self.config.index, task_uid = task.task_uid, "{}を依頼しました", operation);
+        Ok(task.task_uid)
+    }
+
+    /// インデックスを作成し、絞り込みに使う属性などを設定する
+    async fn ensure_settings(&self) -> Result<(), DomainError> {
+        self.settings_applied
+            .get_or_try_init(|| async {
+                // 作成済みのインデックスでは作成のタスクが失敗するだけなので、結果は確認しない
+                self.enqueue(
+                    "インデックスの作成",
+                    self.request(Method::POST, "/indexes").json(&json!({
+                        "uid": self.config.index,
+                        "primaryKey": "id",
+                    })),
+                )
+                .await?;
+                self.enqueue(
+                    "インデックスの設定",
+                    self.request(Method::PATCH, &self.index_path("/settings"))
+                        .json(&json!({
+                            "filterableAttributes": FILTERABLE_ATTRIBUTES,
+                            "sortableAttributes": ["start_at"],
+                            "searchableAttributes": SEARCHABLE_ATTRIBUTES,
+                        })),
+                )
+                .await?;
+                info!(index = %self.config.index, "Meilisearchのインデックスを設定しました");
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+/// 検索条件をMeilisearchの絞り込み条件（すべて満たすもの）にする
+fn filters(query: &SearchQuery) -> Vec<String> {
+    let list = |values: Vec<String>| values.join(", ");
+    let mut filters = Vec::new();
+    if !query.genre_lv1.is_empty() {
+        filters.push(format!(
+            "genre_lv1 IN [{}]",
+            list(query.genre_lv1.iter().map(u8::to_string).collect())
+        ));
+    }
+    if !query.service_ids.is_empty() {
+        filters.push(format!(
+            "service_id IN [{}]",
+            list(query.service_ids.iter().map(i64::to_string).collect())
+        ));
+    }
+    if let Some(from) = query.from {
+        filters.push(format!("end_at > {}", from));
+    }
+    if let Some(to) = query.to {
+        filters.push(format!("start_at < {}", to));
+    }
+    filters
+}
+
+#[async_trait]
+impl SearchIndex for MeilisearchIndex {
+    async fn upsert(&self, documents: &[ProgramDocument]) -> Result<(), DomainError> {
+        self.ensure_settings().await?;
+        self.enqueue(
+            "文書の追加",
+            self.request(Method::POST, &self.index_path("/documents"))
+                .query(&[("primaryKey", "id")])
+                .json(documents),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_stale(&self, service_id: i64, program_ids: &[i64]) -> Result<(), DomainError> {
+        self.ensure_settings().await?;
+        let mut filter = format!("service_id = {}", service_id);
+        if !program_ids.is_empty() {
+            let ids: Vec<String> = program_ids.iter().map(i64::to_string).collect();
+            filter.push_str(&format!(" AND id NOT IN [{}]", ids.join(", ")));
+        }
+        self.enqueue(
+            "文書の削除",
+            self.request(Method::POST, &self.index_path("/documents/delete"))
+                .json(&json!({ "filter": filter })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults, DomainError> {
+        self.ensure_settings().await?;
+        let response: SearchResponse = self
+            .send(
+                "検索",
+                self.request(Method::POST, &self.index_path("/search"))
+                    .json(&json!({
+                        "q": query.text,
+                        "filter": filters(query),
+                        "limit": query.limit,
+                        "offset": query.offset,
+                    })),
+            )
+            .await?
+            .json()
+            .await
+            .map_err(|e| {
+                DomainError::SearchIndexError(format!("検索結果を解釈できません: {}", e))
+            })?;
+        let total = response
+            .total_hits
+            .or(response.estimated_total_hits)
+            .unwrap_or(response.hits.len());
+        Ok(SearchResults {
+            hits: response.hits,
+            total,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Mock, Server, ServerGuard};
+
+    const ENQUEUED: &str = r#"{"taskUid":1,"indexUid":"programs","status":"enqueued","type":"documentAdditionOrUpdate"}"#;
+
+    fn document(id: i64, name: &str) -> ProgramDocument {
+        ProgramDocument {
+            id,
+            service_id: 3273601024,
+            name: name.to_string(),
+            description: "説明".to_string(),
+            extended: "出演者\n山田太郎".to_string(),
+            genres: vec!["アニメ・特撮/国内アニメ".to_string()],
+            genre_lv1: vec![7],
+            channel_name: "ＮＨＫ総合１・東京".to_string(),
+            start_at: 1619856000000,
+            end_at: 1619857800000,
+        }
+    }
+
+    /// インデックスの作成と設定の依頼を受け付けるモック
+    async fn mock_settings(server: &mut ServerGuard) -> (Mock, Mock) {
+        let create = server
+            .mock("POST", "/indexes")
+            .match_header("authorization", "Bearer master-key")
+            .match_body(Matcher::PartialJson(
+                json!({"uid": "programs", "primaryKey": "id"}),
+            ))
+            .with_status(202)
+            .with_body(ENQUEUED)
+            .expect(1)
+            .create_async()
+            .await;
+        let settings = server
+            .mock("PATCH", "/indexes/programs/settings")
+            .match_body(Matcher::PartialJson(json!({
+                "filterableAttributes": FILTERABLE_ATTRIBUTES,
+                "sortableAttributes": ["start_at"],
+            })))
+            .with_status(202)
+            .with_body(ENQUEUED)
+            .expect(1)
+            .create_async()
+            .await;
+        (create, settings)
+    }
+
+    fn index(server: &ServerGuard) -> MeilisearchIndex {
+        MeilisearchIndex::new(&MeilisearchConfig {
+            url: server.url(),
+            api_key: Some("master-key".to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_delete_stale() {
+        let mut server = Server::new_async().await;
+        let (create, settings) = mock_settings(&mut server).await;
+        let documents = server
+            .mock("POST", "/indexes/programs/documents")
+            .match_query(Matcher::UrlEncoded("primaryKey".into(), "id".into()))
+            .match_body(Matcher::Json(json!([document(1, "アニメ")])))
+            .with_status(202)
+            .with_body(ENQUEUED)
+            .create_async()
+            .await;
+        let delete = server
+            .mock("POST", "/indexes/programs/documents/delete")
+            .match_body(Matcher::Json(json!({
+                "filter": "service_id = 3273601024 AND id NOT IN [1, 2]"
+            })))
+            .with_status(202)
+            .with_body(ENQUEUED)
+            .create_async()
+            .await;
+        let index = index(&server);
+
+        index.upsert(&[document(1, "アニメ")]).await.unwrap();
+        index.delete_stale(3273601024, &[1, 2]).await.unwrap();
+
+        // インデックスの設定は一度だけ
+        create.assert_async().await;
+        settings.assert_async().await;
+        documents.assert_async().await;
+        delete.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_search_with_filters() {
+        let mut server = Server::new_async().await;
+        mock_settings(&mut server).await;
+        let search = server
+            .mock("POST", "/indexes/programs/search")
+            .match_body(Matcher::Json(json!({
+                "q": "\"山田太郎\" アニメ",
+                "filter": [
+                    "genre_lv1 IN [7, 6]",
+                    "service_id IN [3273601024]",
+                    "end_at > 1619856000000",
+                    "start_at < 1619942400000",
+                ],
+                "limit": 5,
+                "offset": 0,
+            })))
+            .with_body(
+                json!({
+                    "hits": [document(1, "アニメ")],
+                    "query": "\"山田太郎\" アニメ",
+                    "estimatedTotalHits": 12,
+                    "limit": 5,
+                    "offset": 0,
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let query = SearchQuery::new("\"山田太郎\" アニメ")
+            .with_genre(7)
+            .with_genre(6)
+            .with_service(3273601024)
+            .with_time_range(1619856000000, 1619942400000)
+            .with_limit(5);
+
+        let results = index(&server).search(&query).await.unwrap();
+
+        search.assert_async().await;
+        assert_eq!(results.total, 12);
+        assert_eq!(results.hits, [document(1, "アニメ")]);
+    }
+
+    #[tokio::test]
+    async fn test_delete_all_documents_of_service() {
+        let mut server = Server::new_async().await;
+        mock_settings(&mut server).await;
+        let delete = server
+            .mock("POST", "/indexes/programs/documents/delete")
+            .match_body(Matcher::Json(json!({"filter": "service_id = 3273601024"})))
+            .with_status(202)
+            .with_body(ENQUEUED)
+            .create_async()
+            .await;
+
+        index(&server).delete_stale(3273601024, &[]).await.unwrap();
+
+        delete.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_error_response() {
+        let mut server = Server::new_async().await;
+        server
+            .mock("POST", "/indexes")
+            .with_status(403)
+            .with_body(
+                r#"{"message":"The provided API key is invalid.","code":"invalid_api_key","type":"auth","link":"https://docs.meilisearch.com/errors#invalid_api_key"}"#,
+            )
+            .create_async()
+            .await;
+
+        let result = index(&server).upsert(&[document(1, "アニメ")]).await;
+
+        match result {
+            Err(DomainError::SearchIndexError(message)) => {
+                assert!(message.contains("403"));
+                assert!(message.contains("The provided API key is invalid."));
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,668 @@
+//! mirakcのOpenAPI定義（`docs/develop/mirakc/docs.json`）からAPIのモデルとエンドポイント関数を生成する
+//!
+//! 生成したコードは `$OUT_DIR/mirakc_api.rs` に書き出し、`src/api.rs` で取り込む。
+//! 扱えない書き方のスキーマが現れたら、黙って読み飛ばさずにビルドを失敗させる。
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::{Map, Value};
+
+const SPEC_PATH: &str = "../../../../docs/develop/mirakc/docs.json";
+
+/// 仕様と実際のmirakcの応答が食い違うプロパティの型の上書き（スキーマ名, プロパティ名, Rustの型）
+const TYPE_OVERRIDES: &[(&str, &str, &str)] = &[
+    // 仕様では整数だが、mirakcは録画IDを文字列で返す
+    ("WebRecord", "id", "String"),
+    // 仕様では任意のオブジェクトだが、値は常に文字列
+    (
+        "MirakurunProgram",
+        "extended",
+        "std::collections::BTreeMap<String, String>",
+    ),
+];
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let spec_path = manifest_dir.join(SPEC_PATH);
+    println!("cargo:rerun-if-changed={}", spec_path.display());
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let spec: Value = serde_json::from_str(
+        &fs::read_to_string(&spec_path)
+            .unwrap_or_else(|e| panic!("{} を読めません: {}", spec_path.display(), e)),
+    )
+    .expect("OpenAPI定義のJSONが不正です");
+
+    let code = Generator::new(&spec).generate();
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("mirakc_api.rs");
+    fs::write(out_path, code).unwrap();
+}
+
+struct Generator<'a> {
+    spec: &'a Value,
+    /// 生成済みの型名と、その元になったスキーマ
+    defined: BTreeMap<String, Value>,
+    types: String,
+}
+
+impl<'a> Generator<'a> {
+    fn new(spec: &'a Value) -> Self {
+        Self {
+            spec,
+            defined: BTreeMap::new(),
+            types: String::new(),
+        }
+    }
+
+    fn generate(mut self) -> String {
+        let spec = self.spec;
+        let schemas = spec["components"]["schemas"]
+            .as_object()
+            .expect("components.schemas がありません");
+        for (name, schema) in schemas {
+            let (ty, _) = self.rust_type(schema, name);
+            if ty != *name {
+                let mut code = doc_comment("", schema);
+                writeln!(code, "pub type {} = {};\n", name, ty).unwrap();
+                self.types.push_str(&code);
+            }
+        }
+
+        let operations = self.operations();
+
+        let mut out = String::new();
+        out.push_str("// @generated by build.rs from docs/develop/mirakc/docs.json\n\n");
+        out.push_str(&self.types);
+        out.push_str(&operations);
+        out.push_str(&self.test_helpers(schemas));
+        out
+    }
+
+    /// スキーマに対応するRustの型と、`null` を取りうるかを返す
+    ///
+    /// 名前の必要な型（構造体・列挙型）は `hint` の名前で定義する。
+    fn rust_type(&mut self, schema: &Value, hint: &str) -> (String, bool) {
+        if let Some(reference) = schema["$ref"].as_str() {
+            return (schema_name(reference).to_string(), false);
+        }
+
+        if let Some(variants) = schema["oneOf"].as_array() {
+            let non_null: Vec<&Value> = variants.iter().filter(|v| !is_null_type(v)).collect();
+            if non_null.len() == 1 && non_null.len() < variants.len() {
+                let (ty, _) = self.rust_type(non_null[0], hint);
+                return (ty, true);
+            }
+            if non_null.len() == variants.len() && variants.iter().all(|v| tag_of(v).is_some()) {
+                self.define(hint, schema, |g| g.tagged_enum(hint, variants));
+                return (hint.to_string(), false);
+            }
+            panic!("{} の oneOf は扱えません: {}", hint, schema);
+        }
+
+        let (ty, nullable) = match &schema["type"] {
+            Value::String(ty) => (ty.as_str(), false),
+            Value::Array(types) => {
+                let non_null: Vec<&str> = types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter(|ty| *ty != "null")
+                    .collect();
+                match non_null[..] {
+                    [ty] => (ty, non_null.len() < types.len()),
+                    _ => panic!("{} の type は扱えません: {}", hint, schema),
+                }
+            }
+            _ => panic!("{} に type がありません: {}", hint, schema),
+        };
+
+        let ty = match ty {
+            "string" if schema.get("enum").is_some() => {
+                self.define(hint, schema, |g| g.string_enum(hint, schema));
+                hint.to_string()
+            }
+            "string" => "String".to_string(),
+            "integer" => match schema["format"].as_str() {
+                Some("int32") => "i32".to_string(),
+                _ => "i64".to_string(),
+            },
+            "number" => "f64".to_string(),
+            "boolean" => "bool".to_string(),
+            "array" => {
+                let (item, _) = self.rust_type(&schema["items"], &singular(hint));
+                format!("Vec<{}>", item)
+            }
+            "object" if schema.get("properties").is_some() => {
+                self.define(hint, schema, |g| g.object(hint, schema));
+                hint.to_string()
+            }
+            "object" => "serde_json::Map<String, serde_json::Value>".to_string(),
+            _ => panic!("{} の type は扱えません: {}", hint, schema),
+        };
+        (ty, nullable)
+    }
+
+    /// 型を一度だけ定義する（同じ名前で異なるスキーマが現れたらビルドを失敗させる）
+    fn define(&mut self, name: &str, schema: &Value, generate: impl FnOnce(&mut Self) -> String) {
+        if let Some(defined) = self.defined.get(name) {
+            assert!(
+                without_docs(defined) == without_docs(schema),
+                "型名 {} が異なるスキーマで重複しています",
+                name
+            );
+            return;
+        }
+        self.defined.insert(name.to_string(), schema.clone());
+        let code = generate(self);
+        self.types.push_str(&code);
+    }
+
+    fn object(&mut self, name: &str, schema: &Value) -> String {
+        let fields = self.fields(name, name, schema, "pub ");
+        let derive_default = fields.iter().all(|field| !field.required);
+
+        let mut code = doc_comment("", schema);
+        code.push_str("#[derive(Debug, Clone, ");
+        if derive_default {
+            code.push_str("Default, ");
+        }
+        code.push_str("PartialEq, serde::Serialize, serde::Deserialize)]\n");
+        writeln!(code, "pub struct {} {{", name).unwrap();
+        for field in fields {
+            code.push_str(&field.code);
+        }
+        code.push_str("}\n\n");
+        code
+    }
+
+    fn fields(&mut self, schema_name: &str, hint: &str, schema: &Value, vis: &str) -> Vec<Field> {
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let properties = schema["properties"]
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+
+        let mut fields = Vec::new();
+        for (property, property_schema) in &properties {
+            let is_required = required.contains(&property.as_str());
+            let (ty, nullable) = match override_of(schema_name, property) {
+                Some(ty) => (ty.to_string(), false),
+                None => self.rust_type(property_schema, &format!("{}{}", hint, pascal(property))),
+            };
+
+            let mut serde_attrs = Vec::new();
+            let name = field_name(property);
+            if name.trim_start_matches("r#") != property.as_str() {
+                serde_attrs.push(format!("rename = \"{}\"", property));
+            }
+            let ty = if !is_required && ty.starts_with("Vec<") && !nullable {
+                serde_attrs.push("default, skip_serializing_if = \"Vec::is_empty\"".to_string());
+                ty
+            } else if !is_required {
+                serde_attrs.push("default, skip_serializing_if = \"Option::is_none\"".to_string());
+                format!("Option<{}>", ty)
+            } else if nullable {
+                format!("Option<{}>", ty)
+            } else {
+                ty
+            };
+
+            let mut code = doc_comment("    ", &property_doc(property_schema));
+            if !serde_attrs.is_empty() {
+                writeln!(code, "    #[serde({})]", serde_attrs.join(", ")).unwrap();
+            }
+            writeln!(code, "    {}{}: {},", vis, name, ty).unwrap();
+            fields.push(Field {
+                required: is_required,
+                code,
+            });
+        }
+        fields
+    }
+
+    fn string_enum(&mut self, name: &str, schema: &Value) -> String {
+        let values: Vec<&str> = schema["enum"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().expect("文字列以外のenumは扱えません"))
+            .collect();
+
+        let mut code = doc_comment("", schema);
+        code.push_str(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\n",
+        );
+        writeln!(code, "pub enum {} {{", name).unwrap();
+        for value in &values {
+            writeln!(code, "    #[serde(rename = \"{}\")]", value).unwrap();
+            writeln!(code, "    {},", pascal(value)).unwrap();
+        }
+        code.push_str("}\n\n");
+
+        writeln!(code, "impl {} {{", name).unwrap();
+        code.push_str("    pub fn as_str(&self) -> &'static str {\n        match self {\n");
+        for value in &values {
+            writeln!(
+                code,
+                "            Self::{} => \"{}\",",
+                pascal(value),
+                value
+            )
+            .unwrap();
+        }
+        code.push_str("        }\n    }\n}\n\n");
+
+        writeln!(code, "impl std::fmt::Display for {} {{", name).unwrap();
+        code.push_str(
+            "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n        f.write_str(self.as_str())\n    }\n}\n\n",
+        );
+        code
+    }
+
+    /// `type` プロパティの値で区別するオブジェクトの oneOf を、タグ付きの列挙型にする
+    fn tagged_enum(&mut self, name: &str, variants: &[Value]) -> String {
+        let mut code = String::new();
+        code.push_str("#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n");
+        code.push_str("#[serde(tag = \"type\")]\n");
+        writeln!(code, "pub enum {} {{", name).unwrap();
+        for variant in variants {
+            let tag = tag_of(variant).unwrap();
+            let variant_name = pascal(tag);
+            let mut variant_schema = variant.clone();
+            variant_schema["properties"]
+                .as_object_mut()
+                .unwrap()
+                .remove("type");
+
+            let fields = self.fields(
+                name,
+                &format!("{}{}", name, variant_name),
+                &variant_schema,
+                "",
+            );
+            writeln!(code, "    #[serde(rename = \"{}\")]", tag).unwrap();
+            if fields.is_empty() {
+                writeln!(code, "    {},", variant_name).unwrap();
+            } else {
+                writeln!(code, "    {} {{", variant_name).unwrap();
+                for field in fields {
+                    for line in field.code.lines() {
+                        writeln!(code, "    {}", line).unwrap();
+                    }
+                }
+                code.push_str("    },\n");
+            }
+        }
+        code.push_str("}\n\n");
+        code
+    }
+
+    fn operations(&mut self) -> String {
+        let spec = self.spec;
+        let base_path = spec["servers"][0]["url"].as_str().unwrap_or("");
+        let paths = spec["paths"].as_object().expect("paths がありません");
+
+        let mut list = String::new();
+        let mut code = String::new();
+        for (path, item) in paths {
+            for method in ["get", "head", "post", "put", "delete", "patch"] {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let operation_id = operation["operationId"]
+                    .as_str()
+                    .unwrap_or_else(|| panic!("{} {} に operationId がありません", method, path));
+                writeln!(
+                    list,
+                    "    (\"{}\", \"{}\", \"{}\"),",
+                    method.to_uppercase(),
+                    path,
+                    operation_id
+                )
+                .unwrap();
+                code.push_str(&self.operation(base_path, path, method, operation_id, operation));
+            }
+        }
+
+        format!(
+            "/// 生成したエンドポイント関数の一覧（メソッド, パス, operationId）\npub const OPERATIONS: &[(&str, &str, &str)] = &[\n{}];\n\n{}",
+            list, code
+        )
+    }
+
+    fn operation(
+        &mut self,
+        base_path: &str,
+        path: &str,
+        method: &str,
+        operation_id: &str,
+        operation: &Value,
+    ) -> String {
+        let fn_name = snake(operation_id);
+        let hint = pascal(operation_id);
+        let parameters = operation["parameters"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        let mut args = vec![
+            "client: &reqwest::Client".to_string(),
+            "base_url: &str".to_string(),
+        ];
+        let mut url = format!("{}{}", base_path, path);
+        let mut url_args = Vec::new();
+        let mut build = String::new();
+
+        // パスパラメーターはパスに現れる順に並べる
+        let mut path_params: Vec<&Value> =
+            parameters.iter().filter(|p| p["in"] == "path").collect();
+        path_params.sort_by_key(|p| path.find(&format!("{{{}}}", p["name"].as_str().unwrap())));
+        for param in path_params {
+            let name = param["name"].as_str().unwrap();
+            let arg = field_name(name);
+            let (ty, _) = self.rust_type(&param["schema"], &format!("{}{}", hint, pascal(name)));
+            args.push(format!("{}: {}", arg, borrowed(&ty)));
+            url = url.replace(&format!("{{{}}}", name), "{}");
+            url_args.push(arg);
+        }
+
+        let mut query = String::new();
+        for param in parameters.iter().filter(|p| p["in"] == "query") {
+            let name = param["name"].as_str().unwrap();
+            let arg = field_name(name);
+            let (ty, _) = self.rust_type(&param["schema"], &format!("{}{}", hint, pascal(name)));
+            if ty.starts_with("Vec<") {
+                args.push(format!("{}: &[String]", arg));
+                writeln!(
+                    query,
+                    "    query.extend({}.iter().map(|value| (\"{}\", value.to_string())));",
+                    arg, name
+                )
+                .unwrap();
+            } else {
+                args.push(format!("{}: Option<{}>", arg, borrowed(&ty)));
+                writeln!(
+                    query,
+                    "    if let Some(value) = {} {{\n        query.push((\"{}\", value.to_string()));\n    }}",
+                    arg, name
+                )
+                .unwrap();
+            }
+        }
+        if !query.is_empty() {
+            build.push_str("    let mut query: Vec<(&str, String)> = Vec::new();\n");
+            build.push_str(&query);
+            build.push_str("    let request = request.query(&query);\n");
+        }
+
+        for param in parameters.iter().filter(|p| p["in"] == "header") {
+            let name = param["name"].as_str().unwrap();
+            let arg = field_name(name);
+            let (ty, _) = self.rust_type(&param["schema"], &format!("{}{}", hint, pascal(name)));
+            args.push(format!("{}: Option<{}>", arg, borrowed(&ty)));
+            writeln!(
+                build,
+                "    let request = match {} {{\n        Some(value) => request.header(\"{}\", value.to_string()),\n        None => request,\n    }};",
+                arg, name
+            )
+            .unwrap();
+        }
+
+        if let Some(body) = operation.get("requestBody") {
+            let schema = &body["content"]["application/json"]["schema"];
+            let (ty, _) = self.rust_type(schema, &format!("{}Body", hint));
+            args.push(format!("body: &{}", ty));
+            build.push_str("    let request = request.json(body);\n");
+        }
+
+        let responses = operation["responses"].as_object().unwrap();
+        let success: Vec<&String> = responses.keys().filter(|s| s.starts_with('2')).collect();
+        let content = success
+            .iter()
+            .find_map(|status| responses[status.as_str()]["content"].as_object());
+        let (return_type, read_body) = match content {
+            Some(content) if content.contains_key("application/json") => {
+                let (ty, _) = self.rust_type(
+                    &content["application/json"]["schema"],
+                    &format!("{}Response", hint),
+                );
+                let read = format!("response.json::<{}>().await?", ty);
+                (ty, read)
+            }
+            Some(content) if content.values().any(|c| c["schema"]["type"] == "string") => {
+                ("String".to_string(), "response.text().await?".to_string())
+            }
+            Some(_) => (
+                "bytes::Bytes".to_string(),
+                "response.bytes().await?".to_string(),
+            ),
+            // ストリームは呼び出し側で読み進める
+            None if method == "get" && path.ends_with("/stream") => {
+                ("reqwest::Response".to_string(), "response".to_string())
+            }
+            None => ("()".to_string(), "()".to_string()),
+        };
+
+        let mut code = String::new();
+        if let Some(summary) = operation["summary"].as_str() {
+            writeln!(code, "/// {}", summary).unwrap();
+            code.push_str("///\n");
+        }
+        writeln!(
+            code,
+            "/// `{} {}{}`",
+            method.to_uppercase(),
+            base_path,
+            path
+        )
+        .unwrap();
+        // 引数は仕様のパラメーターのとおりに並べるため、多すぎる場合もまとめない
+        if args.len() > 7 {
+            code.push_str("#[allow(clippy::too_many_arguments)]\n");
+        }
+        writeln!(
+            code,
+            "pub async fn {}(\n    {},\n) -> Result<{}, ApiError> {{",
+            fn_name,
+            args.join(",\n    "),
+            return_type
+        )
+        .unwrap();
+        if url_args.is_empty() {
+            writeln!(code, "    let url = format!(\"{{}}{}\", base_url);", url).unwrap();
+        } else {
+            writeln!(
+                code,
+                "    let url = format!(\"{{}}{}\", base_url, {});",
+                url,
+                url_args.join(", ")
+            )
+            .unwrap();
+        }
+        writeln!(code, "    let request = client.{}(&url);", method).unwrap();
+        code.push_str(&build);
+        code.push_str("    let response = request.send().await?;\n");
+        code.push_str("    match response.status().as_u16() {\n");
+        writeln!(
+            code,
+            "        {} => Ok({}),",
+            success
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(" | "),
+            read_body
+        )
+        .unwrap();
+        code.push_str("        _ => Err(ApiError::Status(response.status())),\n    }\n}\n\n");
+        code
+    }
+
+    fn test_helpers(&self, schemas: &Map<String, Value>) -> String {
+        let mut code = String::new();
+        code.push_str("/// 仕様と実際の応答の食い違いに合わせて型を上書きしたプロパティ（スキーマ名, プロパティ名, Rustの型）\n");
+        code.push_str(
+            "#[cfg(test)]\npub(crate) const TYPE_OVERRIDES: &[(&str, &str, &str)] = &[\n",
+        );
+        for (schema, property, ty) in TYPE_OVERRIDES {
+            writeln!(code, "    (\"{}\", \"{}\", \"{}\"),", schema, property, ty).unwrap();
+        }
+        code.push_str("];\n\n");
+
+        code.push_str("/// 仕様のスキーマ名に対応する型でJSONを読み込み、書き出し直す\n");
+        code.push_str("#[cfg(test)]\npub(crate) fn round_trip(\n    schema: &str,\n    value: serde_json::Value,\n) -> Option<serde_json::Result<serde_json::Value>> {\n");
+        code.push_str("    let result = match schema {\n");
+        for name in schemas.keys() {
+            writeln!(
+                code,
+                "        \"{}\" => serde_json::from_value::<{}>(value).and_then(serde_json::to_value),",
+                name, name
+            )
+            .unwrap();
+        }
+        code.push_str("        _ => return None,\n    };\n    Some(result)\n}\n");
+        code
+    }
+}
+
+struct Field {
+    required: bool,
+    code: String,
+}
+
+fn schema_name(reference: &str) -> &str {
+    reference
+        .strip_prefix("#/components/schemas/")
+        .unwrap_or_else(|| panic!("{} は扱えません", reference))
+}
+
+fn is_null_type(schema: &Value) -> bool {
+    schema["type"] == "null"
+}
+
+/// oneOf の要素が `type` の値で区別できるオブジェクトなら、その値を返す
+fn tag_of(schema: &Value) -> Option<&str> {
+    let values = schema["properties"]["type"]["enum"].as_array()?;
+    match &values[..] {
+        [Value::String(tag)] => Some(tag.as_str()),
+        _ => None,
+    }
+}
+
+fn override_of(schema: &str, property: &str) -> Option<&'static str> {
+    TYPE_OVERRIDES
+        .iter()
+        .find(|(s, p, _)| *s == schema && *p == property)
+        .map(|(_, _, ty)| *ty)
+}
+
+/// プロパティの説明（`null` との oneOf なら、中の説明）
+fn property_doc(schema: &Value) -> Value {
+    if schema.get("description").is_some() {
+        return schema.clone();
+    }
+    schema["oneOf"]
+        .as_array()
+        .and_then(|variants| variants.iter().find(|v| v.get("description").is_some()))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn doc_comment(indent: &str, schema: &Value) -> String {
+    let Some(description) = schema["description"].as_str().or(schema["title"].as_str()) else {
+        return String::new();
+    };
+    let mut code = String::new();
+    for line in description.lines() {
+        if line.is_empty() {
+            writeln!(code, "{}///", indent).unwrap();
+        } else {
+            writeln!(code, "{}/// {}", indent, line).unwrap();
+        }
+    }
+    code
+}
+
+/// 型の重複を比べるときは説明の違いを無視する
+fn without_docs(schema: &Value) -> Value {
+    match schema {
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .filter(|(key, _)| *key != "description" && *key != "title")
+                .map(|(key, value)| (key.clone(), without_docs(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(without_docs).collect()),
+        value => value.clone(),
+    }
+}
+
+/// 引数として受け取る型（文字列は借用する）
+fn borrowed(ty: &str) -> String {
+    match ty {
+        "String" => "&str".to_string(),
+        ty => ty.to_string(),
+    }
+}
+
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        current.push(c.to_ascii_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn pascal(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn snake(name: &str) -> String {
+    words(name).join("_")
+}
+
+fn field_name(name: &str) -> String {
+    let name = snake(name);
+    match name.as_str() {
+        "type" | "match" | "ref" | "self" | "move" | "mod" | "use" => format!("r#{}", name),
+        _ => name,
+    }
+}
+
+/// 配列の要素の型名（`Services` → `Service`）
+fn singular(name: &str) -> String {
+    name.strip_suffix('s').unwrap_or(name).to_string()
+}
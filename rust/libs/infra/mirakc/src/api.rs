@@ -0,0 +1,171 @@
+//! mirakc Web APIのモデルとエンドポイント関数
+//!
+//! `docs/develop/mirakc/docs.json` からビルド時に生成する（`build.rs`）。
+//! 生成できない部分だけをここに書き、生成したコードが仕様どおりかはテストで確かめる。
+
+use reqwest::StatusCode;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ApiError {
+    #[error("HTTP リクエストエラー: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("予期しないステータスコード: {0}")]
+    Status(StatusCode),
+}
+
+mod generated {
+    use super::ApiError;
+
+    include!(concat!(env!("OUT_DIR"), "/mirakc_api.rs"));
+}
+
+pub use generated::*;
+
+impl MirakurunProgram {
+    pub fn get_extended_description(&self) -> Option<String> {
+        self.extended.as_ref().map(|extended| {
+            extended
+                .iter()
+                .map(|(key, value)| format!("{}：{}", key, value))
+                .collect::<Vec<String>>()
+                .join("\n")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::generated::{OPERATIONS, TYPE_OVERRIDES, round_trip};
+    use serde_json::{Value, json};
+
+    const SPEC: &str = include_str!("../../../../../docs/develop/mirakc/docs.json");
+
+    fn spec() -> Value {
+        serde_json::from_str(SPEC).unwrap()
+    }
+
+    /// スキーマに従うJSONを作る（`full` なら必須でないプロパティも含める）
+    fn sample(spec: &Value, schema_name: Option<&str>, schema: &Value, full: bool) -> Value {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            return sample(spec, Some(name), &spec["components"]["schemas"][name], full);
+        }
+        if let Some(variants) = schema["oneOf"].as_array() {
+            let variant = variants.iter().find(|v| v["type"] != "null").unwrap();
+            return sample(spec, schema_name, variant, full);
+        }
+
+        let ty = match &schema["type"] {
+            Value::Array(types) => types.iter().find(|ty| *ty != "null").unwrap().clone(),
+            ty => ty.clone(),
+        };
+        match ty.as_str().unwrap() {
+            "string" => schema["enum"].get(0).cloned().unwrap_or(json!("string")),
+            "integer" => json!(1),
+            "number" => json!(1.5),
+            "boolean" => json!(true),
+            "array" => json!([sample(spec, None, &schema["items"], full)]),
+            "object" => {
+                let Some(properties) = schema["properties"].as_object() else {
+                    return json!({"key": "value"});
+                };
+                let required = schema["required"].as_array().cloned().unwrap_or_default();
+                let mut object = serde_json::Map::new();
+                for (property, property_schema) in properties {
+                    if !full && !required.contains(&json!(property)) {
+                        continue;
+                    }
+                    let value = match override_of(schema_name, property) {
+                        Some(ty) => override_sample(ty),
+                        None => sample(spec, None, property_schema, full),
+                    };
+                    object.insert(property.clone(), value);
+                }
+                Value::Object(object)
+            }
+            ty => panic!("type {} のサンプルは作れません", ty),
+        }
+    }
+
+    fn override_of(schema_name: Option<&str>, property: &str) -> Option<&'static str> {
+        TYPE_OVERRIDES
+            .iter()
+            .find(|(s, p, _)| Some(*s) == schema_name && *p == property)
+            .map(|(_, _, ty)| *ty)
+    }
+
+    fn override_sample(ty: &str) -> Value {
+        match ty {
+            "String" => json!("0000018f2b9c1a00"),
+            "std::collections::BTreeMap<String, String>" => json!({"key": "value"}),
+            ty => panic!("上書きした型 {} のサンプルを追加してください", ty),
+        }
+    }
+
+    #[test]
+    fn test_generated_types_match_spec() {
+        let spec = spec();
+        for (name, schema) in spec["components"]["schemas"].as_object().unwrap() {
+            // タグ付きの oneOf はすべての要素を試す
+            let variants = match schema["oneOf"].as_array() {
+                Some(variants) => variants.clone(),
+                None => vec![schema.clone()],
+            };
+            for variant in &variants {
+                for full in [true, false] {
+                    let value = sample(&spec, Some(name), variant, full);
+                    let result = round_trip(name, value.clone())
+                        .unwrap_or_else(|| panic!("{} の型が生成されていません", name));
+                    match result {
+                        Ok(written) => {
+                            assert_eq!(written, value, "{} の読み書きが一致しません", name)
+                        }
+                        Err(e) => panic!("{} を読めません: {} ({})", name, e, value),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_operations_match_spec() {
+        let spec = spec();
+        let mut expected = Vec::new();
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            for (method, operation) in item.as_object().unwrap() {
+                if let Some(operation_id) = operation["operationId"].as_str() {
+                    expected.push((
+                        method.to_uppercase(),
+                        path.clone(),
+                        operation_id.to_string(),
+                    ));
+                }
+            }
+        }
+        expected.sort();
+
+        let mut generated: Vec<(String, String, String)> = OPERATIONS
+            .iter()
+            .map(|(method, path, id)| (method.to_string(), path.to_string(), id.to_string()))
+            .collect();
+        generated.sort();
+
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn test_type_overrides_refer_to_spec() {
+        let spec = spec();
+        for (schema, property, _) in TYPE_OVERRIDES {
+            assert!(
+                spec["components"]["schemas"][schema]["properties"]
+                    .get(property)
+                    .is_some(),
+                "上書き対象の {}.{} が仕様にありません",
+                schema,
+                property
+            );
+        }
+    }
+}
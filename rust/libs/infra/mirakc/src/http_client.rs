@@ -1,20 +1,48 @@
 use reqwest::{Client, StatusCode};
-use serde::Deserialize;
-use std::collections::BTreeMap;
 use std::time::Duration;
 use thiserror::Error;
 use tracing::{debug, error};
 
+use crate::api::{
+    self, ApiError, MirakurunChannel, MirakurunProgram, MirakurunService, MirakurunTuner, Version,
+    WebOnairProgram,
+};
+
+mod recording;
+
 #[derive(Error, Debug)]
 pub enum MirakcApiError {
     #[error("HTTP リクエストエラー: {0}")]
     RequestError(#[from] reqwest::Error),
     #[error("サービス(ID={0})が見つかりません")]
     ServiceNotFound(i64),
+    #[error("番組(ID={0})が見つかりません")]
+    ProgramNotFound(i64),
+    #[error("番組(ID={0})の録画予約はすでに存在します")]
+    ScheduleAlreadyExists(i64),
+    #[error("番組(ID={0})の録画予約が見つかりません")]
+    ScheduleNotFound(i64),
+    #[error("番組(ID={0})を録画しているレコーダーが見つかりません")]
+    RecorderNotFound(i64),
+    #[error("録画(ID={0})が見つかりません")]
+    RecordNotFound(String),
     #[error("不明なエラー: {0}")]
     UnknownError(String),
 }
 
+impl From<ApiError> for MirakcApiError {
+    fn from(e: ApiError) -> Self {
+        match e {
+            ApiError::Request(e) => Self::RequestError(e),
+            ApiError::Status(status) => {
+                error!("Unexpected status code: {}", status);
+                Self::UnknownError(format!("Unexpected status code: {}", status))
+            }
+        }
+    }
+}
+
+/// 生成したエンドポイント関数に、kurecで使うステータスコードの意味づけを加えたクライアント
 #[derive(Clone, Debug)]
 pub struct MirakcApiClient {
     base_url: String,
@@ -38,145 +66,79 @@ impl MirakcApiClient {
         &self,
         service_id: i64,
     ) -> Result<Vec<MirakurunProgram>, MirakcApiError> {
-        let url = format!("{}/api/services/{}/programs", self.base_url, service_id);
-        debug!("Fetching programs from: {}", url);
-
-        let response = self.client.get(&url).send().await?;
-
-        match response.status() {
-            StatusCode::OK => {
-                let programs = response.json::<Vec<MirakurunProgram>>().await?;
+        debug!("Fetching programs of service {}", service_id);
+        match api::get_programs_of_service(&self.client, &self.base_url, service_id).await {
+            Ok(programs) => {
                 debug!("Got {} programs for service {}", programs.len(), service_id);
                 Ok(programs)
             }
-            StatusCode::NOT_FOUND => {
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
                 error!("Service not found: {}", service_id);
                 Err(MirakcApiError::ServiceNotFound(service_id))
             }
-            status => {
-                error!("Unexpected status code: {}", status);
-                Err(MirakcApiError::UnknownError(format!(
-                    "Unexpected status code: {}",
-                    status
-                )))
-            }
+            Err(e) => Err(e.into()),
         }
     }
 
-    pub async fn get_service(&self, service_id: i64) -> Result<MirakurunService, MirakcApiError> {
-        let url = format!("{}/api/services/{}", self.base_url, service_id);
-        debug!("Fetching service from: {}", url);
+    pub async fn get_version(&self) -> Result<Version, MirakcApiError> {
+        Ok(api::check_version(&self.client, &self.base_url).await?)
+    }
 
-        let response = self.client.get(&url).send().await?;
+    pub async fn get_services(&self) -> Result<Vec<MirakurunService>, MirakcApiError> {
+        let services = api::get_services(&self.client, &self.base_url).await?;
+        debug!("Got {} services", services.len());
+        Ok(services)
+    }
 
-        match response.status() {
-            StatusCode::OK => {
-                let service = response.json::<MirakurunService>().await?;
+    pub async fn get_service(&self, service_id: i64) -> Result<MirakurunService, MirakcApiError> {
+        match api::get_service(&self.client, &self.base_url, service_id).await {
+            Ok(service) => {
                 debug!("Got service: {}", service.name);
                 Ok(service)
             }
-            StatusCode::NOT_FOUND => {
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
                 error!("Service not found: {}", service_id);
                 Err(MirakcApiError::ServiceNotFound(service_id))
             }
-            status => {
-                error!("Unexpected status code: {}", status);
-                Err(MirakcApiError::UnknownError(format!(
-                    "Unexpected status code: {}",
-                    status
-                )))
-            }
+            Err(e) => Err(e.into()),
         }
     }
-}
-
-#[derive(Debug, Clone, Deserialize)]
-pub struct MirakurunProgram {
-    pub id: i64,
-    #[serde(rename = "eventId")]
-    pub event_id: i32,
-    #[serde(rename = "serviceId")]
-    pub service_id: i32,
-    #[serde(rename = "networkId")]
-    pub network_id: i32,
-    #[serde(rename = "startAt")]
-    pub start_at: i64,
-    pub duration: i64,
-    #[serde(rename = "isFree")]
-    pub is_free: bool,
-    pub name: Option<String>,
-    pub description: Option<String>,
-    pub extended: Option<BTreeMap<String, String>>,
-    pub video: Option<MirakurunVideo>,
-    pub audio: Option<MirakurunAudio>,
-    pub audios: Option<Vec<MirakurunAudio>>,
-    pub genres: Option<Vec<MirakurunGenre>>,
-    #[serde(rename = "relatedItems")]
-    pub related_items: Option<Vec<MirakurunRelatedItem>>,
-}
 
-impl MirakurunProgram {
-    pub fn get_extended_description(&self) -> Option<String> {
-        self.extended.as_ref().map(|extended| {
-            extended
-                .iter()
-                .map(|(key, value)| format!("{}：{}", key, value))
-                .collect::<Vec<String>>()
-                .join("\n")
-        })
+    pub async fn get_tuners(&self) -> Result<Vec<MirakurunTuner>, MirakcApiError> {
+        let tuners = api::get_tuners(&self.client, &self.base_url).await?;
+        debug!("Got {} tuners", tuners.len());
+        Ok(tuners)
     }
-}
-
-#[derive(Debug, Clone, Deserialize)]
-pub struct MirakurunGenre {
-    pub lv1: i32,
-    pub lv2: i32,
-    pub un1: i32,
-    pub un2: i32,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-pub struct MirakurunVideo {
-    pub r#type: Option<String>,
-    pub resolution: Option<String>,
-    #[serde(rename = "streamContent")]
-    pub stream_content: Option<u8>,
-    #[serde(rename = "componentType")]
-    pub component_type: Option<u8>,
-}
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct MirakurunAudio {
-    #[serde(rename = "componentType")]
-    pub component_type: Option<u8>,
-    #[serde(rename = "isMain")]
-    pub is_main: Option<bool>,
-    #[serde(rename = "samplingRate")]
-    pub sampling_rate: Option<u32>,
-    pub langs: Option<Vec<String>>,
-}
+    pub async fn get_channels(&self) -> Result<Vec<MirakurunChannel>, MirakcApiError> {
+        let channels = api::get_channels(&self.client, &self.base_url).await?;
+        debug!("Got {} channels", channels.len());
+        Ok(channels)
+    }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct MirakurunRelatedItem {
-    pub r#type: String,
-    #[serde(rename = "networkId")]
-    pub network_id: Option<i32>,
-    #[serde(rename = "serviceId")]
-    pub service_id: i32,
-    #[serde(rename = "eventId")]
-    pub event_id: i32,
-}
+    pub async fn get_onair_programs(&self) -> Result<Vec<WebOnairProgram>, MirakcApiError> {
+        let programs = api::get_onair_programs(&self.client, &self.base_url).await?;
+        debug!("Got on-air programs of {} services", programs.len());
+        Ok(programs)
+    }
 
-#[derive(Debug, Clone, Deserialize)]
-pub struct MirakurunService {
-    pub id: i64,
-    #[serde(rename = "serviceId")]
-    pub service_id: i32,
-    #[serde(rename = "networkId")]
-    pub network_id: i32,
-    #[serde(rename = "type")]
-    pub service_type: i32,
-    pub name: String,
+    pub async fn get_onair_program(
+        &self,
+        service_id: i64,
+    ) -> Result<WebOnairProgram, MirakcApiError> {
+        // 仕様では配列で返るが、要素は指定したサービスの1つだけ
+        match api::get_onair_program(&self.client, &self.base_url, service_id).await {
+            Ok(programs) => programs
+                .into_iter()
+                .find(|program| program.service_id == service_id)
+                .ok_or(MirakcApiError::ServiceNotFound(service_id)),
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                error!("Service not found: {}", service_id);
+                Err(MirakcApiError::ServiceNotFound(service_id))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -197,7 +159,9 @@ mod tests {
                     "serviceId": 23608,
                     "networkId": 32391,
                     "type": 1,
-                    "name": "テストチャンネル"
+                    "name": "テストチャンネル",
+                    "channel": {"type": "GR", "channel": "27"},
+                    "hasLogoData": false
                 });
 
                 Response::builder()
@@ -279,7 +243,127 @@ mod tests {
                     .body(serde_json::to_string(&programs).unwrap())
             });
 
-        let routes = service_route.or(programs_route);
+        let services_route = warp::path!("api" / "services").map(|| {
+            Response::builder()
+                .header("content-type", "application/json")
+                .body(
+                    json!([
+                        {
+                            "id": 1,
+                            "serviceId": 1,
+                            "networkId": 32391,
+                            "type": 1,
+                            "name": "テストチャンネル1",
+                            "channel": {"type": "GR", "channel": "27"},
+                            "hasLogoData": false
+                        },
+                        {
+                            "id": 23608,
+                            "serviceId": 23608,
+                            "networkId": 32391,
+                            "type": 1,
+                            "name": "テストチャンネル2",
+                            "channel": {"type": "GR", "channel": "27"},
+                            "hasLogoData": false
+                        }
+                    ])
+                    .to_string(),
+                )
+        });
+
+        let version_route = warp::path!("api" / "version").map(|| {
+            Response::builder()
+                .header("content-type", "application/json")
+                .body(json!({"current": "3.4.0", "latest": "3.4.1"}).to_string())
+        });
+
+        let tuners_route = warp::path!("api" / "tuners").map(|| {
+            Response::builder()
+                .header("content-type", "application/json")
+                .body(
+                    json!([
+                        {
+                            "index": 0,
+                            "name": "PX-Q3PE4 (GR)",
+                            "types": ["GR"],
+                            "command": "recpt1 --device /dev/px4video2 27 - -",
+                            "pid": 1234,
+                            "users": [
+                                {"id": "recorder", "priority": 5, "agent": null}
+                            ],
+                            "isAvailable": true,
+                            "isRemote": false,
+                            "isFree": false,
+                            "isUsing": true,
+                            "isFault": false
+                        },
+                        {
+                            "index": 1,
+                            "name": "PX-Q3PE4 (BS/CS)",
+                            "types": ["BS", "CS"],
+                            "command": null,
+                            "pid": null,
+                            "users": [],
+                            "isAvailable": true,
+                            "isRemote": false,
+                            "isFree": true,
+                            "isUsing": false,
+                            "isFault": false
+                        }
+                    ])
+                    .to_string(),
+                )
+        });
+
+        let channels_route = warp::path!("api" / "channels").map(|| {
+            Response::builder()
+                .header("content-type", "application/json")
+                .body(
+                    json!([
+                        {
+                            "type": "GR",
+                            "channel": "27",
+                            "name": "NHK総合",
+                            "services": [
+                                {
+                                    "id": 3273601024i64,
+                                    "serviceId": 1024,
+                                    "networkId": 32736,
+                                    "name": "ＮＨＫ総合１・東京"
+                                }
+                            ]
+                        }
+                    ])
+                    .to_string(),
+                )
+        });
+
+        let onair_route = warp::path!("api" / "onair").map(|| {
+            Response::builder()
+                .header("content-type", "application/json")
+                .body(json!([onair_program(1), {"serviceId": 23608}]).to_string())
+        });
+
+        let onair_service_route = warp::path!("api" / "onair" / i64).map(|service_id: i64| {
+            if service_id == 1 {
+                Response::builder()
+                    .header("content-type", "application/json")
+                    .body(json!([onair_program(1)]).to_string())
+            } else {
+                Response::builder()
+                    .status(404)
+                    .body("Not Found".to_string())
+            }
+        });
+
+        let routes = services_route
+            .or(service_route)
+            .or(programs_route)
+            .or(version_route)
+            .or(tuners_route)
+            .or(channels_route)
+            .or(onair_route)
+            .or(onair_service_route);
 
         let (addr, server) =
             warp::serve(routes).bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
@@ -292,6 +376,23 @@ mod tests {
         (url, tx)
     }
 
+    fn onair_program(service_id: i64) -> serde_json::Value {
+        json!({
+            "serviceId": service_id,
+            "current": {
+                "id": service_id * 100000 + 1001,
+                "eventId": 1001,
+                "serviceId": service_id,
+                "networkId": 32391,
+                "startAt": 1619856000000i64,
+                "duration": 1800000,
+                "isFree": true,
+                "name": "放送中の番組"
+            },
+            "next": null
+        })
+    }
+
     #[tokio::test]
     async fn test_get_service() {
         let (url, tx) = create_mock_server();
@@ -303,6 +404,72 @@ mod tests {
         assert_eq!(service.service_id, 23608);
         assert_eq!(service.network_id, 32391);
         assert_eq!(service.name, "テストチャンネル");
+        assert_eq!(service.channel.r#type, api::ChannelType::Gr);
+        assert_eq!(service.channel.channel, "27");
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_get_services() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let services = client.get_services().await.unwrap();
+
+        let ids: Vec<i64> = services.iter().map(|s| s.id).collect();
+        assert_eq!(ids, [1, 23608]);
+        assert_eq!(services[1].name, "テストチャンネル2");
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_get_tuners() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let tuners = client.get_tuners().await.unwrap();
+
+        assert_eq!(tuners.len(), 2);
+        assert_eq!(tuners[0].name, "PX-Q3PE4 (GR)");
+        assert_eq!(tuners[0].types, [api::ChannelType::Gr]);
+        assert_eq!(tuners[0].users[0].id, "recorder");
+        assert!(tuners[0].is_using);
+        assert_eq!(tuners[1].index, 1);
+        assert_eq!(
+            tuners[1].types,
+            [api::ChannelType::Bs, api::ChannelType::Cs]
+        );
+        assert_eq!(tuners[1].command, None);
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_get_channels() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let channels = client.get_channels().await.unwrap();
+
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].r#type, api::ChannelType::Gr);
+        assert_eq!(channels[0].channel, "27");
+        assert_eq!(channels[0].services[0].id, 3273601024);
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_get_version() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let version = client.get_version().await.unwrap();
+
+        assert_eq!(version.current, "3.4.0");
+        assert_eq!(version.latest, "3.4.1");
 
         let _ = tx.send(());
     }
@@ -341,14 +508,16 @@ mod tests {
         let video = program.video.as_ref().unwrap();
         assert_eq!(video.r#type, Some("mpeg2".to_string()));
         assert_eq!(video.resolution, Some("1080i".to_string()));
-        assert_eq!(video.component_type, Some(179));
+        assert_eq!(video.component_type, 179);
 
         assert!(program.audio.is_some());
         let audio = program.audio.as_ref().unwrap();
-        assert_eq!(audio.component_type, Some(3));
-        assert_eq!(audio.is_main, Some(true));
-        assert_eq!(audio.sampling_rate, Some(48000));
-        assert_eq!(audio.langs, Some(vec!["jpn".to_string()]));
+        assert_eq!(audio.component_type, 3);
+        assert!(audio.is_main);
+        assert_eq!(audio.sampling_rate, 48000);
+        assert_eq!(audio.langs, ["jpn"]);
+        assert_eq!(program.audios.len(), 1);
+        assert_eq!(&program.audios[0], audio);
 
         assert!(program.genres.is_some());
         let genres = program.genres.as_ref().unwrap();
@@ -356,8 +525,7 @@ mod tests {
         assert_eq!(genres[0].lv1, 7);
         assert_eq!(genres[0].lv2, 0);
 
-        assert!(program.related_items.is_some());
-        let related_items = program.related_items.as_ref().unwrap();
+        let related_items = &program.related_items;
         assert_eq!(related_items.len(), 2);
         assert_eq!(related_items[0].r#type, "shared");
         assert_eq!(related_items[0].service_id, 1);
@@ -377,4 +545,37 @@ mod tests {
 
         let _ = tx.send(());
     }
+
+    #[tokio::test]
+    async fn test_get_onair_programs() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let programs = client.get_onair_programs().await.unwrap();
+
+        assert_eq!(programs.len(), 2);
+        assert_eq!(programs[0].service_id, 1);
+        assert_eq!(
+            programs[0].current.as_ref().unwrap().name.as_deref(),
+            Some("放送中の番組")
+        );
+        assert!(programs[0].next.is_none());
+        assert!(programs[1].current.is_none());
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_get_onair_program() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let program = client.get_onair_program(1).await.unwrap();
+        assert_eq!(program.current.unwrap().id, 101001);
+
+        let result = client.get_onair_program(999).await;
+        assert!(matches!(result, Err(MirakcApiError::ServiceNotFound(999))));
+
+        let _ = tx.send(());
+    }
 }
@@ -0,0 +1,472 @@
+//! mirakcの録画API（`/api/recording/*`）
+
+use reqwest::StatusCode;
+use tracing::{debug, error};
+
+use super::{MirakcApiClient, MirakcApiError};
+use crate::api::{
+    self, ApiError, WebRecord, WebRecordingRecorder, WebRecordingSchedule,
+    WebRecordingScheduleInput,
+};
+
+impl MirakcApiClient {
+    pub async fn get_recording_schedules(
+        &self,
+    ) -> Result<Vec<WebRecordingSchedule>, MirakcApiError> {
+        let schedules = api::get_recording_schedules(&self.client, &self.base_url).await?;
+        debug!("Got {} recording schedules", schedules.len());
+        Ok(schedules)
+    }
+
+    pub async fn get_recording_schedule(
+        &self,
+        program_id: i64,
+    ) -> Result<WebRecordingSchedule, MirakcApiError> {
+        match api::get_recording_schedule(&self.client, &self.base_url, program_id).await {
+            Ok(schedule) => Ok(schedule),
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                Err(MirakcApiError::ScheduleNotFound(program_id))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn create_recording_schedule(
+        &self,
+        input: &WebRecordingScheduleInput,
+    ) -> Result<WebRecordingSchedule, MirakcApiError> {
+        match api::create_recording_schedule(&self.client, &self.base_url, input).await {
+            Ok(schedule) => {
+                debug!(
+                    "Created recording schedule for program {}",
+                    input.program_id
+                );
+                Ok(schedule)
+            }
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                error!("Program not found: {}", input.program_id);
+                Err(MirakcApiError::ProgramNotFound(input.program_id))
+            }
+            // 仕様には書かれていないが、mirakcは予約の重複に409を返す
+            Err(ApiError::Status(StatusCode::CONFLICT)) => {
+                debug!("Recording schedule already exists: {}", input.program_id);
+                Err(MirakcApiError::ScheduleAlreadyExists(input.program_id))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn delete_recording_schedule(&self, program_id: i64) -> Result<(), MirakcApiError> {
+        match api::delete_recording_schedule(&self.client, &self.base_url, program_id).await {
+            Ok(()) => Ok(()),
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                Err(MirakcApiError::ScheduleNotFound(program_id))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 録画予約をまとめて削除する（`tag` を指定した場合はそのタグを持つものだけ）
+    ///
+    /// mirakcは録画中の予約を削除しない。
+    pub async fn delete_recording_schedules(
+        &self,
+        tag: Option<&str>,
+    ) -> Result<(), MirakcApiError> {
+        Ok(api::delete_recording_schedules(&self.client, &self.base_url, tag).await?)
+    }
+
+    pub async fn get_recorders(&self) -> Result<Vec<WebRecordingRecorder>, MirakcApiError> {
+        Ok(api::get_recorders(&self.client, &self.base_url).await?)
+    }
+
+    pub async fn get_recorder(
+        &self,
+        program_id: i64,
+    ) -> Result<WebRecordingRecorder, MirakcApiError> {
+        match api::get_recorder(&self.client, &self.base_url, program_id).await {
+            Ok(recorder) => Ok(recorder),
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                Err(MirakcApiError::RecorderNotFound(program_id))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 予約を作らずにすぐ録画を始める
+    pub async fn start_recording(
+        &self,
+        input: &WebRecordingScheduleInput,
+    ) -> Result<(), MirakcApiError> {
+        match api::start_recording(&self.client, &self.base_url, input).await {
+            Ok(()) => Ok(()),
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                Err(MirakcApiError::ProgramNotFound(input.program_id))
+            }
+            Err(ApiError::Status(StatusCode::CONFLICT)) => {
+                Err(MirakcApiError::ScheduleAlreadyExists(input.program_id))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn stop_recording(&self, program_id: i64) -> Result<(), MirakcApiError> {
+        match api::stop_recording(&self.client, &self.base_url, program_id).await {
+            Ok(()) => Ok(()),
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                Err(MirakcApiError::RecorderNotFound(program_id))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn get_records(&self) -> Result<Vec<WebRecord>, MirakcApiError> {
+        let records = api::get_records(&self.client, &self.base_url).await?;
+        debug!("Got {} records", records.len());
+        Ok(records)
+    }
+
+    pub async fn get_record(&self, id: &str) -> Result<WebRecord, MirakcApiError> {
+        match api::get_record(&self.client, &self.base_url, id).await {
+            Ok(record) => Ok(record),
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                Err(MirakcApiError::RecordNotFound(id.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 録画を削除する（`purge` が `true` ならコンテンツのファイルも削除する）
+    pub async fn remove_record(&self, id: &str, purge: bool) -> Result<(), MirakcApiError> {
+        match api::remove_record(&self.client, &self.base_url, id, Some(purge)).await {
+            Ok(()) => Ok(()),
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => {
+                Err(MirakcApiError::RecordNotFound(id.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{
+        RecordingFailedReason, RecordingOptions, RecordingScheduleState, WebRecordingStatus,
+    };
+    use serde_json::{Value, json};
+    use tokio::sync::oneshot;
+    use warp::Filter;
+    use warp::http::Response;
+
+    fn program_json(program_id: i64) -> Value {
+        json!({
+            "id": program_id,
+            "eventId": 1001,
+            "serviceId": 1024,
+            "networkId": 32736,
+            "startAt": 1619856000000i64,
+            "duration": 1800000,
+            "isFree": true,
+            "name": "テスト番組"
+        })
+    }
+
+    fn schedule_json(program_id: i64, tags: Value) -> Value {
+        json!({
+            "state": "scheduled",
+            "program": program_json(program_id),
+            "options": {"contentPath": "test.m2ts", "priority": 5, "preFilters": [], "postFilters": []},
+            "tags": tags
+        })
+    }
+
+    fn record_json() -> Value {
+        json!({
+            "id": "0000018f2b9c1a00",
+            "program": program_json(327360102401001),
+            "service": {
+                "id": 3273601024i64,
+                "serviceId": 1024,
+                "networkId": 32736,
+                "type": 1,
+                "name": "テストチャンネル",
+                "channel": {"type": "GR", "channel": "27"},
+                "hasLogoData": true
+            },
+            "tags": ["rule:test"],
+            "recording": {
+                "options": {"contentPath": "test.m2ts", "priority": 5},
+                "status": "failed",
+                "startTime": 1619856000000i64,
+                "endTime": 1619857800000i64,
+                "duration": 1800000,
+                "failedReason": {"type": "pipeline-error", "exitCode": 1}
+            },
+            "content": {"path": "test.m2ts", "type": "video/MP2T", "length": 1024}
+        })
+    }
+
+    fn response(status: u16, body: Value) -> warp::http::Result<Response<String>> {
+        Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(body.to_string())
+    }
+
+    /// 番組ID 327360102401001 の予約・レコーダー・録画だけを知っているmirakcのモック
+    fn create_mock_server() -> (String, oneshot::Sender<()>) {
+        let (tx, rx) = oneshot::channel();
+        const KNOWN: i64 = 327360102401001;
+
+        let list_schedules = warp::get()
+            .and(warp::path!("api" / "recording" / "schedules"))
+            .map(|| response(200, json!([schedule_json(KNOWN, json!(["rule:test"]))])));
+        let get_schedule = warp::get()
+            .and(warp::path!("api" / "recording" / "schedules" / i64))
+            .map(|program_id: i64| match program_id {
+                KNOWN => response(200, schedule_json(KNOWN, json!([]))),
+                _ => response(404, Value::Null),
+            });
+        let create_schedule = warp::post()
+            .and(warp::path!("api" / "recording" / "schedules"))
+            .and(warp::body::json())
+            .map(|input: Value| match input["programId"].as_i64() {
+                Some(KNOWN) => response(409, Value::Null),
+                Some(program_id) if input["options"]["priority"] == 5 => {
+                    response(201, schedule_json(program_id, input["tags"].clone()))
+                }
+                _ => response(404, Value::Null),
+            });
+        let delete_schedules = warp::delete()
+            .and(warp::path!("api" / "recording" / "schedules"))
+            .and(warp::query::<std::collections::HashMap<String, String>>())
+            .map(|query: std::collections::HashMap<String, String>| {
+                match query.get("tag").map(String::as_str) {
+                    None | Some("rule:test") => response(200, Value::Null),
+                    _ => response(500, Value::Null),
+                }
+            });
+        let delete_schedule = warp::delete()
+            .and(warp::path!("api" / "recording" / "schedules" / i64))
+            .map(|program_id: i64| match program_id {
+                KNOWN => response(200, Value::Null),
+                _ => response(404, Value::Null),
+            });
+
+        let list_recorders = warp::get()
+            .and(warp::path!("api" / "recording" / "recorders"))
+            .map(|| {
+                response(
+                    200,
+                    json!([{
+                        "programId": KNOWN,
+                        "startedAt": 1619856000000i64,
+                        "pipeline": [{"command": "recdvb", "pid": 1234}, {"command": "cat", "pid": null}]
+                    }]),
+                )
+            });
+        let start_recording = warp::post()
+            .and(warp::path!("api" / "recording" / "recorders"))
+            .and(warp::body::json())
+            .map(|input: Value| match input["programId"].as_i64() {
+                Some(KNOWN) => response(201, Value::Null),
+                _ => response(404, Value::Null),
+            });
+        let stop_recording = warp::delete()
+            .and(warp::path!("api" / "recording" / "recorders" / i64))
+            .map(|program_id: i64| match program_id {
+                KNOWN => response(201, Value::Null),
+                _ => response(404, Value::Null),
+            });
+
+        let list_records = warp::get()
+            .and(warp::path!("api" / "recording" / "records"))
+            .map(|| response(200, json!([record_json()])));
+        let get_record = warp::get()
+            .and(warp::path!("api" / "recording" / "records" / String))
+            .map(|id: String| match id.as_str() {
+                "0000018f2b9c1a00" => response(200, record_json()),
+                _ => response(404, Value::Null),
+            });
+        let remove_record = warp::delete()
+            .and(warp::path!("api" / "recording" / "records" / String))
+            .and(warp::query::<std::collections::HashMap<String, String>>())
+            .map(
+                |id: String, query: std::collections::HashMap<String, String>| match (
+                    id.as_str(),
+                    query.get("purge").map(String::as_str),
+                ) {
+                    ("0000018f2b9c1a00", Some("true")) => response(200, Value::Null),
+                    _ => response(404, Value::Null),
+                },
+            );
+
+        let routes = list_schedules
+            .or(get_schedule)
+            .or(create_schedule)
+            .or(delete_schedules)
+            .or(delete_schedule)
+            .or(list_recorders)
+            .or(start_recording)
+            .or(stop_recording)
+            .or(list_records)
+            .or(get_record)
+            .or(remove_record);
+
+        let (addr, server) =
+            warp::serve(routes).bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+                rx.await.ok();
+            });
+
+        tokio::spawn(server);
+
+        let url = format!("http://{}", addr);
+        (url, tx)
+    }
+
+    fn input(program_id: i64) -> WebRecordingScheduleInput {
+        WebRecordingScheduleInput {
+            program_id,
+            options: RecordingOptions {
+                priority: Some(5),
+                ..Default::default()
+            },
+            tags: vec!["rule:test".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recording_schedules() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let schedules = client.get_recording_schedules().await.unwrap();
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].state, RecordingScheduleState::Scheduled);
+        assert_eq!(schedules[0].program.id, 327360102401001);
+        assert_eq!(
+            schedules[0].options.content_path.as_deref(),
+            Some("test.m2ts")
+        );
+        assert_eq!(schedules[0].tags, ["rule:test"]);
+
+        let schedule = client
+            .get_recording_schedule(327360102401001)
+            .await
+            .unwrap();
+        assert_eq!(schedule.options.priority, Some(5));
+        assert!(matches!(
+            client.get_recording_schedule(1).await,
+            Err(MirakcApiError::ScheduleNotFound(1))
+        ));
+
+        client
+            .delete_recording_schedule(327360102401001)
+            .await
+            .unwrap();
+        assert!(matches!(
+            client.delete_recording_schedule(1).await,
+            Err(MirakcApiError::ScheduleNotFound(1))
+        ));
+        client.delete_recording_schedules(None).await.unwrap();
+        client
+            .delete_recording_schedules(Some("rule:test"))
+            .await
+            .unwrap();
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_create_recording_schedule() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let schedule = client
+            .create_recording_schedule(&input(327360102401002))
+            .await
+            .unwrap();
+        assert_eq!(schedule.program.id, 327360102401002);
+        assert_eq!(schedule.tags, ["rule:test"]);
+
+        assert!(matches!(
+            client
+                .create_recording_schedule(&input(327360102401001))
+                .await,
+            Err(MirakcApiError::ScheduleAlreadyExists(327360102401001))
+        ));
+        let mut low_priority = input(3);
+        low_priority.options.priority = Some(0);
+        assert!(matches!(
+            client.create_recording_schedule(&low_priority).await,
+            Err(MirakcApiError::ProgramNotFound(3))
+        ));
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_recorders() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let recorders = client.get_recorders().await.unwrap();
+        assert_eq!(recorders.len(), 1);
+        assert_eq!(recorders[0].program_id, 327360102401001);
+        assert_eq!(recorders[0].pipeline[0].command, "recdvb");
+        assert_eq!(recorders[0].pipeline[1].pid, None);
+
+        client
+            .start_recording(&input(327360102401001))
+            .await
+            .unwrap();
+        assert!(matches!(
+            client.start_recording(&input(1)).await,
+            Err(MirakcApiError::ProgramNotFound(1))
+        ));
+        client.stop_recording(327360102401001).await.unwrap();
+        assert!(matches!(
+            client.stop_recording(1).await,
+            Err(MirakcApiError::RecorderNotFound(1))
+        ));
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_records() {
+        let (url, tx) = create_mock_server();
+        let client = MirakcApiClient::new(&url);
+
+        let records = client.get_records().await.unwrap();
+        assert_eq!(records.len(), 1);
+        let record = client.get_record("0000018f2b9c1a00").await.unwrap();
+        assert_eq!(record.service.name, "テストチャンネル");
+        assert_eq!(record.recording.status, WebRecordingStatus::Failed);
+        assert_eq!(
+            record.recording.failed_reason,
+            Some(RecordingFailedReason::PipelineError { exit_code: 1 })
+        );
+        assert_eq!(record.recording.end_time, Some(1619857800000));
+        assert_eq!(record.content.r#type, "video/MP2T");
+        assert_eq!(record.content.length, Some(1024));
+        assert!(matches!(
+            client.get_record("unknown").await,
+            Err(MirakcApiError::RecordNotFound(id)) if id == "unknown"
+        ));
+
+        client
+            .remove_record("0000018f2b9c1a00", true)
+            .await
+            .unwrap();
+        assert!(
+            client
+                .remove_record("0000018f2b9c1a00", false)
+                .await
+                .is_err()
+        );
+
+        let _ = tx.send(());
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use domain::{
+    error::DomainError,
+    model::inventory::{ChannelInfo, ChannelType, Service, Tuner},
+    ports::InventoryRetriever,
+};
+use tracing::error;
+
+use crate::api::{self, MirakurunChannel, MirakurunService, MirakurunTuner};
+use crate::http_client::{MirakcApiClient, MirakcApiError};
+
+#[derive(Clone)]
+pub struct MirakcInventoryRetriever {
+    client: Arc<MirakcApiClient>,
+}
+
+impl MirakcInventoryRetriever {
+    pub fn new(mirakc_url: &str) -> Self {
+        let client = Arc::new(MirakcApiClient::new(mirakc_url));
+        Self { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl InventoryRetriever for MirakcInventoryRetriever {
+    async fn get_tuners(&self) -> Result<Vec<Tuner>, DomainError> {
+        let tuners = self
+            .client
+            .get_tuners()
+            .await
+            .map_err(|e| retrieval_error("チューナー一覧の取得に失敗", e))?;
+        Ok(tuners.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_channels(&self) -> Result<Vec<ChannelInfo>, DomainError> {
+        let channels = self
+            .client
+            .get_channels()
+            .await
+            .map_err(|e| retrieval_error("チャンネル一覧の取得に失敗", e))?;
+        Ok(channels.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_services(&self) -> Result<Vec<Service>, DomainError> {
+        let services = self
+            .client
+            .get_services()
+            .await
+            .map_err(|e| retrieval_error("サービス一覧の取得に失敗", e))?;
+        Ok(services.into_iter().map(Into::into).collect())
+    }
+}
+
+fn retrieval_error(message: &str, e: MirakcApiError) -> DomainError {
+    error!("{}: {:?}", message, e);
+    DomainError::InventoryRetrievalError(format!("{}: {}", message, e))
+}
+
+impl From<api::ChannelType> for ChannelType {
+    fn from(channel_type: api::ChannelType) -> Self {
+        match channel_type {
+            api::ChannelType::Gr => Self::Gr,
+            api::ChannelType::Bs => Self::Bs,
+            api::ChannelType::Cs => Self::Cs,
+            api::ChannelType::Sky => Self::Sky,
+            api::ChannelType::Bs4K => Self::Bs4k,
+        }
+    }
+}
+
+impl From<MirakurunTuner> for Tuner {
+    fn from(tuner: MirakurunTuner) -> Self {
+        Self {
+            index: tuner.index as u32,
+            name: tuner.name,
+            types: tuner.types.into_iter().map(Into::into).collect(),
+            is_available: tuner.is_available,
+            is_fault: tuner.is_fault,
+        }
+    }
+}
+
+impl From<MirakurunChannel> for ChannelInfo {
+    fn from(channel: MirakurunChannel) -> Self {
+        Self {
+            channel_type: channel.r#type.into(),
+            channel: channel.channel,
+            name: channel.name,
+            service_ids: channel.services.into_iter().map(|s| s.id).collect(),
+        }
+    }
+}
+
+impl From<MirakurunService> for Service {
+    fn from(service: MirakurunService) -> Self {
+        Self {
+            id: service.id,
+            service_id: service.service_id,
+            network_id: service.network_id,
+            service_type: service.r#type,
+            name: service.name,
+            channel_type: service.channel.r#type.into(),
+            channel: service.channel.channel,
+            has_logo_data: service.has_logo_data,
+            logo_id: service.logo_id,
+            remote_control_key_id: service.remote_control_key_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{MirakurunChannelService, MirakurunServiceChannel};
+
+    #[test]
+    fn test_convert_tuner() {
+        let tuner: MirakurunTuner = serde_json::from_value(serde_json::json!({
+            "index": 2,
+            "name": "PX-Q3PE4 (BS/CS)",
+            "types": ["BS", "CS", "BS4K"],
+            "users": [],
+            "isAvailable": true,
+            "isRemote": false,
+            "isFree": true,
+            "isUsing": false,
+            "isFault": false
+        }))
+        .unwrap();
+
+        let tuner = Tuner::from(tuner);
+
+        assert_eq!(tuner.index, 2);
+        assert_eq!(
+            tuner.types,
+            [ChannelType::Bs, ChannelType::Cs, ChannelType::Bs4k]
+        );
+        assert!(tuner.supports(ChannelType::Cs));
+    }
+
+    #[test]
+    fn test_convert_channel() {
+        let channel = ChannelInfo::from(MirakurunChannel {
+            channel: "BS15_0".to_string(),
+            name: "BS15".to_string(),
+            services: vec![MirakurunChannelService {
+                id: 400101,
+                name: "ＮＨＫＢＳ１".to_string(),
+                network_id: 4,
+                service_id: 101,
+            }],
+            r#type: api::ChannelType::Bs,
+        });
+
+        assert_eq!(channel.key(), "BS.BS15_0");
+        assert_eq!(channel.service_ids, [400101]);
+    }
+
+    #[test]
+    fn test_convert_service() {
+        let service = Service::from(MirakurunService {
+            channel: MirakurunServiceChannel {
+                channel: "27".to_string(),
+                r#type: api::ChannelType::Gr,
+            },
+            has_logo_data: true,
+            id: 3273601024,
+            logo_id: Some(5),
+            name: "ＮＨＫ総合１・東京".to_string(),
+            network_id: 32736,
+            remote_control_key_id: Some(1),
+            service_id: 1024,
+            r#type: 1,
+        });
+
+        assert_eq!(service.id, 3273601024);
+        assert_eq!(service.service_id, 1024);
+        assert_eq!(service.network_id, 32736);
+        assert_eq!(service.channel_type, ChannelType::Gr);
+        assert_eq!(service.channel, "27");
+        assert!(service.has_logo_data);
+        assert_eq!(service.remote_control_key_id, Some(1));
+    }
+}
@@ -2,12 +2,20 @@ mod sse;
 pub use sse::*;
 pub mod sse_event;
 
+pub mod api;
+
 mod http_client;
 pub use http_client::{MirakcApiClient, MirakcApiError};
 
+mod inventory_retriever;
+pub use inventory_retriever::MirakcInventoryRetriever;
+
 mod programs_retriever;
 pub use programs_retriever::MirakcProgramsRetriever;
 
+mod recording_scheduler;
+pub use recording_scheduler::MirakcRecordingScheduler;
+
 #[cfg(test)]
 mod tests {
     use tracing_subscriber::{EnvFilter, fmt};
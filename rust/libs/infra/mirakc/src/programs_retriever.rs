@@ -1,18 +1,23 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use domain::{
     error::DomainError,
-    model::program::{
-        Audio, Channel, Genre, Program, ProgramIdentifiers, ProgramTiming, RelatedItem, Video,
+    model::{
+        onair::OnairProgram,
+        program::{
+            Audio, Channel, Genre, Program, ProgramIdentifiers, ProgramTiming, RelatedItem, Video,
+        },
     },
-    ports::ProgramsRetriever,
+    ports::{OnairProgramRetriever, ProgramsRetriever, ServicesRetriever},
 };
 use tracing::{debug, error};
 
-use crate::http_client::{
-    MirakcApiClient, MirakcApiError, MirakurunAudio, MirakurunGenre, MirakurunProgram,
-    MirakurunRelatedItem, MirakurunVideo,
+use crate::api::{
+    MirakurunProgram, MirakurunProgramAudio, MirakurunProgramGenre, MirakurunProgramRelatedItem,
+    MirakurunProgramVideo, WebOnairProgram,
 };
+use crate::http_client::{MirakcApiClient, MirakcApiError};
 
 #[derive(Clone)]
 pub struct MirakcProgramsRetriever {
@@ -33,10 +38,21 @@ impl MirakcProgramsRetriever {
             .unwrap_or_default();
 
         let video = mirakc_program.video.clone().map(|v| self.convert_video(v));
-        let audio = mirakc_program.audio.clone().map(|a| self.convert_audio(a));
-        let related_items = mirakc_program
-            .related_items
+        // 音声が複数あると `audio` が省略されることがあるので、主音声で補う
+        let audio = mirakc_program
+            .audio
             .clone()
+            .or_else(|| {
+                let audios = &mirakc_program.audios;
+                audios
+                    .iter()
+                    .find(|a| a.is_main)
+                    .or(audios.first())
+                    .cloned()
+            })
+            .map(|a| self.convert_audio(a));
+        let related_items = Some(mirakc_program.related_items.clone())
+            .filter(|items| !items.is_empty())
             .map(|items| self.convert_related_items(items));
 
         let mut program = Program::new(
@@ -72,7 +88,33 @@ impl MirakcProgramsRetriever {
         program
     }
 
-    fn convert_genres(&self, mirakc_genres: Vec<MirakurunGenre>) -> Vec<Genre> {
+    fn convert_onair_program(&self, onair: WebOnairProgram, service_name: &str) -> OnairProgram {
+        OnairProgram {
+            service_id: onair.service_id,
+            current: onair
+                .current
+                .map(|program| self.convert_program(program, service_name)),
+            next: onair
+                .next
+                .map(|program| self.convert_program(program, service_name)),
+        }
+    }
+
+    /// 番組の `channel.name` に使うサービス名を取得する
+    async fn get_service_name(&self, service_id: i64) -> Result<String, DomainError> {
+        match self.client.get_service(service_id).await {
+            Ok(service) => Ok(service.name),
+            Err(MirakcApiError::ServiceNotFound(_)) => {
+                Err(DomainError::ServiceNotFound(service_id))
+            }
+            Err(e) => Err(DomainError::ProgramsRetrievalError(format!(
+                "サービス情報の取得に失敗: {}",
+                e
+            ))),
+        }
+    }
+
+    fn convert_genres(&self, mirakc_genres: Vec<MirakurunProgramGenre>) -> Vec<Genre> {
         mirakc_genres
             .into_iter()
             .map(|g| Genre {
@@ -82,8 +124,9 @@ impl MirakcProgramsRetriever {
             .collect()
     }
 
-    fn convert_video(&self, mirakc_video: MirakurunVideo) -> Video {
-        let component_type_name = mirakc_video.component_type.map(|ct| match ct {
+    fn convert_video(&self, mirakc_video: MirakurunProgramVideo) -> Video {
+        let component_type = mirakc_video.component_type as u8;
+        let component_type_name = Some(match component_type {
             0x01 => "480i(525i), アスペクト比4:3".to_string(),
             0x02 => "480i(525i), アスペクト比16:9 パンベクトルあり".to_string(),
             0x03 => "480i(525i), アスペクト比16:9 パンベクトルなし".to_string(),
@@ -117,20 +160,21 @@ impl MirakcProgramsRetriever {
             0xf2 => "180p アスペクト比16:9 パンベクトルあり".to_string(),
             0xf3 => "180p アスペクト比16:9 パンベクトルなし".to_string(),
             0xf4 => "180p アスペクト比 > 16:9".to_string(),
-            _ => format!("不明なコンポーネントタイプ: {}", ct),
+            _ => format!("不明なコンポーネントタイプ: {}", component_type),
         });
 
         Video {
             r#type: mirakc_video.r#type,
             resolution: mirakc_video.resolution,
-            stream_content: mirakc_video.stream_content,
-            component_type: mirakc_video.component_type,
+            stream_content: Some(mirakc_video.stream_content as u8),
+            component_type: Some(component_type),
             component_type_name,
         }
     }
 
-    fn convert_audio(&self, mirakc_audio: MirakurunAudio) -> Audio {
-        let component_type_name = mirakc_audio.component_type.map(|ct| match ct {
+    fn convert_audio(&self, mirakc_audio: MirakurunProgramAudio) -> Audio {
+        let component_type = mirakc_audio.component_type as u8;
+        let component_type_name = Some(match component_type {
             0b00000 => "将来使用のためリザーブ".to_string(),
             0b00001 => "1/0モード(シングルモノ)".to_string(),
             0b00010 => "1/0 + 1/0モード(デュアルモノ)".to_string(),
@@ -149,30 +193,34 @@ impl MirakcProgramsRetriever {
             0b01111 => "0/2/0-3/0/2-0.1モード".to_string(),
             0b10000 => "2/0/0-3/2/3-0.2モード".to_string(),
             0b10001 => "3/3/3-5/2/3-3/0/0.2モード".to_string(),
-            _ => format!("不明なコンポーネントタイプ: {}", ct),
+            _ => format!("不明なコンポーネントタイプ: {}", component_type),
         });
 
-        let sampling_rate_name = mirakc_audio.sampling_rate.map(|sr| match sr {
+        let sampling_rate = mirakc_audio.sampling_rate as u32;
+        let sampling_rate_name = Some(match sampling_rate {
             16000 => "16kHz".to_string(),
             22050 => "22.05kHz".to_string(),
             24000 => "24kHz".to_string(),
             32000 => "32kHz".to_string(),
             44100 => "44.1kHz".to_string(),
             48000 => "48kHz".to_string(),
-            _ => format!("{}Hz", sr),
+            _ => format!("{}Hz", sampling_rate),
         });
 
         Audio {
-            component_type: mirakc_audio.component_type,
+            component_type: Some(component_type),
             component_type_name,
-            is_main: mirakc_audio.is_main,
-            sampling_rate: mirakc_audio.sampling_rate,
+            is_main: Some(mirakc_audio.is_main),
+            sampling_rate: Some(sampling_rate),
             sampling_rate_name,
-            langs: mirakc_audio.langs,
+            langs: Some(mirakc_audio.langs),
         }
     }
 
-    fn convert_related_items(&self, mirakc_items: Vec<MirakurunRelatedItem>) -> Vec<RelatedItem> {
+    fn convert_related_items(
+        &self,
+        mirakc_items: Vec<MirakurunProgramRelatedItem>,
+    ) -> Vec<RelatedItem> {
         mirakc_items
             .into_iter()
             .map(|item| RelatedItem {
@@ -188,19 +236,7 @@ impl MirakcProgramsRetriever {
 #[async_trait::async_trait]
 impl ProgramsRetriever for MirakcProgramsRetriever {
     async fn get_programs(&self, service_id: i64) -> Result<Vec<Program>, DomainError> {
-        let service_result = self.client.get_service(service_id).await;
-        let service_name = match service_result {
-            Ok(service) => service.name,
-            Err(e) => {
-                if let MirakcApiError::ServiceNotFound(_) = e {
-                    return Err(DomainError::ServiceNotFound(service_id));
-                }
-                return Err(DomainError::ProgramsRetrievalError(format!(
-                    "サービス情報の取得に失敗: {}",
-                    e
-                )));
-            }
-        };
+        let service_name = self.get_service_name(service_id).await?;
 
         let programs_result = self.client.get_programs_by_service(service_id).await;
 
@@ -223,23 +259,90 @@ impl ProgramsRetriever for MirakcProgramsRetriever {
     }
 }
 
+#[async_trait::async_trait]
+impl ServicesRetriever for MirakcProgramsRetriever {
+    async fn get_service_ids(&self) -> Result<Vec<i64>, DomainError> {
+        match self.client.get_services().await {
+            Ok(services) => Ok(services.into_iter().map(|s| s.id).collect()),
+            Err(e) => {
+                error!("Failed to get services: {:?}", e);
+                Err(DomainError::ProgramsRetrievalError(format!(
+                    "サービス一覧の取得に失敗: {}",
+                    e
+                )))
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl OnairProgramRetriever for MirakcProgramsRetriever {
+    async fn get_onair_program(&self, service_id: i64) -> Result<OnairProgram, DomainError> {
+        let service_name = self.get_service_name(service_id).await?;
+        match self.client.get_onair_program(service_id).await {
+            Ok(onair) => Ok(self.convert_onair_program(onair, &service_name)),
+            Err(MirakcApiError::ServiceNotFound(_)) => {
+                Err(DomainError::ServiceNotFound(service_id))
+            }
+            Err(e) => {
+                error!("Failed to get on-air program: {:?}", e);
+                Err(DomainError::ProgramsRetrievalError(format!(
+                    "放送中の番組の取得に失敗: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    async fn get_onair_programs(&self) -> Result<Vec<OnairProgram>, DomainError> {
+        let retrieval_error = |message: &str, e: MirakcApiError| {
+            error!("{}: {:?}", message, e);
+            DomainError::ProgramsRetrievalError(format!("{}: {}", message, e))
+        };
+        let service_names: HashMap<i64, String> = self
+            .client
+            .get_services()
+            .await
+            .map_err(|e| retrieval_error("サービス一覧の取得に失敗", e))?
+            .into_iter()
+            .map(|service| (service.id, service.name))
+            .collect();
+        let programs = self
+            .client
+            .get_onair_programs()
+            .await
+            .map_err(|e| retrieval_error("放送中の番組の取得に失敗", e))?;
+
+        Ok(programs
+            .into_iter()
+            .map(|onair| {
+                let service_name = service_names
+                    .get(&onair.service_id)
+                    .cloned()
+                    .unwrap_or_default();
+                self.convert_onair_program(onair, &service_name)
+            })
+            .collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::http_client::MirakurunGenre;
+    use crate::api::MirakurunProgramGenre;
 
     #[test]
     fn test_convert_genres() {
         let retriever = MirakcProgramsRetriever::new("http://dummy");
 
         let mirakc_genres = vec![
-            MirakurunGenre {
+            MirakurunProgramGenre {
                 lv1: 0,
                 lv2: 1,
                 un1: 2,
                 un2: 3,
             },
-            MirakurunGenre {
+            MirakurunProgramGenre {
                 lv1: 4,
                 lv2: 5,
                 un1: 6,
@@ -260,11 +363,11 @@ mod tests {
     fn test_convert_video() {
         let retriever = MirakcProgramsRetriever::new("http://dummy");
 
-        let mirakc_video = MirakurunVideo {
+        let mirakc_video = MirakurunProgramVideo {
             r#type: Some("mpeg2".to_string()),
             resolution: Some("1080i".to_string()),
-            stream_content: Some(1),
-            component_type: Some(0xb3),
+            stream_content: 1,
+            component_type: 0xb3,
         };
 
         let video = retriever.convert_video(mirakc_video);
@@ -282,11 +385,11 @@ mod tests {
     fn test_convert_audio() {
         let retriever = MirakcProgramsRetriever::new("http://dummy");
 
-        let mirakc_audio = MirakurunAudio {
-            component_type: Some(3),
-            is_main: Some(true),
-            sampling_rate: Some(48000),
-            langs: Some(vec!["jpn".to_string()]),
+        let mirakc_audio = MirakurunProgramAudio {
+            component_type: 3,
+            is_main: true,
+            sampling_rate: 48000,
+            langs: vec!["jpn".to_string()],
         };
 
         let audio = retriever.convert_audio(mirakc_audio);
@@ -320,7 +423,9 @@ mod mock_tests {
                 "serviceId": service_id,
                 "networkId": 32736,
                 "type": 1,
-                "name": "テストチャンネル"
+                "name": "テストチャンネル",
+                "channel": {"type": "GR", "channel": "27"},
+                "hasLogoData": false
             });
 
             Response::builder()
@@ -383,7 +488,40 @@ mod mock_tests {
                     .body(serde_json::to_string(&programs).unwrap())
             });
 
-        let routes = service_route.or(programs_route);
+        let services_route = warp::path!("api" / "services").map(|| {
+            Response::builder()
+                .header("content-type", "application/json")
+                .body(
+                    json!([{
+                        "id": 3273601024i64,
+                        "serviceId": 1024,
+                        "networkId": 32736,
+                        "type": 1,
+                        "name": "ＮＨＫ総合１・東京",
+                        "channel": {"type": "GR", "channel": "27"},
+                        "hasLogoData": false
+                    }])
+                    .to_string(),
+                )
+        });
+
+        let onair_route = warp::path!("api" / "onair").map(|| {
+            Response::builder()
+                .header("content-type", "application/json")
+                .body(json!([onair_program(3273601024)]).to_string())
+        });
+
+        let onair_service_route = warp::path!("api" / "onair" / i64).map(|service_id: i64| {
+            Response::builder()
+                .header("content-type", "application/json")
+                .body(json!([onair_program(service_id)]).to_string())
+        });
+
+        let routes = services_route
+            .or(service_route)
+            .or(programs_route)
+            .or(onair_route)
+            .or(onair_service_route);
 
         let (addr, server) =
             warp::serve(routes).bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
@@ -396,6 +534,32 @@ mod mock_tests {
         (url, tx)
     }
 
+    fn onair_program(service_id: i64) -> serde_json::Value {
+        json!({
+            "serviceId": service_id,
+            "current": {
+                "id": 327360102412345i64,
+                "eventId": 12345,
+                "serviceId": 1024,
+                "networkId": 32736,
+                "startAt": 1619856000000i64,
+                "duration": 3600000,
+                "isFree": true,
+                "name": "ニュース"
+            },
+            "next": {
+                "id": 327360102412346i64,
+                "eventId": 12346,
+                "serviceId": 1024,
+                "networkId": 32736,
+                "startAt": 1619859600000i64,
+                "duration": 1800000,
+                "isFree": true,
+                "name": "天気予報"
+            }
+        })
+    }
+
     #[tokio::test]
     async fn test_get_programs_success() {
         let (url, tx) = create_mock_server();
@@ -471,4 +635,38 @@ mod mock_tests {
 
         let _ = tx.send(());
     }
+
+    #[tokio::test]
+    async fn test_get_onair_program() {
+        let (url, tx) = create_mock_server();
+        let retriever = MirakcProgramsRetriever::new(&url);
+
+        let onair = retriever.get_onair_program(1).await.unwrap();
+
+        assert_eq!(onair.service_id, 1);
+        let current = onair.current.unwrap();
+        assert_eq!(current.name, Some("ニュース".to_string()));
+        assert_eq!(current.end_at, 1619859600000);
+        assert_eq!(current.channel.name, "テストチャンネル");
+        assert_eq!(onair.next.unwrap().name, Some("天気予報".to_string()));
+
+        let _ = tx.send(());
+    }
+
+    #[tokio::test]
+    async fn test_get_onair_programs() {
+        let (url, tx) = create_mock_server();
+        let retriever = MirakcProgramsRetriever::new(&url);
+
+        let programs = retriever.get_onair_programs().await.unwrap();
+
+        assert_eq!(programs.len(), 1);
+        assert_eq!(programs[0].service_id, 3273601024);
+        assert_eq!(
+            programs[0].current.as_ref().unwrap().channel.name,
+            "ＮＨＫ総合１・東京"
+        );
+
+        let _ = tx.send(());
+    }
 }
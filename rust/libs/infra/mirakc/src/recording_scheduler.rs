@@ -0,0 +1,313 @@
+use std::sync::Arc;
+
+use domain::{
+    error::DomainError,
+    model::recording::{
+        self, ActiveRecorder, Record, RecordingSchedule, ScheduleOutcome, ScheduleRequest,
+        ScheduleState,
+    },
+    ports::{RecorderController, RecordingScheduler, RecordsManager},
+};
+use tracing::error;
+
+use crate::api::{
+    MirakurunProgram, MirakurunProgramAudio, RecordingFailedReason, RecordingOptions,
+    RecordingScheduleState, WebRecord,
+    WebRecordingRecorder, WebRecordingSchedule, WebRecordingScheduleInput, WebRecordingStatus,
+};
+use crate::http_client::{MirakcApiClient, MirakcApiError};
+
+#[derive(Clone)]
+pub struct MirakcRecordingScheduler {
+    client: Arc<MirakcApiClient>,
+}
+
+impl MirakcRecordingScheduler {
+    pub fn new(mirakc_url: &str) -> Self {
+        let client = Arc::new(MirakcApiClient::new(mirakc_url));
+        Self { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl RecordingScheduler for MirakcRecordingScheduler {
+    async fn create_schedule(
+        &self,
+        request: &ScheduleRequest,
+    ) -> Result<ScheduleOutcome, DomainError> {
+        match self
+            .client
+            .create_recording_schedule(&schedule_input(request))
+            .await
+        {
+            Ok(_) => Ok(ScheduleOutcome::Created),
+            Err(MirakcApiError::ScheduleAlreadyExists(_)) => Ok(ScheduleOutcome::AlreadyScheduled),
+            Err(MirakcApiError::ProgramNotFound(_)) => Ok(ScheduleOutcome::ProgramNotFound),
+            Err(e) => Err(schedule_error("録画予約の作成に失敗", e)),
+        }
+    }
+
+    async fn list_schedules(&self) -> Result<Vec<RecordingSchedule>, DomainError> {
+        let mut schedules: Vec<RecordingSchedule> = self
+            .client
+            .get_recording_schedules()
+            .await
+            .map_err(|e| schedule_error("録画予約の取得に失敗", e))?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        schedules.sort_by_key(|schedule| (schedule.start_at, schedule.program_id));
+        Ok(schedules)
+    }
+
+    async fn get_schedule(
+        &self,
+        program_id: i64,
+    ) -> Result<Option<RecordingSchedule>, DomainError> {
+        match self.client.get_recording_schedule(program_id).await {
+            Ok(schedule) => Ok(Some(schedule.into())),
+            Err(MirakcApiError::ScheduleNotFound(_)) => Ok(None),
+            Err(e) => Err(schedule_error("録画予約の取得に失敗", e)),
+        }
+    }
+
+    async fn delete_schedule(&self, program_id: i64) -> Result<bool, DomainError> {
+        match self.client.delete_recording_schedule(program_id).await {
+            Ok(()) => Ok(true),
+            Err(MirakcApiError::ScheduleNotFound(_)) => Ok(false),
+            Err(e) => Err(schedule_error("録画予約の削除に失敗", e)),
+        }
+    }
+
+    async fn delete_schedules_by_tag(&self, tag: &str) -> Result<(), DomainError> {
+        self.client
+            .delete_recording_schedules(Some(tag))
+            .await
+            .map_err(|e| schedule_error("録画予約の削除に失敗", e))
+    }
+}
+
+#[async_trait::async_trait]
+impl RecorderController for MirakcRecordingScheduler {
+    async fn list_recorders(&self) -> Result<Vec<ActiveRecorder>, DomainError> {
+        Ok(self
+            .client
+            .get_recorders()
+            .await
+            .map_err(|e| schedule_error("レコーダーの取得に失敗", e))?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    async fn start_recording(
+        &self,
+        request: &ScheduleRequest,
+    ) -> Result<ScheduleOutcome, DomainError> {
+        match self.client.start_recording(&schedule_input(request)).await {
+            Ok(()) => Ok(ScheduleOutcome::Created),
+            Err(MirakcApiError::ScheduleAlreadyExists(_)) => Ok(ScheduleOutcome::AlreadyScheduled),
+            Err(MirakcApiError::ProgramNotFound(_)) => Ok(ScheduleOutcome::ProgramNotFound),
+            Err(e) => Err(schedule_error("録画の開始に失敗", e)),
+        }
+    }
+
+    async fn stop_recording(&self, program_id: i64) -> Result<bool, DomainError> {
+        match self.client.stop_recording(program_id).await {
+            Ok(()) => Ok(true),
+            Err(MirakcApiError::RecorderNotFound(_)) => Ok(false),
+            Err(e) => Err(schedule_error("録画の停止に失敗", e)),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RecordsManager for MirakcRecordingScheduler {
+    async fn list_records(&self) -> Result<Vec<Record>, DomainError> {
+        Ok(self
+            .client
+            .get_records()
+            .await
+            .map_err(|e| schedule_error("録画の取得に失敗", e))?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    async fn get_record(&self, record_id: &str) -> Result<Option<Record>, DomainError> {
+        match self.client.get_record(record_id).await {
+            Ok(record) => Ok(Some(record.into())),
+            Err(MirakcApiError::RecordNotFound(_)) => Ok(None),
+            Err(e) => Err(schedule_error("録画の取得に失敗", e)),
+        }
+    }
+
+    async fn remove_record(&self, record_id: &str, purge: bool) -> Result<bool, DomainError> {
+        match self.client.remove_record(record_id, purge).await {
+            Ok(()) => Ok(true),
+            Err(MirakcApiError::RecordNotFound(_)) => Ok(false),
+            Err(e) => Err(schedule_error("録画の削除に失敗", e)),
+        }
+    }
+}
+
+fn schedule_input(request: &ScheduleRequest) -> WebRecordingScheduleInput {
+    WebRecordingScheduleInput {
+        program_id: request.program_id,
+        options: RecordingOptions {
+            priority: Some(request.priority),
+            ..Default::default()
+        },
+        tags: request.tags.clone(),
+    }
+}
+
+fn schedule_error(message: &str, e: MirakcApiError) -> DomainError {
+    error!("{}: {:?}", message, e);
+    DomainError::RecordingScheduleError(format!("{}: {}", message, e))
+}
+
+impl From<RecordingScheduleState> for ScheduleState {
+    fn from(state: RecordingScheduleState) -> Self {
+        match state {
+            RecordingScheduleState::Scheduled => Self::Scheduled,
+            RecordingScheduleState::Tracking => Self::Tracking,
+            RecordingScheduleState::Recording => Self::Recording,
+            RecordingScheduleState::Rescheduling => Self::Rescheduling,
+            RecordingScheduleState::Finished => Self::Finished,
+            RecordingScheduleState::Failed => Self::Failed,
+        }
+    }
+}
+
+impl From<WebRecordingStatus> for recording::RecordingStatus {
+    fn from(status: WebRecordingStatus) -> Self {
+        match status {
+            WebRecordingStatus::Recording => Self::Recording,
+            WebRecordingStatus::Finished => Self::Finished,
+            WebRecordingStatus::Canceled => Self::Canceled,
+            WebRecordingStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+impl From<RecordingFailedReason> for recording::RecordingFailedReason {
+    fn from(reason: RecordingFailedReason) -> Self {
+        match reason {
+            RecordingFailedReason::StartRecordingFailed { message } => {
+                Self::StartRecordingFailed { message }
+            }
+            RecordingFailedReason::IoError { message, os_error } => {
+                Self::IoError { message, os_error }
+            }
+            RecordingFailedReason::PipelineError { exit_code } => Self::PipelineError { exit_code },
+            RecordingFailedReason::NeedRescheduling => Self::NeedRescheduling,
+            RecordingFailedReason::ScheduleExpired => Self::ScheduleExpired,
+            RecordingFailedReason::RemovedFromEpg => Self::RemovedFromEpg,
+        }
+    }
+}
+
+impl From<WebRecordingSchedule> for RecordingSchedule {
+    fn from(schedule: WebRecordingSchedule) -> Self {
+        Self {
+            program_id: schedule.program.id,
+            program_name: schedule.program.name,
+            start_at: schedule.program.start_at,
+            end_at: schedule.program.start_at + schedule.program.duration,
+            state: schedule.state.into(),
+            priority: schedule.options.priority.unwrap_or_default(),
+            tags: schedule.tags,
+            failed_reason: schedule.failed_reason.map(Into::into),
+        }
+    }
+}
+
+impl From<WebRecordingRecorder> for ActiveRecorder {
+    fn from(recorder: WebRecordingRecorder) -> Self {
+        Self {
+            program_id: recorder.program_id,
+            started_at: recorder.started_at,
+        }
+    }
+}
+
+impl From<WebRecord> for Record {
+    fn from(record: WebRecord) -> Self {
+        let audio_component_types = audio_component_types(&record.program);
+        Self {
+            id: record.id,
+            program_id: record.program.id,
+            service_id: record.service.id,
+            program_name: record.program.name,
+            tags: record.tags,
+            status: record.recording.status.into(),
+            start_time: record.recording.start_time,
+            end_time: record.recording.end_time,
+            content_path: record.content.path,
+            content_type: record.content.r#type,
+            content_length: record
+                .content
+                .length
+                .and_then(|length| u64::try_from(length).ok()),
+            failed_reason: record.recording.failed_reason.map(Into::into),
+            audio_component_types,
+        }
+    }
+}
+
+/// 番組の音声のコンポーネントタイプを主音声から順に並べる
+fn audio_component_types(program: &MirakurunProgram) -> Vec<u8> {
+    let mut audios: Vec<&MirakurunProgramAudio> = program.audios.iter().collect();
+    if audios.is_empty() {
+        audios.extend(program.audio.as_ref());
+    }
+    // 安定ソートなので、主音声どうし・副音声どうしの順は変わらない
+    audios.sort_by_key(|audio| !audio.is_main);
+    audios
+        .iter()
+        .filter_map(|audio| u8::try_from(audio.component_type).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(audios: serde_json::Value) -> MirakurunProgram {
+        serde_json::from_value(serde_json::json!({
+            "id": 327360102412345i64,
+            "eventId": 12345,
+            "serviceId": 1024,
+            "networkId": 32736,
+            "startAt": 1619856000000i64,
+            "duration": 1800000,
+            "isFree": true,
+            "audios": audios,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_audio_component_types_main_first() {
+        let program = program(serde_json::json!([
+            {"componentType": 3, "isMain": false, "samplingRate": 48000, "langs": ["eng"]},
+            {"componentType": 2, "isMain": true, "samplingRate": 48000, "langs": ["jpn", "eng"]},
+        ]));
+
+        assert_eq!(audio_component_types(&program), [2, 3]);
+    }
+
+    #[test]
+    fn test_audio_component_types_falls_back_to_audio() {
+        let mut program = program(serde_json::json!([]));
+        program.audio = Some(MirakurunProgramAudio {
+            component_type: 3,
+            is_main: true,
+            langs: vec!["jpn".to_string()],
+            sampling_rate: 48000,
+        });
+
+        assert_eq!(audio_component_types(&program), [3]);
+    }
+}
@@ -1,7 +1,466 @@
+use domain::model::event::recording::{epg, onair, record, schedule, timeshift};
+use domain::model::recording;
+use domain::types::Event;
 use serde::Deserialize;
 
+/// mirakc SSEイベントのペイロードをドメインイベントに変換する
+pub trait IntoDomainEvent {
+    type Output: Event;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output;
+}
+
+/// `epg.programs-updated`
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProgramsUpdated {
     pub service_id: i64,
 }
+
+impl IntoDomainEvent for ProgramsUpdated {
+    type Output = epg::Updated;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        epg::Updated {
+            service_id: self.service_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `recording.started`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingStarted {
+    pub program_id: i64,
+}
+
+impl IntoDomainEvent for RecordingStarted {
+    type Output = schedule::Started;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        schedule::Started {
+            program_id: self.program_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `recording.stopped`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingStopped {
+    pub program_id: i64,
+}
+
+impl IntoDomainEvent for RecordingStopped {
+    type Output = schedule::Stopped;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        schedule::Stopped {
+            program_id: self.program_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `recording.failed`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingFailed {
+    pub program_id: i64,
+    pub reason: RecordingFailedReason,
+}
+
+impl IntoDomainEvent for RecordingFailed {
+    type Output = schedule::Failed;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        schedule::Failed {
+            program_id: self.program_id,
+            reason: self.reason.into(),
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `recording.rescheduled`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingRescheduled {
+    pub program_id: i64,
+}
+
+impl IntoDomainEvent for RecordingRescheduled {
+    type Output = schedule::Rescheduled;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        schedule::Rescheduled {
+            program_id: self.program_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `recording.record-saved`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordSaved {
+    pub record_id: String,
+    pub recording_status: RecordingStatus,
+}
+
+impl IntoDomainEvent for RecordSaved {
+    type Output = record::Saved;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        record::Saved {
+            record_id: self.record_id,
+            recording_status: self.recording_status.into(),
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `recording.record-removed`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordRemoved {
+    pub record_id: String,
+}
+
+impl IntoDomainEvent for RecordRemoved {
+    type Output = record::Removed;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        record::Removed {
+            record_id: self.record_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `recording.record-broken`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordBroken {
+    pub record_id: String,
+    pub reason: String,
+}
+
+impl IntoDomainEvent for RecordBroken {
+    type Output = record::Broken;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        record::Broken {
+            record_id: self.record_id,
+            reason: self.reason,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `onair.program-changed`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnairProgramChanged {
+    pub service_id: i64,
+}
+
+impl IntoDomainEvent for OnairProgramChanged {
+    type Output = onair::ProgramChanged;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        onair::ProgramChanged {
+            service_id: self.service_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `timeshift.timeline`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeshiftTimeline {
+    pub recorder: String,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub duration: i64,
+}
+
+impl IntoDomainEvent for TimeshiftTimeline {
+    type Output = timeshift::Timeline;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        timeshift::Timeline {
+            recorder: self.recorder,
+            start_time: self.start_time,
+            end_time: self.end_time,
+            duration: self.duration,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `timeshift.started`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeshiftStarted {
+    pub recorder: String,
+}
+
+impl IntoDomainEvent for TimeshiftStarted {
+    type Output = timeshift::Started;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        timeshift::Started {
+            recorder: self.recorder,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `timeshift.stopped`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeshiftStopped {
+    pub recorder: String,
+}
+
+impl IntoDomainEvent for TimeshiftStopped {
+    type Output = timeshift::Stopped;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        timeshift::Stopped {
+            recorder: self.recorder,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `timeshift.record-started`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeshiftRecordStarted {
+    pub recorder: String,
+    pub record_id: u32,
+}
+
+impl IntoDomainEvent for TimeshiftRecordStarted {
+    type Output = timeshift::RecordStarted;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        timeshift::RecordStarted {
+            recorder: self.recorder,
+            record_id: self.record_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `timeshift.record-updated`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeshiftRecordUpdated {
+    pub recorder: String,
+    pub record_id: u32,
+}
+
+impl IntoDomainEvent for TimeshiftRecordUpdated {
+    type Output = timeshift::RecordUpdated;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        timeshift::RecordUpdated {
+            recorder: self.recorder,
+            record_id: self.record_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+/// `timeshift.record-ended`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeshiftRecordEnded {
+    pub recorder: String,
+    pub record_id: u32,
+}
+
+impl IntoDomainEvent for TimeshiftRecordEnded {
+    type Output = timeshift::RecordEnded;
+
+    fn into_domain_event(self, mirakc_url: &str) -> Self::Output {
+        timeshift::RecordEnded {
+            recorder: self.recorder,
+            record_id: self.record_id,
+            mirakc_url: mirakc_url.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum RecordingFailedReason {
+    #[serde(rename_all = "camelCase")]
+    StartRecordingFailed {
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    IoError {
+        message: String,
+        os_error: Option<i32>,
+    },
+    #[serde(rename_all = "camelCase")]
+    PipelineError {
+        exit_code: i32,
+    },
+    NeedRescheduling,
+    ScheduleExpired,
+    RemovedFromEpg,
+}
+
+impl From<RecordingFailedReason> for recording::RecordingFailedReason {
+    fn from(reason: RecordingFailedReason) -> Self {
+        match reason {
+            RecordingFailedReason::StartRecordingFailed { message } => {
+                Self::StartRecordingFailed { message }
+            }
+            RecordingFailedReason::IoError { message, os_error } => {
+                Self::IoError { message, os_error }
+            }
+            RecordingFailedReason::PipelineError { exit_code } => Self::PipelineError { exit_code },
+            RecordingFailedReason::NeedRescheduling => Self::NeedRescheduling,
+            RecordingFailedReason::ScheduleExpired => Self::ScheduleExpired,
+            RecordingFailedReason::RemovedFromEpg => Self::RemovedFromEpg,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordingStatus {
+    Recording,
+    Finished,
+    Canceled,
+    Failed,
+}
+
+impl From<RecordingStatus> for recording::RecordingStatus {
+    fn from(status: RecordingStatus) -> Self {
+        match status {
+            RecordingStatus::Recording => Self::Recording,
+            RecordingStatus::Finished => Self::Finished,
+            RecordingStatus::Canceled => Self::Canceled,
+            RecordingStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIRAKC_URL: &str = "http://tuner:40772";
+
+    #[test]
+    fn test_programs_updated() {
+        let ev: ProgramsUpdated = serde_json::from_str(r#"{"serviceId":3273601024}"#).unwrap();
+        let domain_ev = ev.into_domain_event(MIRAKC_URL);
+
+        assert_eq!(domain_ev.service_id, 3273601024);
+        assert_eq!(domain_ev.mirakc_url, MIRAKC_URL);
+    }
+
+    #[test]
+    fn test_recording_failed() {
+        let ev: RecordingFailed = serde_json::from_str(
+            r#"{"programId":327360102408478,"reason":{"type":"pipeline-error","exitCode":1}}"#,
+        )
+        .unwrap();
+        let domain_ev = ev.into_domain_event(MIRAKC_URL);
+
+        assert_eq!(domain_ev.program_id, 327360102408478);
+        assert_eq!(
+            domain_ev.reason,
+            recording::RecordingFailedReason::PipelineError { exit_code: 1 }
+        );
+
+        let ev: RecordingFailed = serde_json::from_str(
+            r#"{"programId":1,"reason":{"type":"io-error","message":"No space left","osError":28}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ev.into_domain_event(MIRAKC_URL).reason,
+            recording::RecordingFailedReason::IoError {
+                message: "No space left".to_string(),
+                os_error: Some(28),
+            }
+        );
+
+        let ev: RecordingFailed =
+            serde_json::from_str(r#"{"programId":1,"reason":{"type":"removed-from-epg"}}"#)
+                .unwrap();
+        assert_eq!(
+            ev.into_domain_event(MIRAKC_URL).reason,
+            recording::RecordingFailedReason::RemovedFromEpg
+        );
+    }
+
+    #[test]
+    fn test_record_saved() {
+        let ev: RecordSaved = serde_json::from_str(
+            r#"{"recordId":"1714521600000-327360102408478","recordingStatus":"finished"}"#,
+        )
+        .unwrap();
+        let domain_ev = ev.into_domain_event(MIRAKC_URL);
+
+        assert_eq!(domain_ev.record_id, "1714521600000-327360102408478");
+        assert_eq!(
+            domain_ev.recording_status,
+            recording::RecordingStatus::Finished
+        );
+        assert_eq!(domain_ev.mirakc_url, MIRAKC_URL);
+    }
+
+    #[test]
+    fn test_record_broken() {
+        let ev: RecordBroken =
+            serde_json::from_str(r#"{"recordId":"rec1","reason":"content file not found"}"#)
+                .unwrap();
+        let domain_ev = ev.into_domain_event(MIRAKC_URL);
+
+        assert_eq!(domain_ev.record_id, "rec1");
+        assert_eq!(domain_ev.reason, "content file not found");
+    }
+
+    #[test]
+    fn test_onair_program_changed() {
+        let ev: OnairProgramChanged = serde_json::from_str(r#"{"serviceId":3273601024}"#).unwrap();
+        assert_eq!(ev.into_domain_event(MIRAKC_URL).service_id, 3273601024);
+    }
+
+    #[test]
+    fn test_timeshift_events() {
+        let ev: TimeshiftTimeline = serde_json::from_str(
+            r#"{"recorder":"tokyo-mx","startTime":1714521600000,"endTime":1714525200000,"duration":3600000}"#,
+        )
+        .unwrap();
+        let domain_ev = ev.into_domain_event(MIRAKC_URL);
+        assert_eq!(domain_ev.recorder, "tokyo-mx");
+        assert_eq!(domain_ev.start_time, Some(1714521600000));
+        assert_eq!(domain_ev.end_time, Some(1714525200000));
+        assert_eq!(domain_ev.duration, 3600000);
+
+        let ev: TimeshiftTimeline = serde_json::from_str(
+            r#"{"recorder":"tokyo-mx","startTime":null,"endTime":null,"duration":0}"#,
+        )
+        .unwrap();
+        assert_eq!(ev.start_time, None);
+
+        let ev: TimeshiftRecordStarted =
+            serde_json::from_str(r#"{"recorder":"tokyo-mx","recordId":42}"#).unwrap();
+        let domain_ev = ev.into_domain_event(MIRAKC_URL);
+        assert_eq!(domain_ev.recorder, "tokyo-mx");
+        assert_eq!(domain_ev.record_id, 42);
+    }
+}
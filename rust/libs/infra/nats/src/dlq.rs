@@ -0,0 +1,381 @@
+//! デッドレターキューの参照・再投入・削除
+//!
+//! 処理に失敗したメッセージやデシリアライズできないメッセージは
+//! `stream` モジュールによって `dlq.<subject>` へ転送される。
+//! このモジュールはそれらのメッセージを運用者が扱うための操作を提供する。
+
+use async_nats::HeaderMap;
+use async_nats::jetstream::{self, consumer::pull::OrderedConfig};
+use bytes::Bytes;
+use futures::StreamExt;
+use tracing::{debug, info};
+
+use crate::{
+    error::NatsInfraError,
+    nats::NatsClient,
+    stream::{
+        DLQ_HEADER_CONSUMER, DLQ_HEADER_DELIVERY_COUNT, DLQ_HEADER_ERROR,
+        DLQ_HEADER_ORIGINAL_SUBJECT, DLQ_STREAM_NAME, DLQ_SUBJECT_PREFIX, dlq_subject,
+    },
+};
+
+/// デッドレターキューに格納されたメッセージ
+#[derive(Clone, Debug)]
+pub struct DeadLetter {
+    /// デッドレターキューのストリーム内でのシーケンス番号
+    pub sequence: u64,
+    /// 元のサブジェクト
+    pub original_subject: String,
+    /// 失敗理由
+    pub error: Option<String>,
+    /// 転送されるまでの配信回数
+    pub delivery_count: Option<i64>,
+    /// メッセージを処理していたコンシューマー
+    pub consumer: Option<String>,
+    pub payload: Bytes,
+}
+
+impl DeadLetter {
+    fn from_parts(
+        sequence: u64,
+        subject: &str,
+        headers: Option<&HeaderMap>,
+        payload: Bytes,
+    ) -> Self {
+        let header = |name: &str| {
+            headers
+                .and_then(|h| h.get(name))
+                .map(|v| v.as_str().to_string())
+        };
+        let original_subject = header(DLQ_HEADER_ORIGINAL_SUBJECT).unwrap_or_else(|| {
+            subject
+                .strip_prefix(&format!("{DLQ_SUBJECT_PREFIX}."))
+                .unwrap_or(subject)
+                .to_string()
+        });
+
+        Self {
+            sequence,
+            original_subject,
+            error: header(DLQ_HEADER_ERROR),
+            delivery_count: header(DLQ_HEADER_DELIVERY_COUNT).and_then(|v| v.parse().ok()),
+            consumer: header(DLQ_HEADER_CONSUMER),
+            payload,
+        }
+    }
+}
+
+pub struct DeadLetterQueue {
+    nats_client: NatsClient,
+}
+
+impl DeadLetterQueue {
+    pub fn new(nats_client: NatsClient) -> Self {
+        Self { nats_client }
+    }
+
+    /// デッドレターキュー用のストリーム設定
+    pub fn stream_config() -> jetstream::stream::Config {
+        jetstream::stream::Config {
+            name: DLQ_STREAM_NAME.to_string(),
+            subjects: vec![format!("{DLQ_SUBJECT_PREFIX}.>")],
+            ..Default::default()
+        }
+    }
+
+    async fn get_stream(&self) -> Result<jetstream::stream::Stream, NatsInfraError> {
+        self.nats_client
+            .jetstream_context()
+            .get_stream(DLQ_STREAM_NAME)
+            .await
+            .map_err(|e| NatsInfraError::StreamRetrieval {
+                stream_name: DLQ_STREAM_NAME.to_string(),
+                source: Box::new(e),
+            })
+    }
+
+    /// デッドレターを古い順に最大 `limit` 件取得する
+    ///
+    /// `subject` を指定した場合は元のサブジェクトで絞り込む（ワイルドカード可）。
+    pub async fn list(
+        &self,
+        subject: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<DeadLetter>, NatsInfraError> {
+        let stream = self.get_stream().await?;
+        let consumer = stream
+            .create_consumer(OrderedConfig {
+                filter_subject: dlq_subject(subject.unwrap_or(">")),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| NatsInfraError::DeadLetterQueue {
+                source: Box::new(e),
+            })?;
+
+        let pending = consumer.cached_info().num_pending as usize;
+        let count = pending.min(limit);
+        debug!(pending, count, "デッドレターを取得します");
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut messages = consumer
+            .messages()
+            .await
+            .map_err(|e| NatsInfraError::DeadLetterQueue {
+                source: Box::new(e),
+            })?
+            .take(count);
+
+        let mut dead_letters = Vec::with_capacity(count);
+        while let Some(msg) = messages.next().await {
+            let msg = msg.map_err(|e| NatsInfraError::DeadLetterQueue {
+                source: Box::new(e),
+            })?;
+            let sequence = msg
+                .info()
+                .map_err(|e| NatsInfraError::DeadLetterQueue { source: e })?
+                .stream_sequence;
+            dead_letters.push(DeadLetter::from_parts(
+                sequence,
+                msg.subject.as_str(),
+                msg.headers.as_ref(),
+                msg.payload.clone(),
+            ));
+        }
+        Ok(dead_letters)
+    }
+
+    /// 指定したデッドレターを元のサブジェクトへ再発行し、キューから削除する
+    pub async fn replay(&self, sequence: u64) -> Result<DeadLetter, NatsInfraError> {
+        let stream = self.get_stream().await?;
+        let msg = stream.get_raw_message(sequence).await.map_err(|e| {
+            NatsInfraError::DeadLetterQueue {
+                source: Box::new(e),
+            }
+        })?;
+        let dead_letter = DeadLetter::from_parts(
+            msg.sequence,
+            msg.subject.as_str(),
+            Some(&msg.headers),
+            msg.payload,
+        );
+
+        let js = self.nats_client.jetstream_context();
+        js.publish(
+            dead_letter.original_subject.clone(),
+            dead_letter.payload.clone(),
+        )
+        .await
+        .map_err(|e| NatsInfraError::EventPublish {
+            subject: dead_letter.original_subject.clone(),
+            source: Box::new(e),
+        })?
+        .await
+        .map_err(|e| NatsInfraError::EventPublish {
+            subject: dead_letter.original_subject.clone(),
+            source: Box::new(e),
+        })?;
+
+        stream
+            .delete_message(sequence)
+            .await
+            .map_err(|e| NatsInfraError::DeadLetterQueue {
+                source: Box::new(e),
+            })?;
+
+        info!(
+            sequence,
+            subject = %dead_letter.original_subject,
+            "デッドレターを再投入しました"
+        );
+        Ok(dead_letter)
+    }
+
+    /// 条件に一致するデッドレターをすべて再投入し、再投入した件数を返す
+    pub async fn replay_all(&self, subject: Option<&str>) -> Result<usize, NatsInfraError> {
+        let dead_letters = self.list(subject, usize::MAX).await?;
+        for dead_letter in &dead_letters {
+            self.replay(dead_letter.sequence).await?;
+        }
+        Ok(dead_letters.len())
+    }
+
+    /// 条件に一致するデッドレターを削除し、削除した件数を返す
+    pub async fn purge(&self, subject: Option<&str>) -> Result<u64, NatsInfraError> {
+        let stream = self.get_stream().await?;
+        let response = match subject {
+            Some(subject) => stream.purge().filter(dlq_subject(subject)).await,
+            None => stream.purge().await,
+        }
+        .map_err(|e| NatsInfraError::DeadLetterQueue {
+            source: Box::new(e),
+        })?;
+        Ok(response.purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        nats::connect_nats,
+        stream::{EventReader, EventStore, ReaderConfig},
+        stream_manager::create_or_update_streams,
+        test_util::setup_toxi_proxy_nats,
+    };
+    use domain::service::RetryPolicy;
+    use domain::types::Event;
+    use std::time::Duration;
+
+    pub mod test_domain {
+        pub mod test_resource {
+            use serde::{Deserialize, Serialize};
+
+            #[derive(Clone, Debug, Deserialize, Serialize)]
+            pub struct DlqTestEvent {
+                pub data: String,
+            }
+        }
+    }
+    use test_domain::test_resource::DlqTestEvent;
+
+    impl Event for DlqTestEvent {}
+
+    type TestEventStore = EventStore<DlqTestEvent>;
+
+    async fn setup_streams(nats_client: &NatsClient) {
+        create_or_update_streams(
+            nats_client,
+            &[
+                jetstream::stream::Config {
+                    name: "kurec".to_string(),
+                    subjects: vec![TestEventStore::get_subject()],
+                    ..Default::default()
+                },
+                DeadLetterQueue::stream_config(),
+            ],
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_malformed_message_is_dead_lettered() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        setup_streams(&nats_client).await;
+
+        let subject = TestEventStore::get_subject();
+        let js = nats_client.jetstream_context();
+        js.publish(subject.clone(), "not json".into())
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+
+        let event_store = TestEventStore::new(nats_client.clone()).await.unwrap();
+        let reader = event_store
+            .get_reader("dlq_test_consumer".to_string())
+            .await
+            .unwrap();
+        let result = reader.next().await;
+        assert!(matches!(
+            result,
+            Err(NatsInfraError::JsonDeserialize { .. })
+        ));
+
+        let dlq = DeadLetterQueue::new(nats_client.clone());
+        let dead_letters = dlq.list(None, 10).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].original_subject, subject);
+        assert_eq!(dead_letters[0].payload, Bytes::from("not json"));
+        assert_eq!(dead_letters[0].delivery_count, Some(1));
+        assert_eq!(
+            dead_letters[0].consumer.as_deref(),
+            Some("dlq_test_consumer")
+        );
+        assert!(dead_letters[0].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_message_is_dead_lettered_and_replayed() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        setup_streams(&nats_client).await;
+
+        let event_store = TestEventStore::new(nats_client.clone()).await.unwrap();
+        event_store
+            .publish_event(&DlqTestEvent {
+                data: "poison".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let reader = event_store
+            .get_reader_with_config(
+                "dlq_exhausted_consumer".to_string(),
+                ReaderConfig {
+                    retry_policy: RetryPolicy {
+                        max_attempts: 2,
+                        initial_backoff: Duration::ZERO,
+                        ..Default::default()
+                    },
+                },
+            )
+            .await
+            .unwrap();
+
+        let (_, mut ack_handle) = reader.next().await.unwrap();
+        assert_eq!(ack_handle.delivery_count(), 1);
+        ack_handle.fail("一時的なエラー").await.unwrap();
+
+        let (_, mut ack_handle) = reader.next().await.unwrap();
+        assert_eq!(ack_handle.delivery_count(), 2);
+        assert!(ack_handle.is_last_delivery());
+        ack_handle.fail("恒久的なエラー").await.unwrap();
+
+        let dlq = DeadLetterQueue::new(nats_client.clone());
+        let dead_letters = dlq
+            .list(Some(&TestEventStore::get_subject()), 10)
+            .await
+            .unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].delivery_count, Some(2));
+        assert_eq!(dead_letters[0].error.as_deref(), Some("恒久的なエラー"));
+
+        let replayed = dlq.replay_all(None).await.unwrap();
+        assert_eq!(replayed, 1);
+        assert!(dlq.list(None, 10).await.unwrap().is_empty());
+
+        let (ev, mut ack_handle) = reader.next().await.unwrap();
+        assert_eq!(ev.data, "poison");
+        ack_handle.ack().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_purge() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        setup_streams(&nats_client).await;
+
+        let js = nats_client.jetstream_context();
+        for subject in ["dlq.a.b.c", "dlq.a.b.c", "dlq.x.y.z"] {
+            js.publish(subject, "{}".into())
+                .await
+                .unwrap()
+                .await
+                .unwrap();
+        }
+
+        let dlq = DeadLetterQueue::new(nats_client.clone());
+        assert_eq!(dlq.purge(Some("a.b.c")).await.unwrap(), 2);
+
+        let remaining = dlq.list(None, 10).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].original_subject, "x.y.z");
+
+        assert_eq!(dlq.purge(None).await.unwrap(), 1);
+    }
+}
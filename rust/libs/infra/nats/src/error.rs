@@ -4,6 +4,9 @@ pub enum NatsInfraError {
     #[error("NATS 接続に失敗しました: {0}")]
     Connection(Box<dyn std::error::Error + Send + Sync + 'static>),
 
+    #[error("NATS 接続の終了処理に失敗しました: {0}")]
+    Drain(async_nats::Error),
+
     #[error("JetStream コンテキストの取得に失敗しました: {0}")]
     JetStreamContext(async_nats::Error),
 
@@ -31,6 +34,12 @@ pub enum NatsInfraError {
         source: Box<dyn std::error::Error + Send + Sync + 'static>,
     },
 
+    #[error("オブジェクトストア '{bucket_name}' の作成/取得に失敗しました: {source}")]
+    ObjectStore {
+        bucket_name: String,
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
     #[error("JetStream ストリームの作成に失敗しました: {stream_name}: {source}")]
     StreamCreation {
         stream_name: String,
@@ -67,4 +76,34 @@ pub enum NatsInfraError {
         #[source]
         source: async_nats::Error,
     },
+
+    #[error("メッセージの否定確認（nak）に失敗しました: {source}")]
+    MessageNak {
+        #[source]
+        source: async_nats::Error,
+    },
+
+    #[error("メッセージの処理中通知に失敗しました: {source}")]
+    MessageInProgress {
+        #[source]
+        source: async_nats::Error,
+    },
+
+    #[error("メッセージの再配信停止（term）に失敗しました: {source}")]
+    MessageTerm {
+        #[source]
+        source: async_nats::Error,
+    },
+
+    #[error("デッドレターキュー{subject}へのメッセージ転送に失敗しました: {source}")]
+    DeadLetterPublish {
+        subject: String,
+        source: async_nats::Error,
+    },
+
+    #[error("デッドレターキューの操作に失敗しました: {source}")]
+    DeadLetterQueue {
+        #[source]
+        source: async_nats::Error,
+    },
 }
@@ -5,12 +5,34 @@ use domain::{
     error::DomainError,
     repository::{KvRepository, Versioned},
 };
+use futures::TryStreamExt;
 use heck::ToSnakeCase;
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 use tracing::{debug, error};
 
 use crate::{error::NatsInfraError, nats::NatsClient};
 
+/// KV バケットを新規作成するときの設定
+#[derive(Clone, Debug)]
+pub struct KvBucketConfig {
+    /// キーごとに保持する履歴の数
+    pub history: i64,
+    /// 値の保持期間 (`Duration::ZERO` は無期限)
+    pub max_age: Duration,
+    /// レプリカ数
+    pub replicas: usize,
+}
+
+impl Default for KvBucketConfig {
+    fn default() -> Self {
+        Self {
+            history: 1,
+            max_age: Duration::ZERO,
+            replicas: 1,
+        }
+    }
+}
+
 #[async_trait]
 pub trait NatsKvRepositoryTrait<K, V>: KvRepository<K, V> + Send + Sync
 where
@@ -48,13 +70,27 @@ where
     }
 
     pub async fn new(nats_client: NatsClient) -> Result<Self, NatsInfraError> {
-        let bucket_name = Self::generate_bucket_name();
+        Self::with_bucket_name(nats_client, &Self::generate_bucket_name()).await
+    }
+
+    /// 値の型名ではなく、指定した名前のバケットを使う
+    ///
+    /// 同じ型の値を用途ごとに別のバケットに保存するときに使う。
+    pub async fn with_bucket_name(
+        nats_client: NatsClient,
+        bucket_name: &str,
+    ) -> Result<Self, NatsInfraError> {
+        let bucket_name = bucket_name.to_string();
         let js = nats_client.jetstream_context();
+        let bucket_config = nats_client.kv_bucket_config();
         let kv_store = match js.get_key_value(&bucket_name).await {
             Ok(store) => store,
             Err(_) => js
                 .create_key_value(jetstream::kv::Config {
                     bucket: bucket_name.clone(),
+                    history: bucket_config.history,
+                    max_age: bucket_config.max_age,
+                    num_replicas: bucket_config.replicas,
                     ..Default::default()
                 })
                 .await
@@ -90,6 +126,31 @@ where
             }),
         }
     }
+
+    /// `prefix` で始まるキーを一覧する（削除済みのキーは含まない）
+    pub async fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>, NatsInfraError> {
+        let keys = self
+            .kv_store
+            .keys()
+            .await
+            .map_err(|e| NatsInfraError::KvGet {
+                source: Box::new(e),
+            })?;
+        let keys: Vec<String> = keys
+            .try_filter(|key| futures::future::ready(key.starts_with(prefix)))
+            .try_collect()
+            .await
+            .map_err(|e| NatsInfraError::KvGet {
+                source: Box::new(e),
+            })?;
+        debug!(
+            bucket = %self.bucket_name,
+            prefix = %prefix,
+            count = keys.len(),
+            "KVバケットのキーを一覧しました"
+        );
+        Ok(keys)
+    }
 }
 
 #[async_trait]
@@ -115,7 +176,7 @@ where
                     error = %e,
                     "KVバケットへの値の保存に失敗しました"
                 );
-                DomainError::ProgramsStoreError(format!("KVSへの保存エラー: {}", e))
+                DomainError::KvStoreError(format!("保存に失敗: {}", e))
             })?;
         Ok(())
     }
@@ -136,10 +197,7 @@ where
                     error = %e,
                     "KVバケットからの値の取得に失敗しました"
                 );
-                return Err(DomainError::ProgramsRetrievalError(format!(
-                    "KVSからの取得エラー: {}",
-                    e
-                )));
+                return Err(DomainError::KvStoreError(format!("取得に失敗: {}", e)));
             }
         };
 
@@ -171,7 +229,11 @@ where
                     error = %e,
                     "KVバケットの値の更新に失敗しました"
                 );
-                DomainError::ProgramsStoreError(format!("KVSの更新エラー: {}", e))
+                if e.kind() == jetstream::kv::UpdateErrorKind::WrongLastRevision {
+                    DomainError::RevisionConflict(format!("{}: {}", key.as_ref(), e))
+                } else {
+                    DomainError::KvStoreError(format!("更新に失敗: {}", e))
+                }
             })?;
         Ok(())
     }
@@ -189,7 +251,7 @@ where
                 error = %e,
                 "KVバケットからの値の削除に失敗しました"
             );
-            DomainError::ProgramsStoreError(format!("KVSの削除エラー: {}", e))
+            DomainError::KvStoreError(format!("削除に失敗: {}", e))
         })?;
         Ok(())
     }
@@ -344,7 +406,13 @@ mod tests {
         let key = "non_existent_key";
         let value = repositories::test::TestData(Bytes::from("test_value"));
         let result = repo.update(key.to_string(), &value, 1).await;
-
-        assert!(result.is_err());
+        assert!(matches!(result, Err(DomainError::RevisionConflict(_))));
+
+        // リビジョン0ならキーがないときだけ作成する
+        repo.update(key.to_string(), &value, 0).await.unwrap();
+        let result = repo.update(key.to_string(), &value, 0).await;
+        assert!(matches!(result, Err(DomainError::RevisionConflict(_))));
+        let created = repo.get(key.to_string()).await.unwrap().unwrap();
+        assert_eq!(created.value, value);
     }
 }
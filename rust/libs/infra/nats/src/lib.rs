@@ -1,6 +1,8 @@
+pub mod dlq;
 pub mod error;
 pub mod kvs;
 pub mod nats;
+pub mod object_store;
 pub mod repositories;
 pub mod stream;
 pub mod stream_manager;
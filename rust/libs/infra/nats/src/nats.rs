@@ -7,13 +7,40 @@ use async_nats::{self, ConnectOptions, client::Client, connect_with_options, jet
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
-use crate::error::NatsInfraError;
+use crate::{error::NatsInfraError, kvs::KvBucketConfig};
+
+/// NATS サーバーへの接続オプション。
+#[derive(Clone, Debug)]
+pub struct NatsConnectOptions {
+    /// 接続のタイムアウト
+    pub connection_timeout: Duration,
+    /// 再接続の最大試行回数 (`None` は無制限)
+    pub max_reconnects: Option<usize>,
+    /// サーバーに通知するクライアント名
+    pub name: Option<String>,
+    /// 最初の接続に失敗しても接続できるまでバックグラウンドで試行し続けるか
+    ///
+    /// `false` の場合は最初の接続に失敗するとすぐにエラーを返すため、呼び出し側で再試行します。
+    pub retry_on_initial_connect: bool,
+}
+
+impl Default for NatsConnectOptions {
+    fn default() -> Self {
+        Self {
+            connection_timeout: Duration::from_secs(10),
+            max_reconnects: None,
+            name: None,
+            retry_on_initial_connect: true,
+        }
+    }
+}
 
 /// NATS クライアントと関連コンテキストを保持するラッパー構造体。
 #[derive(Clone, Debug)]
 pub struct NatsClient {
     _client: Client,
     js_context: jetstream::context::Context,
+    kv_bucket_config: KvBucketConfig,
 }
 
 impl NatsClient {
@@ -23,9 +50,26 @@ impl NatsClient {
         Self {
             _client: client,
             js_context,
+            kv_bucket_config: KvBucketConfig::default(),
         }
     }
 
+    /// KV バケットを新規作成するときの設定を指定します。
+    pub fn with_kv_bucket_config(mut self, kv_bucket_config: KvBucketConfig) -> Self {
+        self.kv_bucket_config = kv_bucket_config;
+        self
+    }
+
+    /// サーバーに接続しているかどうかを返します。
+    pub fn is_connected(&self) -> bool {
+        self._client.connection_state() == async_nats::connection::State::Connected
+    }
+
+    /// KV バケットを新規作成するときの設定を取得します。
+    pub(crate) fn kv_bucket_config(&self) -> &KvBucketConfig {
+        &self.kv_bucket_config
+    }
+
     /// 接続済みの NATS クライアントを取得します。
     #[cfg(test)]
     pub(crate) fn client(&self) -> &Client {
@@ -36,19 +80,38 @@ impl NatsClient {
     pub(crate) fn jetstream_context(&self) -> &jetstream::context::Context {
         &self.js_context
     }
+
+    /// 未送信のメッセージを送信し、購読を解除してから接続を閉じます。
+    ///
+    /// 同じ接続を共有しているすべての `NatsClient` が使えなくなるため、終了時に一度だけ呼び出します。
+    pub async fn drain(&self) -> Result<(), NatsInfraError> {
+        info!("NATS 接続を終了しています...");
+        self._client
+            .drain()
+            .await
+            .map_err(|e| NatsInfraError::Drain(Box::new(e)))?;
+        info!("NATS 接続を終了しました。");
+        Ok(())
+    }
 }
 
 /// 指定された URL で NATS サーバーに接続し、`NatsClient` を返します。
 ///
 /// 接続オプションには、再接続試行などのデフォルト設定が含まれます。
 pub async fn connect_nats(nats_url: &str) -> Result<NatsClient, NatsInfraError> {
+    connect_nats_with_options(nats_url, &NatsConnectOptions::default()).await
+}
+
+/// 指定された URL とオプションで NATS サーバーに接続し、`NatsClient` を返します。
+pub async fn connect_nats_with_options(
+    nats_url: &str,
+    connect_options: &NatsConnectOptions,
+) -> Result<NatsClient, NatsInfraError> {
     info!(url = %nats_url, "NATS サーバーへの接続を開始します...");
 
-    // TODO: 設定ファイルから読み込むなど、より柔軟なオプション設定を検討
-    let options = ConnectOptions::new()
-        .retry_on_initial_connect()
-        .connection_timeout(Duration::from_secs(10))
-        .max_reconnects(None) // 無制限に再接続試行
+    let mut options = ConnectOptions::new()
+        .connection_timeout(connect_options.connection_timeout)
+        .max_reconnects(connect_options.max_reconnects)
         .reconnect_delay_callback(|attempts| {
             // 再接続試行回数に応じて遅延時間を調整 (例: 指数バックオフ)
             let delay = Duration::from_millis(100 * 2u64.pow(attempts.min(8) as u32)); // 最大約25秒
@@ -59,6 +122,12 @@ pub async fn connect_nats(nats_url: &str) -> Result<NatsClient, NatsInfraError>
             }
             delay
         });
+    if let Some(name) = &connect_options.name {
+        options = options.name(name);
+    }
+    if connect_options.retry_on_initial_connect {
+        options = options.retry_on_initial_connect();
+    }
 
     let client = connect_with_options(nats_url, options)
         .await
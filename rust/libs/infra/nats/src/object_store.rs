@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use async_nats::HeaderMap;
+use async_nats::jetstream::object_store::{
+    self, DeleteErrorKind, GetErrorKind, InfoErrorKind, ObjectInfo, ObjectMetadata,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use domain::{
+    error::DomainError,
+    model::object::{ObjectMeta, PutOptions, StoredObject},
+    ports::ObjectStore,
+};
+use futures::TryStreamExt;
+use tokio::io::AsyncReadExt;
+use tracing::{debug, error};
+
+use crate::{error::NatsInfraError, nats::NatsClient};
+
+const CONTENT_TYPE_HEADER: &str = "Content-Type";
+
+/// JetStream Object Store に保存する `ObjectStore`
+///
+/// Content-Typeはヘッダーに、それ以外のメタデータはオブジェクトのメタデータに保存する。
+#[derive(Clone)]
+pub struct NatsObjectStore {
+    bucket_name: String,
+    store: object_store::ObjectStore,
+}
+
+impl NatsObjectStore {
+    /// バケットがなければ作成する
+    pub async fn new(nats_client: NatsClient, bucket_name: &str) -> Result<Self, NatsInfraError> {
+        let js = nats_client.jetstream_context();
+        let store = match js.get_object_store(bucket_name).await {
+            Ok(store) => store,
+            Err(_) => js
+                .create_object_store(object_store::Config {
+                    bucket: bucket_name.to_string(),
+                    num_replicas: nats_client.kv_bucket_config().replicas,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| NatsInfraError::ObjectStore {
+                    bucket_name: bucket_name.to_string(),
+                    source: Box::new(e),
+                })?,
+        };
+        Ok(Self {
+            bucket_name: bucket_name.to_string(),
+            store,
+        })
+    }
+
+    pub fn bucket_name(&self) -> &str {
+        &self.bucket_name
+    }
+
+    fn object_metadata(key: &str, options: &PutOptions) -> ObjectMetadata {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE_HEADER, options.content_type.as_str());
+        ObjectMetadata {
+            name: key.to_string(),
+            headers: Some(headers),
+            metadata: options.metadata.clone().into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    async fn put_reader(
+        &self,
+        key: &str,
+        options: &PutOptions,
+        data: &mut (impl tokio::io::AsyncRead + Unpin),
+    ) -> Result<ObjectMeta, DomainError> {
+        debug!(bucket = %self.bucket_name, key = %key, "オブジェクトを保存します");
+        let info = self
+            .store
+            .put(Self::object_metadata(key, options), data)
+            .await
+            .map_err(|e| self.store_error("保存", key, e))?;
+        Ok(to_object_meta(&info))
+    }
+
+    fn store_error(&self, operation: &str, key: &str, e: impl std::fmt::Display) -> DomainError {
+        error!(
+            bucket = %self.bucket_name,
+            key = %key,
+            error = %e,
+            "オブジェクトの{}に失敗しました",
+            operation
+        );
+        DomainError::ObjectStoreError(format!(
+            "{}/{} の{}に失敗: {}",
+            self.bucket_name, key, operation, e
+        ))
+    }
+}
+
+fn to_object_meta(info: &ObjectInfo) -> ObjectMeta {
+    let content_type = info
+        .headers
+        .as_ref()
+        .and_then(|headers| headers.get(CONTENT_TYPE_HEADER))
+        .map(|value| value.as_str().to_string());
+    ObjectMeta {
+        key: info.name.clone(),
+        size: info.size as u64,
+        content_type,
+        metadata: info
+            .metadata
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect::<BTreeMap<_, _>>(),
+        last_modified: info
+            .modified
+            .map(|modified| (modified.unix_timestamp_nanos() / 1_000_000) as i64),
+    }
+}
+
+#[async_trait]
+impl ObjectStore for NatsObjectStore {
+    async fn put(
+        &self,
+        key: &str,
+        data: Bytes,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        self.put_reader(key, options, &mut data.as_ref()).await
+    }
+
+    async fn put_file(
+        &self,
+        key: &str,
+        path: &Path,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .map_err(|e| self.store_error("読み込み", &path.display().to_string(), e))?;
+        self.put_reader(key, options, &mut file).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>, DomainError> {
+        let mut object = match self.store.get(key).await {
+            Ok(object) => object,
+            Err(e) if e.kind() == GetErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(self.store_error("取得", key, e)),
+        };
+        let mut data = Vec::with_capacity(object.info.size);
+        object
+            .read_to_end(&mut data)
+            .await
+            .map_err(|e| self.store_error("取得", key, e))?;
+        Ok(Some(StoredObject {
+            meta: to_object_meta(&object.info),
+            data: Bytes::from(data),
+        }))
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<ObjectMeta>, DomainError> {
+        match self.store.info(key).await {
+            Ok(info) if info.deleted => Ok(None),
+            Ok(info) => Ok(Some(to_object_meta(&info))),
+            Err(e) if e.kind() == InfoErrorKind::NotFound => Ok(None),
+            Err(e) => Err(self.store_error("情報の取得", key, e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DomainError> {
+        debug!(bucket = %self.bucket_name, key = %key, "オブジェクトを削除します");
+        match self.store.delete(key).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == DeleteErrorKind::NotFound => Ok(()),
+            Err(e) => Err(self.store_error("削除", key, e)),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, DomainError> {
+        let list = self
+            .store
+            .list()
+            .await
+            .map_err(|e| self.store_error("一覧", prefix, e))?;
+        let mut objects: Vec<ObjectMeta> = list
+            .try_filter(|info| {
+                futures::future::ready(!info.deleted && info.name.starts_with(prefix))
+            })
+            .map_ok(|info| to_object_meta(&info))
+            .try_collect()
+            .await
+            .map_err(|e| self.store_error("一覧", prefix, e))?;
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(objects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nats::connect_nats, test_util::setup_toxi_proxy_nats};
+
+    #[tokio::test]
+    async fn test_put_get_and_head() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        let store = NatsObjectStore::new(nats_client, "test_objects")
+            .await
+            .unwrap();
+
+        let options = PutOptions::new("image/webp").with_metadata("program_id", 327360102412345i64);
+        let meta = store
+            .put("images/abc", Bytes::from_static(b"webp data"), &options)
+            .await
+            .unwrap();
+        assert_eq!(meta.key, "images/abc");
+        assert_eq!(meta.size, 9);
+
+        let object = store.get("images/abc").await.unwrap().unwrap();
+        assert_eq!(object.data, Bytes::from_static(b"webp data"));
+        assert_eq!(object.meta.content_type.as_deref(), Some("image/webp"));
+        assert_eq!(
+            object.meta.metadata.get("program_id").map(String::as_str),
+            Some("327360102412345")
+        );
+
+        let head = store.head("images/abc").await.unwrap().unwrap();
+        assert_eq!(head.size, 9);
+        assert!(head.last_modified.is_some());
+        assert!(store.get("images/missing").await.unwrap().is_none());
+        assert!(store.head("images/missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_put_file_list_and_delete() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        let store = NatsObjectStore::new(nats_client, "test_objects")
+            .await
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("kurec-object-{}.mp4", rand::random::<u64>()));
+        // チャンクの大きさ（128KiB）を超えるファイル
+        let content = vec![0x47u8; 300 * 1024];
+        tokio::fs::write(&path, &content).await.unwrap();
+        let meta = store
+            .put_file(
+                "recordings/1/movie.mp4",
+                &path,
+                &PutOptions::new("video/mp4"),
+            )
+            .await
+            .unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert_eq!(meta.size, content.len() as u64);
+        store
+            .put(
+                "images/abc",
+                Bytes::from_static(b"webp"),
+                &PutOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        let listed = store.list("recordings/").await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].key, "recordings/1/movie.mp4");
+        assert_eq!(listed[0].content_type.as_deref(), Some("video/mp4"));
+        let object = store.get("recordings/1/movie.mp4").await.unwrap().unwrap();
+        assert_eq!(object.data.len(), content.len());
+
+        store.delete("recordings/1/movie.mp4").await.unwrap();
+        // 存在しないオブジェクトの削除はエラーにしない
+        store.delete("recordings/1/movie.mp4").await.unwrap();
+        assert!(store.list("recordings/").await.unwrap().is_empty());
+        assert!(
+            store
+                .head("recordings/1/movie.mp4")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
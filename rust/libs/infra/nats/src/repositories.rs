@@ -1,6 +1,16 @@
 use async_trait::async_trait;
-use domain::model::program::ProgramsData;
-use domain::repository::KvRepository;
+use bytes::Bytes;
+use domain::error::DomainError;
+use domain::model::inventory::{ChannelInfo, Inventory, Service, Tuner};
+use domain::model::program::{Program, ProgramsData, ProgramsDiff};
+use domain::model::recording_rule::RecordingRule;
+use domain::ports::ProgramQuery;
+use domain::repository::{
+    InventoryRepository, KvRepository, ProgramRepository, RecordingRuleRepository,
+};
+use futures::{StreamExt, TryStreamExt, stream};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::{debug, warn};
 
 use crate::error::NatsInfraError;
 use crate::kvs::{NatsKvRepositoryImpl, NatsKvRepositoryTrait};
@@ -51,6 +61,476 @@ impl KvRepository<String, ProgramsData> for ProgramsDataRepository {
     }
 }
 
+/// JSONで保存する1件分のKVの値（壊れた値は `None` として読む）
+macro_rules! define_json_entry {
+    ($(#[$meta:meta])* $entry:ident, $value:ty) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $entry(pub Option<$value>);
+
+        impl From<Bytes> for $entry {
+            fn from(bytes: Bytes) -> Self {
+                $entry(serde_json::from_slice(&bytes).ok())
+            }
+        }
+
+        impl From<$entry> for Bytes {
+            fn from(entry: $entry) -> Self {
+                Bytes::from(serde_json::to_vec(&entry.0).unwrap_or_default())
+            }
+        }
+    };
+}
+
+define_json_entry!(
+    /// 番組1件分のKVの値
+    ProgramEntry,
+    Program
+);
+define_json_entry!(
+    /// 索引のキーに載っている番組IDの集合
+    ProgramIndex,
+    BTreeSet<i64>
+);
+
+/// 索引の更新が他のサービスの番組表の反映とぶつかったときに読み直す回数
+const MAX_INDEX_UPDATE_ATTEMPTS: usize = 10;
+
+/// 番組を読むときに同時に取得する件数
+const CONCURRENT_PROGRAM_GETS: usize = 32;
+
+/// 索引の日付の区切り（UTCの1日）
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// 番組を `programs.<サービスID>.<番組ID>` のキーで1件ずつ保存するリポジトリ
+///
+/// Mirakurunの番組IDはサービスID * 100000 + イベントIDなので、サービスIDは番組IDから求める。
+/// 検索用に、サービス（`service.<サービスID>`）・放送日（`day.<UNIX時間の日数>`）・
+/// ジャンルの大分類（`genre.<lv1>`）ごとの番組IDを別のバケットに索引として保存する。
+pub struct ProgramEntryRepository {
+    inner: NatsKvRepositoryImpl<String, ProgramEntry>,
+    index: NatsKvRepositoryImpl<String, ProgramIndex>,
+}
+
+impl ProgramEntryRepository {
+    pub async fn new(nats_client: NatsClient) -> Result<Self, NatsInfraError> {
+        let inner = NatsKvRepositoryImpl::new(nats_client.clone()).await?;
+        let index = NatsKvRepositoryImpl::new(nats_client).await?;
+
+        Ok(Self { inner, index })
+    }
+
+    fn key(program_id: i64) -> String {
+        format!("programs.{}.{}", program_id / 100000, program_id)
+    }
+
+    fn service_index_key(service_id: i64) -> String {
+        format!("service.{}", service_id)
+    }
+
+    /// `start_at` から `end_at` まで（`end_at` は含まない）にかかる日の索引のキー
+    fn day_index_keys(start_at: i64, end_at: i64) -> impl Iterator<Item = String> {
+        let first = start_at.div_euclid(DAY_MILLIS);
+        let last = (end_at - 1).max(start_at).div_euclid(DAY_MILLIS);
+        (first..=last).map(|day| format!("day.{}", day))
+    }
+
+    fn genre_index_key(lv1: u8) -> String {
+        format!("genre.{}", lv1)
+    }
+
+    /// 番組が載る索引のキー
+    fn index_keys(program: &Program) -> BTreeSet<String> {
+        let mut keys = BTreeSet::from([Self::service_index_key(program.id / 100000)]);
+        keys.extend(Self::day_index_keys(program.start_at, program.end_at));
+        keys.extend(
+            program
+                .genres
+                .iter()
+                .map(|genre| Self::genre_index_key(genre.lv1)),
+        );
+        keys
+    }
+
+    /// 索引に番組IDを追加・削除する
+    ///
+    /// 同じ索引を他のサービスの番組表の反映でも更新するため、リビジョンを確かめて保存し、
+    /// ぶつかったら読み直す。
+    async fn modify_index(
+        &self,
+        key: &str,
+        added: &BTreeSet<i64>,
+        removed: &BTreeSet<i64>,
+    ) -> Result<(), DomainError> {
+        let mut attempt = 1;
+        loop {
+            let (current, revision) = match self.index.get(key.to_string()).await? {
+                // 壊れた索引は空として読み、書き直す
+                Some(versioned) => (versioned.value.0.unwrap_or_default(), versioned.revision),
+                None => (BTreeSet::new(), 0),
+            };
+            let ids: BTreeSet<i64> = current
+                .iter()
+                .chain(added)
+                .copied()
+                .filter(|id| !removed.contains(id))
+                .collect();
+            if ids == current {
+                return Ok(());
+            }
+            match self
+                .index
+                .update(key.to_string(), &ProgramIndex(Some(ids)), revision)
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(DomainError::RevisionConflict(e)) if attempt < MAX_INDEX_UPDATE_ATTEMPTS => {
+                    debug!(key = %key, error = %e, "番組の索引の更新がぶつかったので読み直します");
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 索引のキーに載っている番組をすべて読み、放送開始時刻の順に並べる
+    async fn load_programs(
+        &self,
+        index_keys: impl IntoIterator<Item = String>,
+    ) -> Result<Vec<Program>, DomainError> {
+        let mut ids = BTreeSet::new();
+        for key in index_keys {
+            if let Some(key_ids) = self.index.get(key).await?.and_then(|v| v.value.0) {
+                ids.extend(key_ids);
+            }
+        }
+
+        let entries: Vec<_> = stream::iter(ids)
+            .map(|id| self.inner.get(Self::key(id)))
+            .buffered(CONCURRENT_PROGRAM_GETS)
+            .try_collect()
+            .await?;
+        // 索引に残っていても番組が消えていれば読み飛ばす
+        let mut programs: Vec<Program> = entries
+            .into_iter()
+            .filter_map(|entry| entry.and_then(|versioned| versioned.value.0))
+            .collect();
+        programs.sort_by_key(|program| (program.start_at, program.id));
+        Ok(programs)
+    }
+}
+
+#[async_trait]
+impl ProgramRepository for ProgramEntryRepository {
+    async fn apply_diff(&self, diff: &ProgramsDiff) -> Result<(), DomainError> {
+        let mut index_added: BTreeMap<String, BTreeSet<i64>> = BTreeMap::new();
+        let mut index_removed: BTreeMap<String, BTreeSet<i64>> = BTreeMap::new();
+
+        for program in &diff.added {
+            self.inner
+                .put(Self::key(program.id), &ProgramEntry(Some(program.clone())))
+                .await?;
+            for key in Self::index_keys(program) {
+                index_added.entry(key).or_default().insert(program.id);
+            }
+        }
+        for (program, _) in &diff.changed {
+            // 放送日やジャンルが変わった番組は、変更前の索引から外す
+            let previous = self
+                .inner
+                .get(Self::key(program.id))
+                .await?
+                .and_then(|versioned| versioned.value.0);
+            self.inner
+                .put(Self::key(program.id), &ProgramEntry(Some(program.clone())))
+                .await?;
+            let keys = Self::index_keys(program);
+            if let Some(previous) = previous {
+                for key in Self::index_keys(&previous).difference(&keys) {
+                    index_removed
+                        .entry(key.clone())
+                        .or_default()
+                        .insert(program.id);
+                }
+            }
+            for key in keys {
+                index_added.entry(key).or_default().insert(program.id);
+            }
+        }
+        for program in &diff.removed {
+            for key in Self::index_keys(program) {
+                index_removed.entry(key).or_default().insert(program.id);
+            }
+        }
+
+        // 番組を保存してから索引に載せ、索引から外してから番組を消す
+        let empty = BTreeSet::new();
+        let index_keys: BTreeSet<&String> =
+            index_added.keys().chain(index_removed.keys()).collect();
+        for key in index_keys {
+            self.modify_index(
+                key,
+                index_added.get(key).unwrap_or(&empty),
+                index_removed.get(key).unwrap_or(&empty),
+            )
+            .await?;
+        }
+        for program in &diff.removed {
+            self.inner.delete(Self::key(program.id)).await?;
+        }
+        debug!(
+            added = diff.added.len(),
+            changed = diff.changed.len(),
+            removed = diff.removed.len(),
+            "番組ごとのKVに差分を反映しました"
+        );
+        Ok(())
+    }
+
+    async fn has_programs(&self, service_id: i64) -> Result<bool, DomainError> {
+        Ok(self
+            .index
+            .get(Self::service_index_key(service_id))
+            .await?
+            .and_then(|versioned| versioned.value.0)
+            .is_some_and(|ids| !ids.is_empty()))
+    }
+}
+
+#[async_trait]
+impl ProgramQuery for ProgramEntryRepository {
+    async fn find_by_id(&self, program_id: i64) -> Result<Option<Program>, DomainError> {
+        Ok(self
+            .inner
+            .get(Self::key(program_id))
+            .await?
+            .and_then(|versioned| versioned.value.0))
+    }
+
+    async fn find_by_service(&self, service_id: i64) -> Result<Vec<Program>, DomainError> {
+        self.load_programs([Self::service_index_key(service_id)])
+            .await
+    }
+
+    async fn find_by_time_range(
+        &self,
+        start_at: i64,
+        end_at: i64,
+    ) -> Result<Vec<Program>, DomainError> {
+        if end_at <= start_at {
+            return Ok(Vec::new());
+        }
+        let mut programs = self
+            .load_programs(Self::day_index_keys(start_at, end_at))
+            .await?;
+        programs.retain(|program| program.overlaps(start_at, end_at));
+        Ok(programs)
+    }
+
+    async fn find_by_genre(&self, lv1: u8, lv2: Option<u8>) -> Result<Vec<Program>, DomainError> {
+        let mut programs = self.load_programs([Self::genre_index_key(lv1)]).await?;
+        programs.retain(|program| program.has_genre(lv1, lv2));
+        Ok(programs)
+    }
+}
+
+define_json_entry!(
+    /// 録画ルール1件分のKVの値
+    RecordingRuleEntry,
+    RecordingRule
+);
+
+/// 録画ルールをルールIDのキーで保存するリポジトリ
+pub struct RecordingRuleEntryRepository {
+    inner: NatsKvRepositoryImpl<String, RecordingRuleEntry>,
+}
+
+impl RecordingRuleEntryRepository {
+    pub async fn new(nats_client: NatsClient) -> Result<Self, NatsInfraError> {
+        let inner = NatsKvRepositoryImpl::new(nats_client).await?;
+
+        Ok(Self { inner })
+    }
+}
+
+#[async_trait]
+impl RecordingRuleRepository for RecordingRuleEntryRepository {
+    async fn list(&self) -> Result<Vec<RecordingRule>, DomainError> {
+        let keys = self
+            .inner
+            .keys_with_prefix("")
+            .await
+            .map_err(|e| DomainError::KvStoreError(format!("キー一覧の取得に失敗: {}", e)))?;
+
+        let mut rules = Vec::with_capacity(keys.len());
+        for key in keys {
+            match self.inner.get(key.clone()).await?.map(|v| v.value.0) {
+                Some(Some(rule)) => rules.push(rule),
+                Some(None) => warn!(key = %key, "読み込めない録画ルールを無視します"),
+                None => {}
+            }
+        }
+        rules.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(rules)
+    }
+
+    async fn put(&self, rule: &RecordingRule) -> Result<(), DomainError> {
+        rule.validate()?;
+        self.inner
+            .put(rule.id.clone(), &RecordingRuleEntry(Some(rule.clone())))
+            .await
+    }
+
+    async fn delete(&self, rule_id: &str) -> Result<(), DomainError> {
+        self.inner.delete(rule_id.to_string()).await
+    }
+}
+
+define_json_entry!(
+    /// チューナー1件分のKVの値
+    TunerEntry,
+    Tuner
+);
+define_json_entry!(
+    /// チャンネル1件分のKVの値
+    ChannelEntry,
+    ChannelInfo
+);
+define_json_entry!(
+    /// サービス1件分のKVの値
+    ServiceEntry,
+    Service
+);
+
+/// チューナー・チャンネル・サービスをそれぞれのバケットに1件ずつ保存するリポジトリ
+///
+/// キーはチューナーが番号、チャンネルが `<種別>.<チャンネル番号>`、サービスがサービスID。
+pub struct InventoryEntryRepository {
+    tuners: NatsKvRepositoryImpl<String, TunerEntry>,
+    channels: NatsKvRepositoryImpl<String, ChannelEntry>,
+    services: NatsKvRepositoryImpl<String, ServiceEntry>,
+}
+
+impl InventoryEntryRepository {
+    pub async fn new(nats_client: NatsClient) -> Result<Self, NatsInfraError> {
+        Ok(Self {
+            tuners: NatsKvRepositoryImpl::new(nats_client.clone()).await?,
+            channels: NatsKvRepositoryImpl::new(nats_client.clone()).await?,
+            services: NatsKvRepositoryImpl::new(nats_client).await?,
+        })
+    }
+}
+
+/// バケットの値をすべて読む（読み込めない値は無視する）
+async fn load_entries<V, T>(
+    inner: &NatsKvRepositoryImpl<String, V>,
+    value: impl Fn(V) -> Option<T>,
+) -> Result<Vec<T>, DomainError>
+where
+    V: Into<Bytes> + From<Bytes> + Send + Sync + Clone + 'static,
+{
+    let keys = inner
+        .keys_with_prefix("")
+        .await
+        .map_err(|e| DomainError::KvStoreError(format!("キー一覧の取得に失敗: {}", e)))?;
+
+    let mut values = Vec::with_capacity(keys.len());
+    for key in keys {
+        match inner.get(key.clone()).await?.map(|v| value(v.value)) {
+            Some(Some(v)) => values.push(v),
+            Some(None) => {
+                warn!(bucket = %inner.bucket_name, key = %key, "読み込めない値を無視します")
+            }
+            None => {}
+        }
+    }
+    Ok(values)
+}
+
+/// バケットの中身を `entries` で置き換える（値が変わらないキーは書き込まない）
+async fn replace_entries<V>(
+    inner: &NatsKvRepositoryImpl<String, V>,
+    entries: Vec<(String, V)>,
+) -> Result<(), DomainError>
+where
+    V: Into<Bytes> + From<Bytes> + Send + Sync + Clone + PartialEq + 'static,
+{
+    let stale_keys = inner
+        .keys_with_prefix("")
+        .await
+        .map_err(|e| DomainError::KvStoreError(format!("キー一覧の取得に失敗: {}", e)))?;
+
+    for (key, value) in &entries {
+        let stored = inner.get(key.clone()).await?;
+        if stored.is_none_or(|stored| stored.value != *value) {
+            inner.put(key.clone(), value).await?;
+        }
+    }
+    for key in stale_keys {
+        if !entries.iter().any(|(k, _)| *k == key) {
+            inner.delete(key).await?;
+        }
+    }
+    debug!(
+        bucket = %inner.bucket_name,
+        count = entries.len(),
+        "KVバケットの中身を置き換えました"
+    );
+    Ok(())
+}
+
+#[async_trait]
+impl InventoryRepository for InventoryEntryRepository {
+    async fn load(&self) -> Result<Inventory, DomainError> {
+        let mut tuners = load_entries(&self.tuners, |entry| entry.0).await?;
+        tuners.sort_by_key(|tuner: &Tuner| tuner.index);
+        let mut channels = load_entries(&self.channels, |entry| entry.0).await?;
+        channels.sort_by(|a: &ChannelInfo, b: &ChannelInfo| {
+            (a.channel_type, &a.channel).cmp(&(b.channel_type, &b.channel))
+        });
+        let mut services = load_entries(&self.services, |entry| entry.0).await?;
+        services.sort_by_key(|service: &Service| service.id);
+
+        Ok(Inventory {
+            tuners,
+            channels,
+            services,
+        })
+    }
+
+    async fn find_service(&self, service_id: i64) -> Result<Option<Service>, DomainError> {
+        Ok(self
+            .services
+            .get(service_id.to_string())
+            .await?
+            .and_then(|versioned| versioned.value.0))
+    }
+
+    async fn replace_tuners(&self, tuners: &[Tuner]) -> Result<(), DomainError> {
+        let entries = tuners
+            .iter()
+            .map(|tuner| (tuner.index.to_string(), TunerEntry(Some(tuner.clone()))))
+            .collect();
+        replace_entries(&self.tuners, entries).await
+    }
+
+    async fn replace_channels(&self, channels: &[ChannelInfo]) -> Result<(), DomainError> {
+        let entries = channels
+            .iter()
+            .map(|channel| (channel.key(), ChannelEntry(Some(channel.clone()))))
+            .collect();
+        replace_entries(&self.channels, entries).await
+    }
+
+    async fn replace_services(&self, services: &[Service]) -> Result<(), DomainError> {
+        let entries = services
+            .iter()
+            .map(|service| (service.id.to_string(), ServiceEntry(Some(service.clone()))))
+            .collect();
+        replace_entries(&self.services, entries).await
+    }
+}
+
 #[macro_export]
 macro_rules! define_repository {
     ($repo_name:ident, $key_type:ty, $value_type:ty) => {
@@ -148,3 +628,175 @@ pub mod test {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nats::connect_nats, test_util::setup_toxi_proxy_nats};
+    use domain::model::inventory::ChannelType;
+    use domain::model::program::Genre;
+    use domain::usecase::test_util::{test_program, test_service, test_tuner};
+
+    #[tokio::test]
+    async fn test_recording_rule_entry_repository() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        let repo = RecordingRuleEntryRepository::new(nats_client)
+            .await
+            .unwrap();
+
+        let rule = |id: &str| -> RecordingRule {
+            serde_json::from_value(serde_json::json!({"id": id, "name": id})).unwrap()
+        };
+        repo.put(&rule("b-rule")).await.unwrap();
+        repo.put(&rule("a-rule")).await.unwrap();
+        repo.put(&rule("removed")).await.unwrap();
+        repo.delete("removed").await.unwrap();
+        assert!(repo.put(&rule("invalid.id")).await.is_err());
+
+        let ids: Vec<String> = repo
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|rule| rule.id)
+            .collect();
+        assert_eq!(ids, ["a-rule", "b-rule"]);
+    }
+
+    #[tokio::test]
+    async fn test_program_entry_repository_apply_diff_and_query() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        let repo = ProgramEntryRepository::new(nats_client).await.unwrap();
+
+        let late = test_program(327360102400001, 3273601024, 1619859600000, "遅い番組");
+        let mut early = test_program(327360102400002, 3273601024, 1619856000000, "早い番組");
+        early.genres = vec![Genre { lv1: 0, lv2: 0 }];
+        let other_service = test_program(327360102500001, 3273601025, 1619856000000, "他局の番組");
+        let removed = test_program(327360102400003, 3273601024, 1619863200000, "消える番組");
+        repo.apply_diff(&ProgramsDiff {
+            added: vec![
+                late.clone(),
+                early.clone(),
+                other_service.clone(),
+                removed.clone(),
+            ],
+            changed: vec![],
+            removed: vec![],
+        })
+        .await
+        .unwrap();
+        repo.apply_diff(&ProgramsDiff {
+            added: vec![],
+            changed: vec![],
+            removed: vec![removed.clone()],
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            repo.find_by_id(early.id).await.unwrap(),
+            Some(early.clone())
+        );
+        assert_eq!(repo.find_by_id(removed.id).await.unwrap(), None);
+
+        let by_service = repo.find_by_service(3273601024).await.unwrap();
+        assert_eq!(by_service, [early.clone(), late.clone()]);
+
+        let by_time = repo
+            .find_by_time_range(1619856000000, 1619857800000)
+            .await
+            .unwrap();
+        assert_eq!(by_time, [early, other_service.clone()]);
+
+        let by_genre = repo.find_by_genre(7, Some(0)).await.unwrap();
+        assert_eq!(by_genre, [other_service, late]);
+    }
+
+    #[tokio::test]
+    async fn test_program_entry_repository_reindexes_changed_program() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        let repo = ProgramEntryRepository::new(nats_client).await.unwrap();
+        assert!(!repo.has_programs(3273601024).await.unwrap());
+
+        let program = test_program(327360102400001, 3273601024, 1619856000000, "番組");
+        repo.apply_diff(&ProgramsDiff {
+            added: vec![program.clone()],
+            changed: vec![],
+            removed: vec![],
+        })
+        .await
+        .unwrap();
+        assert!(repo.has_programs(3273601024).await.unwrap());
+
+        // 翌日に移り、ジャンルも変わった
+        let mut moved = test_program(327360102400001, 3273601024, 1619942400000, "番組");
+        moved.genres = vec![Genre { lv1: 0, lv2: 0 }];
+        repo.apply_diff(&ProgramsDiff {
+            added: vec![],
+            changed: vec![(moved.clone(), vec![])],
+            removed: vec![],
+        })
+        .await
+        .unwrap();
+
+        assert!(
+            repo.find_by_time_range(1619856000000, 1619857800000)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            repo.find_by_time_range(1619942400000, 1619944200000)
+                .await
+                .unwrap(),
+            [moved.clone()]
+        );
+        assert!(repo.find_by_genre(7, None).await.unwrap().is_empty());
+        assert_eq!(repo.find_by_genre(0, Some(0)).await.unwrap(), [moved]);
+    }
+
+    #[tokio::test]
+    async fn test_inventory_entry_repository_replace_and_load() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+        let nats_client = connect_nats(&proxy_nats.nats_url).await.unwrap();
+        let repo = InventoryEntryRepository::new(nats_client).await.unwrap();
+
+        let tuner = |index: u32| test_tuner(index, &[ChannelType::Gr]);
+        let channel = |channel_type: ChannelType, channel: &str| ChannelInfo {
+            channel_type,
+            channel: channel.to_string(),
+            name: channel.to_string(),
+            service_ids: vec![],
+        };
+
+        repo.replace_tuners(&[tuner(1), tuner(0), tuner(2)])
+            .await
+            .unwrap();
+        repo.replace_tuners(&[tuner(1), tuner(0)]).await.unwrap();
+        repo.replace_channels(&[
+            channel(ChannelType::Bs, "BS01_0"),
+            channel(ChannelType::Gr, "27"),
+        ])
+        .await
+        .unwrap();
+        repo.replace_services(&[test_service(3273701032), test_service(3273601024)])
+            .await
+            .unwrap();
+
+        let inventory = repo.load().await.unwrap();
+        let indexes: Vec<u32> = inventory.tuners.iter().map(|t| t.index).collect();
+        assert_eq!(indexes, [0, 1]);
+        let keys: Vec<String> = inventory.channels.iter().map(|c| c.key()).collect();
+        assert_eq!(keys, ["GR.27", "BS.BS01_0"]);
+        let ids: Vec<i64> = inventory.services.iter().map(|s| s.id).collect();
+        assert_eq!(ids, [3273601024, 3273701032]);
+        assert_eq!(
+            repo.find_service(3273601024).await.unwrap(),
+            Some(test_service(3273601024))
+        );
+        assert_eq!(repo.find_service(1).await.unwrap(), None);
+    }
+}
@@ -1,14 +1,56 @@
 use std::any::type_name;
-
-use async_nats::jetstream::consumer::PullConsumer;
+use std::time::Duration;
+
+use async_nats::HeaderMap;
+use async_nats::jetstream::{self, AckKind, consumer::PullConsumer};
+use async_trait::async_trait;
+use domain::error::DomainError;
+use domain::ports::EventPublisher;
+use domain::service::{RetryDecision, RetryPolicy};
 use domain::types::Event;
 use futures::StreamExt;
-use tracing::debug;
+use tracing::{debug, error, warn};
 
 use crate::{error::NatsInfraError, nats::NatsClient};
 
+/// デッドレターキューのストリーム名
+pub const DLQ_STREAM_NAME: &str = "kurec-dlq";
+/// デッドレターキューのサブジェクト接頭辞
+pub const DLQ_SUBJECT_PREFIX: &str = "dlq";
+
+/// デッドレターに付与するヘッダー: 失敗理由
+pub const DLQ_HEADER_ERROR: &str = "Kurec-Dlq-Error";
+/// デッドレターに付与するヘッダー: 配信回数
+pub const DLQ_HEADER_DELIVERY_COUNT: &str = "Kurec-Dlq-Delivery-Count";
+/// デッドレターに付与するヘッダー: 元のサブジェクト
+pub const DLQ_HEADER_ORIGINAL_SUBJECT: &str = "Kurec-Dlq-Original-Subject";
+/// デッドレターに付与するヘッダー: 元のストリーム
+pub const DLQ_HEADER_STREAM: &str = "Kurec-Dlq-Stream";
+/// デッドレターに付与するヘッダー: メッセージを処理したコンシューマー
+pub const DLQ_HEADER_CONSUMER: &str = "Kurec-Dlq-Consumer";
+
+/// 元のサブジェクトに対応するデッドレターキューのサブジェクトを返す
+pub fn dlq_subject(subject: &str) -> String {
+    format!("{DLQ_SUBJECT_PREFIX}.{subject}")
+}
+
+/// メッセージを待つ1回のプルリクエストの有効期限
+const WAIT_EXPIRES: Duration = Duration::from_secs(30);
+
+/// イベントリーダー（プルコンシューマー）の設定
+#[derive(Clone, Debug, Default)]
+pub struct ReaderConfig {
+    /// 処理に失敗したメッセージの再試行方針
+    ///
+    /// `max_attempts` 回目の配信で処理に失敗したメッセージはデッドレターキューへ転送される。
+    /// コンシューマーの最大配信回数は無制限にしておき、処理の失敗以外で
+    /// 配信回数が尽きてJetStreamに黙って捨てられることがないようにする。
+    pub retry_policy: RetryPolicy,
+}
+
 pub struct JsMessageAckHandle {
-    message: async_nats::jetstream::message::Message,
+    message: jetstream::message::Message,
+    retry_policy: RetryPolicy,
 }
 
 impl JsMessageAckHandle {
@@ -18,87 +60,284 @@ impl JsMessageAckHandle {
             .await
             .map_err(|e| NatsInfraError::MessageAck { source: e })
     }
+
+    /// 処理に失敗したことを通知し、即座に再配信を要求する
+    pub async fn nak(&mut self) -> Result<(), NatsInfraError> {
+        self.message
+            .ack_with(AckKind::Nak(None))
+            .await
+            .map_err(|e| NatsInfraError::MessageNak { source: e })
+    }
+
+    /// 処理に失敗したことを通知し、指定時間後の再配信を要求する
+    pub async fn nak_with_delay(&mut self, delay: Duration) -> Result<(), NatsInfraError> {
+        self.message
+            .ack_with(AckKind::Nak(Some(delay)))
+            .await
+            .map_err(|e| NatsInfraError::MessageNak { source: e })
+    }
+
+    /// 処理中であることを通知し、ack待ちのタイムアウトを延長する
+    pub async fn in_progress(&mut self) -> Result<(), NatsInfraError> {
+        self.message
+            .ack_with(AckKind::Progress)
+            .await
+            .map_err(|e| NatsInfraError::MessageInProgress { source: e })
+    }
+
+    /// 処理済みとせずに再配信を停止する
+    pub async fn term(&mut self) -> Result<(), NatsInfraError> {
+        self.message
+            .ack_with(AckKind::Term)
+            .await
+            .map_err(|e| NatsInfraError::MessageTerm { source: e })
+    }
+
+    /// このメッセージの配信回数（初回配信は1）
+    pub fn delivery_count(&self) -> i64 {
+        self.message.info().map(|info| info.delivered).unwrap_or(1)
+    }
+
+    /// 再試行方針の最大試行回数に達しており、次に失敗すればデッドレターキューへ転送されるかどうか
+    pub fn is_last_delivery(&self) -> bool {
+        self.delivery_count() >= self.retry_policy.max_attempts as i64
+    }
+
+    /// メッセージをデッドレターキューへ転送し、再配信を停止する
+    pub async fn dead_letter(&mut self, error: &str) -> Result<(), NatsInfraError> {
+        publish_dead_letter(&self.message, error).await?;
+        self.term().await
+    }
+
+    /// 再試行可能な処理の失敗を通知する
+    ///
+    /// 最大試行回数に達している場合はデッドレターキューへ転送し、
+    /// そうでなければ再試行方針に従った待ち時間の後の再配信を要求する。
+    pub async fn fail(&mut self, error: &str) -> Result<(), NatsInfraError> {
+        let decision = self.retry_policy.decide(self.attempt());
+        self.apply(decision, error).await
+    }
+
+    /// ドメインエラーによる処理の失敗を通知する
+    ///
+    /// 再試行しても成功しないエラーは最大試行回数を待たずにデッドレターキューへ転送する。
+    pub async fn fail_with(&mut self, error: &DomainError) -> Result<(), NatsInfraError> {
+        let decision = self.retry_policy.decide_for(self.attempt(), error);
+        self.apply(decision, &error.to_string()).await
+    }
+
+    fn attempt(&self) -> u32 {
+        self.delivery_count().clamp(1, u32::MAX as i64) as u32
+    }
+
+    async fn apply(&mut self, decision: RetryDecision, error: &str) -> Result<(), NatsInfraError> {
+        match decision {
+            RetryDecision::Retry(delay) => {
+                debug!(
+                    subject = %self.message.subject,
+                    delivered = self.delivery_count(),
+                    ?delay,
+                    "再配信を要求します"
+                );
+                self.nak_with_delay(delay).await
+            }
+            RetryDecision::GiveUp => {
+                warn!(
+                    subject = %self.message.subject,
+                    delivered = self.delivery_count(),
+                    "再試行を打ち切りデッドレターキューへ転送します"
+                );
+                self.dead_letter(error).await
+            }
+        }
+    }
+}
+
+/// メッセージを `dlq.<subject>` へ失敗理由と配信回数のヘッダー付きで再発行する
+async fn publish_dead_letter(
+    message: &jetstream::message::Message,
+    error: &str,
+) -> Result<(), NatsInfraError> {
+    let original_subject = message.subject.to_string();
+    let subject = dlq_subject(&original_subject);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(DLQ_HEADER_ERROR, error);
+    headers.insert(DLQ_HEADER_ORIGINAL_SUBJECT, original_subject.as_str());
+    if let Ok(info) = message.info() {
+        headers.insert(
+            DLQ_HEADER_DELIVERY_COUNT,
+            info.delivered.to_string().as_str(),
+        );
+        headers.insert(DLQ_HEADER_STREAM, info.stream);
+        headers.insert(DLQ_HEADER_CONSUMER, info.consumer);
+    }
+
+    debug!(subject = %subject, error = %error, "デッドレターキューへ転送します");
+    message
+        .context
+        .publish_with_headers(subject.clone(), headers, message.payload.clone())
+        .await
+        .map_err(|e| NatsInfraError::DeadLetterPublish {
+            subject: subject.clone(),
+            source: Box::new(e),
+        })?
+        .await
+        .map_err(|e| NatsInfraError::DeadLetterPublish {
+            subject: subject.clone(),
+            source: Box::new(e),
+        })?;
+    Ok(())
 }
 
 pub trait EventReader<E: Event> {
     fn next(
         &self,
     ) -> impl std::future::Future<Output = Result<(E, JsMessageAckHandle), NatsInfraError>> + Send;
+
+    /// 最大 `max_messages` 件のメッセージをまとめて取得する
+    ///
+    /// すぐに取得できるメッセージがなければ1件届くまで待機する。
+    /// 取得したメッセージはそれぞれ個別にackする。
+    fn next_batch(
+        &self,
+        max_messages: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<(E, JsMessageAckHandle)>, NatsInfraError>> + Send;
 }
 
 pub struct EventStoreReader<E: Event> {
     subject: String,
     consumer: PullConsumer,
+    config: ReaderConfig,
     _phantom: std::marker::PhantomData<E>,
 }
 
-impl<E: Event> EventReader<E> for EventStoreReader<E> {
-    async fn next(&self) -> Result<(E, JsMessageAckHandle), NatsInfraError> {
-        debug!("メッセージを待機しています...");
-        let mut messages =
+impl<E: Event> EventStoreReader<E> {
+    /// 受信したメッセージをイベントにデシリアライズする
+    ///
+    /// デシリアライズできないメッセージは再配信しても成功しないため、
+    /// デッドレターキューへ転送してからエラーを返す。
+    async fn decode(
+        &self,
+        msg: jetstream::message::Message,
+    ) -> Result<(E, JsMessageAckHandle), NatsInfraError> {
+        let mut ack_handle = JsMessageAckHandle {
+            message: msg,
+            retry_policy: self.config.retry_policy.clone(),
+        };
+        match serde_json::from_slice(&ack_handle.message.payload) {
+            Ok(ev) => Ok((ev, ack_handle)),
+            Err(e) => {
+                if let Err(dlq_err) = ack_handle.dead_letter(&e.to_string()).await {
+                    error!(
+                        "デシリアライズに失敗したメッセージの転送に失敗: {:?}",
+                        dlq_err
+                    );
+                }
+                Err(NatsInfraError::JsonDeserialize {
+                    subject: self.subject.clone(),
+                    message: ack_handle.message.payload.clone().into(),
+                    source: e,
+                })
+            }
+        }
+    }
+}
+
+impl<E: Event> EventStoreReader<E> {
+    /// 1回のプルリクエストで最大 `max_messages` 件を取得する
+    ///
+    /// `wait` が `false` なら今あるメッセージだけを返し、`true` なら
+    /// [`WAIT_EXPIRES`] まで届くのを待つ。取得したメッセージはすべて返すので、
+    /// 受け取ったまま放置されてack待ちのタイムアウトで再配信されることはない。
+    async fn pull(
+        &self,
+        max_messages: usize,
+        wait: bool,
+    ) -> Result<Vec<(E, JsMessageAckHandle)>, NatsInfraError> {
+        let batch = if wait {
             self.consumer
+                .batch()
+                .max_messages(max_messages)
+                .expires(WAIT_EXPIRES)
                 .messages()
                 .await
-                .map_err(|e| NatsInfraError::StreamRetrieval {
-                    stream_name: "unknown".to_string(),
-                    source: Box::new(e),
-                })?;
+        } else {
+            self.consumer
+                .fetch()
+                .max_messages(max_messages)
+                .messages()
+                .await
+        };
+        let mut batch = batch.map_err(|e| NatsInfraError::StreamRetrieval {
+            stream_name: self.subject.clone(),
+            source: Box::new(e),
+        })?;
 
-        match messages.next().await {
-            Some(Ok(msg)) => {
-                let ev: E = serde_json::from_slice(&msg.payload).map_err(|e| {
-                    NatsInfraError::JsonDeserialize {
-                        subject: self.subject.clone(),
-                        message: msg.payload.clone().into(),
+        let mut events = Vec::new();
+        while let Some(msg) = batch.next().await {
+            match msg {
+                Ok(msg) => match self.decode(msg).await {
+                    Ok(event) => events.push(event),
+                    Err(e) => warn!("メッセージをスキップしました: {}", e),
+                },
+                Err(e) if events.is_empty() => {
+                    return Err(NatsInfraError::StreamRetrieval {
+                        stream_name: self.subject.clone(),
                         source: e,
-                    }
-                })?;
-                let ack_handle = JsMessageAckHandle { message: msg };
-                Ok((ev, ack_handle))
-            }
-            Some(Err(e)) => Err(NatsInfraError::StreamRetrieval {
-                stream_name: "unknown".to_string(),
-                source: Box::new(e),
-            }),
-            None => {
-                debug!("メッセージストリームが終了しました。再接続します...");
-                loop {
-                    debug!("新しいメッセージストリームを取得します...");
-                    let mut new_messages = self.consumer.messages().await.map_err(|e| {
-                        NatsInfraError::StreamRetrieval {
-                            stream_name: "unknown".to_string(),
-                            source: Box::new(e),
-                        }
-                    })?;
-
-                    if let Some(result) = new_messages.next().await {
-                        match result {
-                            Ok(msg) => {
-                                let ev: E = serde_json::from_slice(&msg.payload).map_err(|e| {
-                                    NatsInfraError::JsonDeserialize {
-                                        subject: self.subject.clone(),
-                                        message: msg.payload.clone().into(),
-                                        source: e,
-                                    }
-                                })?;
-                                let ack_handle = JsMessageAckHandle { message: msg };
-                                return Ok((ev, ack_handle));
-                            }
-                            Err(e) => {
-                                return Err(NatsInfraError::StreamRetrieval {
-                                    stream_name: "unknown".to_string(),
-                                    source: Box::new(e),
-                                });
-                            }
-                        }
-                    }
-                    debug!("メッセージが取得できませんでした。再試行します...");
+                    });
+                }
+                Err(e) => {
+                    warn!("バッチの途中で取得に失敗しました: {:?}", e);
+                    break;
                 }
             }
         }
+        Ok(events)
     }
 }
 
+impl<E: Event> EventReader<E> for EventStoreReader<E> {
+    async fn next_batch(
+        &self,
+        max_messages: usize,
+    ) -> Result<Vec<(E, JsMessageAckHandle)>, NatsInfraError> {
+        let events = self.pull(max_messages.max(1), false).await?;
+        if !events.is_empty() {
+            debug!("{} 件のメッセージをまとめて取得しました", events.len());
+            return Ok(events);
+        }
+
+        // 届くまで1件ずつ待つ。まとめて待つと件数がそろうまで返らないため
+        debug!("メッセージを待機しています...");
+        loop {
+            let events = self.pull(1, true).await?;
+            if !events.is_empty() {
+                return Ok(events);
+            }
+        }
+    }
+
+    async fn next(&self) -> Result<(E, JsMessageAckHandle), NatsInfraError> {
+        loop {
+            if let Some(event) = self.next_batch(1).await?.pop() {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+/// コンシューマーの未処理メッセージ数
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConsumerLag {
+    /// まだ配信されていないメッセージ数
+    pub pending: u64,
+    /// 配信済みで確認応答を待っているメッセージ数
+    pub ack_pending: u64,
+    /// 再配信されて確認応答を待っているメッセージ数
+    pub redelivered: u64,
+}
+
 pub struct EventStore<E: Event> {
     nats_client: NatsClient,
     _phantom: std::marker::PhantomData<E>,
@@ -151,9 +390,50 @@ impl<E: Event> EventStore<E> {
         Ok(())
     }
 
+    /// このイベントを購読している永続コンシューマーの未処理メッセージ数を取得する
+    pub async fn consumer_lag(&self, durable_name: &str) -> Result<ConsumerLag, NatsInfraError> {
+        let subject = Self::get_subject();
+        let js = self.nats_client.jetstream_context();
+        let stream_name =
+            js.stream_by_subject(&subject)
+                .await
+                .map_err(|e| NatsInfraError::StreamRetrieval {
+                    stream_name: subject.clone(),
+                    source: Box::new(e),
+                })?;
+        let stream =
+            js.get_stream(&stream_name)
+                .await
+                .map_err(|e| NatsInfraError::StreamRetrieval {
+                    stream_name: stream_name.clone(),
+                    source: Box::new(e),
+                })?;
+        let info = stream.consumer_info(durable_name).await.map_err(|e| {
+            NatsInfraError::StreamRetrieval {
+                stream_name: stream_name.clone(),
+                source: Box::new(e),
+            }
+        })?;
+
+        Ok(ConsumerLag {
+            pending: info.num_pending,
+            ack_pending: info.num_ack_pending as u64,
+            redelivered: info.num_redelivered as u64,
+        })
+    }
+
     pub async fn get_reader(
         &self,
         durable_name: String,
+    ) -> Result<impl EventReader<E>, NatsInfraError> {
+        self.get_reader_with_config(durable_name, ReaderConfig::default())
+            .await
+    }
+
+    pub async fn get_reader_with_config(
+        &self,
+        durable_name: String,
+        config: ReaderConfig,
     ) -> Result<impl EventReader<E>, NatsInfraError> {
         let subject = Self::get_subject();
         let js = self.nats_client.jetstream_context();
@@ -177,6 +457,8 @@ impl<E: Event> EventStore<E> {
             .create_consumer(async_nats::jetstream::consumer::pull::Config {
                 filter_subject: subject.clone(),
                 durable_name: Some(durable_name),
+                // デッドレターキューへの転送は処理の失敗時に行うため再配信は打ち切らない
+                max_deliver: -1,
                 ..Default::default()
             })
             .await
@@ -188,11 +470,21 @@ impl<E: Event> EventStore<E> {
         Ok(EventStoreReader {
             subject,
             consumer,
+            config,
             _phantom: std::marker::PhantomData,
         })
     }
 }
 
+#[async_trait]
+impl<E: Event> EventPublisher<E> for EventStore<E> {
+    async fn publish(&self, event: &E) -> Result<(), DomainError> {
+        self.publish_event(event)
+            .await
+            .map_err(|e| DomainError::EventPublishError(e.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{nats::connect_nats, test_util::setup_toxi_proxy_nats};
@@ -303,4 +595,160 @@ mod tests {
         let (ev2, _) = reader2.next().await.unwrap();
         assert_eq!(ev2.data, event2.data); // 2番目のイベントを受信
     }
+
+    #[tokio::test]
+    async fn test_next_batch() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+
+        let nats_url = &proxy_nats.nats_url;
+        let nats_client = connect_nats(nats_url).await.unwrap();
+        let event_stream = TestEventStore::new(nats_client).await.unwrap();
+
+        // ストリームを作成しておく
+        let js = event_stream.get_client().jetstream_context();
+        let _stream = js
+            .get_or_create_stream(async_nats::jetstream::stream::Config {
+                name: "kurec".to_string(),
+                subjects: vec![TestEventStore::get_subject()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        for i in 0..5 {
+            let event = TestEvent {
+                data: format!("test data {i}"),
+            };
+            event_stream.publish_event(&event).await.unwrap();
+        }
+
+        let reader = event_stream
+            .get_reader("test_batch_consumer".to_string())
+            .await
+            .unwrap();
+
+        let batch = reader.next_batch(3).await.unwrap();
+        let data: Vec<String> = batch.iter().map(|(ev, _)| ev.data.clone()).collect();
+        assert_eq!(data, vec!["test data 0", "test data 1", "test data 2"]);
+        for (_, mut ack_handle) in batch {
+            ack_handle.ack().await.unwrap();
+        }
+
+        let batch = reader.next_batch(3).await.unwrap();
+        assert_eq!(batch.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_next_batch_on_idle_consumer_does_not_strand_messages() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+
+        let nats_url = &proxy_nats.nats_url;
+        let nats_client = connect_nats(nats_url).await.unwrap();
+        let event_stream = TestEventStore::new(nats_client).await.unwrap();
+
+        let js = event_stream.get_client().jetstream_context();
+        let _stream = js
+            .get_or_create_stream(async_nats::jetstream::stream::Config {
+                name: "kurec".to_string(),
+                subjects: vec![TestEventStore::get_subject()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let durable_name = "test_idle_consumer";
+        let reader = event_stream
+            .get_reader(durable_name.to_string())
+            .await
+            .unwrap();
+
+        // 空のコンシューマーで待機している間にまとめて届く
+        let (batch, _) = tokio::join!(reader.next_batch(1), async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            for i in 0..5 {
+                let event = TestEvent {
+                    data: format!("test data {i}"),
+                };
+                event_stream.publish_event(&event).await.unwrap();
+            }
+        });
+        let mut received = Vec::new();
+        for (ev, mut ack_handle) in batch.unwrap() {
+            received.push(ev.data);
+            ack_handle.ack().await.unwrap();
+        }
+
+        // 残りはack待ちのタイムアウトを待たずに取得できる
+        while received.len() < 5 {
+            let batch = tokio::time::timeout(Duration::from_secs(5), reader.next_batch(5))
+                .await
+                .expect("取り残されたメッセージがある")
+                .unwrap();
+            for (ev, mut ack_handle) in batch {
+                received.push(ev.data);
+                ack_handle.ack().await.unwrap();
+            }
+        }
+        assert_eq!(
+            received,
+            (0..5).map(|i| format!("test data {i}")).collect::<Vec<_>>()
+        );
+
+        // 何も届かない間に繰り返し待機しても再配信は起きない
+        for _ in 0..3 {
+            let result =
+                tokio::time::timeout(Duration::from_millis(300), reader.next_batch(5)).await;
+            assert!(result.is_err());
+        }
+        let lag = event_stream.consumer_lag(durable_name).await.unwrap();
+        assert_eq!(lag.redelivered, 0);
+        assert_eq!(lag.ack_pending, 0);
+        assert_eq!(lag.pending, 0);
+    }
+
+    #[tokio::test]
+    async fn test_consumer_lag() {
+        let proxy_nats = setup_toxi_proxy_nats().await.unwrap();
+
+        let nats_url = &proxy_nats.nats_url;
+        let nats_client = connect_nats(nats_url).await.unwrap();
+        let event_stream = TestEventStore::new(nats_client).await.unwrap();
+
+        let js = event_stream.get_client().jetstream_context();
+        let _stream = js
+            .get_or_create_stream(async_nats::jetstream::stream::Config {
+                name: "kurec".to_string(),
+                subjects: vec![TestEventStore::get_subject()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let reader = event_stream
+            .get_reader("test_lag_consumer".to_string())
+            .await
+            .unwrap();
+        for i in 0..3 {
+            let event = TestEvent {
+                data: format!("test data {i}"),
+            };
+            event_stream.publish_event(&event).await.unwrap();
+        }
+
+        let lag = event_stream
+            .consumer_lag("test_lag_consumer")
+            .await
+            .unwrap();
+        assert_eq!(lag.pending, 3);
+        assert_eq!(lag.ack_pending, 0);
+
+        // 受信して確認応答していないメッセージはack待ちになる
+        let (_, _ack_handle) = reader.next().await.unwrap();
+        let lag = event_stream
+            .consumer_lag("test_lag_consumer")
+            .await
+            .unwrap();
+        assert_eq!(lag.pending, 2);
+        assert_eq!(lag.ack_pending, 1);
+    }
 }
@@ -23,6 +23,18 @@ pub async fn create_or_update_streams(
     Ok(())
 }
 
+/// 指定されたストリームのうち、取得できなかったものの名前を返します。
+pub async fn missing_streams(nats_client: &NatsClient, stream_names: &[String]) -> Vec<String> {
+    let js = nats_client.jetstream_context();
+    let mut missing = Vec::new();
+    for name in stream_names {
+        if js.get_stream(name).await.is_err() {
+            missing.push(name.clone());
+        }
+    }
+    missing
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
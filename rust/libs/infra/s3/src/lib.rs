@@ -0,0 +1,6 @@
+mod s3_object_store;
+
+#[cfg(test)]
+mod test_util;
+
+pub use s3_object_store::*;
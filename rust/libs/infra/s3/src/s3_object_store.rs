@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use aws_sdk_s3::{
+    Client,
+    config::{Credentials, Region},
+    error::ProvideErrorMetadata,
+    primitives::{ByteStream, DateTime},
+};
+use bytes::Bytes;
+use domain::{
+    error::DomainError,
+    model::object::{ObjectMeta, PutOptions, StoredObject},
+    ports::ObjectStore,
+};
+use tokio::sync::OnceCell;
+use tracing::{debug, error, info};
+
+/// S3互換ストレージへの接続設定
+#[derive(Clone)]
+pub struct S3Config {
+    /// `http://minio:9000` のようなエンドポイントのURL
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://minio:9000".to_string(),
+            region: "us-east-1".to_string(),
+            bucket: "kurec".to_string(),
+            access_key: String::new(),
+            secret_key: String::new(),
+        }
+    }
+}
+
+impl fmt::Debug for S3Config {
+    // シークレットキーをログに出さない
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("bucket", &self.bucket)
+            .field("access_key", &self.access_key)
+            .field("secret_key", &"***")
+            .finish()
+    }
+}
+
+/// MinIOなどのS3互換ストレージに保存する `ObjectStore`
+///
+/// バケットは最初の操作のときに、なければ作成する。
+#[derive(Clone)]
+pub struct S3ObjectStore {
+    client: Client,
+    bucket: String,
+    bucket_ready: Arc<OnceCell<()>>,
+}
+
+impl S3ObjectStore {
+    pub fn new(config: &S3Config) -> Self {
+        let credentials =
+            Credentials::new(&config.access_key, &config.secret_key, None, None, "kurec");
+        let s3_config = aws_sdk_s3::Config::builder()
+            .region(Region::new(config.region.clone()))
+            .endpoint_url(&config.endpoint)
+            .credentials_provider(credentials)
+            // MinIOはバケット名をホスト名に含める形式に対応していない
+            .force_path_style(true)
+            .build();
+        Self {
+            client: Client::from_conf(s3_config),
+            bucket: config.bucket.clone(),
+            bucket_ready: Arc::new(OnceCell::new()),
+        }
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    async fn ensure_bucket(&self) -> Result<(), DomainError> {
+        self.bucket_ready
+            .get_or_try_init(|| async {
+                match self.client.head_bucket().bucket(&self.bucket).send().await {
+                    Ok(_) => return Ok(()),
+                    Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {}
+                    Err(e) => return Err(self.store_error("確認", "", e)),
+                }
+                match self
+                    .client
+                    .create_bucket()
+                    .bucket(&self.bucket)
+                    .send()
+                    .await
+                {
+                    Ok(_) => {
+                        info!(bucket = %self.bucket, "バケットを作成しました");
+                        Ok(())
+                    }
+                    // 他のワーカーが先に作成した
+                    Err(e)
+                        if e.as_service_error().is_some_and(|e| {
+                            e.is_bucket_already_owned_by_you() || e.is_bucket_already_exists()
+                        }) =>
+                    {
+                        Ok(())
+                    }
+                    Err(e) => Err(self.store_error("作成", "", e)),
+                }
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn put_body(
+        &self,
+        key: &str,
+        body: ByteStream,
+        size: u64,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        self.ensure_bucket().await?;
+        debug!(bucket = %self.bucket, key = %key, size = %size, "オブジェクトを保存します");
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(body)
+            .content_length(size as i64)
+            .content_type(&options.content_type)
+            .set_metadata(Some(
+                options
+                    .metadata
+                    .clone()
+                    .into_iter()
+                    .collect::<HashMap<_, _>>(),
+            ))
+            .send()
+            .await
+            .map_err(|e| self.store_error("保存", key, e))?;
+        Ok(ObjectMeta {
+            key: key.to_string(),
+            size,
+            content_type: Some(options.content_type.clone()),
+            metadata: options.metadata.clone(),
+            last_modified: None,
+        })
+    }
+
+    fn store_error(
+        &self,
+        operation: &str,
+        key: &str,
+        e: impl fmt::Display + ProvideErrorMetadata,
+    ) -> DomainError {
+        let message = e.message().map_or_else(|| e.to_string(), str::to_string);
+        error!(
+            bucket = %self.bucket,
+            key = %key,
+            code = ?e.code(),
+            error = %message,
+            "オブジェクトの{}に失敗しました",
+            operation
+        );
+        DomainError::ObjectStoreError(format!(
+            "{}/{} の{}に失敗: {}",
+            self.bucket, key, operation, message
+        ))
+    }
+}
+
+fn to_millis(date_time: Option<&DateTime>) -> Option<i64> {
+    date_time.and_then(|date_time| date_time.to_millis().ok())
+}
+
+fn to_metadata(metadata: Option<&HashMap<String, String>>) -> BTreeMap<String, String> {
+    metadata
+        .map(|metadata| metadata.clone().into_iter().collect())
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl ObjectStore for S3ObjectStore {
+    async fn put(
+        &self,
+        key: &str,
+        data: Bytes,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        let size = data.len() as u64;
+        self.put_body(key, ByteStream::from(data), size, options)
+            .await
+    }
+
+    async fn put_file(
+        &self,
+        key: &str,
+        path: &Path,
+        options: &PutOptions,
+    ) -> Result<ObjectMeta, DomainError> {
+        let io_error = |e: &dyn fmt::Display| {
+            DomainError::ObjectStoreError(format!("{}を読み込めません: {}", path.display(), e))
+        };
+        let size = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| io_error(&e))?
+            .len();
+        let body = ByteStream::from_path(path)
+            .await
+            .map_err(|e| io_error(&e))?;
+        self.put_body(key, body, size, options).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<StoredObject>, DomainError> {
+        self.ensure_bucket().await?;
+        let output = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(self.store_error("取得", key, e)),
+        };
+        let meta = ObjectMeta {
+            key: key.to_string(),
+            size: output.content_length().unwrap_or_default().max(0) as u64,
+            content_type: output.content_type().map(str::to_string),
+            metadata: to_metadata(output.metadata()),
+            last_modified: to_millis(output.last_modified()),
+        };
+        let data = output.body.collect().await.map_err(|e| {
+            DomainError::ObjectStoreError(format!("{}/{} の取得に失敗: {}", self.bucket, key, e))
+        })?;
+        Ok(Some(StoredObject {
+            meta,
+            data: data.into_bytes(),
+        }))
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<ObjectMeta>, DomainError> {
+        self.ensure_bucket().await?;
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(ObjectMeta {
+                key: key.to_string(),
+                size: output.content_length().unwrap_or_default().max(0) as u64,
+                content_type: output.content_type().map(str::to_string),
+                metadata: to_metadata(output.metadata()),
+                last_modified: to_millis(output.last_modified()),
+            })),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(self.store_error("情報の取得", key, e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), DomainError> {
+        self.ensure_bucket().await?;
+        debug!(bucket = %self.bucket, key = %key, "オブジェクトを削除します");
+        // S3は存在しないキーの削除も成功として扱う
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| self.store_error("削除", key, e))?;
+        Ok(())
+    }
+
+    /// 一覧にはContent-Typeとメタデータは含まれない（必要なら `head` で取得する）
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectMeta>, DomainError> {
+        self.ensure_bucket().await?;
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+        let mut objects = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(|e| self.store_error("一覧", prefix, e))?;
+            objects.extend(page.contents().iter().filter_map(|object| {
+                Some(ObjectMeta {
+                    key: object.key()?.to_string(),
+                    size: object.size().unwrap_or_default().max(0) as u64,
+                    content_type: None,
+                    metadata: BTreeMap::new(),
+                    last_modified: to_millis(object.last_modified()),
+                })
+            }));
+        }
+        Ok(objects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::setup_minio;
+
+    #[tokio::test]
+    async fn test_put_get_and_head() {
+        let minio = setup_minio().await.unwrap();
+        let store = S3ObjectStore::new(&minio.config("test-objects"));
+
+        let options = PutOptions::new("image/webp").with_metadata("program_id", 327360102412345i64);
+        let meta = store
+            .put("images/abc", Bytes::from_static(b"webp data"), &options)
+            .await
+            .unwrap();
+        assert_eq!(meta.size, 9);
+
+        let object = store.get("images/abc").await.unwrap().unwrap();
+        assert_eq!(object.data, Bytes::from_static(b"webp data"));
+        assert_eq!(object.meta.content_type.as_deref(), Some("image/webp"));
+        assert_eq!(
+            object.meta.metadata.get("program_id").map(String::as_str),
+            Some("327360102412345")
+        );
+
+        let head = store.head("images/abc").await.unwrap().unwrap();
+        assert_eq!(head.size, 9);
+        assert!(head.last_modified.is_some());
+        assert!(store.get("images/missing").await.unwrap().is_none());
+        assert!(store.head("images/missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_put_file_list_and_delete() {
+        let minio = setup_minio().await.unwrap();
+        let store = S3ObjectStore::new(&minio.config("test-objects"));
+
+        let path = std::env::temp_dir().join("kurec-s3-test-movie.mp4");
+        tokio::fs::write(&path, vec![0x47u8; 300 * 1024])
+            .await
+            .unwrap();
+        let meta = store
+            .put_file(
+                "recordings/1/movie.mp4",
+                &path,
+                &PutOptions::new("video/mp4"),
+            )
+            .await
+            .unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert_eq!(meta.size, 300 * 1024);
+        store
+            .put(
+                "images/abc",
+                Bytes::from_static(b"webp"),
+                &PutOptions::default(),
+            )
+            .await
+            .unwrap();
+
+        let listed = store.list("recordings/").await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].key, "recordings/1/movie.mp4");
+        assert_eq!(listed[0].size, 300 * 1024);
+
+        store.delete("recordings/1/movie.mp4").await.unwrap();
+        // 存在しないオブジェクトの削除はエラーにしない
+        store.delete("recordings/1/movie.mp4").await.unwrap();
+        assert!(store.list("recordings/").await.unwrap().is_empty());
+        assert!(
+            store
+                .head("recordings/1/movie.mp4")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! テスト用のMinIOコンテナ
+
+use anyhow::Result;
+use testcontainers::{ContainerAsync, GenericImage, ImageExt, core::WaitFor, runners::AsyncRunner};
+
+use crate::S3Config;
+
+pub const ACCESS_KEY: &str = "minio";
+pub const SECRET_KEY: &str = "minio123";
+
+// テスト終了時に自動的にコンテナを停止・削除するための構造体
+pub struct TestMinioContainer {
+    pub endpoint: String,
+    _container: ContainerAsync<GenericImage>,
+}
+
+impl TestMinioContainer {
+    /// `bucket` を使う接続設定（バケットはまだ作成されていない）
+    pub fn config(&self, bucket: &str) -> S3Config {
+        S3Config {
+            endpoint: self.endpoint.clone(),
+            bucket: bucket.to_string(),
+            access_key: ACCESS_KEY.to_string(),
+            secret_key: SECRET_KEY.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+// テスト用の MinIO サーバーを起動し、コンテナハンドラを返す
+pub async fn setup_minio() -> Result<TestMinioContainer> {
+    let container = GenericImage::new("quay.io/minio/minio", "latest")
+        .with_exposed_port(9000u16.into())
+        .with_wait_for(WaitFor::message_on_stdout("API:"))
+        .with_env_var("MINIO_ROOT_USER", ACCESS_KEY)
+        .with_env_var("MINIO_ROOT_PASSWORD", SECRET_KEY)
+        .with_cmd(vec!["server", "/data"])
+        .start()
+        .await?;
+    let host = container.get_host().await?;
+    let port = container.get_host_port_ipv4(9000u16).await?;
+    Ok(TestMinioContainer {
+        endpoint: format!("http://{}:{}", host, port),
+        _container: container,
+    })
+}
@@ -0,0 +1,126 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use domain::error::DomainError;
+use lindera::{dictionary::load_dictionary, mode::Mode, segmenter::Segmenter};
+use tantivy::tokenizer::{LowerCaser, TextAnalyzer, Token, TokenStream, Tokenizer};
+
+/// linderaで日本語を単語に分けるtantivyのトークナイザー
+///
+/// 全角の英数字・記号は半角にそろえる。
+#[derive(Clone)]
+pub struct JapaneseTokenizer {
+    segmenter: Arc<Segmenter>,
+}
+
+impl JapaneseTokenizer {
+    pub fn new(segmenter: Segmenter) -> Self {
+        Self {
+            segmenter: Arc::new(segmenter),
+        }
+    }
+
+    /// `lindera build` でビルドした辞書のディレクトリ（またはURI）から読み込む
+    pub fn from_dictionary(uri: &str) -> Result<Self, DomainError> {
+        let dictionary = load_dictionary(uri).map_err(|e| {
+            DomainError::SearchIndexError(format!("辞書を読み込めません: {}: {}", uri, e))
+        })?;
+        Ok(Self::new(Segmenter::new(Mode::Normal, dictionary, None)))
+    }
+
+    /// 小文字にそろえる `TextAnalyzer` にする
+    pub fn analyzer(self) -> TextAnalyzer {
+        TextAnalyzer::builder(self).filter(LowerCaser).build()
+    }
+}
+
+/// 全角の英数字・記号（U+FF01〜U+FF5E）と全角スペースを半角にする
+fn to_half_width(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            _ => c,
+        })
+        .collect()
+}
+
+impl Tokenizer for JapaneseTokenizer {
+    type TokenStream<'a> = JapaneseTokenStream;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        // 解析できない文字列は索引しない（辞書の未知語処理があるので通常は起きない）
+        let tokens = self
+            .segmenter
+            .segment(Cow::Borrowed(text))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|token| Token {
+                offset_from: token.byte_start,
+                offset_to: token.byte_end,
+                position: token.position,
+                text: to_half_width(&token.surface),
+                position_length: token.position_length,
+            })
+            .filter(|token| !token.text.trim().is_empty())
+            .collect();
+        JapaneseTokenStream {
+            tokens,
+            index: None,
+        }
+    }
+}
+
+pub struct JapaneseTokenStream {
+    tokens: Vec<Token>,
+    index: Option<usize>,
+}
+
+impl TokenStream for JapaneseTokenStream {
+    fn advance(&mut self) -> bool {
+        let next = self.index.map_or(0, |index| index + 1);
+        self.index = Some(next);
+        next < self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index.unwrap_or(0)]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index.unwrap_or(0)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_tokenizer;
+
+    fn texts(analyzer: &mut TextAnalyzer, text: &str) -> Vec<(String, usize)> {
+        let mut stream = analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        while stream.advance() {
+            tokens.push((stream.token().text.clone(), stream.token().position));
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_segments_japanese_and_normalizes_width() {
+        let mut analyzer = test_tokenizer().analyzer();
+
+        assert_eq!(
+            texts(&mut analyzer, "東京の天気予報　ＮＨＫニュース"),
+            [
+                ("東京".to_string(), 0),
+                ("の".to_string(), 1),
+                ("天気".to_string(), 2),
+                ("予報".to_string(), 3),
+                ("nhk".to_string(), 4),
+                ("ニュース".to_string(), 5),
+            ]
+        );
+        assert!(texts(&mut analyzer, "").is_empty());
+    }
+}
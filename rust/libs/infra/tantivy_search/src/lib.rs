@@ -0,0 +1,8 @@
+mod japanese_tokenizer;
+mod tantivy_index;
+
+#[cfg(test)]
+mod test_util;
+
+pub use japanese_tokenizer::*;
+pub use tantivy_index::*;
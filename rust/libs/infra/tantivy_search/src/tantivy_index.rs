@@ -0,0 +1,549 @@
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use async_trait::async_trait;
+use domain::{
+    error::DomainError,
+    model::search::{ProgramDocument, SearchQuery, SearchResults},
+    ports::SearchIndex,
+};
+use tantivy::{
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term,
+    collector::{Count, TopDocs},
+    directory::MmapDirectory,
+    query::{
+        AllQuery, BooleanQuery, BoostQuery, Occur, PhraseQuery, Query, RangeQuery, TermQuery,
+        TermSetQuery,
+    },
+    schema::{
+        FAST, Field, INDEXED, IndexRecordOption, STORED, Schema, TextFieldIndexing, TextOptions,
+        Value,
+    },
+    tokenizer::TextAnalyzer,
+};
+use tracing::debug;
+
+use crate::JapaneseTokenizer;
+
+/// インデックスに登録するトークナイザーの名前
+pub const TOKENIZER_NAME: &str = "ja";
+
+/// 書き込みに使うメモリの上限（バイト）
+const WRITER_MEMORY_BUDGET: usize = 50_000_000;
+
+/// スキーマのフィールド
+#[derive(Clone, Copy)]
+struct Fields {
+    id: Field,
+    service_id: Field,
+    genre_lv1: Field,
+    start_at: Field,
+    end_at: Field,
+    name: Field,
+    description: Field,
+    extended: Field,
+    genres: Field,
+    channel_name: Field,
+    /// 検索結果に返す文書（JSON）
+    document: Field,
+}
+
+impl Fields {
+    fn schema() -> (Schema, Self) {
+        let mut builder = Schema::builder();
+        let text = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(TOKENIZER_NAME)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        );
+        let fields = Self {
+            id: builder.add_i64_field("id", INDEXED | FAST),
+            service_id: builder.add_i64_field("service_id", INDEXED | FAST),
+            genre_lv1: builder.add_u64_field("genre_lv1", INDEXED),
+            start_at: builder.add_i64_field("start_at", INDEXED | FAST),
+            end_at: builder.add_i64_field("end_at", INDEXED | FAST),
+            name: builder.add_text_field("name", text.clone()),
+            description: builder.add_text_field("description", text.clone()),
+            extended: builder.add_text_field("extended", text.clone()),
+            genres: builder.add_text_field("genres", text.clone()),
+            channel_name: builder.add_text_field("channel_name", text),
+            document: builder.add_text_field("document", STORED),
+        };
+        (builder.build(), fields)
+    }
+
+    /// 検索語と照合するフィールドとその重み
+    fn searchable(&self) -> [(Field, f32); 5] {
+        [
+            (self.name, 4.0),
+            (self.description, 2.0),
+            (self.extended, 1.0),
+            (self.genres, 1.0),
+            (self.channel_name, 1.0),
+        ]
+    }
+
+    fn to_document(self, document: &ProgramDocument) -> TantivyDocument {
+        let mut doc = TantivyDocument::default();
+        doc.add_i64(self.id, document.id);
+        doc.add_i64(self.service_id, document.service_id);
+        for lv1 in &document.genre_lv1 {
+            doc.add_u64(self.genre_lv1, u64::from(*lv1));
+        }
+        doc.add_i64(self.start_at, document.start_at);
+        doc.add_i64(self.end_at, document.end_at);
+        doc.add_text(self.name, &document.name);
+        doc.add_text(self.description, &document.description);
+        doc.add_text(self.extended, &document.extended);
+        for genre in &document.genres {
+            doc.add_text(self.genres, genre);
+        }
+        doc.add_text(self.channel_name, &document.channel_name);
+        doc.add_text(
+            self.document,
+            serde_json::to_string(document).unwrap_or_default(),
+        );
+        doc
+    }
+}
+
+fn index_error(operation: &str, e: impl std::fmt::Display) -> DomainError {
+    DomainError::SearchIndexError(format!("{}に失敗: {}", operation, e))
+}
+
+/// 検索語を空白で区切る（`"` で囲んだ部分は空白を含めて1つの語にする）
+fn split_terms(text: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    for (i, part) in text.split('"').enumerate() {
+        if i % 2 == 1 {
+            if !part.trim().is_empty() {
+                terms.push(part);
+            }
+        } else {
+            terms.extend(part.split_whitespace());
+        }
+    }
+    terms
+}
+
+struct Inner {
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    analyzer: TextAnalyzer,
+    fields: Fields,
+}
+
+impl Inner {
+    /// 書き込んでコミットし、検索に反映する（失敗したら書き込みを取り消す）
+    fn write(
+        &self,
+        operation: &str,
+        f: impl FnOnce(&IndexWriter) -> tantivy::Result<()>,
+    ) -> Result<(), DomainError> {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = f(&writer).and_then(|_| writer.commit().map(|_| ())) {
+            let _ = writer.rollback();
+            return Err(index_error(operation, e));
+        }
+        self.reader
+            .reload()
+            .map_err(|e| index_error("インデックスの再読み込み", e))
+    }
+
+    fn upsert(&self, documents: &[ProgramDocument]) -> Result<(), DomainError> {
+        self.write("文書の追加", |writer| {
+            for document in documents {
+                writer.delete_term(Term::from_field_i64(self.fields.id, document.id));
+                writer.add_document(self.fields.to_document(document))?;
+            }
+            Ok(())
+        })?;
+        debug!(count = documents.len(), "文書を追加しました");
+        Ok(())
+    }
+
+    fn delete_stale(&self, service_id: i64, program_ids: &[i64]) -> Result<(), DomainError> {
+        let fields = self.fields;
+        let query = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_i64(fields.service_id, service_id),
+                    IndexRecordOption::Basic,
+                )),
+            ),
+            (
+                Occur::MustNot,
+                Box::new(TermSetQuery::new(
+                    program_ids
+                        .iter()
+                        .map(|id| Term::from_field_i64(fields.id, *id)),
+                )),
+            ),
+        ]);
+        self.write("文書の削除", |writer| {
+            writer.delete_query(Box::new(query)).map(|_| ())
+        })
+    }
+
+    fn search(&self, query: &SearchQuery) -> Result<SearchResults, DomainError> {
+        let searcher = self.reader.searcher();
+        let tantivy_query = self.query(query);
+        let (top_docs, total) = if query.limit == 0 {
+            let total = searcher
+                .search(&tantivy_query, &Count)
+                .map_err(|e| index_error("検索", e))?;
+            (Vec::new(), total)
+        } else {
+            let top_docs = TopDocs::with_limit(query.limit).and_offset(query.offset);
+            searcher
+                .search(&tantivy_query, &(top_docs, Count))
+                .map_err(|e| index_error("検索", e))?
+        };
+
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (_score, address) in top_docs {
+            let doc: TantivyDocument = searcher
+                .doc(address)
+                .map_err(|e| index_error("文書の読み込み", e))?;
+            let json = doc
+                .get_first(self.fields.document)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default();
+            hits.push(serde_json::from_str(json).map_err(|e| index_error("文書の読み込み", e))?);
+        }
+        Ok(SearchResults { hits, total })
+    }
+
+    /// 検索条件をすべて満たす文書に一致するクエリ
+    fn query(&self, query: &SearchQuery) -> Box<dyn Query> {
+        let fields = self.fields;
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = split_terms(&query.text)
+            .into_iter()
+            .filter_map(|term| self.term_query(term))
+            .map(|query| (Occur::Must, query))
+            .collect();
+        if !query.genre_lv1.is_empty() {
+            let terms = query
+                .genre_lv1
+                .iter()
+                .map(|lv1| Term::from_field_u64(fields.genre_lv1, u64::from(*lv1)));
+            clauses.push((Occur::Must, Box::new(TermSetQuery::new(terms))));
+        }
+        if !query.service_ids.is_empty() {
+            let terms = query
+                .service_ids
+                .iter()
+                .map(|service_id| Term::from_field_i64(fields.service_id, *service_id));
+            clauses.push((Occur::Must, Box::new(TermSetQuery::new(terms))));
+        }
+        if let Some(from) = query.from {
+            clauses.push((
+                Occur::Must,
+                Box::new(RangeQuery::new(
+                    Bound::Excluded(Term::from_field_i64(fields.end_at, from)),
+                    Bound::Unbounded,
+                )),
+            ));
+        }
+        if let Some(to) = query.to {
+            clauses.push((
+                Occur::Must,
+                Box::new(RangeQuery::new(
+                    Bound::Unbounded,
+                    Bound::Excluded(Term::from_field_i64(fields.start_at, to)),
+                )),
+            ));
+        }
+        if clauses.is_empty() {
+            Box::new(AllQuery)
+        } else {
+            Box::new(BooleanQuery::new(clauses))
+        }
+    }
+
+    /// 1つの語をいずれかのフィールドに含む文書に一致するクエリ
+    ///
+    /// 複数の単語に分かれる語は、その語順のまま続けて含むものに一致させる。
+    fn term_query(&self, term: &str) -> Option<Box<dyn Query>> {
+        let mut analyzer = self.analyzer.clone();
+        let mut stream = analyzer.token_stream(term);
+        let mut tokens = Vec::new();
+        while stream.advance() {
+            let token = stream.token();
+            tokens.push((token.position, token.text.clone()));
+        }
+        let first_position = tokens.first()?.0;
+
+        let clauses = self
+            .fields
+            .searchable()
+            .into_iter()
+            .map(|(field, boost)| {
+                let query: Box<dyn Query> = if tokens.len() == 1 {
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(field, &tokens[0].1),
+                        IndexRecordOption::WithFreqs,
+                    ))
+                } else {
+                    Box::new(PhraseQuery::new_with_offset(
+                        tokens
+                            .iter()
+                            .map(|(position, text)| {
+                                (
+                                    position - first_position,
+                                    Term::from_field_text(field, text),
+                                )
+                            })
+                            .collect(),
+                    ))
+                };
+                (
+                    Occur::Should,
+                    Box::new(BoostQuery::new(query, boost)) as Box<dyn Query>,
+                )
+            })
+            .collect();
+        Some(Box::new(BooleanQuery::new(clauses)))
+    }
+}
+
+/// tantivyで作る、外部のサーバーを使わない `SearchIndex`
+///
+/// インデックスに書き込めるのは1つのプロセスだけで、書き込みは呼び出しごとにコミットする。
+#[derive(Clone)]
+pub struct TantivySearchIndex {
+    inner: Arc<Inner>,
+}
+
+impl TantivySearchIndex {
+    /// `index_dir` のインデックスを開く（なければ作成する）
+    pub fn open(index_dir: &Path, tokenizer: JapaneseTokenizer) -> Result<Self, DomainError> {
+        std::fs::create_dir_all(index_dir)
+            .map_err(|e| index_error("インデックスのディレクトリの作成", e))?;
+        let directory =
+            MmapDirectory::open(index_dir).map_err(|e| index_error("インデックスの読み込み", e))?;
+        let (schema, fields) = Fields::schema();
+        let index = Index::open_or_create(directory, schema)
+            .map_err(|e| index_error("インデックスの読み込み", e))?;
+        let analyzer = tokenizer.analyzer();
+        index
+            .tokenizers()
+            .register(TOKENIZER_NAME, analyzer.clone());
+        let writer = index
+            .writer(WRITER_MEMORY_BUDGET)
+            .map_err(|e| index_error("インデックスのロック", e))?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(|e| index_error("インデックスの読み込み", e))?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                reader,
+                writer: Mutex::new(writer),
+                analyzer,
+                fields,
+            }),
+        })
+    }
+
+    /// インデックスの読み書きはブロックするので、専用のスレッドで実行する
+    async fn run_blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Inner) -> Result<T, DomainError> + Send + 'static,
+    ) -> Result<T, DomainError> {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || f(&inner))
+            .await
+            .map_err(|e| index_error("インデックスの操作", e))?
+    }
+}
+
+#[async_trait]
+impl SearchIndex for TantivySearchIndex {
+    async fn upsert(&self, documents: &[ProgramDocument]) -> Result<(), DomainError> {
+        let documents = documents.to_vec();
+        self.run_blocking(move |inner| inner.upsert(&documents))
+            .await
+    }
+
+    async fn delete_stale(&self, service_id: i64, program_ids: &[i64]) -> Result<(), DomainError> {
+        let program_ids = program_ids.to_vec();
+        self.run_blocking(move |inner| inner.delete_stale(service_id, &program_ids))
+            .await
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<SearchResults, DomainError> {
+        let query = query.clone();
+        self.run_blocking(move |inner| inner.search(&query)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_tokenizer;
+    use domain::model::program::{Genre, Program};
+    use domain::usecase::test_util::test_program;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    const NHK: i64 = 3273601024;
+    const ETV: i64 = 3273701032;
+    /// 2021-05-01 09:00 JST
+    const BASE: i64 = 1619827200000;
+    const HOUR: i64 = 3600000;
+
+    fn program(
+        service_id: i64,
+        event_id: i64,
+        name: &str,
+        description: &str,
+        lv1: u8,
+        start_hour: i64,
+        hours: i64,
+    ) -> Program {
+        let mut program = test_program(
+            service_id * 100000 + event_id,
+            service_id,
+            BASE + start_hour * HOUR,
+            name,
+        );
+        program.description = Some(description.to_string());
+        program.duration = hours * HOUR;
+        program.end_at = program.start_at + program.duration;
+        program.genres = vec![Genre { lv1, lv2: 0 }];
+        program.genre_names = program.genres.iter().map(Genre::to_string).collect();
+        program.channel.name = if service_id == NHK {
+            "ＮＨＫ総合１・東京".to_string()
+        } else {
+            "ＮＨＫＥテレ１東京".to_string()
+        };
+        program
+    }
+
+    fn fixtures() -> Vec<ProgramDocument> {
+        let mut cooking = program(NHK, 2, "料理の特集", "旬の料理", 5, 2, 1);
+        cooking.extended = Some(BTreeMap::from([(
+            "出演者".to_string(),
+            "山田太郎".to_string(),
+        )]));
+        [
+            program(NHK, 1, "東京の天気予報", "天気と予報", 0, 1, 1),
+            cooking,
+            program(ETV, 3, "大阪の旅行", "予報の天気", 5, 3, 1),
+            program(NHK, 4, "映画 花子の旅行", "映画の特集", 6, 11, 2),
+        ]
+        .iter()
+        .map(ProgramDocument::from)
+        .collect()
+    }
+
+    async fn index_with_fixtures(dir: &TempDir) -> TantivySearchIndex {
+        let index = TantivySearchIndex::open(dir.path(), test_tokenizer()).unwrap();
+        index.upsert(&fixtures()).await.unwrap();
+        index
+    }
+
+    /// 一致した番組のイベントID（関連度の高い順）
+    async fn search(index: &TantivySearchIndex, query: SearchQuery) -> Vec<i64> {
+        let results = index.search(&query).await.unwrap();
+        results.hits.iter().map(|hit| hit.id % 100000).collect()
+    }
+
+    fn sorted(mut ids: Vec<i64>) -> Vec<i64> {
+        ids.sort_unstable();
+        ids
+    }
+
+    #[tokio::test]
+    async fn test_keyword_and_phrase_search() {
+        let dir = TempDir::new().unwrap();
+        let index = index_with_fixtures(&dir).await;
+
+        // 番組名に含むものを先に返す
+        assert_eq!(search(&index, SearchQuery::new("天気")).await, [1, 3]);
+        assert_eq!(
+            sorted(search(&index, SearchQuery::new("天気 予報")).await),
+            [1, 3]
+        );
+        // 空白を挟まない語や `"` で囲んだ語は続けて含むものだけ
+        assert_eq!(search(&index, SearchQuery::new("天気予報")).await, [1]);
+        assert_eq!(search(&index, SearchQuery::new("\"天気 予報\"")).await, [1]);
+        // 番組詳細・チャンネル名（全角英字は半角の小文字で照合する）
+        assert_eq!(search(&index, SearchQuery::new("山田太郎")).await, [2]);
+        assert_eq!(search(&index, SearchQuery::new("NHK 旅行")).await, [4]);
+        assert!(
+            search(&index, SearchQuery::new("花子 料理"))
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_genre_service_and_time_range_filters() {
+        let dir = TempDir::new().unwrap();
+        let index = index_with_fixtures(&dir).await;
+
+        assert_eq!(
+            sorted(search(&index, SearchQuery::new("").with_genre(5)).await),
+            [2, 3]
+        );
+        assert_eq!(
+            search(&index, SearchQuery::new("旅行").with_genre(5).with_genre(0)).await,
+            [3]
+        );
+        assert_eq!(
+            sorted(search(&index, SearchQuery::new("特集").with_service(NHK)).await),
+            [2, 4]
+        );
+        // 10:30から12:00の間に少しでも放送される番組（12:00に始まる番組は含まない）
+        assert_eq!(
+            sorted(
+                search(
+                    &index,
+                    SearchQuery::new("").with_time_range(BASE + HOUR + HOUR / 2, BASE + 3 * HOUR)
+                )
+                .await
+            ),
+            [1, 2]
+        );
+
+        let results = index
+            .search(&SearchQuery::new("").with_limit(3))
+            .await
+            .unwrap();
+        assert_eq!(results.total, 4);
+        assert_eq!(results.hits.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_replaces_and_delete_stale() {
+        let dir = TempDir::new().unwrap();
+        let index = index_with_fixtures(&dir).await;
+        let mut renamed = fixtures().remove(0);
+        renamed.name = "大阪の天気予報".to_string();
+
+        index.upsert(&[renamed.clone()]).await.unwrap();
+        index.delete_stale(NHK, &[renamed.id]).await.unwrap();
+
+        let results = index.search(&SearchQuery::new("")).await.unwrap();
+        assert_eq!(results.total, 2);
+        assert_eq!(search(&index, SearchQuery::new("大阪 予報")).await, [1, 3]);
+        assert_eq!(
+            index
+                .search(&SearchQuery::new("天気予報"))
+                .await
+                .unwrap()
+                .hits,
+            [renamed]
+        );
+
+        // 開き直しても残っている
+        drop(index);
+        let index = TantivySearchIndex::open(dir.path(), test_tokenizer()).unwrap();
+        assert_eq!(sorted(search(&index, SearchQuery::new("")).await), [1, 3]);
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use lindera::{dictionary::load_fs_dictionary, mode::Mode, segmenter::Segmenter};
+use lindera_dictionary::{builder::DictionaryBuilder, dictionary::metadata::Metadata};
+use tempfile::TempDir;
+
+use crate::JapaneseTokenizer;
+
+const CHAR_DEF: &str = "\
+DEFAULT 0 1 0
+SPACE 0 1 0
+KANJI 0 0 2
+HIRAGANA 1 1 0
+KATAKANA 1 1 0
+ALPHA 1 1 0
+NUMERIC 1 1 0
+0x0020 SPACE
+0x3000 SPACE
+0x3041..0x309F HIRAGANA
+0x30A1..0x30FF KATAKANA
+0x4E00..0x9FFF KANJI
+0x0030..0x0039 NUMERIC
+0x0041..0x005A ALPHA
+0x0061..0x007A ALPHA
+0xFF10..0xFF19 NUMERIC
+0xFF21..0xFF3A ALPHA
+0xFF41..0xFF5A ALPHA
+";
+
+const UNK_DEF: &str = "\
+DEFAULT,0,0,1000,記号,一般,*,*,*,*,*
+SPACE,0,0,1000,記号,空白,*,*,*,*,*
+KANJI,0,0,1000,名詞,一般,*,*,*,*,*
+HIRAGANA,0,0,1000,名詞,一般,*,*,*,*,*
+KATAKANA,0,0,1000,名詞,一般,*,*,*,*,*
+ALPHA,0,0,1000,名詞,固有名詞,*,*,*,*,*
+NUMERIC,0,0,1000,名詞,数,*,*,*,*,*
+";
+
+/// テストの番組に出てくる語（IPADICと同じ形式）
+const LEX: &[&str] = &[
+    "東京", "大阪", "天気", "予報", "料理", "旅行", "映画", "特集", "出演", "山田", "太郎", "花子",
+    "の", "と", "を", "で", "が", "は", "する", "した",
+];
+
+/// テスト用の小さな辞書で日本語を分けるトークナイザー
+///
+/// 辞書はIPADICを埋め込まずにビルドできるよう、その場で作る。
+pub fn test_tokenizer() -> JapaneseTokenizer {
+    let source = TempDir::new().unwrap();
+    let output = TempDir::new().unwrap();
+    write_source(source.path());
+    DictionaryBuilder::new(Metadata::default())
+        .build_dictionary(source.path(), output.path())
+        .unwrap();
+    let dictionary = load_fs_dictionary(output.path()).unwrap();
+    JapaneseTokenizer::new(Segmenter::new(Mode::Normal, dictionary, None))
+}
+
+fn write_source(dir: &Path) {
+    std::fs::write(dir.join("char.def"), CHAR_DEF).unwrap();
+    std::fs::write(dir.join("unk.def"), UNK_DEF).unwrap();
+    std::fs::write(dir.join("matrix.def"), "1 1\n0 0 0\n").unwrap();
+    let lex: String = LEX
+        .iter()
+        .map(|word| format!("{0},0,0,100,名詞,一般,*,*,*,*,{0},*,*\n", word))
+        .collect();
+    std::fs::write(dir.join("lex.csv"), lex).unwrap();
+}
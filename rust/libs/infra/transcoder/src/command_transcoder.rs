@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+use std::process::Stdio;
+
+use async_trait::async_trait;
+use domain::{
+    model::transcode::{TranscodeJob, TranscodeProgressParser},
+    ports::{TranscodeProgressListener, Transcoder, TranscoderError},
+};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// 失敗したときにエラーに含める標準エラー出力の行数
+const STDERR_TAIL_LINES: usize = 20;
+
+/// ffmpeg互換のエンコーダーを外部コマンドとして実行する
+///
+/// 処理が中断された（futureが破棄された）場合はエンコーダーも終了させる。
+#[derive(Clone, Debug)]
+pub struct CommandTranscoder {
+    program: String,
+}
+
+impl Default for CommandTranscoder {
+    fn default() -> Self {
+        Self::new("ffmpeg")
+    }
+}
+
+impl CommandTranscoder {
+    pub fn new(program: &str) -> Self {
+        Self {
+            program: program.to_string(),
+        }
+    }
+}
+
+/// 標準エラー出力を読み切り、最後の数行だけを返す
+async fn read_stderr_tail(stderr: impl AsyncRead + Unpin) -> String {
+    let mut lines = BufReader::new(stderr).lines();
+    let mut tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+    while let Ok(Some(line)) = lines.next_line().await {
+        if tail.len() == STDERR_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+    Vec::from(tail).join("\n")
+}
+
+#[async_trait]
+impl Transcoder for CommandTranscoder {
+    async fn transcode(
+        &self,
+        job: &TranscodeJob,
+        listener: &dyn TranscodeProgressListener,
+    ) -> Result<u64, TranscoderError> {
+        if let Some(output_dir) = job.output_path.parent() {
+            tokio::fs::create_dir_all(output_dir)
+                .await
+                .map_err(|e| TranscoderError::OutputError(e.to_string()))?;
+        }
+
+        let args = job.encoder_args();
+        debug!(
+            "エンコーダーを起動します: {} {}",
+            self.program,
+            args.join(" ")
+        );
+        let mut child = Command::new(&self.program)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| TranscoderError::SpawnError(format!("{}: {}", self.program, e)))?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        // 片方のパイプが詰まらないよう、標準出力と標準エラー出力を並行して読む
+        let read_progress = async {
+            let mut parser = TranscodeProgressParser::new(job.duration);
+            let mut lines = BufReader::new(stdout).lines();
+            loop {
+                match lines.next_line().await {
+                    Ok(Some(line)) => {
+                        if let Some(progress) = parser.parse_line(&line) {
+                            listener.on_progress(&progress).await;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("エンコーダーの進捗の読み取りに失敗: {}", e);
+                        break;
+                    }
+                }
+            }
+        };
+        let ((), stderr_tail) = tokio::join!(read_progress, read_stderr_tail(stderr));
+
+        let status = child.wait().await.map_err(|e| {
+            TranscoderError::SpawnError(format!("{}の終了を待てません: {}", self.program, e))
+        })?;
+        if !status.success() {
+            // 途中まで書き出したファイルを残さない
+            let _ = tokio::fs::remove_file(&job.output_path).await;
+            return Err(TranscoderError::EncoderFailed {
+                exit_code: status.code(),
+                message: stderr_tail,
+            });
+        }
+
+        let metadata = tokio::fs::metadata(&job.output_path).await.map_err(|e| {
+            TranscoderError::OutputError(format!("{}: {}", job.output_path.display(), e))
+        })?;
+        Ok(metadata.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::model::recording::{Record, RecordingStatus};
+    use domain::model::transcode::{TranscodeProfile, TranscodeProgress};
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    /// 進捗を書き出し、受け取った引数を出力ファイルに書き込むエンコーダーの代わり
+    const STUB_ENCODER: &str = r#"#!/bin/sh
+for output; do :; done
+printf 'frame=1\nout_time_us=30000000\nspeed=2.0x\nprogress=continue\n'
+printf 'out_time_us=45000000\nspeed=N/A\nprogress=continue\n'
+printf 'out_time_us=60000000\nprogress=end\n'
+echo "$@" > "$output"
+"#;
+
+    const FAILING_ENCODER: &str = r#"#!/bin/sh
+for output; do :; done
+printf 'out_time_us=1000000\nprogress=continue\n'
+echo partial > "$output"
+echo "Invalid data found when processing input" >&2
+exit 1
+"#;
+
+    #[derive(Default)]
+    struct RecordingListener {
+        progress: Mutex<Vec<TranscodeProgress>>,
+    }
+
+    #[async_trait]
+    impl TranscodeProgressListener for RecordingListener {
+        async fn on_progress(&self, progress: &TranscodeProgress) {
+            self.progress.lock().unwrap().push(progress.clone());
+        }
+    }
+
+    fn write_script(dir: &Path, name: &str, content: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn job(dir: &Path) -> TranscodeJob {
+        let record = Record {
+            id: "0000000000000001".to_string(),
+            program_id: 327360102412345,
+            service_id: 3273601024,
+            program_name: Some("映画".to_string()),
+            tags: vec![],
+            status: RecordingStatus::Finished,
+            start_time: 1619856000000,
+            end_time: Some(1619856060000),
+            content_path: "0000000000000001.m2ts".to_string(),
+            content_type: "video/MP2T".to_string(),
+            content_length: Some(1024),
+            failed_reason: None,
+            audio_component_types: vec![0b00011],
+        };
+        TranscodeJob::new(
+            &record,
+            &TranscodeProfile::default(),
+            &dir.join("records"),
+            &dir.join("videos"),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_transcode_reports_progress() {
+        let dir = TempDir::new().unwrap();
+        let transcoder =
+            CommandTranscoder::new(&write_script(dir.path(), "encoder.sh", STUB_ENCODER));
+        let job = job(dir.path());
+        let listener = RecordingListener::default();
+
+        let output_length = transcoder.transcode(&job, &listener).await.unwrap();
+
+        let output = std::fs::read_to_string(&job.output_path).unwrap();
+        assert_eq!(output_length, output.len() as u64);
+        assert!(output.contains("-progress pipe:1"));
+        assert!(output.contains(&job.input_path.to_string_lossy().into_owned()));
+        let progress = listener.progress.lock().unwrap().clone();
+        let percents: Vec<_> = progress.iter().map(|progress| progress.percent).collect();
+        assert_eq!(percents, [Some(50), Some(75), Some(100)]);
+        assert_eq!(progress[0].speed, Some(2.0));
+        assert_eq!(progress[1].speed, None);
+        assert!(progress[2].finished);
+    }
+
+    #[tokio::test]
+    async fn test_transcode_encoder_failure() {
+        let dir = TempDir::new().unwrap();
+        let transcoder =
+            CommandTranscoder::new(&write_script(dir.path(), "encoder.sh", FAILING_ENCODER));
+        let job = job(dir.path());
+        let listener = RecordingListener::default();
+
+        let result = transcoder.transcode(&job, &listener).await;
+
+        match result {
+            Err(TranscoderError::EncoderFailed { exit_code, message }) => {
+                assert_eq!(exit_code, Some(1));
+                assert_eq!(message, "Invalid data found when processing input");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(!job.output_path.exists());
+        assert_eq!(listener.progress.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transcode_missing_encoder() {
+        let dir = TempDir::new().unwrap();
+        let transcoder = CommandTranscoder::new("/nonexistent/ffmpeg");
+
+        let result = transcoder
+            .transcode(&job(dir.path()), &RecordingListener::default())
+            .await;
+
+        assert!(matches!(result, Err(TranscoderError::SpawnError(_))));
+    }
+}
@@ -0,0 +1,3 @@
+mod command_transcoder;
+
+pub use command_transcoder::*;
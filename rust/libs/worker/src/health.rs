@@ -0,0 +1,224 @@
+//! `/healthz`・`/readyz`・`/metrics` を提供するHTTPサーバー
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use warp::Filter;
+use warp::http::StatusCode;
+
+use crate::MetricsRegistry;
+
+/// 1つの検査で待つ最大時間
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 依存先やタスクの状態の検査
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn check(&self) -> Result<(), String>;
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HealthReport {
+    pub ok: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+/// すべての検査を実行し、結果をまとめる
+pub async fn run_checks(checks: &[Arc<dyn HealthCheck>]) -> HealthReport {
+    let mut results = Vec::with_capacity(checks.len());
+    for check in checks {
+        let error = match tokio::time::timeout(CHECK_TIMEOUT, check.check()).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e),
+            Err(_) => Some("タイムアウトしました".to_string()),
+        };
+        results.push(CheckResult {
+            name: check.name().to_string(),
+            ok: error.is_none(),
+            error,
+        });
+    }
+    HealthReport {
+        ok: results.iter().all(|result| result.ok),
+        checks: results,
+    }
+}
+
+/// 生存確認（`/healthz`）・準備完了確認（`/readyz`）・メトリクス（`/metrics`）を提供するサーバー
+#[derive(Clone, Default)]
+pub struct HealthServer {
+    liveness: Vec<Arc<dyn HealthCheck>>,
+    readiness: Vec<Arc<dyn HealthCheck>>,
+    metrics: MetricsRegistry,
+}
+
+impl HealthServer {
+    pub fn new(metrics: MetricsRegistry) -> Self {
+        Self {
+            metrics,
+            ..Default::default()
+        }
+    }
+
+    /// `/healthz` で検査する項目を追加する
+    pub fn with_liveness(mut self, check: Arc<dyn HealthCheck>) -> Self {
+        self.liveness.push(check);
+        self
+    }
+
+    /// `/readyz` で検査する項目を追加する
+    pub fn with_readiness(mut self, check: Arc<dyn HealthCheck>) -> Self {
+        self.readiness.push(check);
+        self
+    }
+
+    /// `addr` で待ち受けを開始し、実際に待ち受けているアドレスとサーバーのFutureを返す
+    ///
+    /// サーバーは `shutdown` がキャンセルされると停止する。
+    pub fn bind(
+        self,
+        addr: SocketAddr,
+        shutdown: CancellationToken,
+    ) -> Result<(SocketAddr, impl Future<Output = ()> + Send + 'static), warp::Error> {
+        let liveness = Arc::new(self.liveness);
+        let readiness = Arc::new(self.readiness);
+        let metrics = self.metrics;
+
+        let healthz = warp::path!("healthz").and(warp::get()).then(move || {
+            let checks = liveness.clone();
+            async move { report(&checks).await }
+        });
+        let readyz = warp::path!("readyz").and(warp::get()).then(move || {
+            let checks = readiness.clone();
+            async move { report(&checks).await }
+        });
+        let metrics = warp::path!("metrics").and(warp::get()).then(move || {
+            let metrics = metrics.clone();
+            async move {
+                warp::reply::with_header(
+                    metrics.encode().await,
+                    "content-type",
+                    "text/plain; version=0.0.4; charset=utf-8",
+                )
+            }
+        });
+
+        let routes = healthz.or(readyz).or(metrics);
+        let (addr, server) = warp::serve(routes)
+            .try_bind_with_graceful_shutdown(addr, async move { shutdown.cancelled().await })?;
+        info!(%addr, "ヘルスチェックサーバーを開始しました");
+        Ok((addr, server))
+    }
+}
+
+async fn report(checks: &[Arc<dyn HealthCheck>]) -> warp::reply::WithStatus<warp::reply::Json> {
+    let report = run_checks(checks).await;
+    let status = if report.ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    warp::reply::with_status(warp::reply::json(&report), status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WorkerMetrics, WorkerMetricsCollector};
+
+    struct StaticCheck {
+        name: &'static str,
+        result: Result<(), String>,
+    }
+
+    #[async_trait]
+    impl HealthCheck for StaticCheck {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn check(&self) -> Result<(), String> {
+            self.result.clone()
+        }
+    }
+
+    fn start(server: HealthServer) -> (String, CancellationToken) {
+        let shutdown = CancellationToken::new();
+        let (addr, server) = server
+            .bind(([127, 0, 0, 1], 0).into(), shutdown.clone())
+            .unwrap();
+        tokio::spawn(server);
+        (format!("http://{}", addr), shutdown)
+    }
+
+    #[tokio::test]
+    async fn test_healthz_and_readyz() {
+        let server = HealthServer::default()
+            .with_liveness(Arc::new(StaticCheck {
+                name: "supervisor",
+                result: Ok(()),
+            }))
+            .with_readiness(Arc::new(StaticCheck {
+                name: "nats",
+                result: Ok(()),
+            }))
+            .with_readiness(Arc::new(StaticCheck {
+                name: "mirakc",
+                result: Err("接続できません".to_string()),
+            }));
+        let (url, shutdown) = start(server);
+
+        let response = reqwest::get(format!("{}/healthz", url)).await.unwrap();
+        assert_eq!(response.status(), 200);
+
+        let response = reqwest::get(format!("{}/readyz", url)).await.unwrap();
+        assert_eq!(response.status(), 503);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["ok"], false);
+        assert_eq!(body["checks"][0]["name"], "nats");
+        assert_eq!(body["checks"][0]["ok"], true);
+        assert_eq!(body["checks"][1]["error"], "接続できません");
+
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let registry = MetricsRegistry::default();
+        let metrics = Arc::new(WorkerMetrics::default());
+        metrics.record_received();
+        registry.register(
+            "worker/test",
+            Arc::new(WorkerMetricsCollector::new(
+                "test",
+                "recording.epg.updated",
+                metrics,
+            )),
+        );
+        let (url, shutdown) = start(HealthServer::new(registry));
+
+        let response = reqwest::get(format!("{}/metrics", url)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body = response.text().await.unwrap();
+        assert!(body.contains(
+            r#"kurec_messages_received_total{worker="test",subject="recording.epg.updated"} 1"#
+        ));
+
+        shutdown.cancel();
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use domain::{error::DomainError, types::Event};
+use nats::{
+    error::NatsInfraError,
+    nats::NatsClient,
+    stream::{EventReader, EventStore, JsMessageAckHandle, ReaderConfig},
+    stream_manager::missing_streams,
+};
+use tokio_util::sync::CancellationToken;
+
+use tracing::debug;
+
+use crate::{
+    Delivery, EventSource, HealthCheck, MetricsCollector, MetricsEncoder, Worker, WorkerConfig,
+    WorkerMetrics, WorkerRuntime,
+};
+
+#[async_trait]
+impl Delivery for JsMessageAckHandle {
+    type Error = NatsInfraError;
+
+    fn delivery_count(&self) -> i64 {
+        JsMessageAckHandle::delivery_count(self)
+    }
+
+    async fn ack(&mut self) -> Result<(), NatsInfraError> {
+        JsMessageAckHandle::ack(self).await
+    }
+
+    async fn nak(&mut self) -> Result<(), NatsInfraError> {
+        JsMessageAckHandle::nak(self).await
+    }
+
+    async fn in_progress(&mut self) -> Result<(), NatsInfraError> {
+        JsMessageAckHandle::in_progress(self).await
+    }
+
+    async fn fail_with(&mut self, error: &DomainError) -> Result<(), NatsInfraError> {
+        JsMessageAckHandle::fail_with(self, error).await
+    }
+}
+
+/// JetStreamのプルコンシューマーを受信元とする [`EventSource`]
+pub struct JetStreamSource<R>(R);
+
+#[async_trait]
+impl<E, R> EventSource<E> for JetStreamSource<R>
+where
+    E: Event,
+    R: EventReader<E> + Send + Sync,
+{
+    type Delivery = JsMessageAckHandle;
+    type Error = NatsInfraError;
+
+    async fn next(&self) -> Result<(E, JsMessageAckHandle), NatsInfraError> {
+        self.0.next().await
+    }
+
+    async fn next_batch(
+        &self,
+        max_messages: usize,
+    ) -> Result<Vec<(E, JsMessageAckHandle)>, NatsInfraError> {
+        self.0.next_batch(max_messages).await
+    }
+}
+
+/// NATSに接続しており、必要なストリームが存在することを確認する
+pub struct NatsHealthCheck {
+    nats_client: NatsClient,
+    streams: Vec<String>,
+}
+
+impl NatsHealthCheck {
+    pub fn new(nats_client: NatsClient, streams: Vec<String>) -> Self {
+        Self {
+            nats_client,
+            streams,
+        }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for NatsHealthCheck {
+    fn name(&self) -> &str {
+        "nats"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        if !self.nats_client.is_connected() {
+            return Err("NATSサーバーに接続していません".to_string());
+        }
+        let missing = missing_streams(&self.nats_client, &self.streams).await;
+        if !missing.is_empty() {
+            return Err(format!("ストリームがありません: {}", missing.join(", ")));
+        }
+        Ok(())
+    }
+}
+
+/// JetStreamコンシューマーの未処理メッセージ数を出力する
+pub struct ConsumerLagCollector<E: Event> {
+    event_store: EventStore<E>,
+    consumer: String,
+}
+
+impl<E: Event> ConsumerLagCollector<E> {
+    pub fn new(event_store: EventStore<E>, consumer: &str) -> Self {
+        Self {
+            event_store,
+            consumer: consumer.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl<E: Event> MetricsCollector for ConsumerLagCollector<E> {
+    async fn collect(&self, encoder: &mut MetricsEncoder) {
+        let lag = match self.event_store.consumer_lag(&self.consumer).await {
+            Ok(lag) => lag,
+            Err(e) => {
+                debug!(consumer = %self.consumer, "コンシューマー情報の取得に失敗: {}", e);
+                return;
+            }
+        };
+        let subject = EventStore::<E>::get_subject();
+        let labels = [
+            ("consumer", self.consumer.as_str()),
+            ("subject", subject.as_str()),
+        ];
+        encoder.gauge(
+            "kurec_consumer_pending_messages",
+            "コンシューマーにまだ配信されていないメッセージ数",
+            &labels,
+            lag.pending as f64,
+        );
+        encoder.gauge(
+            "kurec_consumer_ack_pending_messages",
+            "確認応答を待っているメッセージ数",
+            &labels,
+            lag.ack_pending as f64,
+        );
+        encoder.gauge(
+            "kurec_consumer_redelivered_messages",
+            "再配信されて確認応答を待っているメッセージ数",
+            &labels,
+            lag.redelivered as f64,
+        );
+    }
+}
+
+/// `consumer` を永続名とするコンシューマーを作成し、受信元として返す
+pub async fn open_jetstream_source<E: Event>(
+    nats_client: NatsClient,
+    consumer: &str,
+    config: &WorkerConfig,
+) -> Result<JetStreamSource<impl EventReader<E> + Send + Sync + 'static>, NatsInfraError> {
+    let event_store = EventStore::<E>::new(nats_client).await?;
+    let reader = event_store
+        .get_reader_with_config(
+            consumer.to_string(),
+            ReaderConfig {
+                retry_policy: config.retry_policy.clone(),
+            },
+        )
+        .await?;
+    Ok(JetStreamSource(reader))
+}
+
+/// ワーカー名を永続名とするコンシューマーを作成し、`shutdown` がキャンセルされるまでワーカーを実行する
+///
+/// 処理件数は `metrics` に記録される。
+pub async fn run_jetstream_worker<E, W>(
+    nats_client: NatsClient,
+    worker: W,
+    config: WorkerConfig,
+    metrics: Arc<WorkerMetrics>,
+    shutdown: CancellationToken,
+) -> Result<(), NatsInfraError>
+where
+    E: Event,
+    W: Worker<E>,
+{
+    let source = open_jetstream_source(nats_client, worker.name(), &config).await?;
+
+    WorkerRuntime::new(worker, source, config)
+        .with_metrics(metrics)
+        .run(shutdown)
+        .await;
+    Ok(())
+}
@@ -0,0 +1,25 @@
+//! イベント駆動ワーカーの実行基盤
+//!
+//! パイプラインの各ステージは [`Worker`] を実装するだけでよく、
+//! コンシューマーのループ・ack/nak・並行数制御・シャットダウンは [`WorkerRuntime`] が受け持つ。
+//! 複数のワーカーを1つのプロセスで動かす場合は [`Supervisor`] が再起動を受け持つ。
+
+mod health;
+mod jetstream;
+mod metrics;
+mod prometheus;
+mod runtime;
+mod shutdown;
+mod source;
+mod supervisor;
+
+pub use health::*;
+pub use jetstream::*;
+pub use metrics::*;
+pub use prometheus::*;
+pub use runtime::*;
+pub use shutdown::*;
+pub use source::*;
+pub use supervisor::*;
+
+pub use tokio_util::sync::CancellationToken;
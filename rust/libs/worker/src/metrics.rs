@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// ワーカーの処理件数
+#[derive(Debug, Default)]
+pub struct WorkerMetrics {
+    received: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    aborted: AtomicU64,
+    redelivered: AtomicU64,
+    receive_errors: AtomicU64,
+    in_flight: AtomicU64,
+}
+
+/// ある時点での [`WorkerMetrics`] の値
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkerMetricsSnapshot {
+    /// 受信したメッセージ数
+    pub received: u64,
+    /// 処理に成功したメッセージ数
+    pub succeeded: u64,
+    /// 処理に失敗したメッセージ数
+    pub failed: u64,
+    /// シャットダウンのため処理を中断したメッセージ数
+    pub aborted: u64,
+    /// 再配信されたメッセージ数
+    pub redelivered: u64,
+    /// メッセージの受信に失敗した回数
+    pub receive_errors: u64,
+    /// 処理中のメッセージ数
+    pub in_flight: u64,
+}
+
+impl WorkerMetrics {
+    pub fn snapshot(&self) -> WorkerMetricsSnapshot {
+        WorkerMetricsSnapshot {
+            received: self.received.load(Ordering::Relaxed),
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            aborted: self.aborted.load(Ordering::Relaxed),
+            redelivered: self.redelivered.load(Ordering::Relaxed),
+            receive_errors: self.receive_errors.load(Ordering::Relaxed),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn record_received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_succeeded(&self) {
+        self.succeeded.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_failed(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_aborted(&self) {
+        self.aborted.fetch_add(1, Ordering::Relaxed);
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_redelivered(&self) {
+        self.redelivered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_receive_error(&self) {
+        self.receive_errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,400 @@
+//! Prometheus のテキスト形式でのメトリクス出力
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::WorkerMetrics;
+
+/// 所要時間のヒストグラムの既定のバケット（秒）
+pub const DEFAULT_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// 所要時間の分布
+#[derive(Debug)]
+pub struct Histogram {
+    buckets: Vec<f64>,
+    counts: Vec<AtomicU64>,
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+/// ある時点での [`Histogram`] の値
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramSnapshot {
+    /// 上限値と、上限値以下の観測数の累計
+    pub buckets: Vec<(f64, u64)>,
+    pub count: u64,
+    /// 観測値の合計（秒）
+    pub sum: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new(DEFAULT_DURATION_BUCKETS)
+    }
+}
+
+impl Histogram {
+    pub fn new(buckets: &[f64]) -> Self {
+        Self {
+            buckets: buckets.to_vec(),
+            counts: buckets.iter().map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(i) = self.buckets.iter().position(|le| seconds <= *le) {
+            self.counts[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0;
+        let buckets = self
+            .buckets
+            .iter()
+            .zip(&self.counts)
+            .map(|(le, count)| {
+                cumulative += count.load(Ordering::Relaxed);
+                (*le, cumulative)
+            })
+            .collect();
+        HistogramSnapshot {
+            buckets,
+            count: self.count.load(Ordering::Relaxed),
+            sum: self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+        }
+    }
+}
+
+struct Family {
+    help: String,
+    kind: &'static str,
+    samples: Vec<String>,
+}
+
+/// メトリクスをメトリクス名ごとにまとめてテキスト形式に変換する
+#[derive(Default)]
+pub struct MetricsEncoder {
+    families: BTreeMap<String, Family>,
+}
+
+impl MetricsEncoder {
+    pub fn counter(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: u64) {
+        self.sample(name, help, "counter", name, labels, &value.to_string());
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        self.sample(name, help, "gauge", name, labels, &value.to_string());
+    }
+
+    pub fn histogram(
+        &mut self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        snapshot: &HistogramSnapshot,
+    ) {
+        let bucket_name = format!("{}_bucket", name);
+        for (le, count) in &snapshot.buckets {
+            let le = le.to_string();
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            self.sample(
+                name,
+                help,
+                "histogram",
+                &bucket_name,
+                &bucket_labels,
+                &count.to_string(),
+            );
+        }
+        let mut inf_labels = labels.to_vec();
+        inf_labels.push(("le", "+Inf"));
+        let count = snapshot.count.to_string();
+        self.sample(name, help, "histogram", &bucket_name, &inf_labels, &count);
+        let sum_name = format!("{}_sum", name);
+        let sum = snapshot.sum.to_string();
+        self.sample(name, help, "histogram", &sum_name, labels, &sum);
+        let count_name = format!("{}_count", name);
+        self.sample(name, help, "histogram", &count_name, labels, &count);
+    }
+
+    fn sample(
+        &mut self,
+        family: &str,
+        help: &str,
+        kind: &'static str,
+        name: &str,
+        labels: &[(&str, &str)],
+        value: &str,
+    ) {
+        let family = self
+            .families
+            .entry(family.to_string())
+            .or_insert_with(|| Family {
+                help: help.to_string(),
+                kind,
+                samples: Vec::new(),
+            });
+        family
+            .samples
+            .push(format!("{}{} {}", name, format_labels(labels), value));
+    }
+
+    pub fn finish(self) -> String {
+        let mut out = String::new();
+        for (name, family) in self.families {
+            let _ = writeln!(out, "# HELP {} {}", name, family.help);
+            let _ = writeln!(out, "# TYPE {} {}", name, family.kind);
+            for sample in family.samples {
+                out.push_str(&sample);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", key, value)
+        })
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+/// スクレイプのたびに呼ばれ、メトリクスを書き出す
+#[async_trait]
+pub trait MetricsCollector: Send + Sync {
+    async fn collect(&self, encoder: &mut MetricsEncoder);
+}
+
+/// `/metrics` で出力するメトリクスの登録先
+#[derive(Clone, Default)]
+pub struct MetricsRegistry {
+    collectors: Arc<Mutex<BTreeMap<String, Arc<dyn MetricsCollector>>>>,
+}
+
+impl MetricsRegistry {
+    /// `key` で識別されるコレクターを登録する（同じ `key` の登録は置き換えられる）
+    pub fn register(&self, key: impl Into<String>, collector: Arc<dyn MetricsCollector>) {
+        self.collectors
+            .lock()
+            .unwrap()
+            .insert(key.into(), collector);
+    }
+
+    /// ラベルを固定したヒストグラムを登録する
+    pub fn register_histogram(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        histogram: Arc<Histogram>,
+    ) {
+        let labels: Vec<(String, String)> = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let key = format!("histogram/{}{:?}", name, labels);
+        self.register(
+            key,
+            Arc::new(HistogramCollector {
+                name: name.to_string(),
+                help: help.to_string(),
+                labels,
+                histogram,
+            }),
+        );
+    }
+
+    pub async fn encode(&self) -> String {
+        let collectors: Vec<_> = self.collectors.lock().unwrap().values().cloned().collect();
+        let mut encoder = MetricsEncoder::default();
+        for collector in collectors {
+            collector.collect(&mut encoder).await;
+        }
+        encoder.finish()
+    }
+}
+
+struct HistogramCollector {
+    name: String,
+    help: String,
+    labels: Vec<(String, String)>,
+    histogram: Arc<Histogram>,
+}
+
+#[async_trait]
+impl MetricsCollector for HistogramCollector {
+    async fn collect(&self, encoder: &mut MetricsEncoder) {
+        let labels: Vec<(&str, &str)> = self
+            .labels
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        encoder.histogram(&self.name, &self.help, &labels, &self.histogram.snapshot());
+    }
+}
+
+/// ワーカーの処理件数をサブジェクトごとに出力する
+pub struct WorkerMetricsCollector {
+    worker: String,
+    subject: String,
+    metrics: Arc<WorkerMetrics>,
+}
+
+impl WorkerMetricsCollector {
+    pub fn new(worker: &str, subject: &str, metrics: Arc<WorkerMetrics>) -> Self {
+        Self {
+            worker: worker.to_string(),
+            subject: subject.to_string(),
+            metrics,
+        }
+    }
+}
+
+#[async_trait]
+impl MetricsCollector for WorkerMetricsCollector {
+    async fn collect(&self, encoder: &mut MetricsEncoder) {
+        let snapshot = self.metrics.snapshot();
+        let labels = [
+            ("worker", self.worker.as_str()),
+            ("subject", self.subject.as_str()),
+        ];
+        encoder.counter(
+            "kurec_messages_received_total",
+            "受信したメッセージ数",
+            &labels,
+            snapshot.received,
+        );
+        encoder.counter(
+            "kurec_messages_processed_total",
+            "処理に成功したメッセージ数",
+            &labels,
+            snapshot.succeeded,
+        );
+        encoder.counter(
+            "kurec_messages_failed_total",
+            "処理に失敗したメッセージ数",
+            &labels,
+            snapshot.failed,
+        );
+        encoder.counter(
+            "kurec_messages_redelivered_total",
+            "再配信されたメッセージ数",
+            &labels,
+            snapshot.redelivered,
+        );
+        encoder.counter(
+            "kurec_messages_aborted_total",
+            "シャットダウンのため処理を中断したメッセージ数",
+            &labels,
+            snapshot.aborted,
+        );
+        encoder.counter(
+            "kurec_receive_errors_total",
+            "メッセージの受信に失敗した回数",
+            &labels,
+            snapshot.receive_errors,
+        );
+        encoder.gauge(
+            "kurec_messages_in_flight",
+            "処理中のメッセージ数",
+            &labels,
+            snapshot.in_flight as f64,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_snapshot_is_cumulative() {
+        let histogram = Histogram::new(&[0.1, 1.0]);
+        histogram.observe(Duration::from_millis(50));
+        histogram.observe(Duration::from_millis(500));
+        histogram.observe(Duration::from_secs(3));
+
+        let snapshot = histogram.snapshot();
+
+        assert_eq!(snapshot.buckets, vec![(0.1, 1), (1.0, 2)]);
+        assert_eq!(snapshot.count, 3);
+        assert!((snapshot.sum - 3.55).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_encode_groups_families() {
+        let registry = MetricsRegistry::default();
+        for worker in ["b", "a"] {
+            let metrics = Arc::new(WorkerMetrics::default());
+            metrics.record_received();
+            metrics.record_succeeded();
+            registry.register(
+                format!("worker/{}", worker),
+                Arc::new(WorkerMetricsCollector::new(worker, "ogp.url.x", metrics)),
+            );
+        }
+        let histogram = Arc::new(Histogram::new(&[1.0]));
+        histogram.observe(Duration::from_millis(250));
+        registry.register_histogram(
+            "kurec_test_duration_seconds",
+            "テスト",
+            &[("operation", "say \"hi\"")],
+            histogram,
+        );
+
+        let text = registry.encode().await;
+
+        let processed: Vec<&str> = text
+            .lines()
+            .filter(|line| line.starts_with("kurec_messages_processed_total"))
+            .collect();
+        assert_eq!(
+            processed,
+            [
+                r#"kurec_messages_processed_total{worker="a",subject="ogp.url.x"} 1"#,
+                r#"kurec_messages_processed_total{worker="b",subject="ogp.url.x"} 1"#,
+            ]
+        );
+        assert_eq!(
+            text.matches("# TYPE kurec_messages_processed_total counter")
+                .count(),
+            1
+        );
+        assert!(
+            text.contains(r#"kurec_test_duration_seconds_bucket{operation="say \"hi\"",le="1"} 1"#)
+        );
+        assert!(
+            text.contains(
+                r#"kurec_test_duration_seconds_bucket{operation="say \"hi\"",le="+Inf"} 1"#
+            )
+        );
+        assert!(text.contains(r#"kurec_test_duration_seconds_sum{operation="say \"hi\""} 0.25"#));
+        assert!(text.contains(r#"kurec_test_duration_seconds_count{operation="say \"hi\""} 1"#));
+    }
+}
@@ -0,0 +1,610 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use domain::{error::DomainError, service::RetryPolicy, types::Event};
+use tokio::task::{JoinError, JoinSet};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, debug, error, info, info_span, warn};
+
+use crate::{Delivery, EventSource, Worker, WorkerMetrics};
+
+/// ワーカーの実行設定
+#[derive(Clone, Debug)]
+pub struct WorkerConfig {
+    /// 同時に処理するメッセージの最大数
+    pub concurrency: usize,
+    /// 処理中通知を送る間隔（`None` の場合は送らない）
+    pub heartbeat_interval: Option<Duration>,
+    /// メッセージの受信に失敗したときに次の受信まで待つ時間
+    pub receive_error_backoff: Duration,
+    /// シャットダウン時に処理中のメッセージの完了を待つ時間。超えた場合は中断して再配信を要求する
+    pub shutdown_timeout: Duration,
+    /// 処理に失敗したメッセージの再試行方針
+    pub retry_policy: RetryPolicy,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 1,
+            heartbeat_interval: Some(Duration::from_secs(10)),
+            receive_error_backoff: Duration::from_secs(1),
+            shutdown_timeout: Duration::from_secs(30),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+}
+
+/// [`Worker`] に受信元からイベントを供給し、処理結果に応じて確認応答を行う
+///
+/// 各メッセージは個別のタスクで処理されるため、CPUを使う処理も最大 `concurrency` 件まで並列に実行される。
+pub struct WorkerRuntime<E, W, S>
+where
+    E: Event,
+    W: Worker<E>,
+    S: EventSource<E>,
+{
+    worker: Arc<W>,
+    source: S,
+    config: WorkerConfig,
+    metrics: Arc<WorkerMetrics>,
+    _phantom: PhantomData<E>,
+}
+
+impl<E, W, S> WorkerRuntime<E, W, S>
+where
+    E: Event,
+    W: Worker<E>,
+    S: EventSource<E>,
+{
+    pub fn new(worker: W, source: S, config: WorkerConfig) -> Self {
+        Self {
+            worker: Arc::new(worker),
+            source,
+            config,
+            metrics: Arc::new(WorkerMetrics::default()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// 処理件数を外部と共有する [`WorkerMetrics`] に差し替える
+    pub fn with_metrics(mut self, metrics: Arc<WorkerMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<WorkerMetrics> {
+        self.metrics.clone()
+    }
+
+    /// `shutdown` がキャンセルされるまでイベントを処理する
+    ///
+    /// 空いている処理枠の数だけメッセージをまとめて受信する。
+    /// キャンセル後は新しいメッセージを受信せず、処理中のメッセージの完了を待ってから戻る。
+    /// `shutdown_timeout` 以内に完了しなかったメッセージは処理を中断して再配信を要求する。
+    pub async fn run(&self, shutdown: CancellationToken) {
+        let concurrency = self.config.concurrency.max(1);
+        info!(
+            worker = self.worker.name(),
+            concurrency, "ワーカーを開始します"
+        );
+
+        let abort = CancellationToken::new();
+        let mut in_flight = JoinSet::new();
+        let mut receiving = None;
+        let mut backoff = None;
+
+        loop {
+            if receiving.is_none() && backoff.is_none() && in_flight.len() < concurrency {
+                receiving = Some(self.source.next_batch(concurrency - in_flight.len()));
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                Some(result) = in_flight.join_next(), if !in_flight.is_empty() => {
+                    self.handle_join_result(result);
+                }
+                _ = async { backoff.as_mut().unwrap().await }, if backoff.is_some() => {
+                    backoff = None;
+                }
+                result = async { receiving.as_mut().unwrap().await }, if receiving.is_some() => {
+                    receiving = None;
+                    match result {
+                        Ok(batch) => {
+                            for (event, delivery) in batch {
+                                self.metrics.record_received();
+                                if delivery.delivery_count() > 1 {
+                                    self.metrics.record_redelivered();
+                                }
+                                in_flight.spawn(process(
+                                    self.worker.clone(),
+                                    self.metrics.clone(),
+                                    self.config.heartbeat_interval,
+                                    abort.clone(),
+                                    event,
+                                    delivery,
+                                ));
+                            }
+                        }
+                        Err(e) => {
+                            error!(worker = self.worker.name(), "イベントの取得に失敗: {}", e);
+                            self.metrics.record_receive_error();
+                            backoff = Some(Box::pin(tokio::time::sleep(
+                                self.config.receive_error_backoff,
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+
+        drop(receiving);
+        info!(
+            worker = self.worker.name(),
+            in_flight = in_flight.len(),
+            "処理中のメッセージの完了を待機しています..."
+        );
+        let drain = async {
+            while let Some(result) = in_flight.join_next().await {
+                self.handle_join_result(result);
+            }
+        };
+        if tokio::time::timeout(self.config.shutdown_timeout, drain)
+            .await
+            .is_err()
+        {
+            warn!(
+                worker = self.worker.name(),
+                in_flight = in_flight.len(),
+                "処理中のメッセージが時間内に完了しなかったため中断します"
+            );
+            abort.cancel();
+            while let Some(result) = in_flight.join_next().await {
+                self.handle_join_result(result);
+            }
+        }
+        info!(worker = self.worker.name(), "ワーカーを停止しました");
+    }
+
+    fn handle_join_result(&self, result: Result<(), JoinError>) {
+        if let Err(e) = result {
+            // ackされなかったメッセージはack待ちのタイムアウト後に再配信される
+            error!(
+                worker = self.worker.name(),
+                "メッセージの処理中にパニックしました: {}", e
+            );
+            self.metrics.record_failed();
+        }
+    }
+}
+
+async fn process<E, W, D>(
+    worker: Arc<W>,
+    metrics: Arc<WorkerMetrics>,
+    heartbeat_interval: Option<Duration>,
+    abort: CancellationToken,
+    event: E,
+    mut delivery: D,
+) where
+    E: Event,
+    W: Worker<E>,
+    D: Delivery,
+{
+    let span = info_span!(
+        "message",
+        worker = worker.name(),
+        delivered = delivery.delivery_count()
+    );
+    async move {
+        debug!("メッセージの処理を開始します");
+        let job = async {
+            match heartbeat_interval {
+                Some(interval) => {
+                    with_heartbeat(&mut delivery, interval, worker.handle(&event)).await
+                }
+                None => worker.handle(&event).await,
+            }
+        };
+        let result = tokio::select! {
+            result = job => Some(result),
+            _ = abort.cancelled() => None,
+        };
+
+        match result {
+            None => {
+                warn!("処理を中断しました");
+                if let Err(e) = delivery.nak().await {
+                    error!("メッセージの否定確認（nak）に失敗: {:?}", e);
+                }
+                metrics.record_aborted();
+            }
+            Some(Ok(())) => {
+                if let Err(e) = delivery.ack().await {
+                    error!("メッセージの確認（ack）に失敗: {:?}", e);
+                }
+                metrics.record_succeeded();
+                debug!("メッセージを処理しました");
+            }
+            Some(Err(e)) => {
+                warn!(
+                    retryable = e.is_retryable(),
+                    "メッセージの処理に失敗: {}", e
+                );
+                if let Err(e) = delivery.fail_with(&e).await {
+                    error!("メッセージの失敗通知に失敗: {:?}", e);
+                }
+                metrics.record_failed();
+            }
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// `job` の完了を待つ間、`interval` ごとに処理中であることを通知する
+async fn with_heartbeat<D, F>(delivery: &mut D, interval: Duration, job: F) -> F::Output
+where
+    D: Delivery,
+    F: Future<Output = Result<(), DomainError>>,
+{
+    let mut job = std::pin::pin!(job);
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        tokio::select! {
+            output = &mut job => return output,
+            _ = ticker.tick() => {
+                if let Err(e) = delivery.in_progress().await {
+                    warn!("処理中通知に失敗: {:?}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde::{Deserialize, Serialize};
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    struct TestEvent {
+        id: u32,
+    }
+    impl Event for TestEvent {}
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Outcome {
+        Ack(u32),
+        Nak(u32),
+        Fail(u32, bool),
+        InProgress(u32),
+    }
+
+    struct MockDelivery {
+        id: u32,
+        outcomes: Arc<Mutex<Vec<Outcome>>>,
+    }
+
+    #[async_trait]
+    impl Delivery for MockDelivery {
+        type Error = String;
+
+        fn delivery_count(&self) -> i64 {
+            1
+        }
+
+        async fn ack(&mut self) -> Result<(), String> {
+            self.outcomes.lock().unwrap().push(Outcome::Ack(self.id));
+            Ok(())
+        }
+
+        async fn nak(&mut self) -> Result<(), String> {
+            self.outcomes.lock().unwrap().push(Outcome::Nak(self.id));
+            Ok(())
+        }
+
+        async fn in_progress(&mut self) -> Result<(), String> {
+            self.outcomes
+                .lock()
+                .unwrap()
+                .push(Outcome::InProgress(self.id));
+            Ok(())
+        }
+
+        async fn fail_with(&mut self, error: &DomainError) -> Result<(), String> {
+            self.outcomes
+                .lock()
+                .unwrap()
+                .push(Outcome::Fail(self.id, error.is_retryable()));
+            Ok(())
+        }
+    }
+
+    /// 用意したイベントを順に返し、尽きたら待ち続ける受信元
+    struct MockSource {
+        events: Mutex<VecDeque<Result<TestEvent, String>>>,
+        outcomes: Arc<Mutex<Vec<Outcome>>>,
+        batch_sizes: Mutex<Vec<usize>>,
+    }
+
+    impl MockSource {
+        fn new(events: Vec<Result<TestEvent, String>>) -> Self {
+            Self {
+                events: Mutex::new(events.into()),
+                outcomes: Arc::new(Mutex::new(Vec::new())),
+                batch_sizes: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EventSource<TestEvent> for MockSource {
+        type Delivery = MockDelivery;
+        type Error = String;
+
+        async fn next(&self) -> Result<(TestEvent, MockDelivery), String> {
+            let next = self.events.lock().unwrap().pop_front();
+            match next {
+                Some(Ok(event)) => Ok((
+                    event.clone(),
+                    MockDelivery {
+                        id: event.id,
+                        outcomes: self.outcomes.clone(),
+                    },
+                )),
+                Some(Err(e)) => Err(e),
+                None => std::future::pending().await,
+            }
+        }
+
+        async fn next_batch(
+            &self,
+            max_messages: usize,
+        ) -> Result<Vec<(TestEvent, MockDelivery)>, String> {
+            self.batch_sizes.lock().unwrap().push(max_messages);
+            let mut batch = vec![self.next().await?];
+            while batch.len() < max_messages {
+                let next = self.events.lock().unwrap().pop_front();
+                match next {
+                    Some(Ok(event)) => batch.push((
+                        event.clone(),
+                        MockDelivery {
+                            id: event.id,
+                            outcomes: self.outcomes.clone(),
+                        },
+                    )),
+                    Some(Err(e)) => {
+                        self.events.lock().unwrap().push_front(Err(e));
+                        break;
+                    }
+                    None => break,
+                }
+            }
+            Ok(batch)
+        }
+    }
+
+    /// 偶数IDのイベントで失敗するワーカー
+    struct MockWorker {
+        delay: Duration,
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    impl MockWorker {
+        fn new(delay: Duration) -> Self {
+            Self {
+                delay,
+                running: AtomicUsize::new(0),
+                max_running: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Worker<TestEvent> for MockWorker {
+        fn name(&self) -> &str {
+            "mock-worker"
+        }
+
+        async fn handle(&self, event: &TestEvent) -> Result<(), DomainError> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            match event.id % 4 {
+                2 => Err(DomainError::ServiceNotFound(event.id as i64)),
+                0 => Err(DomainError::UnknownError("一時的なエラー".to_string())),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    async fn run_until_processed<S: EventSource<TestEvent>>(
+        runtime: &WorkerRuntime<TestEvent, MockWorker, S>,
+        count: u64,
+    ) {
+        let shutdown = CancellationToken::new();
+        let metrics = runtime.metrics();
+        let stopper = shutdown.clone();
+        tokio::join!(runtime.run(shutdown), async move {
+            loop {
+                let snapshot = metrics.snapshot();
+                if snapshot.succeeded + snapshot.failed >= count {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            stopper.cancel();
+        });
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_acks_and_fails() {
+        let source = MockSource::new((1..=4).map(|id| Ok(TestEvent { id })).collect());
+        let outcomes = source.outcomes.clone();
+        let runtime = WorkerRuntime::new(
+            MockWorker::new(Duration::from_millis(1)),
+            source,
+            WorkerConfig {
+                heartbeat_interval: None,
+                ..Default::default()
+            },
+        );
+
+        run_until_processed(&runtime, 4).await;
+
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![
+                Outcome::Ack(1),
+                Outcome::Fail(2, false),
+                Outcome::Ack(3),
+                Outcome::Fail(4, true),
+            ]
+        );
+        let snapshot = runtime.metrics().snapshot();
+        assert_eq!(snapshot.received, 4);
+        assert_eq!(snapshot.succeeded, 2);
+        assert_eq!(snapshot.failed, 2);
+        assert_eq!(snapshot.in_flight, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrency_limit() {
+        let source = MockSource::new(
+            [1, 3, 5, 7, 9, 11]
+                .into_iter()
+                .map(|id| Ok(TestEvent { id }))
+                .collect(),
+        );
+        let runtime = WorkerRuntime::new(
+            MockWorker::new(Duration::from_millis(100)),
+            source,
+            WorkerConfig {
+                concurrency: 3,
+                heartbeat_interval: None,
+                ..Default::default()
+            },
+        );
+
+        run_until_processed(&runtime, 6).await;
+
+        assert_eq!(runtime.worker.max_running.load(Ordering::SeqCst), 3);
+        assert_eq!(runtime.metrics().snapshot().succeeded, 6);
+
+        let batch_sizes = runtime.source.batch_sizes.lock().unwrap().clone();
+        assert_eq!(batch_sizes[0], 3);
+        assert!(batch_sizes.iter().all(|&size| (1..=3).contains(&size)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_heartbeat() {
+        let source = MockSource::new(vec![Ok(TestEvent { id: 1 })]);
+        let outcomes = source.outcomes.clone();
+        let runtime = WorkerRuntime::new(
+            MockWorker::new(Duration::from_millis(250)),
+            source,
+            WorkerConfig {
+                heartbeat_interval: Some(Duration::from_millis(100)),
+                ..Default::default()
+            },
+        );
+
+        run_until_processed(&runtime, 1).await;
+
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![
+                Outcome::InProgress(1),
+                Outcome::InProgress(1),
+                Outcome::Ack(1),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_receive_error_backoff() {
+        let source = MockSource::new(vec![Err("接続エラー".to_string()), Ok(TestEvent { id: 1 })]);
+        let runtime = WorkerRuntime::new(
+            MockWorker::new(Duration::ZERO),
+            source,
+            WorkerConfig {
+                heartbeat_interval: None,
+                ..Default::default()
+            },
+        );
+
+        let started = tokio::time::Instant::now();
+        run_until_processed(&runtime, 1).await;
+
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        let snapshot = runtime.metrics().snapshot();
+        assert_eq!(snapshot.receive_errors, 1);
+        assert_eq!(snapshot.succeeded, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_waits_for_in_flight() {
+        let source = MockSource::new(vec![Ok(TestEvent { id: 1 })]);
+        let outcomes = source.outcomes.clone();
+        let runtime = WorkerRuntime::new(
+            MockWorker::new(Duration::from_secs(5)),
+            source,
+            WorkerConfig {
+                heartbeat_interval: None,
+                ..Default::default()
+            },
+        );
+
+        let shutdown = CancellationToken::new();
+        let stopper = shutdown.clone();
+        let metrics = runtime.metrics();
+        tokio::join!(runtime.run(shutdown), async move {
+            while metrics.snapshot().in_flight == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            stopper.cancel();
+        });
+
+        assert_eq!(*outcomes.lock().unwrap(), vec![Outcome::Ack(1)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_timeout_naks_in_flight() {
+        let source = MockSource::new(vec![Ok(TestEvent { id: 1 })]);
+        let outcomes = source.outcomes.clone();
+        let runtime = WorkerRuntime::new(
+            MockWorker::new(Duration::from_secs(60)),
+            source,
+            WorkerConfig {
+                heartbeat_interval: None,
+                shutdown_timeout: Duration::from_secs(5),
+                ..Default::default()
+            },
+        );
+
+        let shutdown = CancellationToken::new();
+        let stopper = shutdown.clone();
+        let metrics = runtime.metrics();
+        let started = tokio::time::Instant::now();
+        tokio::join!(runtime.run(shutdown), async move {
+            while metrics.snapshot().in_flight == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            stopper.cancel();
+        });
+
+        assert!(started.elapsed() < Duration::from_secs(60));
+        assert_eq!(*outcomes.lock().unwrap(), vec![Outcome::Nak(1)]);
+        let snapshot = runtime.metrics().snapshot();
+        assert_eq!(snapshot.aborted, 1);
+        assert_eq!(snapshot.in_flight, 0);
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt::{Debug, Display};
+
+use async_trait::async_trait;
+use domain::{error::DomainError, types::Event};
+
+/// ドメインイベントを1件ずつ処理するパイプラインのステージ
+#[async_trait]
+pub trait Worker<E: Event>: Send + Sync + 'static {
+    /// ワーカー名（コンシューマーの永続名としても使われる）
+    fn name(&self) -> &str;
+
+    async fn handle(&self, event: &E) -> Result<(), DomainError>;
+}
+
+/// 受信したメッセージの確認応答を行うハンドル
+#[async_trait]
+pub trait Delivery: Send + 'static {
+    type Error: Debug + Send;
+
+    /// このメッセージの配信回数（初回配信は1）
+    fn delivery_count(&self) -> i64;
+
+    async fn ack(&mut self) -> Result<(), Self::Error>;
+
+    /// 処理を中断したことを通知し、再配信を要求する
+    async fn nak(&mut self) -> Result<(), Self::Error>;
+
+    /// 処理中であることを通知する
+    async fn in_progress(&mut self) -> Result<(), Self::Error>;
+
+    /// 処理の失敗を通知し、再試行するかどうかの判断を委ねる
+    async fn fail_with(&mut self, error: &DomainError) -> Result<(), Self::Error>;
+}
+
+/// ワーカーが処理するイベントの受信元
+#[async_trait]
+pub trait EventSource<E: Event>: Send + Sync {
+    type Delivery: Delivery;
+    type Error: Display + Send;
+
+    async fn next(&self) -> Result<(E, Self::Delivery), Self::Error>;
+
+    /// 最大 `max_messages` 件のイベントをまとめて受信する
+    async fn next_batch(
+        &self,
+        max_messages: usize,
+    ) -> Result<Vec<(E, Self::Delivery)>, Self::Error> {
+        let _ = max_messages;
+        Ok(vec![self.next().await?])
+    }
+}
@@ -0,0 +1,499 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::{HealthCheck, MetricsCollector, MetricsEncoder, WorkerMetrics, WorkerMetricsSnapshot};
+
+/// 異常終了したタスクを再起動するまでの待ち時間の方針
+#[derive(Clone, Debug)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// この時間以上動き続けたタスクは、次の異常終了時に待ち時間を初期値に戻す
+    pub reset_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            reset_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// `failures` 回連続で異常終了した後の待ち時間（初回は1）
+    pub fn backoff_for(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// 監視対象タスクの状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskState {
+    Running,
+    /// 異常終了し、再起動を待っている
+    Restarting,
+    Stopped,
+}
+
+/// ある時点での監視対象タスクの状態
+#[derive(Clone, Debug)]
+pub struct TaskHealth {
+    pub name: String,
+    pub state: TaskState,
+    /// 再起動した回数
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    pub metrics: Option<WorkerMetricsSnapshot>,
+}
+
+struct TaskEntry {
+    state: TaskState,
+    restarts: u32,
+    last_error: Option<String>,
+    metrics: Option<Arc<WorkerMetrics>>,
+}
+
+/// [`Supervisor`] が監視しているタスクの状態の集約
+#[derive(Clone, Default)]
+pub struct SupervisorHealth {
+    tasks: Arc<Mutex<BTreeMap<String, TaskEntry>>>,
+}
+
+impl SupervisorHealth {
+    pub fn tasks(&self) -> Vec<TaskHealth> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, entry)| TaskHealth {
+                name: name.clone(),
+                state: entry.state,
+                restarts: entry.restarts,
+                last_error: entry.last_error.clone(),
+                metrics: entry.metrics.as_ref().map(|m| m.snapshot()),
+            })
+            .collect()
+    }
+
+    /// すべてのタスクが動いているかどうか
+    pub fn is_healthy(&self) -> bool {
+        self.tasks
+            .lock()
+            .unwrap()
+            .values()
+            .all(|entry| entry.state == TaskState::Running)
+    }
+
+    fn register(&self, name: &str, metrics: Option<Arc<WorkerMetrics>>) {
+        self.tasks.lock().unwrap().insert(
+            name.to_string(),
+            TaskEntry {
+                state: TaskState::Running,
+                restarts: 0,
+                last_error: None,
+                metrics,
+            },
+        );
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut TaskEntry)) {
+        if let Some(entry) = self.tasks.lock().unwrap().get_mut(name) {
+            f(entry);
+        }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for SupervisorHealth {
+    fn name(&self) -> &str {
+        "supervisor"
+    }
+
+    async fn check(&self) -> Result<(), String> {
+        let restarting: Vec<String> = self
+            .tasks()
+            .into_iter()
+            .filter(|task| task.state == TaskState::Restarting)
+            .map(|task| {
+                format!(
+                    "{}: {}",
+                    task.name,
+                    task.last_error.as_deref().unwrap_or("-")
+                )
+            })
+            .collect();
+        if restarting.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "再起動待ちのタスクがあります: {}",
+                restarting.join(", ")
+            ))
+        }
+    }
+}
+
+#[async_trait]
+impl MetricsCollector for SupervisorHealth {
+    async fn collect(&self, encoder: &mut MetricsEncoder) {
+        for task in self.tasks() {
+            let labels = [("task", task.name.as_str())];
+            encoder.gauge(
+                "kurec_task_up",
+                "タスクが動いているかどうか",
+                &labels,
+                if task.state == TaskState::Running {
+                    1.0
+                } else {
+                    0.0
+                },
+            );
+            encoder.counter(
+                "kurec_task_restarts_total",
+                "タスクを再起動した回数",
+                &labels,
+                task.restarts as u64,
+            );
+        }
+    }
+}
+
+/// 監視対象のタスクが返すエラー
+pub type TaskError = Box<dyn Error + Send + Sync>;
+
+type TaskResult = Result<(), TaskError>;
+type TaskFactory = Box<
+    dyn FnMut(CancellationToken) -> std::pin::Pin<Box<dyn Future<Output = TaskResult> + Send>>
+        + Send,
+>;
+
+/// 複数のタスクを1つのプロセスで動かし、異常終了したタスクを再起動する
+///
+/// 再起動しても直らないエラー（[`Supervisor::with_fatal`] で判定する）で終了したタスクがあれば、
+/// すべてのタスクを止めてそのエラーを返す。
+#[derive(Default)]
+pub struct Supervisor {
+    policy: RestartPolicy,
+    tasks: Vec<(String, TaskFactory)>,
+    health: SupervisorHealth,
+    is_fatal: Option<fn(&(dyn Error + 'static)) -> bool>,
+}
+
+impl Supervisor {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// 再起動せずにすべてのタスクを止めるエラーの判定方法を指定する
+    pub fn with_fatal(mut self, is_fatal: fn(&(dyn Error + 'static)) -> bool) -> Self {
+        self.is_fatal = Some(is_fatal);
+        self
+    }
+
+    /// 監視対象のタスクを追加する
+    ///
+    /// `factory` は起動・再起動のたびに呼ばれ、シャットダウン用のトークンを受け取ってタスクを返す。
+    pub fn add<F, Fut, Er>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: FnMut(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), Er>> + Send + 'static,
+        Er: Into<TaskError>,
+    {
+        self.add_inner(name.into(), None, factory);
+    }
+
+    /// 処理件数を集約する対象として、[`WorkerMetrics`] とともにタスクを追加する
+    pub fn add_worker<F, Fut, Er>(
+        &mut self,
+        name: impl Into<String>,
+        metrics: Arc<WorkerMetrics>,
+        factory: F,
+    ) where
+        F: FnMut(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), Er>> + Send + 'static,
+        Er: Into<TaskError>,
+    {
+        self.add_inner(name.into(), Some(metrics), factory);
+    }
+
+    fn add_inner<F, Fut, Er>(
+        &mut self,
+        name: String,
+        metrics: Option<Arc<WorkerMetrics>>,
+        mut factory: F,
+    ) where
+        F: FnMut(CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), Er>> + Send + 'static,
+        Er: Into<TaskError>,
+    {
+        self.health.register(&name, metrics);
+        self.tasks.push((
+            name,
+            Box::new(move |shutdown| {
+                let task = factory(shutdown);
+                Box::pin(async move { task.await.map_err(Into::into) })
+            }),
+        ));
+    }
+
+    pub fn health(&self) -> SupervisorHealth {
+        self.health.clone()
+    }
+
+    /// `shutdown` がキャンセルされるまでタスクを動かし続け、すべてのタスクの終了を待って戻る
+    ///
+    /// 再起動しても直らないエラーで終了したタスクがあれば、残りのタスクを止めて最初のそのエラーを返す。
+    pub async fn run(self, shutdown: CancellationToken) -> Result<(), TaskError> {
+        let stop = shutdown.child_token();
+        let mut supervised = JoinSet::new();
+        for (name, factory) in self.tasks {
+            supervised.spawn(supervise(
+                name,
+                factory,
+                self.policy.clone(),
+                self.health.clone(),
+                self.is_fatal,
+                stop.clone(),
+            ));
+        }
+
+        let mut fatal = None;
+        while let Some(result) = supervised.join_next().await {
+            if let Ok(Err(e)) = result {
+                stop.cancel();
+                fatal.get_or_insert(e);
+            }
+        }
+        fatal.map_or(Ok(()), Err)
+    }
+}
+
+/// タスクを再起動し続け、再起動しても直らないエラーで終了したらそのエラーを返す
+async fn supervise(
+    name: String,
+    mut factory: TaskFactory,
+    policy: RestartPolicy,
+    health: SupervisorHealth,
+    is_fatal: Option<fn(&(dyn Error + 'static)) -> bool>,
+    shutdown: CancellationToken,
+) -> TaskResult {
+    let mut failures = 0;
+    loop {
+        info!(task = %name, "タスクを開始します");
+        health.update(&name, |entry| entry.state = TaskState::Running);
+
+        let started_at = Instant::now();
+        let result = match tokio::spawn(factory(shutdown.clone())).await {
+            Ok(result) => result,
+            Err(e) => Err(format!("タスクがパニックしました: {}", e).into()),
+        };
+
+        if shutdown.is_cancelled() {
+            if let Err(e) = result {
+                warn!(task = %name, "タスクが異常終了しました: {}", e);
+            }
+            info!(task = %name, "タスクを停止しました");
+            health.update(&name, |entry| entry.state = TaskState::Stopped);
+            return Ok(());
+        }
+
+        let error = result
+            .err()
+            .unwrap_or_else(|| "タスクが予期せず終了しました".into());
+        if is_fatal.is_some_and(|is_fatal| is_fatal(error.as_ref())) {
+            error!(task = %name, "再起動しても直らないエラーで終了しました: {}", error);
+            health.update(&name, |entry| {
+                entry.state = TaskState::Stopped;
+                entry.last_error = Some(error.to_string());
+            });
+            return Err(error);
+        }
+        if started_at.elapsed() >= policy.reset_after {
+            failures = 0;
+        }
+        failures += 1;
+        let backoff = policy.backoff_for(failures);
+        error!(
+            task = %name,
+            backoff = ?backoff,
+            "タスクが異常終了したため再起動します: {}", error
+        );
+        health.update(&name, |entry| {
+            entry.state = TaskState::Restarting;
+            entry.restarts += 1;
+            entry.last_error = Some(error.to_string());
+        });
+
+        tokio::select! {
+            _ = shutdown.cancelled() => {
+                health.update(&name, |entry| entry.state = TaskState::Stopped);
+                return Ok(());
+            }
+            _ = tokio::time::sleep(backoff) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn state_of(health: &SupervisorHealth, name: &str) -> TaskHealth {
+        health
+            .tasks()
+            .into_iter()
+            .find(|task| task.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_backoff_for() {
+        let policy = RestartPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            reset_after: Duration::from_secs(60),
+        };
+        assert_eq!(policy.backoff_for(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff_for(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff_for(4), Duration::from_secs(8));
+        assert_eq!(policy.backoff_for(5), Duration::from_secs(10));
+        assert_eq!(policy.backoff_for(100), Duration::from_secs(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_restarts_failed_task_with_backoff() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut supervisor = Supervisor::new(RestartPolicy::default());
+        let counter = attempts.clone();
+        supervisor.add("flaky", move |shutdown: CancellationToken| {
+            let attempt = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                if attempt <= 2 {
+                    return Err(format!("失敗 {}", attempt));
+                }
+                shutdown.cancelled().await;
+                Ok(())
+            }
+        });
+        let health = supervisor.health();
+        let shutdown = CancellationToken::new();
+        let handle = tokio::spawn(supervisor.run(shutdown.clone()));
+
+        // 1回目の失敗後1秒、2回目の失敗後2秒待って再起動する
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(state_of(&health, "flaky").state, TaskState::Restarting);
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        let task = state_of(&health, "flaky");
+        assert_eq!(task.state, TaskState::Running);
+        assert_eq!(task.restarts, 2);
+        assert_eq!(task.last_error.as_deref(), Some("失敗 2"));
+        assert!(health.is_healthy());
+        assert!(health.check().await.is_ok());
+
+        shutdown.cancel();
+        handle.await.unwrap().unwrap();
+        assert_eq!(state_of(&health, "flaky").state, TaskState::Stopped);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_restarts_panicked_task() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let mut supervisor = Supervisor::new(RestartPolicy::default());
+        let counter = attempts.clone();
+        supervisor.add_worker(
+            "panicky",
+            Arc::new(WorkerMetrics::default()),
+            move |shutdown: CancellationToken| {
+                let attempt = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    if attempt == 1 {
+                        panic!("テスト用のパニック");
+                    }
+                    shutdown.cancelled().await;
+                    Ok::<(), String>(())
+                }
+            },
+        );
+        let health = supervisor.health();
+        let shutdown = CancellationToken::new();
+        let handle = tokio::spawn(supervisor.run(shutdown.clone()));
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let task = state_of(&health, "panicky");
+        assert_eq!(task.state, TaskState::Running);
+        assert_eq!(task.restarts, 1);
+        assert!(task.metrics.is_some());
+
+        shutdown.cancel();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_during_backoff() {
+        let mut supervisor = Supervisor::new(RestartPolicy::default());
+        supervisor.add("broken", |_shutdown: CancellationToken| async {
+            Err::<(), _>("常に失敗")
+        });
+        let health = supervisor.health();
+        let shutdown = CancellationToken::new();
+        let handle = tokio::spawn(supervisor.run(shutdown.clone()));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!health.is_healthy());
+        assert!(
+            health
+                .check()
+                .await
+                .unwrap_err()
+                .contains("broken: 常に失敗")
+        );
+
+        shutdown.cancel();
+        handle.await.unwrap().unwrap();
+        assert_eq!(state_of(&health, "broken").state, TaskState::Stopped);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fatal_error_stops_all_tasks() {
+        let mut supervisor = Supervisor::new(RestartPolicy::default())
+            .with_fatal(|error| error.to_string().starts_with("致命的"));
+        supervisor.add("fatal", |_shutdown: CancellationToken| async {
+            Err::<(), _>("致命的な失敗")
+        });
+        supervisor.add("healthy", |shutdown: CancellationToken| async move {
+            shutdown.cancelled().await;
+            Ok::<(), String>(())
+        });
+        let health = supervisor.health();
+
+        let result = supervisor.run(CancellationToken::new()).await;
+
+        assert_eq!(result.unwrap_err().to_string(), "致命的な失敗");
+        let task = state_of(&health, "fatal");
+        assert_eq!(task.state, TaskState::Stopped);
+        assert_eq!(task.restarts, 0);
+        assert_eq!(state_of(&health, "healthy").state, TaskState::Stopped);
+    }
+}